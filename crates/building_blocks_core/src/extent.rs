@@ -104,13 +104,34 @@ where
 
     /// Returns a new extent that's been padded on all borders by `pad_amount`.
     #[inline]
-    pub fn padded(&self, pad_amount: <PointN<N> as Point>::Scalar) -> Self
-    where
-        <PointN<N> as Point>::Scalar: Add<Output = <PointN<N> as Point>::Scalar>,
-    {
+    pub fn padded(&self, pad_amount: <PointN<N> as Point>::Scalar) -> Self {
+        self.padded_per_axis(PointN::fill(pad_amount))
+    }
+
+    /// Returns a new extent that's been padded on both borders of each axis `i` by `pad_amount.at(i)`.
+    #[inline]
+    pub fn padded_per_axis(&self, pad_amount: PointN<N>) -> Self {
+        Self::from_min_and_shape(
+            self.minimum - pad_amount,
+            self.shape + pad_amount + pad_amount,
+        )
+    }
+
+    /// Returns a new extent that's been shrunk on all borders by `erode_amount`. This is the inverse of `padded`.
+    ///
+    /// The shape is not clamped, so eroding by more than half of the shape gives a negative shape.
+    #[inline]
+    pub fn eroded(&self, erode_amount: <PointN<N> as Point>::Scalar) -> Self {
+        self.eroded_per_axis(PointN::fill(erode_amount))
+    }
+
+    /// Returns a new extent that's been shrunk on both borders of each axis `i` by `erode_amount.at(i)`. This is the
+    /// inverse of `padded_per_axis`.
+    #[inline]
+    pub fn eroded_per_axis(&self, erode_amount: PointN<N>) -> Self {
         Self::from_min_and_shape(
-            self.minimum - PointN::fill(pad_amount),
-            self.shape + PointN::fill(pad_amount + pad_amount),
+            self.minimum + erode_amount,
+            self.shape - erode_amount - erode_amount,
         )
     }
 }
@@ -125,10 +146,23 @@ where
         self.volume() as usize
     }
 
-    /// Returns `true` iff the number of points in the extent is 0.
+    /// Returns `true` iff the extent contains no points, i.e. some component of the shape is not positive. Unlike checking
+    /// `num_points() == 0`, this is also correct for negative shapes, which can have a positive volume.
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.num_points() == 0
+        !(self.shape > PointN::ZERO)
+    }
+
+    /// Same as `from_min_and_shape`, but returns `None` if the least upper bound `minimum + shape` is not representable.
+    #[inline]
+    pub fn from_min_and_shape_checked(minimum: PointN<N>, shape: PointN<N>) -> Option<Self> {
+        // The addition saturated iff we can't get back to the minimum. This subtraction can't overflow.
+        let lub = minimum.saturating_add(shape);
+        if lub - shape == minimum {
+            Some(Self::from_min_and_shape(minimum, shape))
+        } else {
+            None
+        }
     }
 
     /// An alternative representation of an extent as the minimum point and least upper bound.
@@ -180,6 +214,16 @@ where
         Self::from_min_and_max(min, max)
     }
 
+    /// Returns the smallest extent containing all of `points`, or `None` if `points` is empty.
+    #[inline]
+    pub fn from_points(points: &[PointN<N>]) -> Option<Self> {
+        if points.is_empty() {
+            return None;
+        }
+
+        Some(bounding_extent(points.iter().cloned()))
+    }
+
     /// Iterate over all points in the extent.
     /// ```
     /// # use building_blocks_core::prelude::*;
@@ -194,6 +238,51 @@ where
     pub fn iter_points(&self) -> <PointN<N> as IterExtent<N>>::PointIter {
         PointN::iter_extent(self.minimum, self.least_upper_bound())
     }
+
+    /// Returns disjoint extents whose union is all of the points in `self` that are not in `other`. There are at most `2 * D`
+    /// extents for dimension `D`, and none of them are empty. If `self` and `other` are disjoint, this is just `[self]`.
+    /// ```
+    /// # use building_blocks_core::prelude::*;
+    /// #
+    /// let extent = Extent2i::from_min_and_shape(PointN([0, 0]), PointN([4, 4]));
+    /// let cut = Extent2i::from_min_and_shape(PointN([2, -1]), PointN([4, 6]));
+    /// assert_eq!(
+    ///     extent.subtract(&cut),
+    ///     vec![Extent2i::from_min_and_shape(PointN([0, 0]), PointN([2, 4]))]
+    /// );
+    /// ```
+    #[inline]
+    pub fn subtract(&self, other: &Self) -> Vec<Self> {
+        let hole = self.intersection(other);
+        if hole.is_empty() {
+            return vec![*self];
+        }
+        let hole_lub = hole.least_upper_bound();
+
+        // Slice off the slabs below and above the hole along each axis in turn, shrinking the remainder down to the hole.
+        let mut pieces = Vec::new();
+        let mut remainder = *self;
+        for axis in PointN::<N>::basis() {
+            let min = remainder.minimum;
+            let lub = remainder.least_upper_bound();
+            // Replaces the `axis` component of `p` with that of `q`.
+            let with_axis = |p: PointN<N>, q: PointN<N>| p + (q - p) * axis;
+
+            let below = Self::from_min_and_lub(min, with_axis(lub, hole.minimum));
+            if !below.is_empty() {
+                pieces.push(below);
+            }
+            let above = Self::from_min_and_lub(with_axis(min, hole_lub), lub);
+            if !above.is_empty() {
+                pieces.push(above);
+            }
+
+            remainder =
+                Self::from_min_and_lub(with_axis(min, hole.minimum), with_axis(lub, hole_lub));
+        }
+
+        pieces
+    }
 }
 
 impl<N> Add<PointN<N>> for ExtentN<N>
@@ -381,6 +470,82 @@ impl From<Extent3i> for Extent3f {
 mod test {
     use super::*;
 
+    fn assert_disjoint_cover(extent: &Extent3i, other: &Extent3i, pieces: &[Extent3i]) {
+        for (i, a) in pieces.iter().enumerate() {
+            assert!(!a.is_empty());
+            for b in pieces[i + 1..].iter() {
+                assert!(a.intersection(b).is_empty());
+            }
+        }
+        for p in extent.iter_points() {
+            let num_containing = pieces.iter().filter(|piece| piece.contains(p)).count();
+            assert_eq!(num_containing, !other.contains(p) as usize, "p = {:?}", p);
+        }
+    }
+
+    #[test]
+    fn subtract_hole_in_middle() {
+        let extent = Extent3i::from_min_and_shape(PointN([0, 0, 0]), PointN([6, 6, 6]));
+        let hole = Extent3i::from_min_and_shape(PointN([2, 2, 2]), PointN([2, 2, 2]));
+
+        let pieces = extent.subtract(&hole);
+
+        assert_eq!(pieces.len(), 6);
+        assert_disjoint_cover(&extent, &hole, &pieces);
+    }
+
+    #[test]
+    fn subtract_edge_cut() {
+        let extent = Extent3i::from_min_and_shape(PointN([0, 0, 0]), PointN([6, 6, 6]));
+        // Removes the edge along Z where X and Y are maximal.
+        let cut = Extent3i::from_min_and_shape(PointN([4, 4, -2]), PointN([4, 4, 10]));
+
+        let pieces = extent.subtract(&cut);
+
+        assert_eq!(
+            pieces,
+            vec![
+                Extent3i::from_min_and_shape(PointN([0, 0, 0]), PointN([4, 6, 6])),
+                Extent3i::from_min_and_shape(PointN([4, 0, 0]), PointN([2, 4, 6])),
+            ]
+        );
+        assert_disjoint_cover(&extent, &cut, &pieces);
+    }
+
+    #[test]
+    fn subtract_non_overlapping() {
+        let extent = Extent3i::from_min_and_shape(PointN([0, 0, 0]), PointN([6, 6, 6]));
+        // Touching, but not overlapping.
+        let other = Extent3i::from_min_and_shape(PointN([6, 0, 0]), PointN([6, 6, 6]));
+
+        assert_eq!(extent.subtract(&other), vec![extent]);
+    }
+
+    #[test]
+    fn padded_and_eroded_uniform() {
+        let extent = Extent3i::from_min_and_shape(PointN([1, 2, 3]), PointN([4, 5, 6]));
+
+        let padded = extent.padded(2);
+        assert_eq!(padded.minimum, PointN([-1, 0, 1]));
+        assert_eq!(padded.shape, PointN([8, 9, 10]));
+
+        assert_eq!(padded.eroded(2), extent);
+    }
+
+    #[test]
+    fn padded_and_eroded_per_axis() {
+        let extent = Extent3i::from_min_and_shape(PointN([1, 2, 3]), PointN([4, 5, 6]));
+
+        let padded = extent.padded_per_axis(PointN([0, 1, 2]));
+        assert_eq!(padded.minimum, PointN([1, 1, 1]));
+        assert_eq!(padded.shape, PointN([4, 7, 10]));
+
+        let eroded = extent.eroded_per_axis(PointN([2, 1, 0]));
+        assert_eq!(eroded.minimum, PointN([3, 3, 3]));
+        assert_eq!(eroded.shape, PointN([0, 3, 6]));
+        assert!(eroded.is_empty());
+    }
+
     #[test]
     fn row_major_extent_iter2() {
         let extent = Extent2i::from_min_and_shape(PointN([0, 0]), PointN([2, 2]));
@@ -428,4 +593,63 @@ mod test {
         assert_eq!(e1.intersection(&e2).shape, PointN([0; 2]));
         assert!(e1.intersection(&e2).is_empty());
     }
+
+    #[test]
+    fn negative_shape_is_empty() {
+        // Two negative components give a positive volume.
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, PointN([-2, -3, 4]));
+        assert!(extent.volume() > 0);
+        assert!(extent.is_empty());
+        assert_eq!(extent.iter_points().count(), 0);
+
+        assert!(!Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::ONES).is_empty());
+    }
+
+    #[test]
+    fn extent_from_scattered_points() {
+        assert_eq!(Extent3i::from_points(&[]), None);
+
+        let points = [
+            PointN([3, -1, 7]),
+            PointN([-4, 2, 0]),
+            PointN([1, 9, -5]),
+            PointN([0, 0, 0]),
+        ];
+        let extent = Extent3i::from_points(&points).unwrap();
+
+        assert_eq!(extent.minimum, PointN([-4, -1, -5]));
+        assert_eq!(extent.max(), PointN([3, 9, 7]));
+        assert!(points.iter().all(|&p| extent.contains(p)));
+
+        let single = Extent3i::from_points(&[PointN([5, 5, 5])]).unwrap();
+        assert_eq!(
+            single,
+            Extent3i::from_min_and_shape(PointN([5; 3]), PointN([1; 3]))
+        );
+    }
+
+    #[test]
+    fn checked_extent_near_integer_limits() {
+        let shape = PointN([16; 3]);
+
+        let max_min = PointN([i32::MAX - 16; 3]);
+        assert_eq!(
+            Extent3i::from_min_and_shape_checked(max_min, shape),
+            Some(Extent3i::from_min_and_shape(max_min, shape))
+        );
+        assert_eq!(
+            Extent3i::from_min_and_shape_checked(max_min + PointN([0, 1, 0]), shape),
+            None
+        );
+
+        let min_min = PointN([i32::MIN; 3]);
+        assert_eq!(
+            Extent3i::from_min_and_shape_checked(min_min, shape),
+            Some(Extent3i::from_min_and_shape(min_min, shape))
+        );
+        assert_eq!(
+            Extent3i::from_min_and_shape_checked(min_min, PointN([-1, 0, 0])),
+            None
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{Point2i, Point3i};
+use crate::{Point2i, Point3i, PointN};
 
 use bitintr::{Pdep, Pext};
 use std::fmt;
@@ -135,6 +135,55 @@ impl From<Morton3> for Point3i {
     }
 }
 
+/// The smallest coordinate that can be encoded by `morton_encode`. Each coordinate must be in
+/// `MORTON_COORD_MIN..=MORTON_COORD_MAX`, i.e. representable with 21 bits, so that all 3 fit into 63 bits of a `u64`.
+pub const MORTON_COORD_MIN: i32 = -(1 << 20);
+/// The largest coordinate that can be encoded by `morton_encode`. See `MORTON_COORD_MIN`.
+pub const MORTON_COORD_MAX: i32 = (1 << 20) - 1;
+
+/// Interleaves the bits of `p` into a 63-bit Morton code. Unlike `Morton3`, this only supports coordinates in
+/// `MORTON_COORD_MIN..=MORTON_COORD_MAX`, but the code fits in a `u64`. The order of codes is the same as the order of the
+/// corresponding `Morton3`s.
+///
+/// # Panics
+///
+/// In debug builds, if any coordinate of `p` is out of range. In release builds, out-of-range coordinates are truncated.
+#[inline]
+pub fn morton_encode(p: Point3i) -> u64 {
+    debug_assert!(
+        p.0.iter()
+            .all(|&c| (MORTON_COORD_MIN..=MORTON_COORD_MAX).contains(&c)),
+        "{:?} is out of the range supported by morton_encode",
+        p
+    );
+
+    translate_21(p.x()).pdep(Morton3::X_MASK)
+        | translate_21(p.y()).pdep(Morton3::Y_MASK)
+        | translate_21(p.z()).pdep(Morton3::Z_MASK)
+}
+
+/// The inverse of `morton_encode`.
+#[inline]
+pub fn morton_decode(code: u64) -> Point3i {
+    PointN([
+        untranslate_21(code.pext(Morton3::X_MASK)),
+        untranslate_21(code.pext(Morton3::Y_MASK)),
+        untranslate_21(code.pext(Morton3::Z_MASK)),
+    ])
+}
+
+/// Send the range supported by `morton_encode` into the lower 21 bits of a u64 while preserving the total order.
+#[inline]
+fn translate_21(x: i32) -> u64 {
+    (x.wrapping_sub(MORTON_COORD_MIN) as u64) & ((1 << 21) - 1)
+}
+
+/// The inverse of `translate_21`.
+#[inline]
+fn untranslate_21(x: u64) -> i32 {
+    x as i32 + MORTON_COORD_MIN
+}
+
 /// Send the supported range of i32 into the lower 32 bits of a u64 while preserving the total order.
 #[inline]
 fn translate(x: i32) -> u64 {
@@ -157,7 +206,6 @@ fn untranslate(x: u64) -> i32 {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::PointN;
 
     #[test]
     fn limits_of_i32() {
@@ -205,6 +253,56 @@ mod test {
         }
     }
 
+    #[test]
+    fn u64_codes_round_trip_random_points() {
+        let limits = [
+            PointN([MORTON_COORD_MIN; 3]),
+            PointN([MORTON_COORD_MAX; 3]),
+            PointN([MORTON_COORD_MIN, 0, MORTON_COORD_MAX]),
+        ];
+        assert_eq!(morton_encode(limits[0]), 0);
+        assert_eq!(morton_encode(limits[1]), (1 << 63) - 1);
+
+        // A simple xorshift, so the test is deterministic.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut random_coord = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % (1 << 21)) as i32 + MORTON_COORD_MIN
+        };
+        let random_points =
+            (0..1000).map(|_| PointN([random_coord(), random_coord(), random_coord()]));
+
+        let points: Vec<_> = limits.iter().cloned().chain(random_points).collect();
+        for &p in points.iter() {
+            assert_eq!(morton_decode(morton_encode(p)), p);
+        }
+        // Same order as the full-range encoding.
+        for pair in points.windows(2) {
+            assert_eq!(
+                morton_encode(pair[0]).cmp(&morton_encode(pair[1])),
+                Morton3::from(pair[0]).cmp(&Morton3::from(pair[1]))
+            );
+        }
+    }
+
+    #[test]
+    fn u64_codes_increase_along_each_axis() {
+        for axis in 0..3 {
+            let mut prev_code = None;
+            for c in (MORTON_COORD_MIN..=MORTON_COORD_MAX).step_by(997) {
+                let mut p = PointN([5, -3, 7]);
+                p.0[axis] = c;
+                let code = morton_encode(p);
+                if let Some(prev_code) = prev_code {
+                    assert!(code > prev_code, "axis = {}, c = {}", axis, c);
+                }
+                prev_code = Some(code);
+            }
+        }
+    }
+
     fn mortons_are_contiguous(mortons: &[Morton3]) -> bool {
         let min = mortons[0].0;
         let upper_bound = min + mortons.len() as u128;
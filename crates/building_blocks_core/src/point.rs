@@ -88,6 +88,23 @@ where
     }
 }
 
+impl<N> PointN<N>
+where
+    Self: MapComponents<Scalar = i32>,
+{
+    /// Component-wise addition that clamps to the bounds of `i32` instead of overflowing.
+    #[inline]
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        self.map_components_binary(rhs, |c1, c2| c1.saturating_add(c2))
+    }
+
+    /// Component-wise multiplication that clamps to the bounds of `i32` instead of overflowing.
+    #[inline]
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        self.map_components_binary(rhs, |c1, c2| c1.saturating_mul(c2))
+    }
+}
+
 impl<N> Abs for PointN<N>
 where
     Self: MapComponents,
@@ -171,3 +188,89 @@ where
         *self == Self::zero()
     }
 }
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn float_to_int_rounding_at_negative_coordinates() {
+        let p = PointN([-1.5f32, -0.5, -0.25]);
+
+        assert_eq!(p.into_int(), PointN([-1, 0, 0]));
+        assert_eq!(p.floor_int(), PointN([-2, -1, -1]));
+        assert_eq!(p.floor_to_int(), PointN([-2, -1, -1]));
+        assert_eq!(p.ceil_to_int(), PointN([-1, 0, 0]));
+        assert_eq!(p.round_to_int(), PointN([-2, -1, 0]));
+
+        let q = PointN([-2.75f32, 0.5, 1.25]);
+        assert_eq!(q.floor_to_int(), PointN([-3, 0, 1]));
+        assert_eq!(q.ceil_to_int(), PointN([-2, 1, 2]));
+        assert_eq!(q.round_to_int(), PointN([-3, 1, 1]));
+
+        // Integer points convert to float points exactly.
+        let i = PointN([-3, 0, 7]);
+        assert_eq!(Point3f::from(i).floor_to_int(), i);
+    }
+
+    #[test]
+    fn saturating_add_clamps_at_limits() {
+        let p = PointN([i32::MAX - 1, i32::MIN + 1, 0]);
+
+        assert_eq!(
+            p.saturating_add(PointN([5, -5, 5])),
+            PointN([i32::MAX, i32::MIN, 5])
+        );
+        assert_eq!(
+            p.saturating_add(PointN([1, -1, 0])),
+            PointN([i32::MAX, i32::MIN, 0])
+        );
+    }
+
+    #[test]
+    fn saturating_mul_clamps_at_limits() {
+        let p = PointN([i32::MAX / 2 + 1, i32::MIN / 2 - 1, 3]);
+
+        assert_eq!(
+            p.saturating_mul(PointN([2, 2, 2])),
+            PointN([i32::MAX, i32::MIN, 6])
+        );
+        assert_eq!(
+            p.saturating_mul(PointN([-2, -2, 0])),
+            PointN([i32::MIN, i32::MAX, 0])
+        );
+    }
+
+    #[test]
+    fn voxel_containing_floors_negative_coordinates() {
+        assert_eq!(
+            PointN([0.5f32, 0.0, 1.99]).voxel_containing(),
+            PointN([0, 0, 1])
+        );
+        assert_eq!(
+            PointN([-0.5f32, -1.0, -0.01]).voxel_containing(),
+            PointN([-1, -1, -1])
+        );
+        assert_eq!(
+            PointN([-1.5f32, 2.5, -2.0]).voxel_containing(),
+            PointN([-2, 2, -2])
+        );
+    }
+
+    #[test]
+    fn voxel_center_is_contained_by_voxel() {
+        for &p in [Point3i::ZERO, PointN([-1, 2, -3])].iter() {
+            let center: Point3f = p.center();
+            assert_eq!(center, Point3f::from(p) + PointN([0.5; 3]));
+            assert_eq!(center.voxel_containing(), p);
+        }
+        assert_eq!(PointN([-1i32, 0, 1]).center(), PointN([-0.5, 0.5, 1.5]));
+    }
+}
@@ -172,10 +172,30 @@ impl Point3i {
     ];
 }
 
+impl Point3i {
+    /// Returns the center of the voxel at `self`.
+    ///
+    /// Voxel `p` covers the half-open box `[p, p + 1)`, so its center is `p + 0.5`. This is the inverse of
+    /// `Point3f::voxel_containing` for any point in that box.
+    #[inline]
+    pub fn center(&self) -> Point3f {
+        Point3f::from(*self) + PointN([0.5; 3])
+    }
+}
+
 impl Point3f {
-    /// Returns the coordinates of the voxel containing `self`.
+    /// Returns the coordinates of the voxel containing `self`. Same as `voxel_containing`.
     #[inline]
     pub fn in_voxel(self) -> Point3i {
+        self.voxel_containing()
+    }
+
+    /// Returns the coordinates of the voxel containing `self`.
+    ///
+    /// Voxel `p` covers the half-open box `[p, p + 1)`, so this floors each coordinate. Note that this differs from casting
+    /// (truncation) for negative coordinates, e.g. `-0.5` is in voxel `-1`, not `0`.
+    #[inline]
+    pub fn voxel_containing(&self) -> Point3i {
         self.floor_int()
     }
 }
@@ -156,16 +156,38 @@ pub trait FloatPoint<N>: IntoIntegerPoint + Point<Scalar = f32> {
 
     fn fract(self) -> Self;
 
-    /// Ensures that you floor before casting to integers, since this is not the default behavior for negative integers.
+    /// Ensures that you floor before casting to integers, since this is not the default behavior for negative integers. Each
+    /// component is rounded towards negative infinity, e.g. `-0.5` becomes `-1`.
     #[inline]
     fn floor_int(self) -> Self::IntPoint {
         self.floor().into_int()
     }
+
+    /// Same as `floor_int`. Each component is rounded towards negative infinity, e.g. `-0.5` becomes `-1`.
+    #[inline]
+    fn floor_to_int(self) -> Self::IntPoint {
+        self.floor_int()
+    }
+
+    /// Rounds each component towards positive infinity before casting to integers, e.g. `-0.5` becomes `0`.
+    #[inline]
+    fn ceil_to_int(self) -> Self::IntPoint {
+        self.ceil().into_int()
+    }
+
+    /// Rounds each component to the nearest integer before casting to integers. Halfway cases are rounded away from zero,
+    /// e.g. `-0.5` becomes `-1` and `0.5` becomes `1`.
+    #[inline]
+    fn round_to_int(self) -> Self::IntPoint {
+        self.round().into_int()
+    }
 }
 
 pub trait IntoIntegerPoint {
     type IntPoint;
 
+    /// Casts each component to an integer, which truncates towards zero, e.g. `-0.5` becomes `0`. Use `FloatPoint::floor_int`
+    /// to get the integer point of the voxel containing a point.
     fn into_int(self) -> Self::IntPoint;
 }
 
@@ -0,0 +1,101 @@
+use crate::PosNormMesh;
+
+use bevy_render::{
+    mesh::{Indices, Mesh, VertexAttributeValues},
+    pipeline::PrimitiveTopology,
+};
+
+/// Converts `mesh` into a `TriangleList` Bevy `Mesh` with position, normal, and index data.
+///
+/// The normals are normalized first, since Bevy might not normalize them in the vertex shader (before interpolation). All of
+/// the UVs are set to zero, because Bevy's PBR pipeline requires the `Mesh::ATTRIBUTE_UV_0` attribute.
+pub fn to_bevy_mesh(mesh: &PosNormMesh) -> Mesh {
+    assert_eq!(mesh.positions.len(), mesh.normals.len());
+    let num_vertices = mesh.positions.len();
+
+    let normals: Vec<[f32; 3]> = mesh
+        .normals
+        .iter()
+        .map(|n| {
+            let norm = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+
+            [n[0] / norm, n[1] / norm, n[2] / norm]
+        })
+        .collect();
+
+    let mut render_mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    render_mesh.set_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        VertexAttributeValues::Float3(mesh.positions.clone()),
+    );
+    render_mesh.set_attribute(
+        Mesh::ATTRIBUTE_NORMAL,
+        VertexAttributeValues::Float3(normals),
+    );
+    render_mesh.set_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        VertexAttributeValues::Float2(vec![[0.0; 2]; num_vertices]),
+    );
+    // Bevy can't create an empty index buffer.
+    if !mesh.indices.is_empty() {
+        render_mesh.set_indices(Some(Indices::U32(mesh.indices.clone())));
+    }
+
+    render_mesh
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bevy_mesh_has_same_number_of_vertices_and_indices() {
+        let mesh = PosNormMesh {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 1.0, 0.0],
+            ],
+            normals: vec![[0.0, 0.0, 2.0]; 4],
+            indices: vec![0, 1, 2, 2, 1, 3],
+        };
+
+        let render_mesh = to_bevy_mesh(&mesh);
+
+        assert_eq!(
+            render_mesh.primitive_topology(),
+            PrimitiveTopology::TriangleList
+        );
+        assert_eq!(render_mesh.count_vertices(), mesh.positions.len());
+        for &name in [
+            Mesh::ATTRIBUTE_POSITION,
+            Mesh::ATTRIBUTE_NORMAL,
+            Mesh::ATTRIBUTE_UV_0,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                render_mesh.attribute(name).unwrap().len(),
+                mesh.positions.len()
+            );
+        }
+        match render_mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float3(normals)) => {
+                assert_eq!(normals[0], [0.0, 0.0, 1.0])
+            }
+            _ => panic!("Normals should be Float3"),
+        }
+        match render_mesh.indices() {
+            Some(Indices::U32(indices)) => assert_eq!(indices, &mesh.indices),
+            _ => panic!("Indices should be U32"),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::{
+    padded_chunk_extent, padding_for,
     quad::{OrientedCubeFace, UnorientedQuad},
-    IsOpaque,
+    IsOpaque, MesherKind, Winding,
 };
 
 use building_blocks_core::{prelude::*, Axis3Permutation};
@@ -48,6 +49,8 @@ pub struct QuadCoordinateConfig {
     /// avoid incorrect texture mirroring. For example, in a right-handed coordinate system with +Y pointing up, you should set
     /// `u_flip_face` to `Axis3::X`, because those faces need their U coordinates to be flipped relative to the other faces.
     pub u_flip_face: Axis3,
+    /// The winding of the triangles generated from the quads.
+    pub winding: Winding,
 }
 
 pub const RIGHT_HANDED_Y_UP_CONFIG: QuadCoordinateConfig = QuadCoordinateConfig {
@@ -62,19 +65,21 @@ pub const RIGHT_HANDED_Y_UP_CONFIG: QuadCoordinateConfig = QuadCoordinateConfig
         OrientedCubeFace::new(1, Axis3Permutation::Zxy),
     ],
     u_flip_face: Axis3::X,
+    winding: Winding::CounterClockwise,
 };
 
 impl QuadCoordinateConfig {
     pub fn quad_groups(self) -> [QuadGroup; 6] {
         let [f0, f1, f2, f3, f4, f5] = self.faces;
+        let w = self.winding;
 
         [
-            QuadGroup::new(f0),
-            QuadGroup::new(f1),
-            QuadGroup::new(f2),
-            QuadGroup::new(f3),
-            QuadGroup::new(f4),
-            QuadGroup::new(f5),
+            QuadGroup::new(f0.with_winding(w)),
+            QuadGroup::new(f1.with_winding(w)),
+            QuadGroup::new(f2.with_winding(w)),
+            QuadGroup::new(f3.with_winding(w)),
+            QuadGroup::new(f4.with_winding(w)),
+            QuadGroup::new(f5.with_winding(w)),
         ]
     }
 }
@@ -112,7 +117,7 @@ impl GreedyQuadsBuffer {
 /// Pads the given chunk extent with exactly the amount of space required for running the
 /// `greedy_quads` algorithm.
 pub fn padded_greedy_quads_chunk_extent(chunk_extent: &Extent3i) -> Extent3i {
-    chunk_extent.padded(1)
+    padded_chunk_extent(chunk_extent, padding_for(MesherKind::GreedyQuads))
 }
 
 /// The "Greedy Meshing" algorithm described by Mikola Lysenko in the [0fps
@@ -468,3 +473,77 @@ impl<T> VoxelMerger<T> {
 }
 
 // TODO: implement a MergeStrategy for voxels with an ambient occlusion value at each vertex
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{test_utilities::triangle_normal, PosNormMesh};
+
+    #[derive(Clone, Copy, Eq, PartialEq)]
+    struct Voxel(bool);
+
+    impl IsEmpty for Voxel {
+        fn is_empty(&self) -> bool {
+            !self.0
+        }
+    }
+
+    impl IsOpaque for Voxel {
+        fn is_opaque(&self) -> bool {
+            true
+        }
+    }
+
+    impl MergeVoxel for Voxel {
+        type VoxelValue = bool;
+
+        fn voxel_merge_value(&self) -> Self::VoxelValue {
+            self.0
+        }
+    }
+
+    fn cube_mesh(config: QuadCoordinateConfig) -> PosNormMesh {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(4));
+        let cube = Extent3i::from_min_and_shape(Point3i::ONES, Point3i::fill(2));
+        let voxels = Array3x1::fill_with(extent, |p| Voxel(cube.contains(p)));
+
+        let mut buffer = GreedyQuadsBuffer::new(extent, config.quad_groups());
+        greedy_quads(&voxels, &extent, &mut buffer);
+
+        let mut mesh = PosNormMesh::default();
+        for group in buffer.quad_groups.iter() {
+            for quad in group.quads.iter() {
+                group.face.add_quad_to_pos_norm_mesh(quad, 1.0, &mut mesh);
+            }
+        }
+
+        mesh
+    }
+
+    #[test]
+    fn clockwise_winding_flips_cube_faces() {
+        let ccw_mesh = cube_mesh(RIGHT_HANDED_Y_UP_CONFIG);
+        let cw_mesh = cube_mesh(QuadCoordinateConfig {
+            winding: Winding::Clockwise,
+            ..RIGHT_HANDED_Y_UP_CONFIG
+        });
+
+        // One quad per face of the cube.
+        assert_eq!(ccw_mesh.indices.len(), 6 * 6);
+        assert_eq!(ccw_mesh.positions, cw_mesh.positions);
+        assert_eq!(ccw_mesh.normals, cw_mesh.normals);
+
+        for (ccw, cw) in ccw_mesh.indices.chunks(3).zip(cw_mesh.indices.chunks(3)) {
+            let normal = PointN(ccw_mesh.normals[ccw[0] as usize]);
+            assert!(triangle_normal(&ccw_mesh.positions, ccw).dot(normal) > 0.0);
+            assert!(triangle_normal(&cw_mesh.positions, cw).dot(normal) < 0.0);
+        }
+    }
+}
@@ -13,22 +13,49 @@
 #![warn(clippy::doc_markdown)]
 #![doc = include_str!("crate_doc.md")]
 
+#[cfg(feature = "bevy")]
+mod bevy_conversions;
 mod greedy_quads;
 mod height_map;
+mod marching_cubes;
+mod normals;
+mod padding;
+#[cfg(feature = "rayon")]
+mod par_mesh;
+mod pool;
 mod quad;
 mod surface_nets;
+mod tangents;
+mod weld;
 
+pub mod simplify;
+pub mod validate;
+
+#[cfg(test)]
+mod test_utilities;
+
+#[cfg(feature = "bevy")]
+pub use bevy_conversions::*;
 pub use greedy_quads::*;
 pub use height_map::*;
+pub use marching_cubes::*;
+pub use normals::*;
+pub use padding::*;
+#[cfg(feature = "rayon")]
+pub use par_mesh::*;
+pub use pool::*;
 pub use quad::*;
 pub use surface_nets::*;
+pub use tangents::*;
+pub use weld::*;
 
 #[derive(Clone, Default)]
 pub struct PosNormMesh {
     pub positions: Vec<[f32; 3]>,
     /// Surface normal vectors. Not guaranteed to be normalized.
     pub normals: Vec<[f32; 3]>,
-    /// All of the triangles in the mesh, wound counter-clockwise (right-hand rule).
+    /// All of the triangles in the mesh, wound counter-clockwise (right-hand rule) unless the mesher was configured with
+    /// `Winding::Clockwise`.
     pub indices: Vec<u32>,
 }
 
@@ -88,7 +115,8 @@ pub struct PosNormTexMesh {
     pub normals: Vec<[f32; 3]>,
     /// Texture coordinates, AKA UVs.
     pub tex_coords: Vec<[f32; 2]>,
-    /// All of the triangles in the mesh, wound counter-clockwise (right-hand rule).
+    /// All of the triangles in the mesh, wound counter-clockwise (right-hand rule) unless the mesher was configured with
+    /// `Winding::Clockwise`.
     pub indices: Vec<u32>,
 }
 
@@ -105,6 +133,30 @@ impl PosNormTexMesh {
     }
 }
 
+/// The order of the vertices of a triangle when viewed from the front, i.e. from the side its normal points to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Winding {
+    CounterClockwise,
+    Clockwise,
+}
+
+impl Default for Winding {
+    fn default() -> Self {
+        Winding::CounterClockwise
+    }
+}
+
+impl Winding {
+    /// Reorders the triangles in `indices`, which must be wound counter-clockwise, to have this winding.
+    pub fn apply_to_triangles(self, indices: &mut [u32]) {
+        if self == Winding::Clockwise {
+            for tri in indices.chunks_exact_mut(3) {
+                tri.swap(1, 2);
+            }
+        }
+    }
+}
+
 pub trait IsOpaque {
     /// Returns `true` if light cannot pass through this voxel.
     fn is_opaque(&self) -> bool;
@@ -0,0 +1,393 @@
+use super::PosNormMesh;
+
+use building_blocks_core::{prelude::*, EDGES_3};
+use building_blocks_storage::dev_prelude::*;
+
+/// Pads the given chunk extent with exactly the amount of space required for running the `marching_cubes` algorithm, such
+/// that there is one cube for every point in the chunk. Meshes of adjacent chunks will then neither overlap nor have gaps.
+pub fn padded_marching_cubes_chunk_extent(chunk_extent: &Extent3i) -> Extent3i {
+    chunk_extent.add_to_shape(Point3i::ONES)
+}
+
+/// The Marching Cubes isosurface meshing algorithm.
+///
+/// Extracts the isosurface at level `iso` from the [signed distance field](https://en.wikipedia.org/wiki/Signed_distance_function)
+/// `sdf`. Values less than `iso` are considered "interior" of the surface volume, and values greater than or equal to `iso`
+/// are considered "exterior." As with `surface_nets`, the set of corners sampled is exactly the set of points in `extent`,
+/// and `sdf` must contain all of those points. Every unit cube whose corners are all in `extent` is triangulated using the
+/// classic 256-case table, with vertices linearly interpolated to the `iso` level along the cube edges.
+///
+/// Vertices are shared by all triangles touching the same lattice edge, so a closed surface yields a watertight mesh. Like
+/// `surface_nets`, lattice point `p` is treated as the center of voxel `p`, so all positions are offset by `0.5`. Normals are
+/// estimated as the gradient of the SDF using central differences, which become one-sided on the boundary of `extent`.
+pub fn marching_cubes<A, T>(sdf: &A, extent: &Extent3i, iso: f32) -> PosNormMesh
+where
+    A: IndexedArray<[i32; 3]> + Get<Point3i, Item = T>,
+    T: SignedDistance,
+{
+    assert!(
+        extent.is_subset_of(sdf.extent()),
+        "{:?} does not contain {:?}; would cause access out-of-bounds",
+        sdf.extent(),
+        extent
+    );
+
+    let mut mesh = PosNormMesh::default();
+    // Maps each lattice edge, as (minimal endpoint, axis), to its vertex index.
+    let mut edge_vertices = SmallKeyHashMap::default();
+
+    let cube_extent = extent.add_to_shape(Point3i::fill(-1));
+    for p in cube_extent.iter_points() {
+        let mut corner_dists = [0.0; 8];
+        let mut case = 0;
+        for (i, dist) in corner_dists.iter_mut().enumerate() {
+            *dist = sdf.get(p + Point3i::CUBE_CORNER_OFFSETS[i]).into();
+            if *dist < iso {
+                case |= 1 << i;
+            }
+        }
+
+        for &edge in TRIANGLE_TABLE[case].iter().take_while(|&&e| e >= 0) {
+            let [c1, c2] = EDGES_3[edge as usize];
+            let p1 = p + Point3i::CUBE_CORNER_OFFSETS[c1];
+            let axis = (c1 ^ c2).trailing_zeros();
+            let index = *edge_vertices.entry((p1, axis)).or_insert_with(|| {
+                let p2 = p + Point3i::CUBE_CORNER_OFFSETS[c2];
+                let (d1, d2) = (corner_dists[c1], corner_dists[c2]);
+                let t = (iso - d1) / (d2 - d1);
+
+                let position = Point3f::from(p1) + t * Point3f::from(p2 - p1) + Point3f::fill(0.5);
+                let g1 = lattice_gradient(sdf, extent, p1);
+                let g2 = lattice_gradient(sdf, extent, p2);
+                let normal = g1 + t * (g2 - g1);
+
+                mesh.positions.push(position.0);
+                mesh.normals.push(normal.0);
+
+                mesh.positions.len() as u32 - 1
+            });
+            mesh.indices.push(index);
+        }
+    }
+
+    mesh
+}
+
+// Central differences of the SDF at lattice point `p`, falling back to forward or backward differences on the boundary.
+fn lattice_gradient<A, T>(sdf: &A, extent: &Extent3i, p: Point3i) -> Point3f
+where
+    A: Get<Point3i, Item = T>,
+    T: SignedDistance,
+{
+    let min = extent.minimum;
+    let max = extent.max();
+
+    let mut gradient = Point3f::ZERO;
+    for (i, unit) in Point3i::basis().into_iter().enumerate() {
+        let lo = (p - unit).join(min);
+        let hi = (p + unit).meet(max);
+        let (d_lo, d_hi): (f32, f32) = (sdf.get(lo).into(), sdf.get(hi).into());
+        gradient.0[i] = (d_hi - d_lo) / (hi - lo).at(i) as f32;
+    }
+
+    gradient
+}
+
+// For each of the 256 cases of inside (bit set) and outside corners, the `EDGES_3` crossed by each triangle, terminated by -1.
+// Triangles are wound counter-clockwise when viewed from the outside.
+//
+// Ambiguous cube faces are always resolved by separating the interior corners, so that adjacent cubes agree on the face and
+// the surface has no cracks.
+#[rustfmt::skip]
+const TRIANGLE_TABLE: [[i8; 16]; 256] = [
+    [-1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [4, 3, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 4, 1, 4, 3, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 4, 6, 4, 3, 6, 3, 5, -1, -1, -1, -1, -1, -1, -1],
+    [3, 7, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, 3, 7, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [4, 7, 5, 4, 5, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 4, 1, 4, 7, 1, 7, 5, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 3, 6, 3, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 3, 6, 3, 7, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 0, 6, 0, 4, 6, 4, 7, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 4, 6, 4, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 8, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 4, 1, 4, 3, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 2, 9, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 9, 8, 6, 8, 0, 6, 0, 5, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 2, 9, 8, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [6, 9, 8, 6, 8, 4, 6, 4, 3, 6, 3, 5, -1, -1, -1, -1],
+    [2, 9, 8, 3, 7, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 0, 3, 7, 5, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 8, 4, 7, 5, 4, 5, 0, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 4, 1, 4, 7, 1, 7, 5, -1, -1, -1, -1],
+    [6, 1, 3, 6, 3, 7, 2, 9, 8, -1, -1, -1, -1, -1, -1, -1],
+    [6, 9, 8, 6, 8, 0, 6, 0, 3, 6, 3, 7, -1, -1, -1, -1],
+    [6, 1, 0, 6, 0, 4, 6, 4, 7, 2, 9, 8, -1, -1, -1, -1],
+    [6, 9, 8, 6, 8, 4, 6, 4, 7, -1, -1, -1, -1, -1, -1, -1],
+    [10, 4, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [10, 3, 0, 10, 0, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 8, 1, 8, 10, 1, 10, 3, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 5, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 10, 3, 0, 10, 0, 8, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 8, 6, 8, 10, 6, 10, 3, 6, 3, 5, -1, -1, -1, -1],
+    [3, 7, 5, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, 3, 7, 5, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [10, 7, 5, 10, 5, 0, 10, 0, 8, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 8, 1, 8, 10, 1, 10, 7, 1, 7, 5, -1, -1, -1, -1],
+    [6, 1, 3, 6, 3, 7, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 3, 6, 3, 7, 10, 4, 8, -1, -1, -1, -1],
+    [6, 1, 0, 6, 0, 8, 6, 8, 10, 6, 10, 7, -1, -1, -1, -1],
+    [6, 2, 8, 6, 8, 10, 6, 10, 7, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 10, 2, 10, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 10, 1, 10, 4, 1, 4, 0, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 10, 2, 10, 3, 2, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 10, 1, 10, 3, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 2, 9, 10, 2, 10, 4, -1, -1, -1, -1, -1, -1, -1],
+    [6, 9, 10, 6, 10, 4, 6, 4, 0, 6, 0, 5, -1, -1, -1, -1],
+    [6, 1, 5, 2, 9, 10, 2, 10, 3, 2, 3, 0, -1, -1, -1, -1],
+    [6, 9, 10, 6, 10, 3, 6, 3, 5, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 10, 2, 10, 4, 3, 7, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 10, 1, 10, 4, 1, 4, 0, 3, 7, 5, -1, -1, -1, -1],
+    [2, 9, 10, 2, 10, 7, 2, 7, 5, 2, 5, 0, -1, -1, -1, -1],
+    [1, 9, 10, 1, 10, 7, 1, 7, 5, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 3, 6, 3, 7, 2, 9, 10, 2, 10, 4, -1, -1, -1, -1],
+    [6, 9, 10, 6, 10, 4, 6, 4, 0, 6, 0, 3, 6, 3, 7, -1],
+    [6, 1, 0, 6, 0, 2, 6, 2, 9, 6, 9, 10, 6, 10, 7, -1],
+    [6, 9, 10, 6, 10, 7, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 4, 1, 4, 3, -1, -1, -1, -1, -1, -1, -1],
+    [9, 1, 5, 9, 5, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 5, 9, 5, 11, -1, -1, -1, -1, -1, -1, -1],
+    [9, 1, 5, 9, 5, 11, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 4, 9, 4, 3, 9, 3, 5, 9, 5, 11, -1, -1, -1, -1],
+    [9, 6, 11, 3, 7, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 0, 3, 7, 5, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 4, 7, 5, 4, 5, 0, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 4, 1, 4, 7, 1, 7, 5, -1, -1, -1, -1],
+    [9, 1, 3, 9, 3, 7, 9, 7, 11, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 3, 9, 3, 7, 9, 7, 11, -1, -1, -1, -1],
+    [9, 1, 0, 9, 0, 4, 9, 4, 7, 9, 7, 11, -1, -1, -1, -1],
+    [9, 2, 4, 9, 4, 7, 9, 7, 11, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 11, 2, 11, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 11, 1, 11, 8, 1, 8, 0, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 11, 2, 11, 8, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 11, 1, 11, 8, 1, 8, 4, 1, 4, 3, -1, -1, -1, -1],
+    [2, 1, 5, 2, 5, 11, 2, 11, 8, -1, -1, -1, -1, -1, -1, -1],
+    [8, 0, 5, 8, 5, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 5, 2, 5, 11, 2, 11, 8, 4, 3, 0, -1, -1, -1, -1],
+    [4, 3, 5, 4, 5, 11, 4, 11, 8, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 11, 2, 11, 8, 3, 7, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 11, 1, 11, 8, 1, 8, 0, 3, 7, 5, -1, -1, -1, -1],
+    [2, 6, 11, 2, 11, 8, 4, 7, 5, 4, 5, 0, -1, -1, -1, -1],
+    [1, 6, 11, 1, 11, 8, 1, 8, 4, 1, 4, 7, 1, 7, 5, -1],
+    [2, 1, 3, 2, 3, 7, 2, 7, 11, 2, 11, 8, -1, -1, -1, -1],
+    [3, 7, 11, 3, 11, 8, 3, 8, 0, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 0, 2, 0, 4, 2, 4, 7, 2, 7, 11, 2, 11, 8, -1],
+    [4, 7, 11, 4, 11, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 0, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 10, 3, 0, 10, 0, 8, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 8, 1, 8, 10, 1, 10, 3, -1, -1, -1, -1],
+    [9, 1, 5, 9, 5, 11, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 5, 9, 5, 11, 10, 4, 8, -1, -1, -1, -1],
+    [9, 1, 5, 9, 5, 11, 10, 3, 0, 10, 0, 8, -1, -1, -1, -1],
+    [9, 2, 8, 9, 8, 10, 9, 10, 3, 9, 3, 5, 9, 5, 11, -1],
+    [9, 6, 11, 3, 7, 5, 10, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 0, 3, 7, 5, 10, 4, 8, -1, -1, -1, -1],
+    [9, 6, 11, 10, 7, 5, 10, 5, 0, 10, 0, 8, -1, -1, -1, -1],
+    [9, 6, 11, 1, 2, 8, 1, 8, 10, 1, 10, 7, 1, 7, 5, -1],
+    [9, 1, 3, 9, 3, 7, 9, 7, 11, 10, 4, 8, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 3, 9, 3, 7, 9, 7, 11, 10, 4, 8, -1],
+    [9, 1, 0, 9, 0, 8, 9, 8, 10, 9, 10, 7, 9, 7, 11, -1],
+    [9, 2, 8, 9, 8, 10, 9, 10, 7, 9, 7, 11, -1, -1, -1, -1],
+    [2, 6, 11, 2, 11, 10, 2, 10, 4, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 11, 1, 11, 10, 1, 10, 4, 1, 4, 0, -1, -1, -1, -1],
+    [2, 6, 11, 2, 11, 10, 2, 10, 3, 2, 3, 0, -1, -1, -1, -1],
+    [1, 6, 11, 1, 11, 10, 1, 10, 3, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 5, 2, 5, 11, 2, 11, 10, 2, 10, 4, -1, -1, -1, -1],
+    [10, 4, 0, 10, 0, 5, 10, 5, 11, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 5, 2, 5, 11, 2, 11, 10, 2, 10, 3, 2, 3, 0, -1],
+    [10, 3, 5, 10, 5, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 11, 2, 11, 10, 2, 10, 4, 3, 7, 5, -1, -1, -1, -1],
+    [1, 6, 11, 1, 11, 10, 1, 10, 4, 1, 4, 0, 3, 7, 5, -1],
+    [2, 6, 11, 2, 11, 10, 2, 10, 7, 2, 7, 5, 2, 5, 0, -1],
+    [1, 6, 11, 1, 11, 10, 1, 10, 7, 1, 7, 5, -1, -1, -1, -1],
+    [2, 1, 3, 2, 3, 7, 2, 7, 11, 2, 11, 10, 2, 10, 4, -1],
+    [3, 7, 11, 3, 11, 10, 3, 10, 4, 3, 4, 0, -1, -1, -1, -1],
+    [2, 1, 0, 10, 7, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [10, 7, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [7, 10, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, 7, 10, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [7, 10, 11, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 4, 1, 4, 3, 7, 10, 11, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 7, 10, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 5, 7, 10, 11, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 7, 10, 11, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 4, 6, 4, 3, 6, 3, 5, 7, 10, 11, -1, -1, -1, -1],
+    [3, 10, 11, 3, 11, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, 3, 10, 11, 3, 11, 5, -1, -1, -1, -1, -1, -1, -1],
+    [4, 10, 11, 4, 11, 5, 4, 5, 0, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 4, 1, 4, 10, 1, 10, 11, 1, 11, 5, -1, -1, -1, -1],
+    [6, 1, 3, 6, 3, 10, 6, 10, 11, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 3, 6, 3, 10, 6, 10, 11, -1, -1, -1, -1],
+    [6, 1, 0, 6, 0, 4, 6, 4, 10, 6, 10, 11, -1, -1, -1, -1],
+    [6, 2, 4, 6, 4, 10, 6, 10, 11, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 8, 7, 10, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 0, 7, 10, 11, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 8, 7, 10, 11, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 4, 1, 4, 3, 7, 10, 11, -1, -1, -1, -1],
+    [6, 1, 5, 2, 9, 8, 7, 10, 11, -1, -1, -1, -1, -1, -1, -1],
+    [6, 9, 8, 6, 8, 0, 6, 0, 5, 7, 10, 11, -1, -1, -1, -1],
+    [6, 1, 5, 2, 9, 8, 7, 10, 11, 4, 3, 0, -1, -1, -1, -1],
+    [6, 9, 8, 6, 8, 4, 6, 4, 3, 6, 3, 5, 7, 10, 11, -1],
+    [2, 9, 8, 3, 10, 11, 3, 11, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 0, 3, 10, 11, 3, 11, 5, -1, -1, -1, -1],
+    [2, 9, 8, 4, 10, 11, 4, 11, 5, 4, 5, 0, -1, -1, -1, -1],
+    [1, 9, 8, 1, 8, 4, 1, 4, 10, 1, 10, 11, 1, 11, 5, -1],
+    [6, 1, 3, 6, 3, 10, 6, 10, 11, 2, 9, 8, -1, -1, -1, -1],
+    [6, 9, 8, 6, 8, 0, 6, 0, 3, 6, 3, 10, 6, 10, 11, -1],
+    [6, 1, 0, 6, 0, 4, 6, 4, 10, 6, 10, 11, 2, 9, 8, -1],
+    [6, 9, 8, 6, 8, 4, 6, 4, 10, 6, 10, 11, -1, -1, -1, -1],
+    [7, 4, 8, 7, 8, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, 7, 4, 8, 7, 8, 11, -1, -1, -1, -1, -1, -1, -1],
+    [7, 3, 0, 7, 0, 8, 7, 8, 11, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 8, 1, 8, 11, 1, 11, 7, 1, 7, 3, -1, -1, -1, -1],
+    [6, 1, 5, 7, 4, 8, 7, 8, 11, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 5, 7, 4, 8, 7, 8, 11, -1, -1, -1, -1],
+    [6, 1, 5, 7, 3, 0, 7, 0, 8, 7, 8, 11, -1, -1, -1, -1],
+    [6, 2, 8, 6, 8, 11, 6, 11, 7, 6, 7, 3, 6, 3, 5, -1],
+    [3, 4, 8, 3, 8, 11, 3, 11, 5, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 0, 3, 4, 8, 3, 8, 11, 3, 11, 5, -1, -1, -1, -1],
+    [0, 8, 11, 0, 11, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 2, 8, 1, 8, 11, 1, 11, 5, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 3, 6, 3, 4, 6, 4, 8, 6, 8, 11, -1, -1, -1, -1],
+    [6, 2, 0, 6, 0, 3, 6, 3, 4, 6, 4, 8, 6, 8, 11, -1],
+    [6, 1, 0, 6, 0, 8, 6, 8, 11, -1, -1, -1, -1, -1, -1, -1],
+    [6, 2, 8, 6, 8, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 9, 11, 2, 11, 7, 2, 7, 4, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 11, 1, 11, 7, 1, 7, 4, 1, 4, 0, -1, -1, -1, -1],
+    [2, 9, 11, 2, 11, 7, 2, 7, 3, 2, 3, 0, -1, -1, -1, -1],
+    [1, 9, 11, 1, 11, 7, 1, 7, 3, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 5, 2, 9, 11, 2, 11, 7, 2, 7, 4, -1, -1, -1, -1],
+    [6, 9, 11, 6, 11, 7, 6, 7, 4, 6, 4, 0, 6, 0, 5, -1],
+    [6, 1, 5, 2, 9, 11, 2, 11, 7, 2, 7, 3, 2, 3, 0, -1],
+    [6, 9, 11, 6, 11, 7, 6, 7, 3, 6, 3, 5, -1, -1, -1, -1],
+    [2, 9, 11, 2, 11, 5, 2, 5, 3, 2, 3, 4, -1, -1, -1, -1],
+    [1, 9, 11, 1, 11, 5, 1, 5, 3, 1, 3, 4, 1, 4, 0, -1],
+    [2, 9, 11, 2, 11, 5, 2, 5, 0, -1, -1, -1, -1, -1, -1, -1],
+    [1, 9, 11, 1, 11, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 3, 6, 3, 4, 6, 4, 2, 6, 2, 9, 6, 9, 11, -1],
+    [6, 9, 11, 3, 4, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [6, 1, 0, 6, 0, 2, 6, 2, 9, 6, 9, 11, -1, -1, -1, -1],
+    [6, 9, 11, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 10, 1, 2, 0, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 10, 4, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 10, 1, 2, 4, 1, 4, 3, -1, -1, -1, -1],
+    [9, 1, 5, 9, 5, 7, 9, 7, 10, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 5, 9, 5, 7, 9, 7, 10, -1, -1, -1, -1],
+    [9, 1, 5, 9, 5, 7, 9, 7, 10, 4, 3, 0, -1, -1, -1, -1],
+    [9, 2, 4, 9, 4, 3, 9, 3, 5, 9, 5, 7, 9, 7, 10, -1],
+    [9, 6, 5, 9, 5, 3, 9, 3, 10, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 5, 9, 5, 3, 9, 3, 10, 1, 2, 0, -1, -1, -1, -1],
+    [9, 6, 5, 9, 5, 0, 9, 0, 4, 9, 4, 10, -1, -1, -1, -1],
+    [9, 6, 5, 9, 5, 1, 9, 1, 2, 9, 2, 4, 9, 4, 10, -1],
+    [9, 1, 3, 9, 3, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 3, 9, 3, 10, -1, -1, -1, -1, -1, -1, -1],
+    [9, 1, 0, 9, 0, 4, 9, 4, 10, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 4, 9, 4, 10, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 7, 2, 7, 10, 2, 10, 8, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 7, 1, 7, 10, 1, 10, 8, 1, 8, 0, -1, -1, -1, -1],
+    [2, 6, 7, 2, 7, 10, 2, 10, 8, 4, 3, 0, -1, -1, -1, -1],
+    [1, 6, 7, 1, 7, 10, 1, 10, 8, 1, 8, 4, 1, 4, 3, -1],
+    [2, 1, 5, 2, 5, 7, 2, 7, 10, 2, 10, 8, -1, -1, -1, -1],
+    [7, 10, 8, 7, 8, 0, 7, 0, 5, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 5, 2, 5, 7, 2, 7, 10, 2, 10, 8, 4, 3, 0, -1],
+    [7, 10, 8, 7, 8, 4, 7, 4, 3, 7, 3, 5, -1, -1, -1, -1],
+    [2, 6, 5, 2, 5, 3, 2, 3, 10, 2, 10, 8, -1, -1, -1, -1],
+    [1, 6, 5, 1, 5, 3, 1, 3, 10, 1, 10, 8, 1, 8, 0, -1],
+    [2, 6, 5, 2, 5, 0, 2, 0, 4, 2, 4, 10, 2, 10, 8, -1],
+    [1, 6, 5, 4, 10, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 3, 2, 3, 10, 2, 10, 8, -1, -1, -1, -1, -1, -1, -1],
+    [3, 10, 8, 3, 8, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 0, 2, 0, 4, 2, 4, 10, 2, 10, 8, -1, -1, -1, -1],
+    [4, 10, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 4, 9, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 4, 9, 4, 8, 1, 2, 0, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 3, 9, 3, 0, 9, 0, 8, -1, -1, -1, -1],
+    [9, 6, 7, 9, 7, 3, 9, 3, 1, 9, 1, 2, 9, 2, 8, -1],
+    [9, 1, 5, 9, 5, 7, 9, 7, 4, 9, 4, 8, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 5, 9, 5, 7, 9, 7, 4, 9, 4, 8, -1],
+    [9, 1, 5, 9, 5, 7, 9, 7, 3, 9, 3, 0, 9, 0, 8, -1],
+    [9, 2, 8, 7, 3, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 5, 9, 5, 3, 9, 3, 4, 9, 4, 8, -1, -1, -1, -1],
+    [9, 6, 5, 9, 5, 3, 9, 3, 4, 9, 4, 8, 1, 2, 0, -1],
+    [9, 6, 5, 9, 5, 0, 9, 0, 8, -1, -1, -1, -1, -1, -1, -1],
+    [9, 6, 5, 9, 5, 1, 9, 1, 2, 9, 2, 8, -1, -1, -1, -1],
+    [9, 1, 3, 9, 3, 4, 9, 4, 8, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 0, 9, 0, 3, 9, 3, 4, 9, 4, 8, -1, -1, -1, -1],
+    [9, 1, 0, 9, 0, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [9, 2, 8, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 7, 2, 7, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 7, 1, 7, 4, 1, 4, 0, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 7, 2, 7, 3, 2, 3, 0, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 7, 1, 7, 3, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 5, 2, 5, 7, 2, 7, 4, -1, -1, -1, -1, -1, -1, -1],
+    [7, 4, 0, 7, 0, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 5, 2, 5, 7, 2, 7, 3, 2, 3, 0, -1, -1, -1, -1],
+    [7, 3, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 6, 5, 2, 5, 3, 2, 3, 4, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 5, 1, 5, 3, 1, 3, 4, 1, 4, 0, -1, -1, -1, -1],
+    [2, 6, 5, 2, 5, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [1, 6, 5, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 3, 2, 3, 4, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [3, 4, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [2, 1, 0, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+    [-1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1, -1],
+];
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::validate::is_watertight;
+
+    #[test]
+    fn sphere_mesh_is_watertight_with_expected_radius() {
+        let radius = 10.0;
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-16), Point3i::fill(32));
+        let sdf = Array3x1::fill_with(extent, |p| {
+            Sd8::from(0.1 * (Point3f::from(p).norm() - radius))
+        });
+
+        let mesh = marching_cubes(&sdf, &extent, 0.0);
+
+        assert!(!mesh.is_empty());
+        assert_eq!(mesh.positions.len(), mesh.normals.len());
+        assert_eq!(is_watertight(&mesh), Ok(()));
+        for (position, normal) in mesh.positions.iter().zip(mesh.normals.iter()) {
+            let p = PointN(*position) - Point3f::fill(0.5);
+            assert!((p.norm() - radius).abs() < 0.25, "{:?}", p);
+            assert!(p.dot(PointN(*normal)) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::PosNormMesh;
+
+/// How the normals of adjacent triangles are weighted when they are averaged into a vertex normal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NormalMode {
+    /// Each triangle is weighted by its area.
+    FaceWeighted,
+    /// Each triangle is weighted by its interior angle at the vertex. This is less sensitive to how a surface is tessellated.
+    AngleWeighted,
+}
+
+/// Replaces the normals of `mesh` with the weighted average of the normals of the triangles touching each vertex.
+///
+/// The resulting normals are normalized, except for vertices that are only touched by degenerate triangles (or no triangles),
+/// which get a zero normal.
+pub fn recompute_normals(mesh: &mut PosNormMesh, mode: NormalMode) {
+    mesh.normals.clear();
+    mesh.normals.resize(mesh.positions.len(), [0.0; 3]);
+
+    for tri in mesh.indices.chunks_exact(3) {
+        let p = [
+            mesh.positions[tri[0] as usize],
+            mesh.positions[tri[1] as usize],
+            mesh.positions[tri[2] as usize],
+        ];
+        // Twice the area, in the direction of the normal.
+        let face_normal = cross(sub(p[1], p[0]), sub(p[2], p[0]));
+        if face_normal == [0.0; 3] {
+            continue;
+        }
+
+        for corner in 0..3 {
+            let weight = match mode {
+                NormalMode::FaceWeighted => 1.0,
+                NormalMode::AngleWeighted => {
+                    let e1 = sub(p[(corner + 1) % 3], p[corner]);
+                    let e2 = sub(p[(corner + 2) % 3], p[corner]);
+
+                    angle_between(e1, e2) / norm(face_normal)
+                }
+            };
+            let n = &mut mesh.normals[tri[corner] as usize];
+            for i in 0..3 {
+                n[i] += weight * face_normal[i];
+            }
+        }
+    }
+
+    for n in mesh.normals.iter_mut() {
+        let len = norm(*n);
+        if len > 0.0 {
+            for c in n.iter_mut() {
+                *c /= len;
+            }
+        }
+    }
+}
+
+fn angle_between(u: [f32; 3], v: [f32; 3]) -> f32 {
+    norm(cross(u, v)).atan2(dot(u, v))
+}
+
+pub(crate) fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub(crate) fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(crate) fn norm(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+pub(crate) fn cross(u: [f32; 3], v: [f32; 3]) -> [f32; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A cube centered at the origin with 4 vertices per face, so that every vertex belongs to exactly one face. Returns the
+    // outward normal of each vertex's face along with the mesh.
+    fn cube_with_wrong_normals() -> (PosNormMesh, Vec<[f32; 3]>) {
+        let mut mesh = PosNormMesh::default();
+        let mut face_normals = Vec::new();
+        for axis in 0..3 {
+            let (u_axis, v_axis) = ((axis + 1) % 3, (axis + 2) % 3);
+            for &sign in [1.0, -1.0].iter() {
+                let mut corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+                if sign < 0.0 {
+                    corners.reverse();
+                }
+
+                let first = mesh.positions.len() as u32;
+                let mut face_normal = [0.0; 3];
+                face_normal[axis] = sign;
+                for &(u, v) in corners.iter() {
+                    let mut p = [0.0; 3];
+                    p[axis] = sign;
+                    p[u_axis] = u;
+                    p[v_axis] = v;
+                    mesh.positions.push(p);
+                    // Deliberately pointing inward.
+                    mesh.normals
+                        .push([-face_normal[0], -face_normal[1], -face_normal[2]]);
+                    face_normals.push(face_normal);
+                }
+                mesh.indices.extend_from_slice(&[
+                    first,
+                    first + 1,
+                    first + 2,
+                    first,
+                    first + 2,
+                    first + 3,
+                ]);
+            }
+        }
+        // A degenerate triangle shouldn't contribute anything.
+        mesh.indices.extend_from_slice(&[0, 0, 1]);
+
+        (mesh, face_normals)
+    }
+
+    #[test]
+    fn recomputed_cube_normals_point_outward() {
+        for &mode in [NormalMode::FaceWeighted, NormalMode::AngleWeighted].iter() {
+            let (mut mesh, face_normals) = cube_with_wrong_normals();
+
+            recompute_normals(&mut mesh, mode);
+
+            assert_eq!(mesh.normals.len(), mesh.positions.len());
+            for (n, expected) in mesh.normals.iter().zip(face_normals.iter()) {
+                for i in 0..3 {
+                    assert!(
+                        (n[i] - expected[i]).abs() < 1e-6,
+                        "{:?} != {:?}",
+                        n,
+                        expected
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use building_blocks_core::prelude::*;
+
+/// The 3D meshing algorithms in this crate that need their input padded by the same amount on both sides of each axis.
+///
+/// `marching_cubes` only pads the maximum side of the chunk, so it's not included here; use
+/// `padded_marching_cubes_chunk_extent` instead.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MesherKind {
+    SurfaceNets,
+    GreedyQuads,
+}
+
+/// The number of voxels that `mesher` needs to read on each side of a chunk, per axis.
+pub fn padding_for(mesher: MesherKind) -> Point3i {
+    match mesher {
+        MesherKind::SurfaceNets | MesherKind::GreedyQuads => Point3i::ONES,
+    }
+}
+
+/// Pads `extent` by `padding` on both the minimum and maximum side of each axis.
+pub fn padded_chunk_extent(extent: &Extent3i, padding: Point3i) -> Extent3i {
+    extent.padded_per_axis(padding)
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{padded_greedy_quads_chunk_extent, padded_surface_nets_chunk_extent};
+
+    #[test]
+    fn padded_extents_grow_by_one_voxel_on_each_side() {
+        let chunk_extent = Extent3i::from_min_and_shape(Point3i::fill(-16), Point3i::fill(16));
+        // The chunk covers [-16, -1] on each axis, so the padded extent covers [-17, 0].
+        let expected = Extent3i::from_min_and_max(Point3i::fill(-17), Point3i::fill(0));
+
+        assert_eq!(padded_surface_nets_chunk_extent(&chunk_extent), expected);
+        assert_eq!(padded_greedy_quads_chunk_extent(&chunk_extent), expected);
+        assert_eq!(
+            padded_chunk_extent(&chunk_extent, PointN([1, 2, 3])),
+            Extent3i::from_min_and_max(PointN([-17, -18, -19]), PointN([0, 1, 2]))
+        );
+    }
+}
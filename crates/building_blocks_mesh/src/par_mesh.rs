@@ -0,0 +1,366 @@
+use super::{
+    greedy_quads, padded_greedy_quads_chunk_extent, padded_surface_nets_chunk_extent,
+    surface_nets_with_config, GreedyQuadsBuffer, IsOpaque, MergeVoxel, PosNormMesh,
+    QuadCoordinateConfig, SurfaceNetsBuffer, SurfaceNetsConfig, RIGHT_HANDED_Y_UP_CONFIG,
+};
+
+use building_blocks_core::prelude::*;
+use building_blocks_storage::{chunk::ChunkCopySrc, prelude::*};
+
+use rayon::prelude::*;
+use std::sync::Mutex;
+
+/// Generates meshes for the chunks of a `Map`, using reusable `Buffers` to avoid reallocating memory for every chunk.
+pub trait ChunkMesher<Map>: Sync {
+    type Buffers;
+
+    /// Allocates a new set of buffers. This is called about once per worker thread, not once per chunk.
+    fn init_buffers(&self, map: &Map) -> Self::Buffers;
+
+    /// Meshes the chunk at `key`, returning `None` if the mesh is empty.
+    fn mesh_chunk(
+        &self,
+        map: &Map,
+        key: ChunkKey3,
+        buffers: &mut Self::Buffers,
+    ) -> Option<PosNormMesh>;
+}
+
+/// Meshes the chunks at `keys` in parallel on the global `rayon` thread pool. The results are in the same order as `keys`.
+///
+/// Each worker thread lazily initializes its own buffers with `ChunkMesher::init_buffers` and reuses them for all of the chunks
+/// it meshes. A chunk meshed off of the pool, or while its thread's buffers are already in use by a nested parallel call, gets
+/// fresh buffers instead.
+pub fn par_mesh_chunks<T, Bldr, Store, M>(
+    map: &ChunkMap3<T, Bldr, Store>,
+    keys: &[ChunkKey3],
+    mesher: &M,
+) -> Vec<(ChunkKey3, Option<PosNormMesh>)>
+where
+    ChunkMap3<T, Bldr, Store>: Sync,
+    M: ChunkMesher<ChunkMap3<T, Bldr, Store>>,
+    M::Buffers: Send,
+{
+    let thread_buffers: Vec<Mutex<Option<M::Buffers>>> = (0..rayon::current_num_threads())
+        .map(|_| Mutex::new(None))
+        .collect();
+
+    keys.par_iter()
+        .map(|&key| {
+            let slot = rayon::current_thread_index()
+                .and_then(|i| thread_buffers.get(i))
+                .and_then(|slot| slot.try_lock().ok());
+            let mesh = match slot {
+                Some(mut slot) => {
+                    let buffers = slot.get_or_insert_with(|| mesher.init_buffers(map));
+                    mesher.mesh_chunk(map, key, buffers)
+                }
+                None => mesher.mesh_chunk(map, key, &mut mesher.init_buffers(map)),
+            };
+
+            (key, mesh)
+        })
+        .collect()
+}
+
+/// A `ChunkMesher` that runs `surface_nets` on each chunk of a single-channel signed distance `ChunkMap`. Each chunk's voxels
+/// are scaled by `2^lod`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SurfaceNetsMesher {
+    pub config: SurfaceNetsConfig,
+}
+
+impl<T, Store> ChunkMesher<ChunkMap3x1<T, Store>> for SurfaceNetsMesher
+where
+    T: Clone + SignedDistance,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Array3x1<T>>,
+    Array3x1<T>: GetUnchecked<Stride, Item = T>
+        + for<'r> WriteExtent<[i32; 3], ChunkCopySrc<[i32; 3], T, &'r Array3x1<T>>>,
+{
+    type Buffers = (SurfaceNetsBuffer, Array3x1<T>);
+
+    fn init_buffers(&self, map: &ChunkMap3x1<T, Store>) -> Self::Buffers {
+        let extent = padded_surface_nets_chunk_extent(&Extent3i::from_min_and_shape(
+            Point3i::ZERO,
+            map.chunk_shape(),
+        ));
+
+        (
+            SurfaceNetsBuffer::default(),
+            Array3x1::fill(extent, map.ambient_value()),
+        )
+    }
+
+    fn mesh_chunk(
+        &self,
+        map: &ChunkMap3x1<T, Store>,
+        key: ChunkKey3,
+        (mesh_buffer, neighborhood_buffer): &mut Self::Buffers,
+    ) -> Option<PosNormMesh> {
+        let chunk_extent = map.indexer.extent_for_chunk_with_min(key.minimum);
+        let padded_chunk_extent = padded_surface_nets_chunk_extent(&chunk_extent);
+        neighborhood_buffer.set_minimum(padded_chunk_extent.minimum);
+        copy_extent(
+            &padded_chunk_extent,
+            &map.lod_view(key.lod),
+            neighborhood_buffer,
+        );
+
+        let voxel_size = (1 << key.lod) as f32;
+        surface_nets_with_config(
+            neighborhood_buffer,
+            &padded_chunk_extent,
+            voxel_size,
+            true,
+            self.config,
+            mesh_buffer,
+        );
+
+        if mesh_buffer.mesh.is_empty() {
+            None
+        } else {
+            Some(mesh_buffer.mesh.clone())
+        }
+    }
+}
+
+/// A `ChunkMesher` that runs `greedy_quads` on each chunk of a single-channel `ChunkMap` and converts the quads into a
+/// `PosNormMesh` using the faces in `config`. Each chunk's voxels are scaled by `2^lod`.
+///
+/// Only the chunk itself is copied into the meshing buffer; the padding is left with the ambient value so that there are no
+/// holes on LOD boundaries. That means faces between two solid chunks are meshed too.
+#[derive(Clone)]
+pub struct GreedyQuadsMesher {
+    pub config: QuadCoordinateConfig,
+}
+
+impl Default for GreedyQuadsMesher {
+    fn default() -> Self {
+        Self {
+            config: RIGHT_HANDED_Y_UP_CONFIG,
+        }
+    }
+}
+
+impl<T, Store> ChunkMesher<ChunkMap3x1<T, Store>> for GreedyQuadsMesher
+where
+    T: Clone + IsEmpty + IsOpaque + MergeVoxel,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Array3x1<T>>,
+    Array3x1<T>: ForEach<[i32; 3], (Point3i, Stride), Item = T>
+        + Get<Stride, Item = T>
+        + FillExtent<[i32; 3], Item = T>
+        + for<'r> WriteExtent<[i32; 3], ChunkCopySrc<[i32; 3], T, &'r Array3x1<T>>>,
+{
+    type Buffers = (GreedyQuadsBuffer, Array3x1<T>);
+
+    fn init_buffers(&self, map: &ChunkMap3x1<T, Store>) -> Self::Buffers {
+        let extent = padded_greedy_quads_chunk_extent(&Extent3i::from_min_and_shape(
+            Point3i::ZERO,
+            map.chunk_shape(),
+        ));
+
+        (
+            GreedyQuadsBuffer::new(extent, self.config.clone().quad_groups()),
+            Array3x1::fill(extent, map.ambient_value()),
+        )
+    }
+
+    fn mesh_chunk(
+        &self,
+        map: &ChunkMap3x1<T, Store>,
+        key: ChunkKey3,
+        (mesh_buffer, neighborhood_buffer): &mut Self::Buffers,
+    ) -> Option<PosNormMesh> {
+        let chunk_extent = map.indexer.extent_for_chunk_with_min(key.minimum);
+        let padded_chunk_extent = padded_greedy_quads_chunk_extent(&chunk_extent);
+
+        // The buffer is reused, so the padding has to be reset from the last chunk.
+        neighborhood_buffer.set_minimum(padded_chunk_extent.minimum);
+        neighborhood_buffer.fill_extent(&padded_chunk_extent, map.ambient_value());
+        copy_extent(&chunk_extent, &map.lod_view(key.lod), neighborhood_buffer);
+
+        greedy_quads(neighborhood_buffer, &padded_chunk_extent, mesh_buffer);
+
+        if mesh_buffer.num_quads() == 0 {
+            return None;
+        }
+
+        let voxel_size = (1 << key.lod) as f32;
+        let mut mesh = PosNormMesh::default();
+        for group in mesh_buffer.quad_groups.iter() {
+            for quad in group.quads.iter() {
+                group
+                    .face
+                    .add_quad_to_pos_norm_mesh(quad, voxel_size, &mut mesh);
+            }
+        }
+
+        Some(mesh)
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::surface_nets;
+
+    struct SphereMesher;
+
+    impl ChunkMesher<ChunkHashMap3x1<f32>> for SphereMesher {
+        type Buffers = (SurfaceNetsBuffer, Array3x1<f32>);
+
+        fn init_buffers(&self, map: &ChunkHashMap3x1<f32>) -> Self::Buffers {
+            let extent = padded_surface_nets_chunk_extent(&Extent3i::from_min_and_shape(
+                Point3i::ZERO,
+                map.chunk_shape(),
+            ));
+
+            (Default::default(), Array3x1::fill(extent, 1.0))
+        }
+
+        fn mesh_chunk(
+            &self,
+            map: &ChunkHashMap3x1<f32>,
+            key: ChunkKey3,
+            (mesh_buffer, neighborhood_buffer): &mut Self::Buffers,
+        ) -> Option<PosNormMesh> {
+            let chunk_extent = map.indexer.extent_for_chunk_with_min(key.minimum);
+            let padded_chunk_extent = padded_surface_nets_chunk_extent(&chunk_extent);
+            neighborhood_buffer.set_minimum(padded_chunk_extent.minimum);
+            copy_extent(
+                &padded_chunk_extent,
+                &map.lod_view(key.lod),
+                neighborhood_buffer,
+            );
+
+            surface_nets(
+                neighborhood_buffer,
+                &padded_chunk_extent,
+                1.0,
+                true,
+                mesh_buffer,
+            );
+
+            if mesh_buffer.mesh.is_empty() {
+                None
+            } else {
+                Some(mesh_buffer.mesh.clone())
+            }
+        }
+    }
+
+    fn sphere_map() -> ChunkHashMap3x1<f32> {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), 1.0);
+        let mut map = builder.build_with_hash_map_storage();
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-32), Point3i::fill(64));
+        map.lod_view_mut(0)
+            .for_each_mut(&extent, |p: Point3i, d: &mut f32| {
+                *d = Point3f::from(p).norm() - 20.0;
+            });
+
+        map
+    }
+
+    #[test]
+    fn parallel_meshes_match_serial_meshes() {
+        let map = sphere_map();
+        let keys: Vec<_> = map.storage().keys().cloned().collect();
+        assert_eq!(keys.len(), 64);
+
+        let par_meshes = par_mesh_chunks(&map, &keys, &SphereMesher);
+
+        let mut buffers = SphereMesher.init_buffers(&map);
+        let num_nonempty = par_meshes.iter().filter(|(_, mesh)| mesh.is_some()).count();
+        assert!(num_nonempty > 0 && num_nonempty < keys.len());
+        for (&key, (par_key, par_mesh)) in keys.iter().zip(par_meshes.iter()) {
+            assert_eq!(key, *par_key);
+            let serial_mesh = SphereMesher.mesh_chunk(&map, key, &mut buffers);
+            match (par_mesh, serial_mesh) {
+                (Some(par_mesh), Some(serial_mesh)) => {
+                    assert_eq!(par_mesh.positions, serial_mesh.positions);
+                    assert_eq!(par_mesh.normals, serial_mesh.normals);
+                    assert_eq!(par_mesh.indices, serial_mesh.indices);
+                }
+                (None, None) => (),
+                _ => panic!("Parallel and serial meshes differ for {:?}", key),
+            }
+        }
+    }
+
+    #[test]
+    fn surface_nets_mesher_matches_hand_written_mesher() {
+        let map = sphere_map();
+        let keys: Vec<_> = map.storage().keys().cloned().collect();
+
+        let builtin_meshes = par_mesh_chunks(&map, &keys, &SurfaceNetsMesher::default());
+
+        let mut buffers = SphereMesher.init_buffers(&map);
+        for (key, builtin_mesh) in builtin_meshes {
+            let expected = SphereMesher.mesh_chunk(&map, key, &mut buffers);
+            assert_eq!(
+                builtin_mesh.map(|m| m.indices),
+                expected.map(|m| m.indices),
+                "{:?}",
+                key
+            );
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    struct Voxel(bool);
+
+    impl IsEmpty for Voxel {
+        fn is_empty(&self) -> bool {
+            !self.0
+        }
+    }
+
+    impl IsOpaque for Voxel {
+        fn is_opaque(&self) -> bool {
+            true
+        }
+    }
+
+    impl MergeVoxel for Voxel {
+        type VoxelValue = bool;
+
+        fn voxel_merge_value(&self) -> Self::VoxelValue {
+            self.0
+        }
+    }
+
+    #[test]
+    fn greedy_quads_mesher_meshes_each_solid_chunk_as_a_cube() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(4), Voxel(false));
+        let mut map = builder.build_with_hash_map_storage();
+        let solid = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(8));
+        map.lod_view_mut(0).fill_extent(&solid, Voxel(true));
+        map.lod_view_mut(0).fill_extent(
+            &Extent3i::from_min_and_shape(Point3i::fill(-4), Point3i::fill(4)),
+            Voxel(false),
+        );
+
+        let keys: Vec<_> = map.storage().keys().cloned().collect();
+        assert_eq!(keys.len(), 9);
+
+        let meshes = par_mesh_chunks(&map, &keys, &GreedyQuadsMesher::default());
+
+        for (key, mesh) in meshes {
+            if key.minimum == Point3i::fill(-4) {
+                assert!(mesh.is_none());
+            } else {
+                // The padding is never copied, so every solid chunk is a closed cube of 6 quads.
+                let mesh = mesh.unwrap();
+                assert_eq!(mesh.positions.len(), 6 * 4);
+                assert_eq!(mesh.indices.len(), 6 * 6);
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// A thread-safe pool of reusable mesh buffers, like `SurfaceNetsBuffer` or `GreedyQuadsBuffer`, so their allocations can be
+/// recycled across chunks and frames without managing a `RefCell` or thread-local storage.
+///
+/// Buffers are returned to the pool as-is, without being cleared. The meshing functions already reset the buffers they are
+/// given, and meshing options like `SurfaceNetsConfig` are passed to each call instead of being stored in the buffer, so
+/// nothing carries over from one acquisition to the next.
+pub struct MeshBufferPool<B> {
+    free_buffers: Mutex<Vec<B>>,
+    new_buffer: Box<dyn Fn() -> B + Send + Sync>,
+}
+
+impl<B: Default> Default for MeshBufferPool<B> {
+    fn default() -> Self {
+        Self::new(B::default)
+    }
+}
+
+impl<B> MeshBufferPool<B> {
+    /// Creates an empty pool, where `new_buffer` is called to create a buffer whenever `acquire` finds the pool empty.
+    /// This is how to pool buffers without a `Default`, like `GreedyQuadsBuffer`, which needs to know its extent.
+    pub fn new(new_buffer: impl Fn() -> B + Send + Sync + 'static) -> Self {
+        Self {
+            free_buffers: Mutex::new(Vec::new()),
+            new_buffer: Box::new(new_buffer),
+        }
+    }
+
+    /// Takes a buffer out of the pool, or creates a new one if the pool is empty. The buffer is returned to the pool when the
+    /// guard is dropped.
+    pub fn acquire(&self) -> PooledMeshBuffer<'_, B> {
+        let buffer = self.free_buffers.lock().unwrap().pop();
+
+        PooledMeshBuffer {
+            buffer: Some(buffer.unwrap_or_else(|| (self.new_buffer)())),
+            pool: self,
+        }
+    }
+
+    /// The number of buffers waiting in the pool to be acquired.
+    pub fn num_free(&self) -> usize {
+        self.free_buffers.lock().unwrap().len()
+    }
+}
+
+/// A buffer borrowed from a `MeshBufferPool`. Returns the buffer to the pool on drop.
+pub struct PooledMeshBuffer<'a, B> {
+    // Only `None` after the buffer has been returned on drop.
+    buffer: Option<B>,
+    pool: &'a MeshBufferPool<B>,
+}
+
+impl<'a, B> Deref for PooledMeshBuffer<'a, B> {
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        self.buffer.as_ref().unwrap()
+    }
+}
+
+impl<'a, B> DerefMut for PooledMeshBuffer<'a, B> {
+    fn deref_mut(&mut self) -> &mut B {
+        self.buffer.as_mut().unwrap()
+    }
+}
+
+impl<'a, B> Drop for PooledMeshBuffer<'a, B> {
+    fn drop(&mut self) {
+        if let Some(buffer) = self.buffer.take() {
+            self.pool.free_buffers.lock().unwrap().push(buffer);
+        }
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        surface_nets, surface_nets_with_config, SurfaceNetsBuffer, SurfaceNetsConfig, Winding,
+    };
+
+    use building_blocks_core::prelude::*;
+    use building_blocks_storage::prelude::*;
+
+    #[test]
+    fn released_buffer_allocation_is_reused() {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-8), Point3i::fill(16));
+        let sdf = Array3x1::fill_with(extent, |p| p.norm() - 5.0);
+
+        let pool = MeshBufferPool::<SurfaceNetsBuffer>::default();
+        let positions_ptr = {
+            let mut buffer = pool.acquire();
+            surface_nets(&sdf, &extent, 1.0, true, &mut buffer);
+            assert!(!buffer.mesh.positions.is_empty());
+            assert_eq!(pool.num_free(), 0);
+
+            buffer.mesh.positions.as_ptr()
+        };
+        assert_eq!(pool.num_free(), 1);
+
+        let mut buffer = pool.acquire();
+        assert_eq!(pool.num_free(), 0);
+        assert_eq!(buffer.mesh.positions.as_ptr(), positions_ptr);
+
+        // The same mesh fits in the recycled allocation.
+        surface_nets(&sdf, &extent, 1.0, true, &mut buffer);
+        assert_eq!(buffer.mesh.positions.as_ptr(), positions_ptr);
+
+        // A second concurrent acquisition needs a new buffer.
+        let other_buffer = pool.acquire();
+        assert!(other_buffer.mesh.positions.is_empty());
+        drop(buffer);
+        drop(other_buffer);
+        assert_eq!(pool.num_free(), 2);
+    }
+
+    #[test]
+    fn config_does_not_leak_through_pool() {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-8), Point3i::fill(16));
+        let sdf = Array3x1::fill_with(extent, |p| p.norm() - 5.0);
+
+        let mut fresh_buffer = SurfaceNetsBuffer::default();
+        surface_nets(&sdf, &extent, 1.0, true, &mut fresh_buffer);
+
+        let pool = MeshBufferPool::<SurfaceNetsBuffer>::default();
+        {
+            let mut buffer = pool.acquire();
+            let config = SurfaceNetsConfig {
+                weld_distance: 1.5,
+                winding: Winding::Clockwise,
+                ..Default::default()
+            };
+            surface_nets_with_config(&sdf, &extent, 1.0, true, config, &mut buffer);
+            assert_ne!(buffer.mesh.indices, fresh_buffer.mesh.indices);
+        }
+        assert_eq!(pool.num_free(), 1);
+
+        let mut buffer = pool.acquire();
+        surface_nets(&sdf, &extent, 1.0, true, &mut buffer);
+        assert_eq!(buffer.mesh.positions, fresh_buffer.mesh.positions);
+        assert_eq!(buffer.mesh.normals, fresh_buffer.mesh.normals);
+        assert_eq!(buffer.mesh.indices, fresh_buffer.mesh.indices);
+    }
+}
@@ -1,4 +1,4 @@
-use super::{PosNormMesh, PosNormTexMesh};
+use super::{PosNormMesh, PosNormTexMesh, Winding};
 
 use building_blocks_core::{prelude::*, Axis3Permutation, SignedAxis3};
 
@@ -17,6 +17,9 @@ pub struct OrientedCubeFace {
     pub u: Point3i,
     /// Third in the `permutation` of +X, +Y, and +Z.
     pub v: Point3i,
+
+    /// The winding of the triangles generated by `quad_mesh_indices`. Set with `with_winding`.
+    winding: Winding,
 }
 
 impl OrientedCubeFace {
@@ -29,9 +32,20 @@ impl OrientedCubeFace {
             n: n_axis.get_unit_vector(),
             u: u_axis.get_unit_vector(),
             v: v_axis.get_unit_vector(),
+            winding: Winding::CounterClockwise,
         }
     }
 
+    /// The same face, but generating triangles with `winding`.
+    pub fn with_winding(self, winding: Winding) -> Self {
+        Self { winding, ..self }
+    }
+
+    /// The winding of the triangles generated by `quad_mesh_indices`.
+    pub fn winding(&self) -> Winding {
+        self.winding
+    }
+
     /// A cube face, using axes with an even permutation.
     pub fn canonical(normal: SignedAxis3) -> Self {
         Self::new(
@@ -106,10 +120,16 @@ impl OrientedCubeFace {
         [self.mesh_normal().0; 4]
     }
 
-    /// Returns the 6 vertex indices for the quad in order to make two triangles in a mesh. Winding order depends on both the
-    /// sign of the surface normal and the permutation of the UVs.
+    /// Returns the 6 vertex indices for the quad in order to make two triangles in a mesh. The order of the vertices depends on
+    /// the sign of the surface normal and the permutation of the UVs, so the triangles have `self.winding` when viewed from
+    /// the front.
     pub fn quad_mesh_indices(&self, start: u32) -> [u32; 6] {
-        quad_indices(start, self.n_sign * self.permutation.sign() > 0)
+        let corners_are_counter_clockwise = self.n_sign * self.permutation.sign() > 0;
+
+        quad_indices(
+            start,
+            corners_are_counter_clockwise == (self.winding == Winding::CounterClockwise),
+        )
     }
 
     /// Returns the UV coordinates of the 4 corners of the quad. Returns vertices in the same order as
@@ -0,0 +1,516 @@
+//! Mesh simplification by edge collapse.
+
+use super::PosNormMesh;
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Reduces the number of triangles in `mesh` to about `target_ratio` times the original count. `target_ratio` is clamped to
+/// `[0, 1]`.
+///
+/// This repeatedly collapses the edge with the least [quadric error](https://www.cs.cmu.edu/~garland/Papers/quadrics.pdf).
+/// Edges with both ends on the boundary of the mesh are never collapsed, and vertices on the boundary never move, so the
+/// boundary is preserved exactly. Collapses that would flip a triangle or make the mesh non-manifold are also skipped, which
+/// means the target might not be reached.
+///
+/// Each remaining vertex keeps the normal it had in `mesh`, so consider calling `recompute_normals` afterwards. Vertices that
+/// are no longer referenced by any triangle are dropped.
+pub fn decimate(mesh: &PosNormMesh, target_ratio: f32) -> PosNormMesh {
+    let target_ratio = target_ratio.clamp(0.0, 1.0);
+
+    let mut decimation = Decimation::new(mesh);
+    let target_triangles = (decimation.num_triangles as f32 * target_ratio).round() as usize;
+    decimation.run(target_triangles);
+
+    decimation.into_mesh(mesh)
+}
+
+struct Decimation {
+    positions: Vec<[f64; 3]>,
+    quadrics: Vec<Quadric>,
+    // `None` once a triangle has collapsed.
+    triangles: Vec<Option<[u32; 3]>>,
+    vertex_triangles: Vec<Vec<usize>>,
+    is_boundary: Vec<bool>,
+    is_alive: Vec<bool>,
+    // Bumped whenever a vertex changes, so that stale candidates can be skipped.
+    versions: Vec<u32>,
+    num_triangles: usize,
+    candidates: BinaryHeap<Candidate>,
+}
+
+impl Decimation {
+    fn new(mesh: &PosNormMesh) -> Self {
+        let num_vertices = mesh.positions.len();
+        let positions: Vec<[f64; 3]> = mesh
+            .positions
+            .iter()
+            .map(|p| [p[0] as f64, p[1] as f64, p[2] as f64])
+            .collect();
+
+        let mut quadrics = vec![Quadric::default(); num_vertices];
+        let mut triangles = Vec::with_capacity(mesh.indices.len() / 3);
+        let mut vertex_triangles = vec![Vec::new(); num_vertices];
+        let mut edge_counts = HashMap::new();
+        let mut num_triangles = 0;
+        for tri in mesh.indices.chunks_exact(3) {
+            let tri = [tri[0], tri[1], tri[2]];
+            if tri[0] == tri[1] || tri[1] == tri[2] || tri[2] == tri[0] {
+                triangles.push(None);
+                continue;
+            }
+
+            if let Some(plane) = triangle_plane(triangle_positions(tri, &positions)) {
+                let q = Quadric::from_plane(plane);
+                for &v in tri.iter() {
+                    quadrics[v as usize] = quadrics[v as usize].add(&q);
+                }
+            }
+            for i in 0..3 {
+                vertex_triangles[tri[i] as usize].push(triangles.len());
+                *edge_counts
+                    .entry(sorted_edge(tri[i], tri[(i + 1) % 3]))
+                    .or_insert(0) += 1;
+            }
+            triangles.push(Some(tri));
+            num_triangles += 1;
+        }
+
+        let mut is_boundary = vec![false; num_vertices];
+        for (&(a, b), &count) in edge_counts.iter() {
+            if count == 1 {
+                is_boundary[a as usize] = true;
+                is_boundary[b as usize] = true;
+            }
+        }
+
+        let mut decimation = Self {
+            positions,
+            quadrics,
+            triangles,
+            vertex_triangles,
+            is_boundary,
+            is_alive: vec![true; num_vertices],
+            versions: vec![0; num_vertices],
+            num_triangles,
+            candidates: BinaryHeap::new(),
+        };
+        for &(a, b) in edge_counts.keys() {
+            decimation.push_candidate(a, b);
+        }
+
+        decimation
+    }
+
+    fn push_candidate(&mut self, a: u32, b: u32) {
+        let (keep, remove) = match (self.is_boundary[a as usize], self.is_boundary[b as usize]) {
+            (true, true) => return,
+            (false, true) => (b, a),
+            _ => (a, b),
+        };
+
+        let q = self.quadrics[keep as usize].add(&self.quadrics[remove as usize]);
+        let keep_pos = self.positions[keep as usize];
+        let remove_pos = self.positions[remove as usize];
+        let target = if self.is_boundary[keep as usize] {
+            keep_pos
+        } else {
+            q.minimizer().unwrap_or_else(|| {
+                let midpoint = lerp(keep_pos, remove_pos, 0.5);
+                *[midpoint, keep_pos, remove_pos]
+                    .iter()
+                    .min_by(|p1, p2| compare_costs(q.error(**p1), q.error(**p2)))
+                    .unwrap()
+            })
+        };
+
+        self.candidates.push(Candidate {
+            cost: q.error(target),
+            keep,
+            remove,
+            keep_version: self.versions[keep as usize],
+            remove_version: self.versions[remove as usize],
+            target,
+        });
+    }
+
+    fn run(&mut self, target_triangles: usize) {
+        while self.num_triangles > target_triangles {
+            let candidate = match self.candidates.pop() {
+                Some(c) => c,
+                None => break,
+            };
+
+            let (keep, remove) = (candidate.keep as usize, candidate.remove as usize);
+            if !self.is_alive[keep]
+                || !self.is_alive[remove]
+                || self.versions[keep] != candidate.keep_version
+                || self.versions[remove] != candidate.remove_version
+            {
+                // Stale.
+                continue;
+            }
+
+            if self.can_collapse(&candidate) {
+                self.collapse(&candidate);
+            }
+        }
+    }
+
+    fn can_collapse(&self, candidate: &Candidate) -> bool {
+        let (keep, remove) = (candidate.keep, candidate.remove);
+
+        // Link condition: the only vertices adjacent to both ends of the edge must be those opposite the edge in the triangles
+        // that will collapse. Otherwise the collapse would create a non-manifold edge.
+        let keep_neighbors = self.neighbors(keep);
+        let remove_neighbors = self.neighbors(remove);
+        let num_common_neighbors = keep_neighbors
+            .iter()
+            .filter(|n| remove_neighbors.binary_search(n).is_ok())
+            .count();
+        let num_collapsing_triangles = self.vertex_triangles[keep as usize]
+            .iter()
+            .filter_map(|&t| self.triangles[t])
+            .filter(|tri| tri.contains(&remove))
+            .count();
+        if num_common_neighbors != num_collapsing_triangles {
+            return false;
+        }
+
+        // No surviving triangle may flip or become degenerate.
+        for &v in [keep, remove].iter() {
+            for tri in self.vertex_triangles[v as usize]
+                .iter()
+                .filter_map(|&t| self.triangles[t])
+            {
+                if tri.contains(&keep) && tri.contains(&remove) {
+                    continue;
+                }
+                let old_positions = triangle_positions(tri, &self.positions);
+                let mut new_positions = old_positions;
+                for (p, &tv) in new_positions.iter_mut().zip(tri.iter()) {
+                    if tv == v {
+                        *p = candidate.target;
+                    }
+                }
+                if dot(
+                    triangle_normal(old_positions),
+                    triangle_normal(new_positions),
+                ) <= 0.0
+                {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    fn collapse(&mut self, candidate: &Candidate) {
+        let (keep, remove) = (candidate.keep as usize, candidate.remove as usize);
+
+        self.positions[keep] = candidate.target;
+        self.quadrics[keep] = self.quadrics[keep].add(&self.quadrics[remove]);
+        self.is_alive[remove] = false;
+        self.versions[keep] = self.versions[keep].wrapping_add(1);
+        self.versions[remove] = self.versions[remove].wrapping_add(1);
+
+        for t in std::mem::take(&mut self.vertex_triangles[remove]) {
+            let tri = match &mut self.triangles[t] {
+                Some(tri) => tri,
+                None => continue,
+            };
+            if tri.contains(&candidate.keep) {
+                self.triangles[t] = None;
+                self.num_triangles -= 1;
+            } else {
+                for v in tri.iter_mut() {
+                    if *v == candidate.remove {
+                        *v = candidate.keep;
+                    }
+                }
+                self.vertex_triangles[keep].push(t);
+            }
+        }
+        let triangles = &self.triangles;
+        self.vertex_triangles[keep].retain(|&t| triangles[t].is_some());
+
+        for n in self.neighbors(candidate.keep) {
+            self.push_candidate(candidate.keep, n);
+        }
+    }
+
+    // Sorted and deduplicated.
+    fn neighbors(&self, v: u32) -> Vec<u32> {
+        let mut neighbors: Vec<u32> = self.vertex_triangles[v as usize]
+            .iter()
+            .filter_map(|&t| self.triangles[t])
+            .flat_map(|tri| (0..3).map(move |i| tri[i]))
+            .filter(|&n| n != v)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+
+        neighbors
+    }
+
+    fn into_mesh(self, original: &PosNormMesh) -> PosNormMesh {
+        let mut mesh = PosNormMesh::default();
+        let mut new_index = vec![u32::MAX; self.positions.len()];
+        for tri in self.triangles.iter().filter_map(|t| *t) {
+            for &v in tri.iter() {
+                let v = v as usize;
+                if new_index[v] == u32::MAX {
+                    new_index[v] = mesh.positions.len() as u32;
+                    let p = self.positions[v];
+                    mesh.positions.push([p[0] as f32, p[1] as f32, p[2] as f32]);
+                    if let Some(n) = original.normals.get(v) {
+                        mesh.normals.push(*n);
+                    }
+                }
+                mesh.indices.push(new_index[v]);
+            }
+        }
+
+        mesh
+    }
+}
+
+// An edge collapse waiting in the priority queue.
+struct Candidate {
+    cost: f64,
+    keep: u32,
+    remove: u32,
+    keep_version: u32,
+    remove_version: u32,
+    target: [f64; 3],
+}
+
+// Ordered by *ascending* cost, so the max-heap pops the cheapest candidate first.
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare_costs(other.cost, self.cost)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+fn compare_costs(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+}
+
+/// The symmetric 4x4 matrix `Q` such that `[x y z 1] Q [x y z 1]^T` is the sum of squared distances from `(x, y, z)` to a set
+/// of planes. Only the upper triangle is stored.
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane([a, b, c, d]: [f64; 4]) -> Self {
+        Self([
+            a * a,
+            a * b,
+            a * c,
+            a * d,
+            b * b,
+            b * c,
+            b * d,
+            c * c,
+            c * d,
+            d * d,
+        ])
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let mut sum = *self;
+        for (s, o) in sum.0.iter_mut().zip(other.0.iter()) {
+            *s += o;
+        }
+
+        sum
+    }
+
+    fn error(&self, [x, y, z]: [f64; 3]) -> f64 {
+        let q = &self.0;
+
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+
+    /// The point with the least error, if it's unique.
+    fn minimizer(&self) -> Option<[f64; 3]> {
+        let q = &self.0;
+        let c0 = [q[0], q[1], q[2]];
+        let c1 = [q[1], q[4], q[5]];
+        let c2 = [q[2], q[5], q[7]];
+        let rhs = [-q[3], -q[6], -q[8]];
+
+        // Cramer's rule.
+        let det = det3(c0, c1, c2);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        Some([
+            det3(rhs, c1, c2) / det,
+            det3(c0, rhs, c2) / det,
+            det3(c0, c1, rhs) / det,
+        ])
+    }
+}
+
+fn triangle_positions(tri: [u32; 3], positions: &[[f64; 3]]) -> [[f64; 3]; 3] {
+    [
+        positions[tri[0] as usize],
+        positions[tri[1] as usize],
+        positions[tri[2] as usize],
+    ]
+}
+
+fn sorted_edge(a: u32, b: u32) -> (u32, u32) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn triangle_normal([p1, p2, p3]: [[f64; 3]; 3]) -> [f64; 3] {
+    cross(sub(p2, p1), sub(p3, p1))
+}
+
+fn triangle_plane(tri: [[f64; 3]; 3]) -> Option<[f64; 4]> {
+    let n = triangle_normal(tri);
+    let len = dot(n, n).sqrt();
+    if len == 0.0 {
+        return None;
+    }
+    let n = [n[0] / len, n[1] / len, n[2] / len];
+
+    Some([n[0], n[1], n[2], -dot(n, tri[0])])
+}
+
+fn lerp(a: [f64; 3], b: [f64; 3], t: f64) -> [f64; 3] {
+    [
+        a[0] + t * (b[0] - a[0]),
+        a[1] + t * (b[1] - a[1]),
+        a[2] + t * (b[2] - a[2]),
+    ]
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(u: [f64; 3], v: [f64; 3]) -> [f64; 3] {
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn det3(c0: [f64; 3], c1: [f64; 3], c2: [f64; 3]) -> f64 {
+    dot(c0, cross(c1, c2))
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utilities;
+
+    // A square in the XY plane made of `n * n` quads.
+    fn tessellated_plane(n: u32) -> PosNormMesh {
+        let mut mesh = PosNormMesh::default();
+        for y in 0..=n {
+            for x in 0..=n {
+                mesh.positions.push([x as f32, y as f32, 0.0]);
+                mesh.normals.push([0.0, 0.0, 1.0]);
+            }
+        }
+        let row = n + 1;
+        for y in 0..n {
+            for x in 0..n {
+                let v = y * row + x;
+                mesh.indices
+                    .extend_from_slice(&[v, v + 1, v + row + 1, v, v + row + 1, v + row]);
+            }
+        }
+
+        mesh
+    }
+
+    #[test]
+    fn decimate_plane_to_half() {
+        let n = 16;
+        let mesh = tessellated_plane(n);
+        let num_triangles = mesh.indices.len() / 3;
+
+        let decimated = decimate(&mesh, 0.5);
+        let num_decimated = decimated.indices.len() / 3;
+        assert!(num_decimated * 10 >= num_triangles * 4);
+        assert!(num_decimated * 10 <= num_triangles * 6);
+
+        assert_eq!(decimated.positions.len(), decimated.normals.len());
+        for p in decimated.positions.iter() {
+            assert!(p[2].abs() < 1e-5);
+            assert!(p[0] >= 0.0 && p[0] <= n as f32);
+            assert!(p[1] >= 0.0 && p[1] <= n as f32);
+        }
+        // No triangles were flipped.
+        for tri in decimated.indices.chunks(3) {
+            assert!(test_utilities::triangle_normal(&decimated.positions, tri).z() >= 0.0);
+        }
+        // The corners are on the boundary, so they must survive.
+        for corner in [
+            [0.0, 0.0],
+            [n as f32, 0.0],
+            [0.0, n as f32],
+            [n as f32, n as f32],
+        ]
+        .iter()
+        {
+            assert!(decimated
+                .positions
+                .iter()
+                .any(|p| p[0] == corner[0] && p[1] == corner[1]));
+        }
+    }
+
+    #[test]
+    fn decimate_clamps_ratio() {
+        let mesh = tessellated_plane(4);
+
+        assert_eq!(decimate(&mesh, 2.0).indices.len(), mesh.indices.len());
+    }
+}
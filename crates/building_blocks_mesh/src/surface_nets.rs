@@ -1,11 +1,15 @@
-use super::PosNormMesh;
+use super::{
+    padded_chunk_extent, padding_for,
+    weld::{find_weld_representatives, remove_degenerate_triangles},
+    MesherKind, PosNormMesh, Winding,
+};
 
 use building_blocks_core::{prelude::*, EDGES_3};
 use building_blocks_storage::{array::for_each::ArrayForEach, dev_prelude::*};
 
 /// Pads the given chunk extent with exactly the amount of space required for running the `surface_nets` algorithm.
 pub fn padded_surface_nets_chunk_extent(chunk_extent: &Extent3i) -> Extent3i {
-    chunk_extent.padded(1)
+    padded_chunk_extent(chunk_extent, padding_for(MesherKind::SurfaceNets))
 }
 
 /// The output buffers used by `surface_nets`. These buffers can be reused to avoid reallocating memory.
@@ -21,6 +25,9 @@ pub struct SurfaceNetsBuffer {
 
     // Used to map back from voxel stride to vertex index.
     stride_to_index: Vec<u32>,
+    // Used to find nearby vertices when welding.
+    weld_grid: SmallKeyHashMap<Point3i, Vec<u32>>,
+    weld_remap: Vec<u32>,
 }
 
 impl SurfaceNetsBuffer {
@@ -35,6 +42,21 @@ impl SurfaceNetsBuffer {
     }
 }
 
+/// Options for `surface_nets_with_config`. The default config extracts the zero isosurface with counter-clockwise triangles
+/// and no welding.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SurfaceNetsConfig {
+    /// The level of the isosurface to extract. Values below this level are considered interior. A positive level grows the
+    /// surface outward, and a negative level shrinks it inward.
+    pub iso: f32,
+    /// Vertices closer together than this distance are welded into a single vertex, and any triangles that become degenerate
+    /// are removed. Welded vertices stay in `mesh.positions` (so it remains parallel to `surface_points`), but they are no
+    /// longer referenced by `mesh.indices`. The default of `0.0` disables welding.
+    pub weld_distance: f32,
+    /// The winding of the triangles in the output mesh. The normals point outward regardless of the winding.
+    pub winding: Winding,
+}
+
 /// The Naive Surface Nets smooth voxel meshing algorithm.
 ///
 /// This is basically just dual contouring a uniform grid with:
@@ -43,9 +65,10 @@ impl SurfaceNetsBuffer {
 ///
 /// Extracts an isosurface mesh from the [signed distance field](https://en.wikipedia.org/wiki/Signed_distance_function) `sdf`.
 /// Each value in the field determines how close that point is to the isosurface. Negative values are considered "interior" of
-/// the surface volume, and positive values are considered "exterior." These lattice points will be considered corners of unit
-/// cubes. For each unit cube, at most one isosurface vertex will be estimated, as below, where `p` is a positive corner value,
-/// `n` is a negative corner value, `s` is an isosurface vertex, and `|` or `-` are mesh polygons connecting the vertices.
+/// the surface volume, and the rest are considered "exterior." These lattice points will
+/// be considered corners of unit cubes. For each unit cube, at most one isosurface vertex will be estimated, as below, where
+/// `p` is an exterior corner value, `n` is an interior corner value, `s` is an isosurface vertex, and `|` or `-` are mesh
+/// polygons connecting the vertices.
 ///
 /// ```text
 /// p   p   p   p
@@ -67,6 +90,28 @@ pub fn surface_nets<A, T>(
 ) where
     A: IndexedArray<[i32; 3]> + GetUnchecked<Stride, Item = T>,
     T: SignedDistance,
+{
+    surface_nets_with_config(
+        sdf,
+        extent,
+        voxel_size,
+        estimate_normals,
+        SurfaceNetsConfig::default(),
+        output,
+    )
+}
+
+/// Like `surface_nets`, but values less than `config.iso` are considered interior instead of negative values.
+pub fn surface_nets_with_config<A, T>(
+    sdf: &A,
+    extent: &Extent3i,
+    voxel_size: f32,
+    estimate_normals: bool,
+    config: SurfaceNetsConfig,
+    output: &mut SurfaceNetsBuffer,
+) where
+    A: IndexedArray<[i32; 3]> + GetUnchecked<Stride, Item = T>,
+    T: SignedDistance,
 {
     assert!(
         extent.is_subset_of(sdf.extent()),
@@ -77,8 +122,15 @@ pub fn surface_nets<A, T>(
 
     output.reset(sdf.extent().num_points());
 
-    estimate_surface(sdf, extent, voxel_size, estimate_normals, output);
-    make_all_quads(sdf, extent, output);
+    let iso = config.iso;
+    estimate_surface(sdf, extent, voxel_size, iso, estimate_normals, output);
+    make_all_quads(sdf, extent, iso, output);
+
+    if config.weld_distance > 0.0 {
+        weld_vertices(output, config.weld_distance);
+    }
+
+    config.winding.apply_to_triangles(&mut output.mesh.indices);
 }
 
 // Find all vertex positions and normals. Also generate a map from grid position to vertex index to be used to look up vertices
@@ -87,6 +139,7 @@ fn estimate_surface<A, T>(
     sdf: &A,
     extent: &Extent3i,
     voxel_size: f32,
+    iso: f32,
     estimate_normals: bool,
     output: &mut SurfaceNetsBuffer,
 ) where
@@ -111,6 +164,7 @@ fn estimate_surface<A, T>(
         if estimate_surface_in_cube(
             sdf,
             voxel_size,
+            iso,
             &p,
             &corner_strides,
             estimate_normals,
@@ -131,6 +185,7 @@ fn estimate_surface<A, T>(
 fn estimate_surface_in_cube<A, T>(
     sdf: &A,
     voxel_size: f32,
+    iso: f32,
     cube_min_corner: &Point3i,
     corner_strides: &[Stride],
     estimate_normals: bool,
@@ -140,11 +195,12 @@ where
     A: GetUnchecked<Stride, Item = T>,
     T: SignedDistance,
 {
-    // Get the signed distance values at each corner of this cube.
+    // Get the signed distance values at each corner of this cube, relative to the isosurface.
     let mut corner_dists = [0.0; 8];
     let mut num_negative = 0;
     for (i, dist) in corner_dists.iter_mut().enumerate() {
-        let d = unsafe { sdf.get_unchecked(corner_strides[i]).into() };
+        let d: f32 = unsafe { sdf.get_unchecked(corner_strides[i]).into() };
+        let d = d - iso;
         *dist = d;
         if d < 0.0 {
             num_negative += 1;
@@ -230,7 +286,7 @@ fn sdf_gradient(dists: &[f32; 8], s: &Point3f) -> [f32; 3] {
 // For every edge that crosses the isosurface, make a quad between the "centers" of the four cubes touching that surface. The
 // "centers" are actually the vertex positions found earlier. Also, make sure the triangles are facing the right way. See the
 // comments on `maybe_make_quad` to help with understanding the indexing.
-fn make_all_quads<A, T>(sdf: &A, extent: &Extent3i, output: &mut SurfaceNetsBuffer)
+fn make_all_quads<A, T>(sdf: &A, extent: &Extent3i, iso: f32, output: &mut SurfaceNetsBuffer)
 where
     A: IndexedArray<[i32; 3]> + GetUnchecked<Stride, Item = T>,
     T: SignedDistance,
@@ -257,6 +313,7 @@ where
         if p.y() != min.y() && p.z() != min.z() && p.x() != max.x() {
             maybe_make_quad(
                 sdf,
+                iso,
                 &output.stride_to_index,
                 &output.mesh.positions,
                 *p_stride,
@@ -270,6 +327,7 @@ where
         if p.x() != min.x() && p.z() != min.z() && p.y() != max.y() {
             maybe_make_quad(
                 sdf,
+                iso,
                 &output.stride_to_index,
                 &output.mesh.positions,
                 *p_stride,
@@ -283,6 +341,7 @@ where
         if p.x() != min.x() && p.y() != min.y() && p.z() != max.z() {
             maybe_make_quad(
                 sdf,
+                iso,
                 &output.stride_to_index,
                 &output.mesh.positions,
                 *p_stride,
@@ -325,6 +384,7 @@ where
 // directions; these are axis B and axis C.
 fn maybe_make_quad<A, T>(
     sdf: &A,
+    iso: f32,
     stride_to_index: &[u32],
     positions: &[[f32; 3]],
     p1: Stride,
@@ -336,9 +396,9 @@ fn maybe_make_quad<A, T>(
     A: GetUnchecked<Stride, Item = T>,
     T: SignedDistance,
 {
-    let d1 = unsafe { sdf.get_unchecked(p1) };
-    let d2 = unsafe { sdf.get_unchecked(p2) };
-    let negative_face = match (d1.is_negative(), d2.is_negative()) {
+    let d1: f32 = unsafe { sdf.get_unchecked(p1) }.into();
+    let d2: f32 = unsafe { sdf.get_unchecked(p2) }.into();
+    let negative_face = match (d1 < iso, d2 < iso) {
         (true, false) => false,
         (false, true) => true,
         _ => return, // No face.
@@ -377,3 +437,151 @@ fn sq_dist(a: [f32; 3], b: [f32; 3]) -> f32 {
 
     d[0] * d[0] + d[1] * d[1] + d[2] * d[2]
 }
+
+// Map every vertex to the first vertex found within `weld_distance` of it, then remove the triangles that have collapsed.
+fn weld_vertices(output: &mut SurfaceNetsBuffer, weld_distance: f32) {
+    let SurfaceNetsBuffer {
+        mesh,
+        weld_grid,
+        weld_remap,
+        ..
+    } = output;
+
+    find_weld_representatives(&mesh.positions, weld_distance, weld_grid, weld_remap);
+    remove_degenerate_triangles(&mut mesh.indices, weld_remap);
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{test_utilities::triangle_normal, validate::is_watertight};
+
+    use building_blocks_storage::signed_distance::offset;
+
+    fn sphere_sdf(radius: f32) -> Array3x1<f32> {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-16), Point3i::fill(32));
+
+        Array3x1::fill_with(extent, |p| Point3f::from(p).norm() - radius)
+    }
+
+    /// The mean distance of the mesh positions from the center of `sphere_sdf`, which is offset by half a voxel in mesh space.
+    fn mean_radius(buffer: &SurfaceNetsBuffer) -> f32 {
+        let positions = &buffer.mesh.positions;
+        let sum: f32 = positions
+            .iter()
+            .map(|p| (PointN(*p) - Point3f::fill(0.5)).norm())
+            .sum();
+
+        sum / positions.len() as f32
+    }
+
+    #[test]
+    fn clockwise_winding_flips_cube_triangles() {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-4), Point3i::fill(8));
+        let cube_sdf = Array3x1::fill_with(extent, |p| p.abs().max_component() as f32 - 2.5);
+
+        let mut ccw_buffer = SurfaceNetsBuffer::default();
+        surface_nets(&cube_sdf, &extent, 1.0, true, &mut ccw_buffer);
+        let mut cw_buffer = SurfaceNetsBuffer::default();
+        let config = SurfaceNetsConfig {
+            winding: Winding::Clockwise,
+            ..Default::default()
+        };
+        surface_nets_with_config(&cube_sdf, &extent, 1.0, true, config, &mut cw_buffer);
+
+        assert!(!ccw_buffer.mesh.is_empty());
+        assert_eq!(ccw_buffer.mesh.positions, cw_buffer.mesh.positions);
+        assert_eq!(ccw_buffer.mesh.normals, cw_buffer.mesh.normals);
+        for (ccw, cw) in ccw_buffer
+            .mesh
+            .indices
+            .chunks(3)
+            .zip(cw_buffer.mesh.indices.chunks(3))
+        {
+            assert_eq!([ccw[0], ccw[2], ccw[1]], [cw[0], cw[1], cw[2]]);
+
+            // The counter-clockwise triangle faces along the outward normal, and the clockwise triangle faces the opposite way.
+            let ccw_normal = triangle_normal(&ccw_buffer.mesh.positions, ccw);
+            let cw_normal = triangle_normal(&cw_buffer.mesh.positions, cw);
+            let outward = PointN(ccw_buffer.mesh.normals[ccw[0] as usize]);
+            assert!(ccw_normal.dot(outward) > 0.0);
+            assert!(cw_normal.dot(outward) < 0.0);
+        }
+    }
+
+    #[test]
+    fn welding_sphere_reduces_triangles_and_stays_closed() {
+        let sdf = sphere_sdf(10.0);
+
+        let mut buffer = SurfaceNetsBuffer::default();
+        surface_nets(&sdf, sdf.extent(), 1.0, true, &mut buffer);
+        let num_unwelded_triangles = buffer.mesh.indices.len() / 3;
+        assert_eq!(is_watertight(&buffer.mesh), Ok(()));
+
+        let config = SurfaceNetsConfig {
+            weld_distance: 1.5,
+            ..Default::default()
+        };
+        surface_nets_with_config(&sdf, sdf.extent(), 1.0, true, config, &mut buffer);
+        let num_welded_triangles = buffer.mesh.indices.len() / 3;
+        assert_eq!(is_watertight(&buffer.mesh), Ok(()));
+
+        assert!(num_welded_triangles > 0);
+        assert!(num_welded_triangles < num_unwelded_triangles);
+    }
+
+    #[test]
+    fn positive_iso_level_grows_sphere() {
+        let radius = 10.0;
+        let sdf = sphere_sdf(radius);
+
+        let mut buffer = SurfaceNetsBuffer::default();
+        surface_nets(&sdf, sdf.extent(), 1.0, true, &mut buffer);
+        let zero_radius = mean_radius(&buffer);
+        assert_eq!(is_watertight(&buffer.mesh), Ok(()));
+
+        let config = SurfaceNetsConfig {
+            iso: 0.5,
+            ..Default::default()
+        };
+        surface_nets_with_config(&sdf, sdf.extent(), 1.0, true, config, &mut buffer);
+        let offset_radius = mean_radius(&buffer);
+        assert_eq!(is_watertight(&buffer.mesh), Ok(()));
+
+        assert!((zero_radius - radius).abs() < 0.1, "{}", zero_radius);
+        assert!(
+            (offset_radius - zero_radius - 0.5).abs() < 0.1,
+            "{}",
+            offset_radius
+        );
+    }
+
+    #[test]
+    fn offset_sdf_grows_sphere() {
+        let radius = 10.0;
+        let mut sdf = sphere_sdf(radius);
+
+        let mut buffer = SurfaceNetsBuffer::default();
+        surface_nets(&sdf, sdf.extent(), 1.0, true, &mut buffer);
+        let original_radius = mean_radius(&buffer);
+
+        offset(&mut sdf, 1.0);
+        surface_nets(&sdf, sdf.extent(), 1.0, true, &mut buffer);
+        let offset_radius = mean_radius(&buffer);
+        assert_eq!(is_watertight(&buffer.mesh), Ok(()));
+
+        assert!(
+            (offset_radius - original_radius - 1.0).abs() < 0.1,
+            "{}",
+            offset_radius
+        );
+    }
+}
@@ -0,0 +1,270 @@
+use super::{
+    normals::{cross, dot, norm, sub},
+    PosNormTexMesh,
+};
+
+use std::collections::HashMap;
+
+/// Generates a tangent for each vertex of `mesh`, for use with normal mapping.
+///
+/// This uses [Lengyel's method](http://www.terathon.com/code/tangent.html). The `xyz` part of each tangent is a unit vector
+/// orthogonal to the vertex normal, pointing in the direction of increasing U. The `w` component is the handedness (`1.0` or
+/// `-1.0`) of the tangent space, so the bitangent can be computed in a shader as `w * cross(normal, tangent.xyz)`.
+///
+/// Vertices that share a position, normal, and handedness, such as those duplicated along a UV seam, are given the same
+/// averaged tangent so there is no visible discontinuity. Vertices on either side of a mirror seam have opposite handedness,
+/// so they keep their own tangents rather than cancelling out. Vertices that are only touched by triangles with degenerate
+/// UVs get a zero tangent.
+pub fn generate_tangents(mesh: &PosNormTexMesh) -> Vec<[f32; 4]> {
+    let num_vertices = mesh.positions.len();
+    let mut u_dirs = vec![[0.0; 3]; num_vertices];
+    let mut v_dirs = vec![[0.0; 3]; num_vertices];
+
+    for tri in mesh.indices.chunks_exact(3) {
+        let (i1, i2, i3) = (tri[0] as usize, tri[1] as usize, tri[2] as usize);
+
+        let e1 = sub(mesh.positions[i2], mesh.positions[i1]);
+        let e2 = sub(mesh.positions[i3], mesh.positions[i1]);
+        let (w1, w2, w3) = (
+            mesh.tex_coords[i1],
+            mesh.tex_coords[i2],
+            mesh.tex_coords[i3],
+        );
+        let (s1, s2) = (w2[0] - w1[0], w3[0] - w1[0]);
+        let (t1, t2) = (w2[1] - w1[1], w3[1] - w1[1]);
+
+        let det = s1 * t2 - s2 * t1;
+        if det == 0.0 {
+            continue;
+        }
+        let r = 1.0 / det;
+        let u_dir = [
+            (t2 * e1[0] - t1 * e2[0]) * r,
+            (t2 * e1[1] - t1 * e2[1]) * r,
+            (t2 * e1[2] - t1 * e2[2]) * r,
+        ];
+        let v_dir = [
+            (s1 * e2[0] - s2 * e1[0]) * r,
+            (s1 * e2[1] - s2 * e1[1]) * r,
+            (s1 * e2[2] - s2 * e1[2]) * r,
+        ];
+
+        for &i in [i1, i2, i3].iter() {
+            add_assign(&mut u_dirs[i], u_dir);
+            add_assign(&mut v_dirs[i], v_dir);
+        }
+    }
+
+    // Average across vertices that are only split because of their UVs. Mirrored UVs flip the handedness, and averaging
+    // across a mirror seam would cancel out the tangents.
+    let mut seam_groups: HashMap<_, Vec<usize>> = HashMap::new();
+    for (i, (p, n)) in mesh.positions.iter().zip(mesh.normals.iter()).enumerate() {
+        let is_left_handed = dot(cross(*n, u_dirs[i]), v_dirs[i]) < 0.0;
+        seam_groups
+            .entry((bits(*p), bits(*n), is_left_handed))
+            .or_insert_with(Vec::new)
+            .push(i);
+    }
+    for group in seam_groups.values().filter(|g| g.len() > 1) {
+        let mut u_sum = [0.0; 3];
+        let mut v_sum = [0.0; 3];
+        for &i in group.iter() {
+            add_assign(&mut u_sum, u_dirs[i]);
+            add_assign(&mut v_sum, v_dirs[i]);
+        }
+        for &i in group.iter() {
+            u_dirs[i] = u_sum;
+            v_dirs[i] = v_sum;
+        }
+    }
+
+    mesh.normals
+        .iter()
+        .zip(u_dirs.iter().zip(v_dirs.iter()))
+        .map(|(n, (u_dir, v_dir))| {
+            let n = normalized(*n);
+
+            // Gram-Schmidt orthogonalize.
+            let n_dot_u = dot(n, *u_dir);
+            let t = normalized([
+                u_dir[0] - n_dot_u * n[0],
+                u_dir[1] - n_dot_u * n[1],
+                u_dir[2] - n_dot_u * n[2],
+            ]);
+            let w = if dot(cross(n, t), *v_dir) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+
+            [t[0], t[1], t[2], w]
+        })
+        .collect()
+}
+
+fn add_assign(a: &mut [f32; 3], b: [f32; 3]) {
+    for i in 0..3 {
+        a[i] += b[i];
+    }
+}
+
+fn normalized(a: [f32; 3]) -> [f32; 3] {
+    let len = norm(a);
+    if len == 0.0 {
+        return a;
+    }
+
+    [a[0] / len, a[1] / len, a[2] / len]
+}
+
+fn bits(a: [f32; 3]) -> [u32; 3] {
+    [a[0].to_bits(), a[1].to_bits(), a[2].to_bits()]
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn assert_tangent_eq(actual: [f32; 4], expected: [f32; 4]) {
+        for i in 0..4 {
+            assert!(
+                (actual[i] - expected[i]).abs() < 1e-6,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn quad_tangents_align_with_u_axis() {
+        // A quad in the XZ plane facing +Y, with U along +X and V along -Z.
+        let mesh = PosNormTexMesh {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [2.0, 0.0, 0.0],
+                [2.0, 0.0, -2.0],
+                [0.0, 0.0, -2.0],
+            ],
+            normals: vec![[0.0, 1.0, 0.0]; 4],
+            tex_coords: vec![[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+
+        let tangents = generate_tangents(&mesh);
+
+        assert_eq!(tangents.len(), 4);
+        for t in tangents.iter() {
+            assert_tangent_eq(*t, [1.0, 0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_handedness() {
+        let mesh = PosNormTexMesh {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [2.0, 0.0, 0.0],
+                [2.0, 0.0, -2.0],
+                [0.0, 0.0, -2.0],
+            ],
+            normals: vec![[0.0, 1.0, 0.0]; 4],
+            tex_coords: vec![[0.0, 1.0], [1.0, 1.0], [1.0, 0.0], [0.0, 0.0]],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        };
+
+        for t in generate_tangents(&mesh).iter() {
+            assert_tangent_eq(*t, [1.0, 0.0, 0.0, -1.0]);
+        }
+    }
+
+    #[test]
+    fn mirror_seam_keeps_tangents_on_each_side() {
+        // Two quads facing +Y that share the edge at X = 1, with U mirrored across the seam. The seam vertices are duplicated.
+        let mesh = PosNormTexMesh {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, -1.0],
+                [0.0, 0.0, -1.0],
+                [1.0, 0.0, 0.0],
+                [2.0, 0.0, 0.0],
+                [2.0, 0.0, -1.0],
+                [1.0, 0.0, -1.0],
+            ],
+            normals: vec![[0.0, 1.0, 0.0]; 8],
+            tex_coords: vec![
+                [0.0, 0.0],
+                [1.0, 0.0],
+                [1.0, 1.0],
+                [0.0, 1.0],
+                [1.0, 0.0],
+                [0.0, 0.0],
+                [0.0, 1.0],
+                [1.0, 1.0],
+            ],
+            indices: vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7],
+        };
+
+        let tangents = generate_tangents(&mesh);
+
+        for t in tangents[..4].iter() {
+            assert_tangent_eq(*t, [1.0, 0.0, 0.0, 1.0]);
+        }
+        for t in tangents[4..].iter() {
+            assert_tangent_eq(*t, [-1.0, 0.0, 0.0, -1.0]);
+        }
+    }
+
+    #[test]
+    fn uv_seam_copies_get_equal_tangents() {
+        // Two quads facing +Y that share the edge at X = 1, like the mirror seam above, but with the same handedness on both
+        // sides. U restarts at the seam, and on the right quad, U is skewed so that its tangent points along +X+Z.
+        let mesh = PosNormTexMesh {
+            positions: vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 0.0, -1.0],
+                [0.0, 0.0, -1.0],
+                [1.0, 0.0, 0.0],
+                [2.0, 0.0, 0.0],
+                [2.0, 0.0, -1.0],
+                [1.0, 0.0, -1.0],
+            ],
+            normals: vec![[0.0, 1.0, 0.0]; 8],
+            tex_coords: vec![
+                [0.0, 0.0],
+                [1.0, 0.0],
+                [1.0, 1.0],
+                [0.0, 1.0],
+                [0.0, 0.0],
+                [1.0, 1.0],
+                [1.0, 2.0],
+                [0.0, 1.0],
+            ],
+            indices: vec![0, 1, 2, 0, 2, 3, 4, 5, 6, 4, 6, 7],
+        };
+
+        let tangents = generate_tangents(&mesh);
+
+        // Each copy of a seam vertex gets the sum of the tangents from the triangles on both sides.
+        let a = 1.0 / 13.0f32.sqrt();
+        assert_tangent_eq(tangents[1], [3.0 * a, 0.0, 2.0 * a, 1.0]);
+        assert_tangent_eq(tangents[4], [3.0 * a, 0.0, 2.0 * a, 1.0]);
+        let b = 1.0 / 10.0f32.sqrt();
+        assert_tangent_eq(tangents[2], [3.0 * b, 0.0, b, 1.0]);
+        assert_tangent_eq(tangents[7], [3.0 * b, 0.0, b, 1.0]);
+
+        // Vertices off the seam keep the tangent of their own side.
+        assert_tangent_eq(tangents[0], [1.0, 0.0, 0.0, 1.0]);
+        let c = 1.0 / 2.0f32.sqrt();
+        assert_tangent_eq(tangents[5], [c, 0.0, c, 1.0]);
+    }
+}
@@ -0,0 +1,10 @@
+use building_blocks_core::prelude::*;
+
+/// The unnormalized normal of the triangle with indices `tri` in `positions`, following the right-hand rule.
+pub fn triangle_normal(positions: &[[f32; 3]], tri: &[u32]) -> Point3f {
+    let p0 = PointN(positions[tri[0] as usize]);
+    let p1 = PointN(positions[tri[1] as usize]);
+    let p2 = PointN(positions[tri[2] as usize]);
+
+    (p1 - p0).cross(p2 - p0)
+}
@@ -0,0 +1,130 @@
+//! Checks for mesh defects, e.g. before exporting a mesh for 3D printing.
+
+use super::PosNormMesh;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// The edges that keep a mesh from being watertight. Each edge is a pair of vertex indices, with the smaller index first, and
+/// both lists are sorted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MeshDefects {
+    /// Edges that belong to only one triangle, i.e. the edges of holes in the surface.
+    pub boundary_edges: Vec<[u32; 2]>,
+    /// Edges that are shared by more than two triangles.
+    pub non_manifold_edges: Vec<[u32; 2]>,
+}
+
+impl fmt::Display for MeshDefects {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Mesh is not watertight: {} boundary edges, {} non-manifold edges",
+            self.boundary_edges.len(),
+            self.non_manifold_edges.len()
+        )
+    }
+}
+
+impl std::error::Error for MeshDefects {}
+
+/// Returns `Ok` iff every edge of `mesh` is shared by exactly two triangles, i.e. the mesh is a closed, manifold surface.
+///
+/// Edges are identified by their vertex indices, not positions, so a mesh with duplicate vertices along a seam will report
+/// boundary edges there even if it's closed geometrically. Weld those vertices first.
+pub fn is_watertight(mesh: &PosNormMesh) -> Result<(), MeshDefects> {
+    let mut edge_counts = HashMap::new();
+    for tri in mesh.indices.chunks_exact(3) {
+        for i in 0..3 {
+            *edge_counts
+                .entry(sorted_edge(tri[i], tri[(i + 1) % 3]))
+                .or_insert(0) += 1;
+        }
+    }
+
+    let mut defects = MeshDefects::default();
+    for (edge, count) in edge_counts.into_iter() {
+        if count == 1 {
+            defects.boundary_edges.push(edge);
+        } else if count > 2 {
+            defects.non_manifold_edges.push(edge);
+        }
+    }
+
+    if defects == MeshDefects::default() {
+        return Ok(());
+    }
+    defects.boundary_edges.sort_unstable();
+    defects.non_manifold_edges.sort_unstable();
+
+    Err(defects)
+}
+
+fn sorted_edge(a: u32, b: u32) -> [u32; 2] {
+    if a < b {
+        [a, b]
+    } else {
+        [b, a]
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn mesh_with_indices(num_vertices: usize, indices: Vec<u32>) -> PosNormMesh {
+        PosNormMesh {
+            positions: vec![[0.0; 3]; num_vertices],
+            normals: vec![[0.0; 3]; num_vertices],
+            indices,
+        }
+    }
+
+    #[test]
+    fn closed_cube_is_watertight() {
+        // Vertex i is at corner (i & 1, (i >> 1) & 1, (i >> 2) & 1).
+        let cube = mesh_with_indices(
+            8,
+            vec![
+                0, 2, 1, 1, 2, 3, // -Z
+                4, 5, 6, 5, 7, 6, // +Z
+                0, 1, 4, 1, 5, 4, // -Y
+                2, 6, 3, 3, 6, 7, // +Y
+                0, 4, 2, 2, 4, 6, // -X
+                1, 3, 5, 3, 7, 5, // +X
+            ],
+        );
+
+        assert_eq!(is_watertight(&cube), Ok(()));
+    }
+
+    #[test]
+    fn open_quad_reports_boundary_edges() {
+        let quad = mesh_with_indices(4, vec![0, 1, 2, 0, 2, 3]);
+
+        assert_eq!(
+            is_watertight(&quad),
+            Err(MeshDefects {
+                boundary_edges: vec![[0, 1], [0, 3], [1, 2], [2, 3]],
+                non_manifold_edges: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn fin_reports_non_manifold_edge() {
+        // Three triangles share the edge [0, 1].
+        let fin = mesh_with_indices(5, vec![0, 1, 2, 1, 0, 3, 0, 1, 4]);
+
+        let defects = is_watertight(&fin).unwrap_err();
+        assert_eq!(defects.non_manifold_edges, vec![[0, 1]]);
+        assert_eq!(defects.boundary_edges.len(), 6);
+    }
+}
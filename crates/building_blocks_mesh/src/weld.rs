@@ -0,0 +1,167 @@
+use super::PosNormMesh;
+
+use building_blocks_core::prelude::*;
+use building_blocks_storage::SmallKeyHashMap;
+
+/// Merges the vertices of `mesh` that are within `epsilon` of each other, e.g. the duplicate vertices along the seams of chunk
+/// meshes that were concatenated. Triangles that collapse to a line or point are removed.
+///
+/// Each group of merged vertices is replaced by its first vertex, and the normals of the group are summed. The other vertices
+/// of the group are removed from `positions` and `normals`, so the remaining vertices may get new indices.
+///
+/// # Panics
+///
+/// If `epsilon` is not positive.
+pub fn weld_vertices(mesh: &mut PosNormMesh, epsilon: f32) {
+    assert!(epsilon > 0.0, "Weld epsilon must be positive");
+
+    let mut grid = SmallKeyHashMap::default();
+    let mut remap = Vec::new();
+    find_weld_representatives(&mesh.positions, epsilon, &mut grid, &mut remap);
+
+    // Compact the vertex buffers so only representatives remain.
+    let mut new_index = vec![0; mesh.positions.len()];
+    let mut num_kept = 0;
+    for (i, &r) in remap.iter().enumerate() {
+        if r as usize == i {
+            mesh.positions[num_kept] = mesh.positions[i];
+            mesh.normals[num_kept] = mesh.normals[i];
+            new_index[i] = num_kept as u32;
+            num_kept += 1;
+        } else {
+            let n = mesh.normals[i];
+            let sum = &mut mesh.normals[new_index[r as usize] as usize];
+            for (s, n) in sum.iter_mut().zip(n.iter()) {
+                *s += n;
+            }
+        }
+    }
+    mesh.positions.truncate(num_kept);
+    mesh.normals.truncate(num_kept);
+
+    for r in remap.iter_mut() {
+        *r = new_index[*r as usize];
+    }
+    remove_degenerate_triangles(&mut mesh.indices, &remap);
+}
+
+/// Maps every position to the index of the first position found within `weld_distance` of it, which is always at or before
+/// itself.
+///
+/// Positions are bucketed into a grid of cells with side length `weld_distance`, so only the 27 cells around a position need
+/// to be searched.
+pub(crate) fn find_weld_representatives(
+    positions: &[[f32; 3]],
+    weld_distance: f32,
+    grid: &mut SmallKeyHashMap<Point3i, Vec<u32>>,
+    remap: &mut Vec<u32>,
+) {
+    let sq_weld_distance = weld_distance * weld_distance;
+
+    grid.clear();
+    remap.clear();
+    for (i, position) in positions.iter().enumerate() {
+        let p = PointN(*position);
+        let cell = (p / weld_distance).floor_int();
+
+        let neighbor_cells = Extent3i::from_min_and_shape(cell - Point3i::ONES, Point3i::fill(3));
+        let representative = neighbor_cells
+            .iter_points()
+            .filter_map(|c| grid.get(&c))
+            .flatten()
+            .find(|r| p.l2_distance_squared(PointN(positions[**r as usize])) <= sq_weld_distance)
+            .copied();
+
+        let index = match representative {
+            Some(r) => r,
+            None => {
+                grid.entry(cell).or_insert_with(Vec::new).push(i as u32);
+                i as u32
+            }
+        };
+        remap.push(index);
+    }
+}
+
+/// Replaces every index `i` with `remap[i]`, then removes the triangles that have collapsed.
+pub(crate) fn remove_degenerate_triangles(indices: &mut Vec<u32>, remap: &[u32]) {
+    let mut num_kept = 0;
+    for t in 0..indices.len() / 3 {
+        let tri = [
+            remap[indices[3 * t] as usize],
+            remap[indices[3 * t + 1] as usize],
+            remap[indices[3 * t + 2] as usize],
+        ];
+        if tri[0] != tri[1] && tri[1] != tri[2] && tri[2] != tri[0] {
+            indices[3 * num_kept..3 * num_kept + 3].copy_from_slice(&tri);
+            num_kept += 1;
+        }
+    }
+    indices.truncate(3 * num_kept);
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{padded_surface_nets_chunk_extent, surface_nets, SurfaceNetsBuffer};
+
+    use building_blocks_storage::prelude::*;
+
+    #[test]
+    fn welding_concatenated_chunk_meshes_collapses_seam_vertices() {
+        // A horizontal plane that crosses two chunks along X.
+        let chunk_shape = Point3i::fill(16);
+        let chunk_extents = [
+            Extent3i::from_min_and_shape(Point3i::ZERO, chunk_shape),
+            Extent3i::from_min_and_shape(PointN([16, 0, 0]), chunk_shape),
+        ];
+        let sdf_extent = Extent3i::from_min_and_max(Point3i::fill(-1), PointN([32, 16, 16]));
+        let sdf = Array3x1::fill_with(sdf_extent, |p| p.z() as f32 - 7.5);
+
+        let mut mesh = PosNormMesh::default();
+        let mut buffer = SurfaceNetsBuffer::default();
+        for chunk_extent in chunk_extents.iter() {
+            surface_nets(
+                &sdf,
+                &padded_surface_nets_chunk_extent(chunk_extent),
+                1.0,
+                true,
+                &mut buffer,
+            );
+            let offset = mesh.positions.len() as u32;
+            mesh.positions.extend_from_slice(&buffer.mesh.positions);
+            mesh.normals.extend_from_slice(&buffer.mesh.normals);
+            mesh.indices
+                .extend(buffer.mesh.indices.iter().map(|i| i + offset));
+        }
+
+        let num_positions = mesh.positions.len();
+        let num_triangles = mesh.indices.len() / 3;
+        let mut distinct_positions: Vec<_> = mesh.positions.clone();
+        distinct_positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        distinct_positions.dedup();
+        let num_seam_duplicates = num_positions - distinct_positions.len();
+        assert!(num_seam_duplicates > 0);
+
+        weld_vertices(&mut mesh, 1e-4);
+
+        assert_eq!(mesh.positions.len(), num_positions - num_seam_duplicates);
+        assert_eq!(mesh.normals.len(), mesh.positions.len());
+        assert_eq!(mesh.indices.len() / 3, num_triangles);
+        let mut welded_positions = mesh.positions.clone();
+        welded_positions.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(welded_positions, distinct_positions);
+        assert!(mesh
+            .indices
+            .iter()
+            .all(|&i| (i as usize) < mesh.positions.len()));
+    }
+}
@@ -26,7 +26,8 @@ impl<Ni, Nf> GridRayTraversal<Ni, Nf> {
         PointN<Nf>: IntoIntegerPoint<IntPoint = PointN<Ni>> + FloatPoint<Nf>,
         PointN<Nf>: From<PointN<Ni>>,
     {
-        let current_grid_point: PointN<Ni> = start.into_int();
+        // Round down, not toward zero, so that a negative start is in the voxel that actually contains it.
+        let current_grid_point: PointN<Ni> = start.floor_int();
         let vel_signs = velocity.signum();
         let step = vel_signs.into_int();
         let t_delta = vel_signs / velocity;
@@ -128,6 +129,23 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_start_at_negative_coordinates() {
+        let mut traversal =
+            GridRayTraversal3::new(PointN([-1.5, -0.5, 2.5]), PointN([1.0, 0.0, 0.0]));
+
+        let mut voxels = Vec::new();
+        for _ in 0..3 {
+            voxels.push(traversal.current_voxel());
+            traversal.step();
+        }
+
+        assert_eq!(
+            voxels,
+            vec![PointN([-2, -1, 2]), PointN([-1, -1, 2]), PointN([0, -1, 2]),]
+        )
+    }
+
     #[test]
     fn test_move_along_all_axes_some_negative() {
         let mut traversal =
@@ -15,12 +15,14 @@
 mod find_surface;
 mod flood_fill;
 mod grid_ray_traversal;
+mod line_of_sight;
 mod pathfinding;
 
 pub use self::pathfinding::*;
 pub use find_surface::*;
 pub use flood_fill::*;
 pub use grid_ray_traversal::*;
+pub use line_of_sight::*;
 
 #[cfg(feature = "ncollide")]
 pub mod collision;
@@ -0,0 +1,136 @@
+use crate::GridRayTraversal3;
+
+use building_blocks_core::prelude::*;
+use building_blocks_storage::dev_prelude::{Chunk, ChunkMap3, ChunkReadStorage, Get, GetUnchecked};
+
+/// Returns `true` iff none of the voxels strictly between the voxels containing `from` and `to` are opaque, as determined by
+/// `is_opaque`. The voxels are visited in order along the line segment with a `GridRayTraversal3`, stopping at the first
+/// opaque voxel.
+///
+/// The voxels containing `from` and `to` are not tested, so this can determine whether an opaque voxel is visible from a
+/// point. When `map` is a `ChunkMapLodView`, points in vacant chunks take the map's ambient value, so whether vacant chunks
+/// block the line of sight is decided by `is_opaque(&ambient_value)`.
+pub fn line_of_sight<Map, T>(
+    map: &Map,
+    from: Point3f,
+    to: Point3f,
+    is_opaque: impl Fn(&T) -> bool,
+) -> bool
+where
+    Map: Get<Point3i, Item = T>,
+{
+    let mut traversal = GridRayTraversal3::new(from, to - from);
+
+    // Every step moves one voxel along one axis, so this is exactly the number of steps to reach the voxel containing `to`.
+    let num_steps = traversal.current_voxel().l1_distance(to.floor_int());
+    for _ in 1..num_steps {
+        traversal.step();
+        if is_opaque(&map.get(traversal.current_voxel())) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Provides `line_of_sight` as a method of `ChunkMap3`.
+pub trait LineOfSight<T> {
+    /// The same as `line_of_sight` through the voxels at level of detail `lod`.
+    fn line_of_sight(
+        &self,
+        lod: u8,
+        from: Point3f,
+        to: Point3f,
+        is_opaque: impl Fn(&T) -> bool,
+    ) -> bool;
+}
+
+impl<T, Ch, Bldr, Store> LineOfSight<T> for ChunkMap3<T, Bldr, Store>
+where
+    T: Clone,
+    Ch: Chunk,
+    Ch::Array: GetUnchecked<Point3i, Item = T>,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Ch>,
+{
+    fn line_of_sight(
+        &self,
+        lod: u8,
+        from: Point3f,
+        to: Point3f,
+        is_opaque: impl Fn(&T) -> bool,
+    ) -> bool {
+        line_of_sight(&self.lod_view(lod), from, to, is_opaque)
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use building_blocks_storage::prelude::*;
+
+    #[test]
+    fn wall_blocks_line_of_sight() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), false);
+        let mut map = builder.build_with_hash_map_storage();
+
+        // A wall in the plane X = 12, with a hole at Y = 42. It straddles the chunk boundaries at Y = 32 and Z = 32.
+        let wall = Extent3i::from_min_and_shape(PointN([12, 24, 24]), PointN([1, 24, 16]));
+        map.fill_extent(0, &wall, true);
+        *map.get_mut_point(0, PointN([12, 42, 32])) = false;
+
+        let is_opaque = |v: &bool| *v;
+
+        // Through the wall.
+        assert!(!map.line_of_sight(
+            0,
+            PointN([8.5, 32.5, 30.5]),
+            PointN([16.5, 34.5, 33.5]),
+            is_opaque
+        ));
+        // Through the hole.
+        assert!(map.line_of_sight(
+            0,
+            PointN([8.5, 42.5, 32.5]),
+            PointN([16.5, 42.5, 32.5]),
+            is_opaque
+        ));
+        // Alongside the wall without touching it. This also passes through vacant chunks, which are transparent.
+        assert!(map.line_of_sight(
+            0,
+            PointN([4.5, 11.5, 26.5]),
+            PointN([11.5, 52.5, 37.5]),
+            is_opaque
+        ));
+        // The end points themselves may be opaque.
+        assert!(map.line_of_sight(
+            0,
+            PointN([8.5, 32.5, 32.5]),
+            PointN([12.5, 32.5, 32.5]),
+            is_opaque
+        ));
+        // Blocked by a single voxel in negative coordinates, which is only visited if the traversal starts in the voxel that
+        // contains `from`.
+        *map.get_mut_point(0, PointN([-1, -1, -1])) = true;
+        assert!(!map.line_of_sight(
+            0,
+            PointN([-1.5, -0.5, -0.5]),
+            PointN([1.5, -0.5, -0.5]),
+            is_opaque
+        ));
+        // The free function works on any `Get` map, like a `ChunkMapLodView`.
+        assert!(!line_of_sight(
+            &map.lod_view(0),
+            PointN([8.5, 32.5, 30.5]),
+            PointN([16.5, 34.5, 33.5]),
+            is_opaque
+        ));
+    }
+}
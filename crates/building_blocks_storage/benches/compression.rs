@@ -1,12 +1,13 @@
 use building_blocks_core::prelude::*;
-use building_blocks_storage::prelude::*;
+use building_blocks_storage::{array::compression::SparseChunkCompression, prelude::*};
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use utilities::data_sets::{terrain_material_array, terrain_sdf_array};
 
-#[cfg(feature = "lz4")]
-use building_blocks_storage::compression::Lz4;
 #[cfg(feature = "snap")]
 use building_blocks_storage::compression::Snappy;
+#[cfg(feature = "lz4")]
+use building_blocks_storage::compression::{AdaptiveCompression, Lz4};
 
 #[cfg(feature = "lz4")]
 fn decompress_array_with_bincode_lz4(c: &mut Criterion) {
@@ -95,25 +96,187 @@ fn decompress_array_with_fast_snappy(c: &mut Criterion) {
     group.finish();
 }
 
+#[cfg(feature = "lz4")]
+fn compress_terrain_with_fast_lz4(c: &mut Criterion) {
+    bench_compress(
+        c,
+        "compress_terrain_sdf_with_fast_lz4",
+        FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 }),
+        set_up_terrain_sdf,
+    );
+    bench_compress(
+        c,
+        "compress_terrain_material_sdf_with_fast_lz4",
+        FastArrayCompressionNx2::from_bytes_compression(Lz4 { level: 10 }),
+        set_up_terrain_material_sdf,
+    );
+}
+
+#[cfg(feature = "lz4")]
+fn decompress_terrain_with_fast_lz4(c: &mut Criterion) {
+    bench_decompress(
+        c,
+        "decompress_terrain_sdf_with_fast_lz4",
+        FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 }),
+        set_up_terrain_sdf,
+    );
+    bench_decompress(
+        c,
+        "decompress_terrain_material_sdf_with_fast_lz4",
+        FastArrayCompressionNx2::from_bytes_compression(Lz4 { level: 10 }),
+        set_up_terrain_material_sdf,
+    );
+}
+
+#[cfg(feature = "snap")]
+fn compress_terrain_with_fast_snappy(c: &mut Criterion) {
+    bench_compress(
+        c,
+        "compress_terrain_sdf_with_fast_snappy",
+        FastArrayCompressionNx1::from_bytes_compression(Snappy),
+        set_up_terrain_sdf,
+    );
+    bench_compress(
+        c,
+        "compress_terrain_material_sdf_with_fast_snappy",
+        FastArrayCompressionNx2::from_bytes_compression(Snappy),
+        set_up_terrain_material_sdf,
+    );
+}
+
+#[cfg(feature = "snap")]
+fn decompress_terrain_with_fast_snappy(c: &mut Criterion) {
+    bench_decompress(
+        c,
+        "decompress_terrain_sdf_with_fast_snappy",
+        FastArrayCompressionNx1::from_bytes_compression(Snappy),
+        set_up_terrain_sdf,
+    );
+    bench_decompress(
+        c,
+        "decompress_terrain_material_sdf_with_fast_snappy",
+        FastArrayCompressionNx2::from_bytes_compression(Snappy),
+        set_up_terrain_material_sdf,
+    );
+}
+
+fn compress_surface_with_sparse(c: &mut Criterion) {
+    bench_compress(
+        c,
+        "compress_surface_with_sparse",
+        SparseChunkCompression::new(0),
+        set_up_surface_array,
+    );
+}
+
+fn decompress_surface_with_sparse(c: &mut Criterion) {
+    bench_decompress(
+        c,
+        "decompress_surface_with_sparse",
+        SparseChunkCompression::new(0),
+        set_up_surface_array,
+    );
+}
+
+#[cfg(feature = "lz4")]
+fn compress_terrain_with_fast_adaptive(c: &mut Criterion) {
+    bench_compress(
+        c,
+        "compress_terrain_sdf_with_fast_adaptive",
+        FastArrayCompressionNx1::from_bytes_compression(AdaptiveCompression::default()),
+        set_up_terrain_sdf,
+    );
+    bench_compress(
+        c,
+        "compress_terrain_material_sdf_with_fast_adaptive",
+        FastArrayCompressionNx2::from_bytes_compression(AdaptiveCompression::default()),
+        set_up_terrain_material_sdf,
+    );
+}
+
+#[cfg(feature = "lz4")]
+fn decompress_terrain_with_fast_adaptive(c: &mut Criterion) {
+    bench_decompress(
+        c,
+        "decompress_terrain_sdf_with_fast_adaptive",
+        FastArrayCompressionNx1::from_bytes_compression(AdaptiveCompression::default()),
+        set_up_terrain_sdf,
+    );
+    bench_decompress(
+        c,
+        "decompress_terrain_material_sdf_with_fast_adaptive",
+        FastArrayCompressionNx2::from_bytes_compression(AdaptiveCompression::default()),
+        set_up_terrain_material_sdf,
+    );
+}
+
+fn bench_compress<Compr: Compression>(
+    c: &mut Criterion,
+    group_name: &str,
+    compression: Compr,
+    set_up: fn(i32) -> Compr::Data,
+) {
+    let mut group = c.benchmark_group(group_name);
+    for size in ARRAY_SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, &size| {
+            b.iter_with_setup(|| set_up(size), |array| compression.compress(&array));
+        });
+    }
+    group.finish();
+}
+
+fn bench_decompress<Compr: Compression>(
+    c: &mut Criterion,
+    group_name: &str,
+    compression: Compr,
+    set_up: fn(i32) -> Compr::Data,
+) {
+    let mut group = c.benchmark_group(group_name);
+    for size in ARRAY_SIZES.iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, &size| {
+            b.iter_with_setup(
+                || compression.compress(&set_up(size)),
+                |compressed_array| {
+                    compressed_array.decompress();
+                },
+            );
+        });
+    }
+    group.finish();
+}
+
 #[cfg(feature = "lz4")]
 criterion_group!(
     lz4_benches,
     decompress_array_with_bincode_lz4,
     decompress_array_with_fast_lz4,
-    compress_array_with_fast_lz4
+    compress_array_with_fast_lz4,
+    compress_terrain_with_fast_lz4,
+    decompress_terrain_with_fast_lz4,
+    compress_terrain_with_fast_adaptive,
+    decompress_terrain_with_fast_adaptive
 );
 #[cfg(feature = "snap")]
 criterion_group!(
     snappy_benches,
     decompress_array_with_bincode_snappy,
     decompress_array_with_fast_snappy,
+    compress_terrain_with_fast_snappy,
+    decompress_terrain_with_fast_snappy
+);
+criterion_group!(
+    sparse_benches,
+    compress_surface_with_sparse,
+    decompress_surface_with_sparse
 );
+#[cfg(all(not(feature = "lz4"), not(feature = "snap")))]
+criterion_main!(sparse_benches);
 #[cfg(all(not(feature = "lz4"), feature = "snap"))]
-criterion_main!(snappy_benches);
+criterion_main!(sparse_benches, snappy_benches);
 #[cfg(all(feature = "lz4", not(feature = "snap")))]
-criterion_main!(lz4_benches);
+criterion_main!(sparse_benches, lz4_benches);
 #[cfg(all(feature = "lz4", feature = "snap"))]
-criterion_main!(lz4_benches, snappy_benches);
+criterion_main!(sparse_benches, lz4_benches, snappy_benches);
 
 const ARRAY_SIZES: [i32; 3] = [16, 32, 64];
 
@@ -123,3 +286,29 @@ fn set_up_array(size: i32) -> Array3x1<i32> {
     // Might be tough to compress this.
     Array3x1::fill_with(array_extent, |p: Point3i| p.x() % 3 + p.y() % 3 + p.z() % 3)
 }
+
+/// Only a single layer of voxels is non-zero, like a chunk that a flat surface passes through.
+fn set_up_surface_array(size: i32) -> Array3x1<u16> {
+    let array_extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(size));
+
+    Array3x1::fill_with(array_extent, |p: Point3i| {
+        if p.y() == size / 2 {
+            1 + (p.x() + p.z()) as u16 % 4
+        } else {
+            0
+        }
+    })
+}
+
+/// The terrain surface crosses the middle of the array, so it has both solid and empty regions.
+fn terrain_extent(size: i32) -> Extent3i {
+    Extent3i::from_min_and_shape(PointN([0, -size / 2, 0]), Point3i::fill(size))
+}
+
+fn set_up_terrain_sdf(size: i32) -> Array3x1<Sd8> {
+    terrain_sdf_array(terrain_extent(size), 0)
+}
+
+fn set_up_terrain_material_sdf(size: i32) -> Array3x2<u8, Sd8> {
+    terrain_material_array(terrain_extent(size), 0)
+}
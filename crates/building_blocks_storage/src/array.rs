@@ -137,9 +137,15 @@
 //! let src = array.borrow_channels(|(_id, dist)| dist);
 //! copy_extent(&extent, &src, &mut dst);
 //! ```
+//!
+//! There are aliases like `Array3x4` for up to 6 channels in 2 or 3 dimensions, and `ArrayNx1` through `ArrayNx6` for any
+//! dimension. Each channel can be initialized separately with `Array::fill_channels_with`, and a `ChunkMap` of multichannel
+//! chunks is built with the matching `ChunkMapBuilderNxM` alias, e.g. `ChunkMapBuilder3x4`.
 
 mod coords;
+mod double_buffered;
 mod indexer;
+mod neighborhood;
 
 pub mod channels;
 pub mod compression;
@@ -153,14 +159,16 @@ mod image_conversions;
 
 pub use channels::Channel;
 pub use coords::*;
+pub use double_buffered::*;
 pub use indexer::IndexedArray;
+pub use neighborhood::Neighborhood26;
 
 pub(crate) use channels::*;
 pub(crate) use for_each::*;
 pub(crate) use indexer::*;
 
 use crate::{
-    chunk::ChunkCopySrc,
+    chunk::{ChunkCopySrc, ChunkMapLodView, SampledChunkCopySrc},
     dev_prelude::{
         FillExtent, ForEach, ForEachMut, ForEachMutPtr, Get, GetMut, GetMutPtr, GetRef, ReadExtent,
         TransformMap, WriteExtent,
@@ -172,12 +180,14 @@ use crate::{
 use building_blocks_core::prelude::*;
 
 use core::iter::{once, Once};
-use core::ops::{Add, Deref};
+use core::ops::{Add, Deref, DerefMut};
 use either::Either;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// A map from lattice location `PointN<N>` to data `T`, stored as a flat array.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Array<N, Chan> {
     channels: Chan,
     extent: ExtentN<N>,
@@ -195,6 +205,8 @@ macro_rules! array_type_alias {
     };
 }
 
+// Aliases for arrays of any dimension `N` with 1 to 6 channels. `Array` itself accepts any tuple of up to 6 `Channel`s, so
+// these only save typing.
 pub type ArrayNx1<N, A, S1 = Vec<A>> = Array<N, Channel<A, S1>>;
 array_n_type_alias!(ArrayNx2, A: S1, B: S2);
 array_n_type_alias!(ArrayNx3, A: S1, B: S2, C: S3);
@@ -250,6 +262,15 @@ impl<N, Chan> Array<N, Chan> {
     pub fn channels_mut(&mut self) -> &mut Chan {
         &mut self.channels
     }
+
+    /// The number of channels in this array. Same as `Chan::CHANNELS`.
+    #[inline]
+    pub fn num_channels(&self) -> usize
+    where
+        Chan: Channels,
+    {
+        Chan::CHANNELS
+    }
 }
 
 impl<N, Chan> IndexedArray<N> for Array<N, Chan>
@@ -362,6 +383,28 @@ where
     }
 }
 
+impl<N, Chan> Array<N, Chan>
+where
+    PointN<N>: IntegerPoint<N>,
+{
+    /// Create a new array for `extent` where each channel is filled by its own function of the point. For a tuple of channels,
+    /// `fillers` is a tuple with one `FnMut(PointN<N>) -> T` per channel.
+    ///
+    /// ```
+    /// # use building_blocks_core::prelude::*;
+    /// # use building_blocks_storage::prelude::*;
+    /// let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(4));
+    /// let array = Array3x2::fill_channels_with(extent, (|p: Point3i| p.x() as u8, |p: Point3i| p.z() as f32));
+    /// assert_eq!(array.get(PointN([1, 2, 3])), (1, 3.0));
+    /// ```
+    pub fn fill_channels_with<Fillers>(extent: ExtentN<N>, fillers: Fillers) -> Self
+    where
+        Chan: FillChannelsWith<N, Fillers>,
+    {
+        Self::new(extent, Chan::fill_with(&extent, fillers))
+    }
+}
+
 impl<N, Chan, UninitChan> Array<N, Chan>
 where
     Array<N, UninitChan>: ForEachMutPtr<N, PointN<N>, Item = UninitChan::Ptr>,
@@ -419,6 +462,45 @@ where
     }
 }
 
+impl<T, Store> ArrayNx1<[i32; 3], T, Store>
+where
+    Store: DerefMut<Target = [T]>,
+{
+    /// Mutably borrow each contiguous row of values along the X axis within `extent`, which is clamped to the array's extent.
+    /// Rows are yielded in the same order as iterating over points, i.e. Y varies before Z. This is useful for writing whole
+    /// rows at once with `copy_from_slice`.
+    pub fn rows_mut(&mut self, extent: &Extent3i) -> impl Iterator<Item = &mut [T]> {
+        let array_extent = self.extent;
+        let extent = extent.intersection(&array_extent);
+        let row_len = extent.shape.x() as usize;
+        let row_starts = Extent3i::from_min_and_shape(
+            extent.minimum,
+            PointN([1, extent.shape.y(), extent.shape.z()]),
+        )
+        .iter_points()
+        .map(move |p| {
+            let local = p - array_extent.minimum;
+            let shape = array_extent.shape;
+
+            (local.x() + shape.x() * (local.y() + shape.y() * local.z())) as usize
+        });
+
+        // Rows are strictly increasing in memory, so we can keep splitting them off of the remaining values.
+        let mut rest: &mut [T] = self.channels_mut().store_mut();
+        let mut rest_start = 0;
+        row_starts
+            .filter(move |_| row_len > 0)
+            .map(move |row_start| {
+                let (_, tail) = core::mem::take(&mut rest).split_at_mut(row_start - rest_start);
+                let (row, tail) = tail.split_at_mut(row_len);
+                rest = tail;
+                rest_start = row_start + row_len;
+
+                row
+            })
+    }
+}
+
 //  ██████╗ ███████╗████████╗████████╗███████╗██████╗ ███████╗
 // ██╔════╝ ██╔════╝╚══██╔══╝╚══██╔══╝██╔════╝██╔══██╗██╔════╝
 // ██║  ███╗█████╗     ██║      ██║   █████╗  ██████╔╝███████╗
@@ -766,6 +848,107 @@ impl_array_for_each!(
     forwarder = |_p, _stride| ();
 );
 
+impl<Chan> Array<[i32; 3], Chan>
+where
+    Self: GetUnchecked<Stride>,
+{
+    /// Like `ForEach::for_each`, but visits the points of `iter_extent` in the order given by `axis_order`, where
+    /// `axis_order[0]` is the fastest-varying axis. So `[Axis3::X, Axis3::Y, Axis3::Z]` matches the order of `for_each`, and
+    /// `[Axis3::Z, Axis3::Y, Axis3::X]` visits in Z-major order.
+    ///
+    /// # Panics
+    /// If `axis_order` repeats an axis.
+    pub fn for_each_ordered(
+        &self,
+        iter_extent: &Extent3i,
+        axis_order: [Axis3; 3],
+        mut f: impl FnMut(Point3i, <Self as GetUnchecked<Stride>>::Item),
+    ) {
+        for_each_stride_ordered(self.extent(), iter_extent, axis_order, |p, stride| {
+            // This is safe because `p` is inside of the array's extent.
+            f(p, unsafe { self.get_unchecked(stride) })
+        })
+    }
+}
+
+impl<T, Store> Array<[i32; 3], Channel<T, Store>>
+where
+    T: Clone,
+{
+    /// Copies every value into `out`, which is laid out with `axis_order[0]` as the fastest-varying axis, as in
+    /// `for_each_ordered`. So `[Axis3::X, Axis3::Y, Axis3::Z]` gives a row-major buffer with Z outermost and X innermost, which
+    /// is the same as the layout of this array.
+    ///
+    /// # Panics
+    /// If `out.len()` is not the number of points in the array, or `axis_order` repeats an axis.
+    pub fn copy_to_row_major(&self, out: &mut [T], axis_order: [Axis3; 3])
+    where
+        Store: AsRef<[T]>,
+    {
+        assert_eq!(out.len(), self.extent().num_points());
+
+        let values = self.channels().store().as_ref();
+        let mut out_values = out.iter_mut();
+        for_each_stride_ordered(self.extent(), self.extent(), axis_order, |_p, stride| {
+            *out_values.next().unwrap() = values[stride.0].clone();
+        });
+    }
+
+    /// The inverse of `copy_to_row_major`: copies every value from `src`, which is laid out with `axis_order[0]` as the
+    /// fastest-varying axis.
+    ///
+    /// # Panics
+    /// If `src.len()` is not the number of points in the array, or `axis_order` repeats an axis.
+    pub fn copy_from_row_major(&mut self, src: &[T], axis_order: [Axis3; 3])
+    where
+        Store: AsMut<[T]>,
+    {
+        assert_eq!(src.len(), self.extent().num_points());
+
+        let extent = *self.extent();
+        let values = self.channels_mut().store_mut().as_mut();
+        let mut src_values = src.iter();
+        for_each_stride_ordered(&extent, &extent, axis_order, |_p, stride| {
+            values[stride.0] = src_values.next().unwrap().clone();
+        });
+    }
+}
+
+/// Visits the points of `iter_extent` that are inside of `array_extent` in the order given by `axis_order`, along with their
+/// strides in an array with `array_extent`.
+fn for_each_stride_ordered(
+    array_extent: &Extent3i,
+    iter_extent: &Extent3i,
+    axis_order: [Axis3; 3],
+    mut f: impl FnMut(Point3i, Stride),
+) {
+    let [a0, a1, a2] = axis_order;
+    assert!(
+        a0 != a1 && a1 != a2 && a0 != a2,
+        "Axis order must be a permutation"
+    );
+    let (a0, a1, a2) = (a0.index(), a1.index(), a2.index());
+
+    let extent = iter_extent.intersection(array_extent);
+    let shape = extent.shape;
+    for i2 in 0..shape.at(a2) {
+        for i1 in 0..shape.at(a1) {
+            for i0 in 0..shape.at(a0) {
+                let mut p = extent.minimum;
+                p.0[a0] += i0;
+                p.0[a1] += i1;
+                p.0[a2] += i2;
+                let stride = <[i32; 3]>::stride_from_local_point(
+                    array_extent.shape,
+                    Local(p - array_extent.minimum),
+                );
+
+                f(p, stride)
+            }
+        }
+    }
+}
+
 //  ██████╗ ██████╗ ██████╗ ██╗   ██╗
 // ██╔════╝██╔═══██╗██╔══██╗╚██╗ ██╔╝
 // ██║     ██║   ██║██████╔╝ ╚████╔╝
@@ -870,7 +1053,9 @@ fn unchecked_copy_extent_between_arrays<Dst, Src, N, Ptr>(
 
 impl<N, Chan, Ch> WriteExtent<N, ChunkCopySrc<N, Chan::Data, Ch>> for Array<N, Chan>
 where
-    Self: ForEachMutPtr<N, (), Item = Chan::Ptr> + WriteExtent<N, ArrayCopySrc<Ch>>,
+    Self: ForEachMutPtr<N, (), Item = Chan::Ptr>
+        + WriteExtent<N, ArrayCopySrc<Ch>>
+        + ForEachMutPtr<N, PointN<N>, Item = Chan::Ptr>,
     PointN<N>: IntegerPoint<N>,
     Chan: ResetChannels,
     Chan::Data: Clone,
@@ -878,7 +1063,31 @@ where
     fn write_extent(&mut self, extent: &ExtentN<N>, src: ChunkCopySrc<N, Chan::Data, Ch>) {
         match src {
             Either::Left(array) => self.write_extent(extent, array),
-            Either::Right(ambient) => self.fill_extent(extent, ambient.get()),
+            Either::Right(ambient) if ambient.is_constant() => {
+                self.fill_extent(extent, ambient.get())
+            }
+            Either::Right(ambient) => self.write_extent(extent, |p| ambient.get_at(p)),
+        }
+    }
+}
+
+impl<'a, N, Chan, Delegate, Ch> WriteExtent<N, SampledChunkCopySrc<'a, N, Chan::Data, Delegate, Ch>>
+    for Array<N, Chan>
+where
+    Self: WriteExtent<N, ChunkCopySrc<N, Chan::Data, Ch>>
+        + ForEachMutPtr<N, PointN<N>, Item = Chan::Ptr>,
+    PointN<N>: IntegerPoint<N>,
+    Chan: Channels,
+    ChunkMapLodView<Delegate>: Get<PointN<N>, Item = Chan::Data>,
+{
+    fn write_extent(
+        &mut self,
+        extent: &ExtentN<N>,
+        src: SampledChunkCopySrc<'a, N, Chan::Data, Delegate, Ch>,
+    ) {
+        match src {
+            Either::Left(chunk_src) => self.write_extent(extent, chunk_src),
+            Either::Right(clamp_src) => self.write_extent(extent, |p| clamp_src.get(p)),
         }
     }
 }
@@ -906,9 +1115,63 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::prelude::{copy_extent, Array2x1, Array3x1};
+    use crate::prelude::{
+        copy_extent, Array2x1, Array3x1, Array3x2, Array3x3, ChunkMapBuilder, ChunkMapBuilder3x3,
+    };
     use core::mem::MaybeUninit;
 
+    #[test]
+    fn for_each_ordered_z_major() {
+        let extent = Extent3i::from_min_and_shape(PointN([1, 2, 3]), PointN([2, 3, 2]));
+        let array = Array3x1::fill_with(extent, |p| p);
+
+        let mut default_order = Vec::new();
+        array.for_each_ordered(&extent, [Axis3::X, Axis3::Y, Axis3::Z], |p, v| {
+            assert_eq!(p, v);
+            default_order.push(p);
+        });
+        let for_each_order: Vec<_> = extent.iter_points().collect();
+        assert_eq!(default_order, for_each_order);
+
+        let mut z_major = Vec::new();
+        array.for_each_ordered(&extent, [Axis3::Z, Axis3::Y, Axis3::X], |p, _| {
+            z_major.push(p)
+        });
+        let mut expected = Vec::new();
+        for x in 1..3 {
+            for y in 2..5 {
+                for z in 3..5 {
+                    expected.push(PointN([x, y, z]));
+                }
+            }
+        }
+        assert_eq!(z_major, expected);
+    }
+
+    #[test]
+    fn row_major_round_trip() {
+        let extent = Extent3i::from_min_and_shape(PointN([-1, 2, 3]), PointN([2, 3, 4]));
+        let array = Array3x1::fill_with(extent, |p| p);
+        let shape = extent.shape;
+
+        // X is outermost and Z is innermost.
+        let mut buffer = vec![Point3i::ZERO; extent.num_points()];
+        array.copy_to_row_major(&mut buffer, [Axis3::Z, Axis3::Y, Axis3::X]);
+        for p in extent.iter_points() {
+            let l = p - extent.minimum;
+            let index = l.z() + shape.z() * (l.y() + shape.y() * l.x());
+            assert_eq!(buffer[index as usize], p);
+        }
+
+        let mut copy = Array3x1::fill(extent, Point3i::ZERO);
+        copy.copy_from_row_major(&buffer, [Axis3::Z, Axis3::Y, Axis3::X]);
+        assert_eq!(copy, array);
+
+        // The default order is the same as the array's own layout.
+        array.copy_to_row_major(&mut buffer, [Axis3::X, Axis3::Y, Axis3::Z]);
+        assert_eq!(&buffer, array.channels().store());
+    }
+
     #[test]
     fn fill_and_get_2d() {
         let extent = Extent2i::from_min_and_shape(PointN([1, 1]), PointN([10, 10]));
@@ -943,6 +1206,78 @@ mod tests {
         assert_eq!(array.get_mut(PointN([1, 1, 1])), &mut 1);
     }
 
+    #[test]
+    fn fill_rows_with_slices() {
+        let array_extent = Extent3i::from_min_and_shape(Point3i::fill(-2), PointN([5, 4, 3]));
+        let write_extent = Extent3i::from_min_and_shape(PointN([-1, 0, -4]), PointN([10, 3, 4]));
+        let value = |x: i32| x * 10 + 1;
+
+        let mut scalar_filled = Array3x1::fill(array_extent, 0);
+        let clamped_extent = write_extent.intersection(&array_extent);
+        for p in clamped_extent.iter_points() {
+            *scalar_filled.get_mut(p) = value(p.x());
+        }
+
+        let mut row_filled = Array3x1::fill(array_extent, 0);
+        let row: Vec<_> = (clamped_extent.minimum.x()..clamped_extent.least_upper_bound().x())
+            .map(value)
+            .collect();
+        let mut num_rows = 0;
+        for dst_row in row_filled.rows_mut(&write_extent) {
+            dst_row.copy_from_slice(&row);
+            num_rows += 1;
+        }
+
+        assert_eq!(num_rows, 4);
+        assert_eq!(row_filled, scalar_filled);
+    }
+
+    #[test]
+    fn generic_channel_count() {
+        fn count<N, Chan: Channels>(array: &Array<N, Chan>) -> (usize, usize) {
+            (Chan::CHANNELS, array.num_channels())
+        }
+
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(2));
+        assert_eq!(count(&Array3x1::fill(extent, 0u8)), (1, 1));
+        assert_eq!(count(&Array3x2::fill(extent, (0u8, 0.0f32))), (2, 2));
+    }
+
+    #[test]
+    fn stride_iter_matches_stride_from_local_point() {
+        let array_extent = Extent3i::from_min_and_shape(PointN([-2, 1, 3]), PointN([7, 5, 6]));
+        let array = Array3x1::fill(array_extent, 0u8);
+        // Sticks out of the array, so it must be clipped.
+        let iter_extent = Extent3i::from_min_and_shape(PointN([0, 2, 1]), PointN([4, 3, 10]));
+
+        let clipped_extent = iter_extent.intersection(&array_extent);
+        let expected: Vec<_> = clipped_extent
+            .iter_points()
+            .map(|p| {
+                let local = Local(p - array_extent.minimum);
+                (local, array.stride_from_local_point(local))
+            })
+            .collect();
+        let actual: Vec<_> = array.stride_iter(&iter_extent).collect();
+        assert_eq!(actual, expected);
+
+        let empty = Extent3i::from_min_and_shape(PointN([100, 0, 0]), Point3i::ONES);
+        assert_eq!(array.stride_iter(&empty).count(), 0);
+
+        let array_extent = Extent2i::from_min_and_shape(PointN([3, -1]), PointN([5, 4]));
+        let array = Array2x1::fill(array_extent, 0u8);
+        let iter_extent = Extent2i::from_min_and_shape(PointN([4, 0]), PointN([3, 2]));
+        let expected: Vec<_> = iter_extent
+            .iter_points()
+            .map(|p| {
+                let local = Local(p - array_extent.minimum);
+                (local, array.stride_from_local_point(local))
+            })
+            .collect();
+        let actual: Vec<_> = array.stride_iter(&iter_extent).collect();
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn fill_and_for_each_2d() {
         let extent = Extent2i::from_min_and_shape(Point2i::fill(1), Point2i::fill(10));
@@ -1007,6 +1342,30 @@ mod tests {
         assert_eq!(array, other_array);
     }
 
+    #[test]
+    fn empty_extent_operations_are_no_ops() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(10));
+        let src = Array3x1::fill_with(extent, |p| p.x() + p.y() + p.z());
+        let mut dst = Array3x1::fill(extent, -1);
+        let original_dst = dst.clone();
+
+        for &shape in [PointN([0, 5, 5]), PointN([-2, 5, 5]), PointN([-2, -2, 5])].iter() {
+            let empty = Extent3i::from_min_and_shape(Point3i::fill(3), shape);
+            assert!(empty.is_empty());
+
+            copy_extent(&empty, &src, &mut dst);
+            dst.fill_extent(&empty, 7);
+            dst.for_each(&empty, |_: Point3i, _| {
+                panic!("Visited a point in an empty extent")
+            });
+            dst.for_each_mut(&empty, |_: Point3i, _| {
+                panic!("Visited a point in an empty extent")
+            });
+        }
+
+        assert_eq!(dst, original_dst);
+    }
+
     #[test]
     fn multichannel_get() {
         let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(10));
@@ -1062,6 +1421,47 @@ mod tests {
         copy_extent(&extent, &other, &mut borrowed);
     }
 
+    #[test]
+    fn three_channels_are_filled_and_written_independently() {
+        let extent = Extent3i::from_min_and_shape(PointN([-2, 0, 3]), PointN([4, 3, 2]));
+        let mut array = Array3x3::fill_channels_with(
+            extent,
+            (
+                |p: Point3i| p.x() as i8,
+                |p: Point3i| p.y() as f32,
+                |p: Point3i| p.z() == 3,
+            ),
+        );
+        assert_eq!(array.num_channels(), 3);
+        for p in extent.iter_points() {
+            assert_eq!(array.get(p), (p.x() as i8, p.y() as f32, p.z() == 3));
+        }
+
+        // Write the second channel only.
+        array
+            .borrow_channels_mut(|(_, c2, _)| c2)
+            .for_each_mut(&extent, |p: Point3i, y| *y += 0.5 * p.x() as f32);
+
+        let c1 = array.borrow_channels(|(c1, _, _)| c1);
+        let c2 = array.borrow_channels(|(_, c2, _)| c2);
+        let c3 = array.borrow_channels(|(_, _, c3)| c3);
+        for p in extent.iter_points() {
+            assert_eq!(c1.get(p), p.x() as i8);
+            assert_eq!(c2.get(p), p.y() as f32 + 0.5 * p.x() as f32);
+            assert_eq!(c3.get(p), p.z() == 3);
+        }
+
+        // A chunk map of 3-channel chunks uses the matching builder.
+        let builder = ChunkMapBuilder3x3::new(Point3i::fill(4), (0i8, 0.0f32, false));
+        let mut map = builder.build_with_hash_map_storage();
+        map.fill_extent(0, &extent, (1, 2.0, true));
+        assert_eq!(map.clone_point(0, extent.minimum), (1, 2.0, true));
+        assert_eq!(
+            map.clone_point(0, extent.minimum - Point3i::ONES),
+            (0, 0.0, false)
+        );
+    }
+
     #[test]
     fn multichannel_for_each() {
         let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(10));
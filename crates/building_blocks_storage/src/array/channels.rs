@@ -8,8 +8,13 @@ pub use multichannel::*;
 
 use crate::multi_ptr::MultiMutPtr;
 
+use building_blocks_core::prelude::*;
+
 /// Implemented by any tuple of `Channel`s to indicate the types of data being stored.
 pub trait Channels {
+    /// The number of `Channel`s, which is 1 for a single `Channel`, or the total number of `Channel`s in a tuple.
+    const CHANNELS: usize;
+
     type Data;
     type Ptr: MultiMutPtr<Data = Self::Data>;
     type UninitSelf: UninitChannels;
@@ -57,6 +62,12 @@ pub trait FillChannels: Channels {
     fn fill(length: usize, value: Self::Data) -> Self;
 }
 
+/// Fills every channel with its own function of the point, e.g. a tuple of `Channel`s from a tuple of closures.
+pub trait FillChannelsWith<N, Fillers>: Channels {
+    /// The values are in the same order as the points of `extent.iter_points()`, which is the memory order of an `Array`.
+    fn fill_with(extent: &ExtentN<N>, fillers: Fillers) -> Self;
+}
+
 pub trait UninitChannels: Channels {
     type InitSelf;
 
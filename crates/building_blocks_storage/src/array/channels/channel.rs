@@ -1,16 +1,20 @@
 use crate::{
     array::{
-        BorrowChannels, BorrowChannelsMut, CopySlices, FillChannels, ResetChannels, Slices,
-        SlicesMut, UninitChannels,
+        BorrowChannels, BorrowChannelsMut, CopySlices, FillChannels, FillChannelsWith,
+        ResetChannels, Slices, SlicesMut, UninitChannels,
     },
     dev_prelude::{Channels, GetMut, GetMutPtr, GetRef},
     prelude::{GetMutUnchecked, GetRefUnchecked},
 };
 
+use building_blocks_core::prelude::*;
+
 use core::mem::MaybeUninit;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Channel<T, Store = Vec<T>> {
     store: Store,
     marker: std::marker::PhantomData<T>,
@@ -73,6 +77,8 @@ where
 }
 
 impl<T, Store> Channels for Channel<T, Store> {
+    const CHANNELS: usize = 1;
+
     type Data = T;
     type Ptr = *mut T;
     type UninitSelf = Channel<MaybeUninit<T>>;
@@ -143,6 +149,16 @@ where
     }
 }
 
+impl<N, T, F> FillChannelsWith<N, F> for Channel<T>
+where
+    PointN<N>: IntegerPoint<N>,
+    F: FnMut(PointN<N>) -> T,
+{
+    fn fill_with(extent: &ExtentN<N>, filler: F) -> Self {
+        Self::new(extent.iter_points().map(filler).collect())
+    }
+}
+
 impl<T, Store> ResetChannels for Channel<T, Store>
 where
     T: Clone,
@@ -1,6 +1,7 @@
 use crate::dev_prelude::{BytesCompression, Channel, Compression, FromBytesCompression};
 
-use bytemuck::{bytes_of, bytes_of_mut, cast_slice, cast_slice_mut, Pod};
+use bytemuck::{cast_slice, cast_slice_mut, Pod, Zeroable};
+use std::convert::TryFrom;
 use std::io;
 
 /// Compresses a tuple of `Channel`s into a tuple of `FastCompressedChannel`s.
@@ -42,45 +43,419 @@ impl<By, Chan> FromBytesCompression<By> for FastChannelsCompression<By, Chan> {
     }
 }
 
+/// A `Pod` type that is compressed as little-endian bytes, so compressed channels are portable across platforms with different
+/// endianness.
+///
+/// This is implemented for the primitive numeric types, `Sd8`, `Sd16`, and small arrays of them. A custom `Pod` voxel type can
+/// implement it by converting each of its fields with `to_le`.
+pub trait FixedEndian: Pod {
+    /// Converts between native and little-endian byte order. This is a no-op on little-endian targets, and it's its own
+    /// inverse, so it also converts from little-endian back to native order.
+    fn to_le(self) -> Self;
+}
+
+macro_rules! impl_fixed_endian_for_int {
+    ($($t:ty),+) => {
+        $(
+            impl FixedEndian for $t {
+                #[inline]
+                fn to_le(self) -> Self {
+                    <$t>::to_le(self)
+                }
+            }
+        )+
+    };
+}
+
+impl_fixed_endian_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl FixedEndian for f32 {
+    #[inline]
+    fn to_le(self) -> Self {
+        f32::from_bits(self.to_bits().to_le())
+    }
+}
+
+impl FixedEndian for f64 {
+    #[inline]
+    fn to_le(self) -> Self {
+        f64::from_bits(self.to_bits().to_le())
+    }
+}
+
+macro_rules! impl_fixed_endian_for_array {
+    ($($n:literal),+) => {
+        $(
+            impl<T: FixedEndian> FixedEndian for [T; $n] {
+                #[inline]
+                fn to_le(mut self) -> Self {
+                    for value in self.iter_mut() {
+                        *value = value.to_le();
+                    }
+
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_fixed_endian_for_array!(1, 2, 3, 4);
+
+/// Converts `values` from little-endian to native byte order in place.
+pub(crate) fn values_from_le<T: FixedEndian>(values: &mut [T]) {
+    if cfg!(target_endian = "big") {
+        for value in values.iter_mut() {
+            *value = value.to_le();
+        }
+    }
+}
+
+/// Calls `f` with the little-endian bytes of `values`. This only copies `values` on big-endian targets.
+pub(crate) fn with_le_bytes<T: FixedEndian, R>(values: &[T], f: impl FnOnce(&[u8]) -> R) -> R {
+    if cfg!(target_endian = "little") {
+        f(cast_slice(values))
+    } else {
+        let le_values: Vec<T> = values.iter().map(|v| v.to_le()).collect();
+
+        f(cast_slice(le_values.as_slice()))
+    }
+}
+
 impl<By, T> Compression for FastChannelsCompression<By, Channel<T>>
 where
     By: BytesCompression,
-    T: Pod,
+    T: FixedEndian,
 {
     type Data = Channel<T>;
 
     // Compress the map using some `B: BytesCompression`.
     //
-    // WARNING: For performance, this reinterprets the inner vector as a byte slice without accounting for endianness. This is
-    // not compatible across platforms.
+    // On little-endian targets, the values are reinterpreted as bytes without copying. Big-endian targets have to convert a
+    // copy of the values to little-endian first.
     fn compress_to_writer(
         &self,
         data: &Self::Data,
         mut compressed_bytes: impl io::Write,
     ) -> io::Result<()> {
-        // Start with the number of values in the channel so we can allocate that up front during decompression.
-        compressed_bytes.write_all(bytes_of(&data.store().len()))?;
+        // Start with the number of values in the channel so we can allocate that up front during decompression. This is
+        // always a little-endian u64, regardless of the target.
+        compressed_bytes.write_all(&(data.store().len() as u64).to_le_bytes())?;
 
         // Compress the values.
+        with_le_bytes(data.store().as_slice(), |bytes| {
+            self.bytes_compression
+                .compress_bytes(bytes, compressed_bytes)
+        })
+    }
+
+    fn decompress_from_reader(compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
+        decompress_values::<By, T>(compressed_bytes, None)
+    }
+
+    fn decompress_into_from_reader(
+        compressed_bytes: impl io::Read,
+        data: &mut Self::Data,
+    ) -> io::Result<()> {
+        decompress_values_into::<By, T>(compressed_bytes, None, data)
+    }
+}
+
+impl<By, T> ChannelsCompression for FastChannelsCompression<By, Channel<T>>
+where
+    By: BytesCompression,
+    T: FixedEndian,
+{
+    fn decompress_channels_from_reader(
+        compressed_bytes: impl io::Read,
+        num_points: usize,
+    ) -> io::Result<Self::Data> {
+        decompress_values::<By, T>(compressed_bytes, Some(num_points))
+    }
+
+    fn decompress_channels_into_from_reader(
+        compressed_bytes: impl io::Read,
+        num_points: usize,
+        data: &mut Self::Data,
+    ) -> io::Result<()> {
+        decompress_values_into::<By, T>(compressed_bytes, Some(num_points), data)
+    }
+
+    fn uncompressed_channels_len(num_points: usize) -> Option<usize> {
+        num_points.checked_mul(std::mem::size_of::<T>())
+    }
+}
+
+/// A `Compression` for the channels of an `Array`. `FastArrayCompression` uses this to check the number of values stored for
+/// each channel against the number of points in the array's extent, so corrupt bytes can't make it allocate an arbitrary
+/// amount of memory.
+pub trait ChannelsCompression: Compression {
+    /// Same as `decompress_from_reader`, but fails with `io::ErrorKind::InvalidData` unless every channel has `num_points`
+    /// values.
+    fn decompress_channels_from_reader(
+        compressed_bytes: impl io::Read,
+        num_points: usize,
+    ) -> io::Result<Self::Data>;
+
+    /// Same as `decompress_into_from_reader`, but fails with `io::ErrorKind::InvalidData` unless every channel has
+    /// `num_points` values.
+    fn decompress_channels_into_from_reader(
+        compressed_bytes: impl io::Read,
+        num_points: usize,
+        data: &mut Self::Data,
+    ) -> io::Result<()>;
+
+    /// The number of bytes occupied by the values of every channel when each has `num_points` values, if this is known. By
+    /// default, this is unknown.
+    fn uncompressed_channels_len(_num_points: usize) -> Option<usize> {
+        None
+    }
+}
+
+fn decompress_values<By, T>(
+    mut compressed_bytes: impl io::Read,
+    expected_num_values: Option<usize>,
+) -> io::Result<Channel<T>>
+where
+    By: BytesCompression,
+    T: FixedEndian,
+{
+    let num_values = read_num_values(
+        &mut compressed_bytes,
+        expected_num_values,
+        std::mem::size_of::<T>(),
+    )?;
+
+    // Allocate the vector with element type T so the alignment is correct.
+    let mut decompressed_values: Vec<T> = Vec::with_capacity(num_values);
+    unsafe { decompressed_values.set_len(num_values) };
+
+    // Decompress the values by consuming the rest of the bytes.
+    By::decompress_bytes(
+        compressed_bytes,
+        cast_slice_mut(decompressed_values.as_mut_slice()),
+    )?;
+    values_from_le(&mut decompressed_values);
+
+    Ok(Channel::new(decompressed_values))
+}
+
+fn decompress_values_into<By, T>(
+    mut compressed_bytes: impl io::Read,
+    expected_num_values: Option<usize>,
+    data: &mut Channel<T>,
+) -> io::Result<()>
+where
+    By: BytesCompression,
+    T: FixedEndian,
+{
+    let num_values = read_num_values(
+        &mut compressed_bytes,
+        expected_num_values,
+        std::mem::size_of::<T>(),
+    )?;
+
+    // Reuse the existing allocation. Only new elements need to be initialized, since the rest is overwritten below.
+    let values = data.store_mut();
+    values.resize(num_values, T::zeroed());
+
+    By::decompress_bytes(compressed_bytes, cast_slice_mut(values.as_mut_slice()))?;
+    values_from_le(values);
+
+    Ok(())
+}
+
+/// Reads the little-endian u64 number of values that precedes the values of a compressed channel.
+///
+/// Fails with `io::ErrorKind::InvalidData` if the number doesn't match `expected_num_values`, or if that many values of
+/// `value_size` bytes could never be allocated.
+fn read_num_values(
+    mut compressed_bytes: impl io::Read,
+    expected_num_values: Option<usize>,
+    value_size: usize,
+) -> io::Result<usize> {
+    let mut num_values_bytes = [0; 8];
+    compressed_bytes.read_exact(&mut num_values_bytes)?;
+    let num_values = u64::from_le_bytes(num_values_bytes);
+
+    let num_values = usize::try_from(num_values)
+        .ok()
+        .filter(|&n| {
+            n.checked_mul(value_size)
+                .map_or(false, |num_bytes| num_bytes <= isize::MAX as usize)
+        })
+        .ok_or_else(|| invalid_data(format!("Channel has too many values: {}", num_values)))?;
+    if let Some(expected) = expected_num_values {
+        if num_values != expected {
+            return Err(invalid_data(format!(
+                "Channel has {} values, but the array has {} points",
+                num_values, expected
+            )));
+        }
+    }
+
+    Ok(num_values)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Compresses a `Channel<bool>` by packing 8 values into each byte before applying the `BytesCompression`.
+///
+/// Unlike `FastChannelsCompression`, the packed bytes don't depend on endianness, so they are portable across platforms.
+pub struct BitPackedCompression<By> {
+    bytes_compression: By,
+}
+
+impl<By> Clone for BitPackedCompression<By>
+where
+    By: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            bytes_compression: self.bytes_compression.clone(),
+        }
+    }
+}
+
+impl<By> Copy for BitPackedCompression<By> where By: Copy {}
+
+impl<By> BitPackedCompression<By> {
+    pub fn new(bytes_compression: By) -> Self {
+        Self { bytes_compression }
+    }
+
+    pub fn bytes_compression(&self) -> &By {
+        &self.bytes_compression
+    }
+}
+
+impl<By> FromBytesCompression<By> for BitPackedCompression<By> {
+    fn from_bytes_compression(bytes_compression: By) -> Self {
+        Self::new(bytes_compression)
+    }
+}
+
+impl<By> Compression for BitPackedCompression<By>
+where
+    By: BytesCompression,
+{
+    type Data = Channel<bool>;
+
+    fn compress_to_writer(
+        &self,
+        data: &Self::Data,
+        mut compressed_bytes: impl io::Write,
+    ) -> io::Result<()> {
+        // Start with the number of values in the channel, since this isn't recoverable from the number of packed bytes.
+        let values = data.store();
+        compressed_bytes.write_all(&(values.len() as u64).to_le_bytes())?;
+
+        // Pack the values, least significant bit first. The unused bits of the last byte are zero.
+        let packed: Vec<u8> = values
+            .chunks(8)
+            .map(|bits| {
+                bits.iter()
+                    .enumerate()
+                    .fold(0, |byte, (i, &bit)| byte | ((bit as u8) << i))
+            })
+            .collect();
+
         self.bytes_compression
-            .compress_bytes(cast_slice(data.store().as_slice()), compressed_bytes)
+            .compress_bytes(packed.as_slice(), compressed_bytes)
+    }
+
+    fn decompress_from_reader(compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
+        decompress_bits::<By>(compressed_bytes, None)
+    }
+}
+
+impl<By> ChannelsCompression for BitPackedCompression<By>
+where
+    By: BytesCompression,
+{
+    fn decompress_channels_from_reader(
+        compressed_bytes: impl io::Read,
+        num_points: usize,
+    ) -> io::Result<Self::Data> {
+        decompress_bits::<By>(compressed_bytes, Some(num_points))
+    }
+
+    fn decompress_channels_into_from_reader(
+        compressed_bytes: impl io::Read,
+        num_points: usize,
+        data: &mut Self::Data,
+    ) -> io::Result<()> {
+        *data = decompress_bits::<By>(compressed_bytes, Some(num_points))?;
+
+        Ok(())
     }
 
-    fn decompress_from_reader(mut compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
-        // Extract the number of values in the original channel.
-        let mut num_values = 0usize;
-        compressed_bytes.read_exact(bytes_of_mut(&mut num_values))?;
+    fn uncompressed_channels_len(num_points: usize) -> Option<usize> {
+        Some(num_points)
+    }
+}
+
+fn decompress_bits<By>(
+    mut compressed_bytes: impl io::Read,
+    expected_num_values: Option<usize>,
+) -> io::Result<Channel<bool>>
+where
+    By: BytesCompression,
+{
+    let num_values = read_num_values(&mut compressed_bytes, expected_num_values, 1)?;
+    // `read_num_values` already bounds `num_values`, but the rounding up must not overflow either.
+    let num_packed_bytes = num_values
+        .checked_add(7)
+        .ok_or_else(|| invalid_data(format!("Channel has too many values: {}", num_values)))?
+        / 8;
+
+    let mut packed = Vec::with_capacity(num_packed_bytes);
+    By::decompress_bytes(compressed_bytes, &mut packed)?;
+    if packed.len() < num_packed_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Too few packed bytes for the number of values",
+        ));
+    }
+
+    let values = (0..num_values)
+        .map(|i| packed[i / 8] & (1 << (i % 8)) != 0)
+        .collect();
+
+    Ok(Channel::new(values))
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "lz4"))]
+mod tests {
+    use super::*;
+
+    use crate::prelude::Lz4;
+
+    #[test]
+    fn bit_packed_sparse_channel_round_trip_is_smaller() {
+        // A sparse pattern that isn't byte-aligned, with a partial last byte.
+        let num_values = 32 * 32 * 32 + 3;
+        let values: Vec<bool> = (0..num_values).map(|i| (i * 7919) % 61 == 0).collect();
+        let channel = Channel::new(values.clone());
 
-        // Allocate the vector with element type T so the alignment is correct.
-        let mut decompressed_values: Vec<T> = Vec::with_capacity(num_values);
-        unsafe { decompressed_values.set_len(num_values) };
+        let lz4 = Lz4 { level: 10 };
+        let compressed = BitPackedCompression::new(lz4).compress(&channel);
+        let decompressed = compressed.decompress();
+        assert_eq!(decompressed.store(), &values);
 
-        // Decompress the values by consuming the rest of the bytes.
-        By::decompress_bytes(
-            compressed_bytes,
-            cast_slice_mut(decompressed_values.as_mut_slice()),
-        )?;
+        // The naive path stores one byte per value.
+        let naive_channel = Channel::new(values.iter().map(|&v| v as u8).collect::<Vec<u8>>());
+        let naive_compressed = FastChannelsCompression::new(lz4).compress(&naive_channel);
 
-        Ok(Channel::new(decompressed_values))
+        assert!(compressed.compressed_len() < naive_compressed.compressed_len());
     }
 }
@@ -1,11 +1,14 @@
 use crate::{
     array::{
-        BorrowChannels, BorrowChannelsMut, Channel, Channels, CopySlices, FastChannelsCompression,
-        FillChannels, ResetChannels, Slices, SlicesMut, UninitChannels,
+        BorrowChannels, BorrowChannelsMut, Channel, Channels, ChannelsCompression, CopySlices,
+        FastChannelsCompression, FillChannels, FillChannelsWith, ResetChannels, Slices, SlicesMut,
+        UninitChannels,
     },
     prelude::Compression,
 };
 
+use building_blocks_core::prelude::*;
+
 use std::io;
 
 macro_rules! impl_channels_for_tuple {
@@ -15,6 +18,8 @@ macro_rules! impl_channels_for_tuple {
         where
             $($t: Channels),+
         {
+            const CHANNELS: usize = 0 $( + $t::CHANNELS )+;
+
             type Data = ($($t::Data,)+);
             type Ptr = ($(*mut $t::Data,)+);
             type UninitSelf = ($($t::UninitSelf,)+);
@@ -151,6 +156,46 @@ macro_rules! impl_channels_for_tuple {
 
                 Ok(($($var1,)+))
             }
+
+            fn decompress_into_from_reader(mut compressed_bytes: impl io::Read, data: &mut Self::Data) -> io::Result<()> {
+                let ($($var1,)+) = data;
+
+                // Decompress each channel in tuple order.
+                $( FastChannelsCompression::<By, Channel<$t>>::decompress_into_from_reader(&mut compressed_bytes, $var1)?; )+
+
+                Ok(())
+            }
+        }
+
+        impl<$($t),+, By> ChannelsCompression for FastChannelsCompression<By, ($(Channel<$t>,)+)>
+        where
+            $( FastChannelsCompression<By, Channel<$t>>: ChannelsCompression<Data = Channel<$t>>, )+
+            By: Clone,
+        {
+            fn decompress_channels_from_reader(mut compressed_bytes: impl io::Read, num_points: usize) -> io::Result<Self::Data> {
+                $( let $var1 = FastChannelsCompression::<By, Channel<$t>>::decompress_channels_from_reader(&mut compressed_bytes, num_points)?; )+
+
+                Ok(($($var1,)+))
+            }
+
+            fn decompress_channels_into_from_reader(
+                mut compressed_bytes: impl io::Read,
+                num_points: usize,
+                data: &mut Self::Data
+            ) -> io::Result<()> {
+                let ($($var1,)+) = data;
+
+                $( FastChannelsCompression::<By, Channel<$t>>::decompress_channels_into_from_reader(&mut compressed_bytes, num_points, $var1)?; )+
+
+                Ok(())
+            }
+
+            fn uncompressed_channels_len(num_points: usize) -> Option<usize> {
+                let mut len = 0;
+                $( len += FastChannelsCompression::<By, Channel<$t>>::uncompressed_channels_len(num_points)?; )+
+
+                Some(len)
+            }
         }
     }
 }
@@ -162,6 +207,28 @@ impl_channels_for_tuple! { a1, a2: A, b1, b2: B, c1, c2: C, d1, d2: D }
 impl_channels_for_tuple! { a1, a2: A, b1, b2: B, c1, c2: C, d1, d2: D, e1, e2: E }
 impl_channels_for_tuple! { a1, a2: A, b1, b2: B, c1, c2: C, d1, d2: D, e1, e2: E, f1, f2: F }
 
+macro_rules! impl_fill_channels_with_for_tuple {
+    ( $( $var:ident : $t:ident : $f:ident ),+ ) => {
+        impl<N, $($t, $f),+> FillChannelsWith<N, ($($f,)+)> for ($($t,)+)
+        where
+            $($t: FillChannelsWith<N, $f>),+
+        {
+            fn fill_with(extent: &ExtentN<N>, fillers: ($($f,)+)) -> Self {
+                let ($($var,)+) = fillers;
+
+                ($($t::fill_with(extent, $var),)+)
+            }
+        }
+    }
+}
+
+impl_fill_channels_with_for_tuple! { a: A: FA }
+impl_fill_channels_with_for_tuple! { a: A: FA, b: B: FB }
+impl_fill_channels_with_for_tuple! { a: A: FA, b: B: FB, c: C: FC }
+impl_fill_channels_with_for_tuple! { a: A: FA, b: B: FB, c: C: FC, d: D: FD }
+impl_fill_channels_with_for_tuple! { a: A: FA, b: B: FB, c: C: FC, d: D: FD, e: E: FE }
+impl_fill_channels_with_for_tuple! { a: A: FA, b: B: FB, c: C: FC, d: D: FD, e: E: FE, f: F: FF }
+
 pub mod multichannel_aliases {
     use super::*;
 
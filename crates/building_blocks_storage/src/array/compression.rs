@@ -1,12 +1,32 @@
-use crate::dev_prelude::{Array, Compression, FromBytesCompression};
+use crate::{
+    array::channels::{values_from_le, with_le_bytes},
+    dev_prelude::{
+        Array, BytesCompression, Channel, ChannelsCompression, Compression, FixedEndian,
+        FromBytesCompression,
+    },
+};
 
 use building_blocks_core::prelude::*;
 
-use bytemuck::{bytes_of, bytes_of_mut};
-use std::io;
+use bytemuck::{bytes_of, bytes_of_mut, cast_slice_mut};
+use std::convert::{TryFrom, TryInto};
+use std::io::{self, Read};
+use std::sync::Arc;
 
-/// A compression algorithm for arrays that avoid the overhead of serialization but ignores endianness and therefore isn't
-/// portable.
+/// A compression algorithm for arrays that avoid the overhead of serialization.
+///
+/// Everything is encoded as little-endian: the array extent, the channel lengths, and the voxel values, so the compressed bytes
+/// are portable across machines of different endianness. On little-endian machines, the values are copied as plain bytes.
+///
+/// Channel element types must implement `FixedEndian`, which is a `Pod` type that knows how to convert itself to
+/// little-endian. With the `serde` feature, `Array` implements `Serialize` and `Deserialize`, so arrays of other types, like
+/// structs with a `String` field, can be compressed with `BincodeCompression<Array<N, Chan>, B>` instead.
+///
+/// # Migration
+///
+/// Older versions encoded the extent, channel lengths, and voxel values with native endianness, and used a native-width
+/// length. Data written on 64-bit, little-endian machines is unaffected, but data written on any other kind of machine must be
+/// decompressed with the old version and recompressed.
 #[derive(Clone, Copy, Debug)]
 pub struct FastArrayCompression<N, C> {
     pub channels_compression: C,
@@ -38,7 +58,7 @@ where
 impl<N, C> Compression for FastArrayCompression<N, C>
 where
     PointN<N>: IntegerPoint<N>,
-    C: Compression,
+    C: ChannelsCompression,
 {
     type Data = Array<N, C::Data>;
 
@@ -47,8 +67,11 @@ where
         data: &Self::Data,
         mut compressed_bytes: impl io::Write,
     ) -> io::Result<()> {
-        // First write the extent.
-        compressed_bytes.write_all(bytes_of(data.extent()))?;
+        // First write the extent, one little-endian i32 at a time.
+        for component in bytes_of(data.extent()).chunks_exact(4) {
+            let component = i32::from_ne_bytes(component.try_into().unwrap());
+            compressed_bytes.write_all(&component.to_le_bytes())?;
+        }
 
         // Compress the channels.
         self.channels_compression
@@ -57,14 +80,362 @@ where
 
     fn decompress_from_reader(mut compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
         // First read the extent.
-        let mut extent = ExtentN::from_min_and_shape(PointN::ZERO, PointN::ZERO);
-        compressed_bytes.read_exact(bytes_of_mut(&mut extent))?;
+        let extent = read_extent(&mut compressed_bytes)?;
 
-        // Decompress the channels.
-        let channels = C::decompress_from_reader(compressed_bytes)?;
+        // Decompress the channels, which must have one value per point.
+        let channels = C::decompress_channels_from_reader(compressed_bytes, num_points(&extent)?)?;
 
         Ok(Array::new(extent, channels))
     }
+
+    fn decompress_into_from_reader(
+        mut compressed_bytes: impl io::Read,
+        data: &mut Self::Data,
+    ) -> io::Result<()> {
+        let extent = read_extent(&mut compressed_bytes)?;
+        C::decompress_channels_into_from_reader(
+            compressed_bytes,
+            num_points(&extent)?,
+            &mut data.channels,
+        )?;
+        data.extent = extent;
+
+        Ok(())
+    }
+
+    fn uncompressed_len(&self, compressed_bytes: &[u8]) -> Option<usize> {
+        let extent = read_extent(compressed_bytes).ok()?;
+
+        C::uncompressed_channels_len(num_points(&extent).ok()?)
+    }
+}
+
+/// A compression algorithm for single-channel arrays that are mostly filled with an ambient value, like chunks that only
+/// contain a thin surface. Only the non-ambient values are stored, each with its stride, so the compressed size grows with the
+/// number of non-ambient values rather than the volume of the array.
+///
+/// The ambient value is stored with the compressed bytes, so decompression doesn't depend on the compressor. Like
+/// `FastArrayCompression`, everything is little-endian.
+///
+/// Since the compressed size doesn't bound the volume, arrays are limited to `SPARSE_CHUNK_MAX_POINTS`. Compressing a larger
+/// array fails with `io::ErrorKind::InvalidInput`, and decompressing one fails with `io::ErrorKind::InvalidData`, so a corrupt
+/// header can't cause an arbitrarily large allocation.
+#[derive(Clone, Copy, Debug)]
+pub struct SparseChunkCompression<N, T> {
+    pub ambient_value: T,
+    marker: std::marker::PhantomData<N>,
+}
+
+impl<N, T> SparseChunkCompression<N, T> {
+    pub fn new(ambient_value: T) -> Self {
+        Self {
+            ambient_value,
+            marker: Default::default(),
+        }
+    }
+}
+
+/// The largest number of points in an array compressed with `SparseChunkCompression`, e.g. a 256x256x256 chunk.
+pub const SPARSE_CHUNK_MAX_POINTS: usize = 1 << 24;
+
+impl<N, T> Compression for SparseChunkCompression<N, T>
+where
+    PointN<N>: IntegerPoint<N>,
+    T: FixedEndian + PartialEq,
+{
+    type Data = Array<N, Channel<T>>;
+
+    fn compress_to_writer(
+        &self,
+        data: &Self::Data,
+        mut compressed_bytes: impl io::Write,
+    ) -> io::Result<()> {
+        if data.extent().num_points() > SPARSE_CHUNK_MAX_POINTS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Array is too large for SparseChunkCompression",
+            ));
+        }
+
+        for component in bytes_of(data.extent()).chunks_exact(4) {
+            let component = i32::from_ne_bytes(component.try_into().unwrap());
+            compressed_bytes.write_all(&component.to_le_bytes())?;
+        }
+        compressed_bytes.write_all(bytes_of(&self.ambient_value.to_le()))?;
+
+        let values = data.channels().store();
+        let num_sparse = values.iter().filter(|v| **v != self.ambient_value).count();
+        compressed_bytes.write_all(&(num_sparse as u64).to_le_bytes())?;
+        for (stride, value) in values.iter().enumerate() {
+            if *value != self.ambient_value {
+                compressed_bytes.write_all(&(stride as u64).to_le_bytes())?;
+                compressed_bytes.write_all(bytes_of(&value.to_le()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decompress_from_reader(mut compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
+        let extent = read_extent(&mut compressed_bytes)?;
+        let ambient_value: T = read_le(&mut compressed_bytes)?;
+        let num_points = num_points(&extent)?;
+        if num_points > SPARSE_CHUNK_MAX_POINTS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Array shape is too large for SparseChunkCompression",
+            ));
+        }
+        let num_sparse = read_u64(&mut compressed_bytes)?;
+        if num_sparse > num_points as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "More sparse values than the array has points",
+            ));
+        }
+
+        // Make sure that the input actually contains every sparse value before allocating the array.
+        let entry_len = 8 + std::mem::size_of::<T>();
+        let entries_len = num_sparse as usize * entry_len;
+        let mut entries = Vec::new();
+        (&mut compressed_bytes)
+            .take(entries_len as u64)
+            .read_to_end(&mut entries)?;
+        if entries.len() != entries_len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Sparse values are truncated",
+            ));
+        }
+
+        let mut values = vec![ambient_value; num_points];
+        for mut entry in entries.chunks_exact(entry_len) {
+            let stride = read_u64(&mut entry)? as usize;
+            let value = read_le(&mut entry)?;
+            *values.get_mut(stride).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Sparse stride is out of bounds")
+            })? = value;
+        }
+
+        Ok(Array::new(extent, Channel::new(values)))
+    }
+
+    fn uncompressed_len(&self, compressed_bytes: &[u8]) -> Option<usize> {
+        values_len::<N, T>(compressed_bytes)
+    }
+}
+
+/// Compresses single-channel arrays against a shared dictionary, which is much smaller overall than compressing each array
+/// independently when there are many similar arrays, e.g. small chunks of the same terrain.
+///
+/// The dictionary is trained from a sample of arrays by a majority vote at every byte offset. Each array's bytes are XORed with
+/// the dictionary before being compressed with `bytes_compression`, so every byte that matches the dictionary becomes a zero,
+/// and long runs of zeros compress very well. Like `FastArrayCompression`, everything is little-endian, and the dictionary is
+/// trained on the little-endian bytes of the values.
+///
+/// The dictionary is not stored with the compressed arrays, so decompression needs this value, through
+/// `Compression::decompress_with` or `Compressed::decompress_with`. `decompress_from_reader` fails with
+/// `io::ErrorKind::InvalidInput`. A `ChunkDb` can store the dictionary in its metadata tree with
+/// `ChunkDb::save_compression_dictionary`, and load it back into a `DictionaryCompression` with
+/// `ChunkDb::new_with_dictionary`.
+#[derive(Clone, Debug)]
+pub struct DictionaryCompression<N, T, B> {
+    pub bytes_compression: B,
+    dictionary: Arc<[u8]>,
+    marker: std::marker::PhantomData<(N, T)>,
+}
+
+impl<N, T, B> DictionaryCompression<N, T, B>
+where
+    PointN<N>: IntegerPoint<N>,
+    T: FixedEndian,
+    B: BytesCompression,
+{
+    /// Trains a dictionary from `samples`. The dictionary is as long as the longest sample.
+    ///
+    /// Each byte of the dictionary is chosen with the Boyer-Moore majority vote, so training only needs a constant amount of
+    /// memory per byte. This finds the byte that more than half of the samples agree on, if there is one. Otherwise the
+    /// chosen byte is not necessarily the most common one, so the samples should mostly agree.
+    pub fn train<'a>(
+        bytes_compression: B,
+        samples: impl IntoIterator<Item = &'a Array<N, Channel<T>>>,
+    ) -> Self
+    where
+        N: 'a,
+        T: 'a,
+    {
+        let mut dictionary = Vec::new();
+        let mut votes: Vec<u32> = Vec::new();
+        for sample in samples.into_iter() {
+            let bytes: Vec<u8> =
+                with_le_bytes(sample.channels().store().as_slice(), |bytes| bytes.to_vec());
+            if bytes.len() > dictionary.len() {
+                dictionary.resize(bytes.len(), 0);
+                votes.resize(bytes.len(), 0);
+            }
+            for ((candidate, votes), &byte) in dictionary
+                .iter_mut()
+                .zip(votes.iter_mut())
+                .zip(bytes.iter())
+            {
+                if *votes == 0 {
+                    *candidate = byte;
+                    *votes = 1;
+                } else if *candidate == byte {
+                    *votes += 1;
+                } else {
+                    *votes -= 1;
+                }
+            }
+        }
+
+        Self::from_dictionary(bytes_compression, dictionary)
+    }
+
+    /// Uses a dictionary that was previously returned by `dictionary`.
+    pub fn from_dictionary(bytes_compression: B, dictionary: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            bytes_compression,
+            dictionary: dictionary.into(),
+            marker: Default::default(),
+        }
+    }
+
+    pub fn dictionary(&self) -> &[u8] {
+        &self.dictionary
+    }
+}
+
+impl<N, T, B> Compression for DictionaryCompression<N, T, B>
+where
+    PointN<N>: IntegerPoint<N>,
+    T: FixedEndian,
+    B: BytesCompression,
+{
+    type Data = Array<N, Channel<T>>;
+
+    fn compress_to_writer(
+        &self,
+        data: &Self::Data,
+        mut compressed_bytes: impl io::Write,
+    ) -> io::Result<()> {
+        for component in bytes_of(data.extent()).chunks_exact(4) {
+            let component = i32::from_ne_bytes(component.try_into().unwrap());
+            compressed_bytes.write_all(&component.to_le_bytes())?;
+        }
+
+        let mut bytes: Vec<u8> =
+            with_le_bytes(data.channels().store().as_slice(), |bytes| bytes.to_vec());
+        xor_dictionary(&self.dictionary, &mut bytes);
+
+        self.bytes_compression
+            .compress_bytes(bytes.as_slice(), compressed_bytes)
+    }
+
+    fn decompress_from_reader(_compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "DictionaryCompression needs its dictionary to decompress; use decompress_with",
+        ))
+    }
+
+    fn decompress_with(&self, mut compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
+        let extent: ExtentN<N> = read_extent(&mut compressed_bytes)?;
+
+        // Check the decompressed length before allocating the values, in case the extent is corrupt.
+        let num_points = num_points(&extent)?;
+        let mut bytes = Vec::new();
+        B::decompress_bytes(compressed_bytes, &mut bytes)?;
+        if num_points.checked_mul(std::mem::size_of::<T>()) != Some(bytes.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Decompressed length does not match the array extent",
+            ));
+        }
+        xor_dictionary(&self.dictionary, &mut bytes);
+        let mut values = vec![T::zeroed(); num_points];
+        cast_slice_mut(values.as_mut_slice()).copy_from_slice(&bytes);
+        values_from_le(&mut values);
+
+        Ok(Array::new(extent, Channel::new(values)))
+    }
+
+    fn uncompressed_len(&self, compressed_bytes: &[u8]) -> Option<usize> {
+        values_len::<N, T>(compressed_bytes)
+    }
+}
+
+/// The number of bytes in the values of a single-channel array, given compressed bytes that start with its extent.
+fn values_len<N, T>(compressed_bytes: &[u8]) -> Option<usize>
+where
+    PointN<N>: IntegerPoint<N>,
+{
+    let extent = read_extent::<N>(compressed_bytes).ok()?;
+
+    num_points(&extent)
+        .ok()?
+        .checked_mul(std::mem::size_of::<T>())
+}
+
+fn xor_dictionary(dictionary: &[u8], bytes: &mut [u8]) {
+    for (byte, d) in bytes.iter_mut().zip(dictionary.iter()) {
+        *byte ^= d;
+    }
+}
+
+fn read_u64(mut compressed_bytes: impl io::Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    compressed_bytes.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Reads a little-endian value.
+fn read_le<T: FixedEndian>(mut compressed_bytes: impl io::Read) -> io::Result<T> {
+    let mut value = T::zeroed();
+    compressed_bytes.read_exact(bytes_of_mut(&mut value))?;
+
+    Ok(value.to_le())
+}
+
+/// Reads the extent header of an array compressed with `FastArrayCompression`.
+pub(crate) fn read_extent<N>(mut compressed_bytes: impl io::Read) -> io::Result<ExtentN<N>>
+where
+    PointN<N>: IntegerPoint<N>,
+{
+    let mut extent = ExtentN::from_min_and_shape(PointN::ZERO, PointN::ZERO);
+    compressed_bytes.read_exact(bytes_of_mut(&mut extent))?;
+    for component in bytes_of_mut(&mut extent).chunks_exact_mut(4) {
+        let value = i32::from_le_bytes((&*component).try_into().unwrap());
+        component.copy_from_slice(&value.to_ne_bytes());
+    }
+
+    Ok(extent)
+}
+
+/// The number of points in an `extent` that was read from compressed bytes. Fails with `io::ErrorKind::InvalidData` if the
+/// shape is negative or the volume doesn't fit in a `usize`.
+///
+/// This only guards against overflow. The volume itself may still be huge, so callers must check it against the rest of the
+/// input, e.g. the number of stored values, before allocating.
+pub(crate) fn num_points<N>(extent: &ExtentN<N>) -> io::Result<usize>
+where
+    PointN<N>: IntegerPoint<N>,
+{
+    bytes_of(&extent.shape)
+        .chunks_exact(4)
+        .try_fold(1usize, |volume, component| {
+            let side = i32::from_ne_bytes(component.try_into().unwrap());
+            usize::try_from(side)
+                .ok()
+                .and_then(|side| volume.checked_mul(side))
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Array shape is negative or too large",
+            )
+        })
 }
 
 pub mod multichannel_aliases {
@@ -97,7 +468,7 @@ pub use multichannel_aliases::*;
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::prelude::{Array3x1, BytesCompression};
+    use crate::prelude::{Array3x1, BitPackedCompression, BytesCompression};
 
     use crate::test_utilities::sphere_bit_array;
     use utilities::test::test_print;
@@ -107,6 +478,82 @@ mod test {
     #[cfg(feature = "snap")]
     use crate::compression::Snappy;
 
+    #[test]
+    fn sparse_compression_size_scales_with_surface_area() {
+        let surface_chunk = |side_length: i32| {
+            let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(side_length));
+            Array3x1::fill_with(extent, |p| if p.y() == 0 { 7u16 } else { 0 })
+        };
+        let compression = SparseChunkCompression::new(0u16);
+
+        let small = surface_chunk(16);
+        let large = surface_chunk(32);
+        let small_bytes = compression.compress(&small).take_bytes();
+        let large_bytes = compression.compress(&large).take_bytes();
+
+        // The surface area quadruples while the volume grows by 8x.
+        let header_bytes = 24 + 2 + 8;
+        let surface_bytes = |side_length: usize| side_length * side_length * (8 + 2);
+        assert_eq!(small_bytes.len(), header_bytes + surface_bytes(16));
+        assert_eq!(large_bytes.len(), header_bytes + surface_bytes(32));
+
+        let decompressed =
+            SparseChunkCompression::<[i32; 3], u16>::decompress_from_reader(large_bytes.as_slice())
+                .unwrap();
+        assert_eq!(decompressed.extent(), large.extent());
+        assert_eq!(decompressed.channels().store(), large.channels().store());
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn dictionary_compression_of_similar_chunks_is_smaller() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+
+        // All chunks share the same noisy base, which doesn't compress well on its own, and each chunk has a few unique
+        // voxels.
+        let mut state = 0x2545_f491u32;
+        let mut next_random = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+        let base: Vec<u8> = (0..extent.num_points())
+            .map(|_| next_random() as u8)
+            .collect();
+        let chunks: Vec<_> = (0..100)
+            .map(|i| {
+                let mut values = base.clone();
+                for _ in 0..8 {
+                    values[next_random() as usize % values.len()] = i as u8;
+                }
+                Array::new(extent, Channel::new(values))
+            })
+            .collect();
+
+        let independent = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+        let independent_len: usize = chunks
+            .iter()
+            .map(|chunk| independent.compress(chunk).compressed_len())
+            .sum();
+
+        let dictionary = DictionaryCompression::train(Lz4 { level: 10 }, chunks.iter().take(10));
+        assert_eq!(dictionary.dictionary(), base.as_slice());
+        let mut dictionary_len = dictionary.dictionary().len();
+        for chunk in chunks.iter() {
+            let compressed = dictionary.compress(chunk);
+            dictionary_len += compressed.compressed_len();
+
+            assert_eq!(&compressed.decompress_with(&dictionary), chunk);
+        }
+
+        test_print(&format!(
+            "independent = {} bytes, dictionary = {} bytes\n",
+            independent_len, dictionary_len
+        ));
+        assert!(dictionary_len < independent_len / 4);
+    }
+
     #[cfg(feature = "snap")]
     #[test]
     fn sphere_array_compression_rate_snappy() {
@@ -166,4 +613,313 @@ mod test {
             100.0 * (compressed_size_bytes as f32 / source_size_bytes as f32)
         ));
     }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn decompress_into_reused_buffer() {
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+
+        // Arrays that grow and shrink, so the buffer has to be resized both ways.
+        let arrays: Vec<Array3x1<u16>> = [4, 16, 8]
+            .iter()
+            .enumerate()
+            .map(|(i, &side)| {
+                let extent = Extent3i::from_min_and_shape(
+                    Point3i::fill(i as i32 * 100),
+                    Point3i::fill(side),
+                );
+                Array3x1::fill_with(extent, |p| (p.x() + 3 * p.y() + 5 * p.z()) as u16)
+            })
+            .collect();
+
+        let mut buffer = Array3x1::fill(
+            Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::ZERO),
+            0,
+        );
+        for array in arrays.iter() {
+            compression.compress(array).decompress_into(&mut buffer);
+
+            assert_eq!(buffer.extent(), array.extent());
+            assert_eq!(buffer.channels().store(), array.channels().store());
+        }
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn header_is_little_endian_on_any_target() {
+        let extent = Extent3i::from_min_and_shape(PointN([-1, 0, 258]), PointN([1, 1, 2]));
+        #[rustfmt::skip]
+        let header_fixture: Vec<u8> = vec![
+            // minimum
+            0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x02, 0x01, 0x00, 0x00,
+            // shape
+            0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+            // number of values
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+
+        let array = Array3x1::fill(extent, 7u8);
+        let compressed_bytes = compression.compress(&array).take_bytes();
+        assert_eq!(
+            &compressed_bytes[..header_fixture.len()],
+            header_fixture.as_slice()
+        );
+
+        let mut fixture = header_fixture;
+        Lz4 { level: 10 }
+            .compress_bytes(&[7u8, 9][..], &mut fixture)
+            .unwrap();
+        let decompressed = FastArrayCompressionNx1::<[i32; 3], Lz4, u8>::decompress_from_reader(
+            fixture.as_slice(),
+        )
+        .unwrap();
+        assert_eq!(decompressed.extent(), &extent);
+        assert_eq!(decompressed.channels().store(), &vec![7, 9]);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn multi_byte_values_are_little_endian_on_any_target() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, PointN([2, 1, 1]));
+        #[rustfmt::skip]
+        let mut fixture: Vec<u8> = vec![
+            // minimum
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // shape
+            0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+            // number of values
+            0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        Lz4 { level: 10 }
+            .compress_bytes(&[0x34, 0x12, 0xCD, 0xAB][..], &mut fixture)
+            .unwrap();
+
+        let decompressed = FastArrayCompressionNx1::<[i32; 3], Lz4, u16>::decompress_from_reader(
+            fixture.as_slice(),
+        )
+        .unwrap();
+        assert_eq!(decompressed.extent(), &extent);
+        assert_eq!(decompressed.channels().store(), &vec![0x1234, 0xABCD]);
+
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+        assert_eq!(compression.compress(&decompressed).take_bytes(), fixture);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn corrupt_lengths_are_rejected_before_allocating() {
+        let header = |shape: [u8; 12], num_values: [u8; 8]| -> Vec<u8> {
+            let mut bytes = vec![0; 12];
+            bytes.extend_from_slice(&shape);
+            bytes.extend_from_slice(&num_values);
+            bytes
+        };
+        let shape_2x1x1 = [2, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0];
+        let decompress = |bytes: Vec<u8>| {
+            FastArrayCompressionNx1::<[i32; 3], Lz4, u16>::decompress_from_reader(bytes.as_slice())
+                .map(|_| ())
+                .unwrap_err()
+                .kind()
+        };
+
+        // More values than the extent has points.
+        assert_eq!(
+            decompress(header(shape_2x1x1, [3, 0, 0, 0, 0, 0, 0, 0])),
+            io::ErrorKind::InvalidData
+        );
+        // A count so large that it would overflow the allocation size.
+        assert_eq!(
+            decompress(header(shape_2x1x1, [0xFF; 8])),
+            io::ErrorKind::InvalidData
+        );
+        // A negative shape.
+        assert_eq!(
+            decompress(header(
+                [0xFF, 0xFF, 0xFF, 0xFF, 1, 0, 0, 0, 1, 0, 0, 0],
+                [0; 8]
+            )),
+            io::ErrorKind::InvalidData
+        );
+
+        // The same checks apply to bit-packed channels.
+        let bits =
+            FastArrayCompression::<[i32; 3], _>::new(BitPackedCompression::new(Lz4 { level: 10 }));
+        let array = Array3x1::fill(
+            Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(3)),
+            true,
+        );
+        let mut bytes = bits.compress(&array).take_bytes();
+        bytes[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(
+            FastArrayCompression::<[i32; 3], BitPackedCompression<Lz4>>::decompress_from_reader(
+                bytes.as_slice()
+            )
+            .map(|_| ())
+            .unwrap_err()
+            .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn corrupt_sparse_header_is_rejected_before_allocating() {
+        let sparse_header = |shape: [i32; 3], num_sparse: u64| -> Vec<u8> {
+            let mut bytes = vec![0; 12];
+            for side in shape.iter() {
+                bytes.extend_from_slice(&side.to_le_bytes());
+            }
+            bytes.extend_from_slice(&0u16.to_le_bytes());
+            bytes.extend_from_slice(&num_sparse.to_le_bytes());
+            bytes
+        };
+        let decompress_sparse = |bytes: Vec<u8>| {
+            SparseChunkCompression::<[i32; 3], u16>::decompress_from_reader(bytes.as_slice())
+                .map(|_| ())
+                .unwrap_err()
+                .kind()
+        };
+        // A volume over the limit.
+        assert_eq!(
+            decompress_sparse(sparse_header([1 << 12, 1 << 12, 1 << 12], 0)),
+            io::ErrorKind::InvalidData
+        );
+        // More sparse values than points.
+        assert_eq!(
+            decompress_sparse(sparse_header([2, 1, 1], 3)),
+            io::ErrorKind::InvalidData
+        );
+        // More sparse values than the input holds, which is caught before allocating the largest allowed volume.
+        assert_eq!(
+            decompress_sparse(sparse_header([256, 256, 256], 1 << 20)),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+}
+
+#[cfg(all(test, any(feature = "lz4", feature = "snap")))]
+mod roundtrip_proptests {
+    use super::*;
+    use crate::prelude::{Array3x1, Array3x2, BytesCompression, Channel, Compression, Sd8};
+
+    #[cfg(feature = "serde")]
+    use crate::prelude::BincodeCompression;
+
+    #[cfg(feature = "lz4")]
+    use crate::compression::Lz4;
+    #[cfg(feature = "snap")]
+    use crate::compression::Snappy;
+
+    use proptest::prelude::*;
+
+    // Includes empty extents.
+    fn arb_extent() -> impl Strategy<Value = Extent3i> {
+        (
+            prop::array::uniform3(-100i32..100),
+            prop::array::uniform3(0i32..8),
+        )
+            .prop_map(|(min, shape)| Extent3i::from_min_and_shape(PointN(min), PointN(shape)))
+    }
+
+    impl Arbitrary for Array3x1<u8> {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            arb_extent()
+                .prop_flat_map(|extent| {
+                    prop::collection::vec(any::<u8>(), extent.num_points())
+                        .prop_map(move |values| Array3x1::new_one_channel(extent, values))
+                })
+                .boxed()
+        }
+    }
+
+    impl Arbitrary for Array3x2<u8, Sd8> {
+        type Parameters = ();
+        type Strategy = BoxedStrategy<Self>;
+
+        fn arbitrary_with(_: ()) -> Self::Strategy {
+            arb_extent()
+                .prop_flat_map(|extent| {
+                    let n = extent.num_points();
+                    (
+                        prop::collection::vec(any::<u8>(), n),
+                        prop::collection::vec(any::<i8>().prop_map(Sd8), n),
+                    )
+                        .prop_map(move |(a, b)| {
+                            Array3x2::new(extent, (Channel::new(a), Channel::new(b)))
+                        })
+                })
+                .boxed()
+        }
+    }
+
+    fn assert_roundtrip<B: BytesCompression + Copy>(
+        bytes_compression: B,
+        array1: &Array3x1<u8>,
+        array2: &Array3x2<u8, Sd8>,
+    ) {
+        let fast1 = FastArrayCompressionNx1::from_bytes_compression(bytes_compression);
+        assert_eq!(&fast1.compress(array1).decompress(), array1);
+        let fast2 = FastArrayCompressionNx2::from_bytes_compression(bytes_compression);
+        assert_eq!(&fast2.compress(array2).decompress(), array2);
+
+        #[cfg(feature = "serde")]
+        {
+            let bincode1 = BincodeCompression::<Array3x1<u8>, B>::new(bytes_compression);
+            assert_eq!(&bincode1.compress(array1).decompress(), array1);
+            let bincode2 = BincodeCompression::<Array3x2<u8, Sd8>, B>::new(bytes_compression);
+            assert_eq!(&bincode2.compress(array2).decompress(), array2);
+        }
+    }
+
+    fn edge_case_arrays() -> Vec<(Array3x1<u8>, Array3x2<u8, Sd8>)> {
+        let empty = Extent3i::from_min_and_shape(Point3i::fill(3), Point3i::ZERO);
+        let single = Extent3i::from_min_and_shape(Point3i::fill(-7), Point3i::ONES);
+        let cube = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(8));
+
+        vec![
+            (Array3x1::fill(empty, 0), Array3x2::fill(empty, (0, Sd8(0)))),
+            (
+                Array3x1::fill(single, u8::MAX),
+                Array3x2::fill(single, (u8::MAX, Sd8(i8::MAX))),
+            ),
+            (
+                Array3x1::fill(cube, u8::MAX),
+                Array3x2::fill(cube, (u8::MAX, Sd8(i8::MIN))),
+            ),
+        ]
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn lz4_edge_cases_roundtrip() {
+        for (array1, array2) in edge_case_arrays().iter() {
+            assert_roundtrip(Lz4 { level: 10 }, array1, array2);
+        }
+    }
+
+    #[cfg(feature = "snap")]
+    #[test]
+    fn snappy_edge_cases_roundtrip() {
+        for (array1, array2) in edge_case_arrays().iter() {
+            assert_roundtrip(Snappy, array1, array2);
+        }
+    }
+
+    proptest! {
+        #[cfg(feature = "lz4")]
+        #[test]
+        fn lz4_roundtrip(array1 in any::<Array3x1<u8>>(), array2 in any::<Array3x2<u8, Sd8>>()) {
+            assert_roundtrip(Lz4 { level: 10 }, &array1, &array2);
+        }
+
+        #[cfg(feature = "snap")]
+        #[test]
+        fn snappy_roundtrip(array1 in any::<Array3x1<u8>>(), array2 in any::<Array3x2<u8, Sd8>>()) {
+            assert_roundtrip(Snappy, &array1, &array2);
+        }
+    }
 }
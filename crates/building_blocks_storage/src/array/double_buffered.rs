@@ -0,0 +1,129 @@
+use crate::{
+    array::FillChannels,
+    dev_prelude::{Array, Channel},
+};
+
+use building_blocks_core::prelude::*;
+
+/// Two `Array`s with the same extent, where one is read while the other is written. This is useful for passes like cellular
+/// automata, where every new value depends on the old values of its neighbors. Calling `swap` after each pass avoids
+/// reallocating.
+#[derive(Clone, Debug)]
+pub struct DoubleBufferedArray<N, Chan> {
+    read: Array<N, Chan>,
+    write: Array<N, Chan>,
+}
+
+/// A 2-dimensional, single-channel `DoubleBufferedArray`.
+pub type DoubleBufferedArray2x1<T> = DoubleBufferedArray<[i32; 2], Channel<T>>;
+/// A 3-dimensional, single-channel `DoubleBufferedArray`.
+pub type DoubleBufferedArray3x1<T> = DoubleBufferedArray<[i32; 3], Channel<T>>;
+
+impl<N, Chan> DoubleBufferedArray<N, Chan>
+where
+    PointN<N>: IntegerPoint<N>,
+{
+    /// Uses `array` as the read buffer and a clone of it as the write buffer.
+    pub fn new(array: Array<N, Chan>) -> Self
+    where
+        Chan: Clone,
+    {
+        Self {
+            write: array.clone(),
+            read: array,
+        }
+    }
+
+    /// Fills both buffers with `value`.
+    pub fn fill(extent: ExtentN<N>, value: Chan::Data) -> Self
+    where
+        Chan: FillChannels,
+        Chan::Data: Clone,
+    {
+        Self {
+            read: Array::fill(extent, value.clone()),
+            write: Array::fill(extent, value),
+        }
+    }
+
+    /// The extent of both buffers.
+    pub fn extent(&self) -> &ExtentN<N> {
+        self.read.extent()
+    }
+
+    /// The buffer holding the result of the last pass.
+    pub fn read(&self) -> &Array<N, Chan> {
+        &self.read
+    }
+
+    /// The buffer for the next pass. Its contents are whatever was in the read buffer before the last `swap`.
+    pub fn write_mut(&mut self) -> &mut Array<N, Chan> {
+        &mut self.write
+    }
+
+    /// Borrows both buffers at once, so a pass can read from one while writing the other.
+    pub fn read_and_write_mut(&mut self) -> (&Array<N, Chan>, &mut Array<N, Chan>) {
+        (&self.read, &mut self.write)
+    }
+
+    /// Makes the write buffer the new read buffer, and vice versa.
+    pub fn swap(&mut self) {
+        std::mem::swap(&mut self.read, &mut self.write);
+    }
+
+    /// Consumes `self` and returns the read buffer.
+    pub fn into_read(self) -> Array<N, Chan> {
+        self.read
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn shifted_copy_propagates_one_voxel_per_tick() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, PointN([8, 2, 2]));
+        let mut buffers = DoubleBufferedArray3x1::fill(extent, 0);
+        for z in 0..2 {
+            for y in 0..2 {
+                *buffers.write_mut().get_mut(PointN([0, y, z])) = 1;
+            }
+        }
+        buffers.swap();
+
+        for tick in 1..6 {
+            // Every voxel takes the value of its -X neighbor, and the X = 0 plane is cleared.
+            let (read, write) = buffers.read_and_write_mut();
+            for p in extent.iter_points() {
+                let shifted = p - PointN([1, 0, 0]);
+                *write.get_mut(p) = if read.extent().contains(shifted) {
+                    read.get(shifted)
+                } else {
+                    0
+                };
+            }
+            buffers.swap();
+
+            for p in extent.iter_points() {
+                let expected = if p.x() == tick { 1 } else { 0 };
+                assert_eq!(
+                    buffers.read().get(p),
+                    expected,
+                    "tick = {}, p = {:?}",
+                    tick,
+                    p
+                );
+            }
+        }
+    }
+}
@@ -3,10 +3,12 @@ mod for_each2;
 #[macro_use]
 mod for_each3;
 
+mod local_stride_iter;
 mod lock_step;
 mod single_array;
 mod stride_iter;
 
+pub use local_stride_iter::*;
 pub use lock_step::*;
 pub use single_array::*;
 
@@ -0,0 +1,129 @@
+use crate::array::{ArrayIndexer, Local, Local2i, Local3i, Stride};
+
+use building_blocks_core::prelude::*;
+
+/// An iterator over the `Local` coordinates and `Stride`s of all points in an extent of an array, with X changing fastest.
+/// The `Stride` is advanced incrementally instead of being recomputed for each point.
+///
+/// Created by `IndexedArray::stride_iter`.
+#[derive(Clone)]
+pub struct ArrayLocalStrideIter<N> {
+    local_extent: ExtentN<N>,
+    next_local: PointN<N>,
+    next_stride: Stride,
+    // Added to the stride after finishing a row in order to get to the start of the next row.
+    row_wrap: usize,
+    // Added to the stride after finishing a plane in order to get to the start of the next plane. (Only used for 3D).
+    plane_wrap: usize,
+    remaining: usize,
+}
+
+impl<N> ArrayLocalStrideIter<N>
+where
+    N: ArrayIndexer<N>,
+    PointN<N>: IntegerPoint<N>,
+{
+    fn new(array_shape: PointN<N>, local_extent: ExtentN<N>, plane_wrap: usize) -> Self {
+        let remaining = local_extent.num_points();
+        let next_stride = if remaining == 0 {
+            Stride(0)
+        } else {
+            N::stride_from_local_point(array_shape, Local(local_extent.minimum))
+        };
+
+        Self {
+            local_extent,
+            next_local: local_extent.minimum,
+            next_stride,
+            row_wrap: (array_shape.at(0) - local_extent.shape.at(0)) as usize,
+            plane_wrap,
+            remaining,
+        }
+    }
+}
+
+impl ArrayLocalStrideIter<[i32; 2]> {
+    pub(crate) fn new_2d(array_shape: Point2i, local_extent: Extent2i) -> Self {
+        Self::new(array_shape, local_extent, 0)
+    }
+}
+
+impl ArrayLocalStrideIter<[i32; 3]> {
+    pub(crate) fn new_3d(array_shape: Point3i, local_extent: Extent3i) -> Self {
+        let plane_wrap = ((array_shape.y() - local_extent.shape.y()) * array_shape.x()) as usize;
+
+        Self::new(array_shape, local_extent, plane_wrap)
+    }
+}
+
+impl Iterator for ArrayLocalStrideIter<[i32; 2]> {
+    type Item = (Local2i, Stride);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let item = (Local(self.next_local), self.next_stride);
+
+        let min = self.local_extent.minimum;
+        let lub = self.local_extent.least_upper_bound();
+        let p = &mut self.next_local.0;
+        p[0] += 1;
+        self.next_stride.0 += 1;
+        if p[0] == lub.x() {
+            p[0] = min.x();
+            p[1] += 1;
+            self.next_stride.0 += self.row_wrap;
+        }
+
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl Iterator for ArrayLocalStrideIter<[i32; 3]> {
+    type Item = (Local3i, Stride);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let item = (Local(self.next_local), self.next_stride);
+
+        let min = self.local_extent.minimum;
+        let lub = self.local_extent.least_upper_bound();
+        let p = &mut self.next_local.0;
+        p[0] += 1;
+        self.next_stride.0 += 1;
+        if p[0] == lub.x() {
+            p[0] = min.x();
+            p[1] += 1;
+            self.next_stride.0 += self.row_wrap;
+            if p[1] == lub.y() {
+                p[1] = min.y();
+                p[2] += 1;
+                self.next_stride.0 += self.plane_wrap;
+            }
+        }
+
+        Some(item)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for ArrayLocalStrideIter<[i32; 2]> {}
+impl ExactSizeIterator for ArrayLocalStrideIter<[i32; 3]> {}
@@ -1,6 +1,7 @@
 use crate::array::{
-    for_each2, for_each3, Array2ForEach, Array3ForEach, ArrayForEach, ArrayStrideIter, Local,
-    Local2i, Local3i, LockStepArrayForEach, LockStepArrayForEach2, LockStepArrayForEach3, Stride,
+    for_each2, for_each3, Array2ForEach, Array3ForEach, ArrayForEach, ArrayLocalStrideIter,
+    ArrayStrideIter, Local, Local2i, Local3i, LockStepArrayForEach, LockStepArrayForEach2,
+    LockStepArrayForEach3, Stride,
 };
 
 use building_blocks_core::prelude::*;
@@ -14,6 +15,11 @@ pub trait ArrayIndexer<N> {
         step: PointN<N>,
     ) -> ArrayStrideIter;
 
+    fn make_local_stride_iter(
+        array_shape: PointN<N>,
+        local_extent: ExtentN<N>,
+    ) -> ArrayLocalStrideIter<N>;
+
     fn for_each(for_each: ArrayForEach<N>, f: impl FnMut(PointN<N>, Stride));
 
     fn for_each_lockstep_unchecked(
@@ -43,6 +49,14 @@ impl ArrayIndexer<[i32; 2]> for [i32; 2] {
         ArrayStrideIter::new_2d(array_shape, origin, step)
     }
 
+    #[inline]
+    fn make_local_stride_iter(
+        array_shape: Point2i,
+        local_extent: Extent2i,
+    ) -> ArrayLocalStrideIter<[i32; 2]> {
+        ArrayLocalStrideIter::new_2d(array_shape, local_extent)
+    }
+
     #[inline]
     fn for_each(for_each: Array2ForEach, f: impl FnMut(Point2i, Stride)) {
         let Array2ForEach { iter_extent, iter } = for_each;
@@ -74,6 +88,14 @@ impl ArrayIndexer<[i32; 3]> for [i32; 3] {
         ArrayStrideIter::new_3d(array_shape, origin, step)
     }
 
+    #[inline]
+    fn make_local_stride_iter(
+        array_shape: Point3i,
+        local_extent: Extent3i,
+    ) -> ArrayLocalStrideIter<[i32; 3]> {
+        ArrayLocalStrideIter::new_3d(array_shape, local_extent)
+    }
+
     #[inline]
     fn for_each(for_each: Array3ForEach, f: impl FnMut(Point3i, Stride)) {
         let Array3ForEach { iter_extent, iter } = for_each;
@@ -118,4 +140,18 @@ pub trait IndexedArray<N> {
     {
         Self::Indexer::strides_from_local_points(self.extent().shape, points, strides)
     }
+
+    /// Iterate over the `Local` coordinates and `Stride`s of every point in `extent` that is also in the array. `extent` is in
+    /// global coordinates. The X coordinate changes fastest, and the `Stride` is advanced incrementally, which makes this a
+    /// cheap way to index channels directly in a hand-written loop.
+    #[inline]
+    fn stride_iter(&self, extent: &ExtentN<N>) -> ArrayLocalStrideIter<N>
+    where
+        PointN<N>: IntegerPoint<N>,
+    {
+        let array_extent = self.extent();
+        let local_extent = extent.intersection(array_extent) - array_extent.minimum;
+
+        Self::Indexer::make_local_stride_iter(array_extent.shape, local_extent)
+    }
 }
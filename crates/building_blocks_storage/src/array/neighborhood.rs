@@ -0,0 +1,160 @@
+use crate::dev_prelude::{Array, Channel, GetRef};
+
+use building_blocks_core::prelude::*;
+
+/// Borrowed values of a voxel and its 26 neighbors, i.e. the 3x3x3 cube of voxels centered on a point. Useful for writing
+/// stencil kernels like cellular automata.
+///
+/// Values are addressed by their offset from the center, where each component is in `-1..=1`.
+#[derive(Clone, Copy, Debug)]
+pub struct Neighborhood26<'a, T> {
+    values: [&'a T; 27],
+}
+
+impl<'a, T> Neighborhood26<'a, T> {
+    /// The value at the center of the neighborhood.
+    #[inline]
+    pub fn center(&self) -> &'a T {
+        self.values[13]
+    }
+
+    /// The value at `offset` from the center.
+    ///
+    /// # Panics
+    ///
+    /// If any component of `offset` is not in `-1..=1`.
+    #[inline]
+    pub fn get(&self, offset: Point3i) -> &'a T {
+        assert!(
+            offset.abs().max_component() <= 1,
+            "Offset {:?} is outside of the neighborhood",
+            offset
+        );
+
+        self.values[Self::index(offset)]
+    }
+
+    /// Iterates over the 26 neighbors (excluding the center) and their offsets from the center.
+    #[inline]
+    pub fn neighbors(&self) -> impl Iterator<Item = (Point3i, &'a T)> + '_ {
+        Self::offsets()
+            .filter(|offset| *offset != Point3i::ZERO)
+            .map(move |offset| (offset, self.values[Self::index(offset)]))
+    }
+
+    fn offsets() -> impl Iterator<Item = Point3i> {
+        Extent3i::from_min_and_shape(Point3i::fill(-1), Point3i::fill(3)).iter_points()
+    }
+
+    fn index(offset: Point3i) -> usize {
+        let p = offset + Point3i::ONES;
+
+        (p.x() + 3 * p.y() + 9 * p.z()) as usize
+    }
+}
+
+impl<T, Store> Array<[i32; 3], Channel<T, Store>>
+where
+    Store: AsRef<[T]>,
+{
+    /// Borrows the values of `p` and its 26 neighbors. Neighbors outside of the array extent are clamped to the nearest point
+    /// on the boundary of the extent.
+    ///
+    /// # Panics
+    ///
+    /// If the array extent is empty.
+    pub fn neighborhood(&self, p: Point3i) -> Neighborhood26<'_, T> {
+        let extent = *self.extent();
+        assert!(!extent.is_empty());
+        let (min, max) = (extent.minimum, extent.max());
+
+        self.neighborhood_with(p, |q| self.get_ref(q.join(min).meet(max)))
+    }
+
+    /// Borrows the values of `p` and its 26 neighbors. Neighbors outside of the array extent take the value `ambient`.
+    pub fn neighborhood_or<'a>(&'a self, p: Point3i, ambient: &'a T) -> Neighborhood26<'a, T> {
+        let extent = *self.extent();
+
+        self.neighborhood_with(p, |q| {
+            if extent.contains(q) {
+                self.get_ref(q)
+            } else {
+                ambient
+            }
+        })
+    }
+
+    fn neighborhood_with<'a>(
+        &'a self,
+        p: Point3i,
+        get: impl Fn(Point3i) -> &'a T,
+    ) -> Neighborhood26<'a, T> {
+        let center = get(p);
+        let mut values = [center; 27];
+        for offset in Neighborhood26::<T>::offsets() {
+            values[Neighborhood26::<T>::index(offset)] = get(p + offset);
+        }
+
+        Neighborhood26 { values }
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::prelude::Array3x1;
+
+    /// A unique value for every point in the test extent.
+    fn encode(p: Point3i) -> i32 {
+        p.x() + 10 * p.y() + 100 * p.z()
+    }
+
+    fn test_array() -> Array3x1<i32> {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(4));
+
+        Array3x1::fill_with(extent, encode)
+    }
+
+    #[test]
+    fn interior_neighborhood() {
+        let array = test_array();
+        let p = PointN([1, 2, 1]);
+
+        let neighborhood = array.neighborhood(p);
+
+        assert_eq!(*neighborhood.center(), encode(p));
+        let mut num_neighbors = 0;
+        for (offset, value) in neighborhood.neighbors() {
+            assert_eq!(*value, encode(p + offset));
+            assert_eq!(neighborhood.get(offset), value);
+            num_neighbors += 1;
+        }
+        assert_eq!(num_neighbors, 26);
+    }
+
+    #[test]
+    fn corner_neighborhood_is_clamped_or_ambient() {
+        let array = test_array();
+        let corner = Point3i::ZERO;
+
+        let clamped = array.neighborhood(corner);
+        assert_eq!(*clamped.get(PointN([-1, -1, -1])), encode(corner));
+        assert_eq!(*clamped.get(PointN([-1, 1, 0])), encode(PointN([0, 1, 0])));
+        assert_eq!(*clamped.get(PointN([1, 1, 1])), encode(Point3i::ONES));
+
+        let ambient = -1;
+        let with_ambient = array.neighborhood_or(corner, &ambient);
+        assert_eq!(*with_ambient.center(), encode(corner));
+        assert_eq!(*with_ambient.get(PointN([-1, -1, -1])), ambient);
+        assert_eq!(*with_ambient.get(PointN([-1, 1, 0])), ambient);
+        assert_eq!(*with_ambient.get(PointN([1, 1, 1])), encode(Point3i::ONES));
+    }
+}
@@ -1,6 +1,6 @@
 use core::hash::{BuildHasher, Hash};
 use std::cell::UnsafeCell;
-use std::collections::HashMap;
+use std::collections::{hash_map::Entry, HashMap};
 use std::pin::Pin;
 
 /// A cache with a very specific niche. When reading from shared, two-tier storage, if you miss the cache and need to fetch from
@@ -50,6 +50,20 @@ where
         mut_store.entry(key).or_insert_with(|| Box::pin(f()))
     }
 
+    /// Like `get_or_insert_with`, but fetching can fail, in which case nothing is inserted.
+    pub fn try_get_or_insert_with<E>(
+        &self,
+        key: K,
+        f: impl FnOnce() -> Result<V, E>,
+    ) -> Result<&V, E> {
+        let mut_store = unsafe { &mut *self.store.get() };
+
+        match mut_store.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => Ok(entry.insert(Box::pin(f()?))),
+        }
+    }
+
     /// Consume and iterate over all (key, value) pairs.
     pub fn drain_iter<'a>(&'a mut self) -> impl 'a + Iterator<Item = (K, V)> {
         self.store
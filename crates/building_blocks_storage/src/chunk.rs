@@ -1,16 +1,20 @@
 pub mod indexer;
+pub mod log;
 pub mod map;
 pub mod storage;
 
 pub use indexer::*;
+pub use log::*;
 pub use map::*;
 pub use storage::*;
 
 use building_blocks_core::prelude::{IntegerPoint, PointN};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// A newtype wrapper for `PointN` or `ExtentN` where each point represents exactly one chunk.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ChunkUnits<T>(pub T);
 
 impl<N> ChunkUnits<PointN<N>>
@@ -1,18 +1,28 @@
+use crate::dev_prelude::ChunkKey;
+
 use building_blocks_core::prelude::*;
 
 use core::ops::{Div, Mul};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// Uses a bitmask to calculate the minimum of the chunk that contains a given point.
 ///
-/// We use chunk minimums as keys for chunk storage.
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+/// We use chunk minimums as keys for chunk storage. Every `ChunkMap` has an indexer, but one can also be constructed on its
+/// own from a chunk shape, e.g. to plan work on chunks without owning a map.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ChunkIndexer<N> {
     chunk_shape: PointN<N>,
     chunk_shape_mask: PointN<N>,
     chunk_shape_log2: PointN<N>,
 }
 
+/// A 2-dimensional `ChunkIndexer`.
+pub type ChunkIndexer2 = ChunkIndexer<[i32; 2]>;
+/// A 3-dimensional `ChunkIndexer`.
+pub type ChunkIndexer3 = ChunkIndexer<[i32; 3]>;
+
 impl<N> ChunkIndexer<N>
 where
     PointN<N>: IntegerPoint<N>,
@@ -47,21 +57,67 @@ where
         self.chunk_shape_mask() & point
     }
 
-    /// Returns an iterator over all chunk minimums for chunks that overlap the given extent.
+    /// Returns an iterator over all chunk minimums for chunks that overlap the given extent. An empty extent doesn't overlap
+    /// any chunks.
     pub fn chunk_mins_for_extent(&self, extent: &ExtentN<N>) -> impl Iterator<Item = PointN<N>> {
-        let range_min = extent.minimum >> self.chunk_shape_log2;
-        let range_max = extent.max() >> self.chunk_shape_log2;
         let shape_log2 = self.chunk_shape_log2;
 
-        ExtentN::from_min_and_max(range_min, range_max)
+        self.chunk_range_for_extent(extent)
             .iter_points()
             .map(move |p| p << shape_log2)
     }
 
+    /// Returns the number of chunks that overlap the given extent, i.e. the number of minimums yielded by
+    /// `chunk_mins_for_extent`, without iterating over them.
+    pub fn num_chunks_for_extent(&self, extent: &ExtentN<N>) -> usize {
+        self.chunk_range_for_extent(extent).num_points()
+    }
+
+    // The extent, in units of chunks, of the chunks that overlap `extent`.
+    fn chunk_range_for_extent(&self, extent: &ExtentN<N>) -> ExtentN<N> {
+        let range_min = extent.minimum >> self.chunk_shape_log2;
+        let range_max = extent.max() >> self.chunk_shape_log2;
+
+        // Otherwise the range would include the chunk containing the minimum.
+        if extent.is_empty() {
+            ExtentN::from_min_and_shape(range_min, PointN::ZERO)
+        } else {
+            ExtentN::from_min_and_max(range_min, range_max)
+        }
+    }
+
+    /// Splits `extent` along chunk boundaries. Yields the key of every chunk at `lod` that overlaps `extent`, along with the
+    /// part of `extent` inside of that chunk, so each piece can be written to exactly one chunk.
+    pub fn split_extent_by_chunk(
+        &self,
+        lod: u8,
+        extent: &ExtentN<N>,
+    ) -> impl Iterator<Item = (ChunkKey<N>, ExtentN<N>)> {
+        let chunk_shape = self.chunk_shape;
+        let extent = ExtentN::from_min_and_shape(extent.minimum, extent.shape);
+
+        self.chunk_mins_for_extent(&extent).map(move |min| {
+            (
+                ChunkKey::new(lod, min),
+                ExtentN::from_min_and_shape(min, chunk_shape).intersection(&extent),
+            )
+        })
+    }
+
     /// The extent spanned by the chunk at `min`.
     pub fn extent_for_chunk_with_min(&self, min: PointN<N>) -> ExtentN<N> {
         ExtentN::from_min_and_shape(min, self.chunk_shape)
     }
+
+    /// The extent spanned by the chunk at `key`, in the voxel coordinates of `key.lod`.
+    pub fn extent_for_chunk_at_key(&self, key: ChunkKey<N>) -> ExtentN<N> {
+        self.extent_for_chunk_with_min(key.minimum)
+    }
+
+    /// Returns the key of the chunk at `lod` that contains `point`, which is given in the voxel coordinates of `lod`.
+    pub fn chunk_key_containing_point(&self, lod: u8, point: PointN<N>) -> ChunkKey<N> {
+        ChunkKey::new(lod, self.min_of_chunk_containing_point(point))
+    }
 }
 
 // ████████╗███████╗███████╗████████╗
@@ -96,6 +152,81 @@ mod tests {
                 PointN([16, 16, 16])
             ]
         );
+        assert_eq!(
+            indexer.num_chunks_for_extent(&query_extent),
+            chunk_mins.len()
+        );
+        assert_eq!(
+            indexer.num_chunks_for_extent(&Extent3i::from_min_and_shape(
+                Point3i::fill(15),
+                Point3i::ZERO
+            )),
+            0
+        );
+    }
+
+    #[test]
+    fn standalone_indexer_matches_hand_computed_values() {
+        let indexer = ChunkIndexer3::new(Point3i::fill(16));
+
+        assert_eq!(
+            indexer.min_of_chunk_containing_point(PointN([17, -3, 47])),
+            PointN([16, -16, 32])
+        );
+
+        let key = indexer.chunk_key_containing_point(1, PointN([-17, 0, 15]));
+        assert_eq!(key, ChunkKey::new(1, PointN([-32, 0, 0])));
+        assert_eq!(
+            indexer.extent_for_chunk_at_key(key),
+            Extent3i::from_min_and_max(PointN([-32, 0, 0]), PointN([-17, 15, 15]))
+        );
+
+        let query_extent = Extent3i::from_min_and_max(PointN([-1, 0, 0]), PointN([16, 15, 15]));
+        let chunk_mins: Vec<_> = indexer.chunk_mins_for_extent(&query_extent).collect();
+        assert_eq!(
+            chunk_mins,
+            vec![PointN([-16, 0, 0]), PointN([0, 0, 0]), PointN([16, 0, 0])]
+        );
+    }
+
+    #[test]
+    fn split_extent_by_chunk_clips_to_each_chunk() {
+        let indexer = ChunkIndexer3::new(Point3i::fill(16));
+        let extent = Extent3i::from_min_and_max(PointN([10, -4, 12]), PointN([20, 9, 17]));
+        let pieces: Vec<_> = indexer.split_extent_by_chunk(1, &extent).collect();
+
+        let x_ranges = [(10, 15), (16, 20)];
+        let y_ranges = [(-4, -1), (0, 9)];
+        let z_ranges = [(12, 15), (16, 17)];
+        let mut expected = Vec::new();
+        for &(z_min, z_max) in z_ranges.iter() {
+            for &(y_min, y_max) in y_ranges.iter() {
+                for &(x_min, x_max) in x_ranges.iter() {
+                    let piece = Extent3i::from_min_and_max(
+                        PointN([x_min, y_min, z_min]),
+                        PointN([x_max, y_max, z_max]),
+                    );
+                    let key =
+                        ChunkKey::new(1, indexer.min_of_chunk_containing_point(piece.minimum));
+                    expected.push((key, piece));
+                }
+            }
+        }
+        assert_eq!(pieces, expected);
+
+        // The pieces exactly cover the extent.
+        let volume: i32 = pieces.iter().map(|(_, piece)| piece.volume()).sum();
+        assert_eq!(volume, extent.volume());
+    }
+
+    #[test]
+    fn empty_extent_overlaps_no_chunks() {
+        let indexer = ChunkIndexer3::new(Point3i::fill(16));
+
+        for &shape in [PointN([0, 4, 4]), PointN([-3, 4, 4]), PointN([-3, -3, 4])].iter() {
+            let extent = Extent3i::from_min_and_shape(Point3i::fill(5), shape);
+            assert_eq!(indexer.chunk_mins_for_extent(&extent).count(), 0);
+        }
     }
 
     #[test]
@@ -105,4 +236,26 @@ mod tests {
         let min = indexer.min_of_chunk_containing_point(p);
         assert_eq!(min, Point3i::fill(-16));
     }
+
+    #[test]
+    fn point_to_key_to_extent_round_trip() {
+        let indexer = ChunkIndexer::new(PointN([8, 16, 32]));
+
+        for &p in [
+            PointN([0, 0, 0]),
+            PointN([7, 15, 31]),
+            PointN([-1, -1, -1]),
+            PointN([100, -33, 64]),
+        ]
+        .iter()
+        {
+            let key = indexer.chunk_key_containing_point(2, p);
+            assert_eq!(key.lod, 2);
+            assert!(indexer.chunk_min_is_valid(key.minimum));
+
+            let extent = indexer.extent_for_chunk_at_key(key);
+            assert!(extent.contains(p));
+            assert_eq!(extent.shape, indexer.chunk_shape());
+        }
+    }
 }
@@ -0,0 +1,192 @@
+//! An append-only log of compressed chunk snapshots, e.g. for a write-ahead log of edits.
+//!
+//! Every record is length-prefixed, so any record can be read back given the offset returned when it was appended. The layout
+//! of a record is:
+//!
+//! ```text
+//! [record length: u64 LE][lod: u8][minimum: 3 x i32 LE][compressed chunk]
+//! ```
+//!
+//! where the record length counts every byte after the length itself.
+
+use crate::dev_prelude::{ChunkKey3, Compression};
+
+use building_blocks_core::prelude::*;
+
+use std::convert::{TryFrom, TryInto};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// The byte offset of a record in a `ChunkLog`.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LogOffset(pub u64);
+
+/// Appends chunks compressed with `compression` to `file`, which can be any seekable stream, like a `File` or an in-memory
+/// `Cursor`.
+pub struct ChunkLog<Compr, F> {
+    compression: Compr,
+    file: F,
+    buffer: Vec<u8>,
+}
+
+const KEY_LEN: usize = 1 + 3 * 4;
+
+impl<Compr, F> ChunkLog<Compr, F>
+where
+    Compr: Compression,
+    F: Read + Seek + Write,
+{
+    /// Uses `file` as the log. Records are always appended at the end, so `file` may already contain records.
+    pub fn new(compression: Compr, file: F) -> Self {
+        Self {
+            compression,
+            file,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Compresses `chunk` and appends it to the log. Returns the offset to read it back with `read_at`.
+    pub fn append(&mut self, key: ChunkKey3, chunk: &Compr::Data) -> io::Result<LogOffset> {
+        self.buffer.clear();
+        self.buffer.push(key.lod);
+        for c in key.minimum.0.iter() {
+            self.buffer.extend_from_slice(&c.to_le_bytes());
+        }
+        self.compression.compress_into(chunk, &mut self.buffer);
+
+        let offset = self.file.seek(SeekFrom::End(0))?;
+        self.file
+            .write_all(&(self.buffer.len() as u64).to_le_bytes())?;
+        self.file.write_all(&self.buffer)?;
+
+        Ok(LogOffset(offset))
+    }
+
+    /// Reads the record at `offset`, which must have been returned by `append`.
+    ///
+    /// Returns an `InvalidData` error if the record length is too short or longer than the rest of the stream, so a corrupt
+    /// length can't cause a huge allocation.
+    pub fn read_at(&mut self, offset: LogOffset) -> io::Result<(ChunkKey3, Compr::Data)> {
+        let end = self.file.seek(SeekFrom::End(0))?;
+        self.file.seek(SeekFrom::Start(offset.0))?;
+        let mut len_bytes = [0; 8];
+        self.file.read_exact(&mut len_bytes)?;
+        let len = u64::from_le_bytes(len_bytes);
+        if len < KEY_LEN as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Chunk log record is too short",
+            ));
+        }
+        let remaining = end.saturating_sub(offset.0 + len_bytes.len() as u64);
+        let len = match usize::try_from(len) {
+            Ok(len) if len as u64 <= remaining => len,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Chunk log record is longer than the rest of the log",
+                ))
+            }
+        };
+
+        self.buffer.resize(len, 0);
+        self.file.read_exact(&mut self.buffer)?;
+
+        let (key_bytes, chunk_bytes) = self.buffer.split_at(KEY_LEN);
+        let mut minimum = Point3i::ZERO;
+        for (c, bytes) in minimum.0.iter_mut().zip(key_bytes[1..].chunks_exact(4)) {
+            *c = i32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        let key = ChunkKey3::new(key_bytes[0], minimum);
+        let chunk = Compr::decompress_from_reader(chunk_bytes)?;
+
+        Ok((key, chunk))
+    }
+
+    /// Flushes any buffered writes to the underlying stream.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    /// Consumes the log and returns the underlying stream.
+    pub fn into_inner(self) -> F {
+        self.file
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "lz4"))]
+mod test {
+    use super::*;
+
+    use crate::prelude::{Array3x1, FastArrayCompressionNx1, FromBytesCompression, Lz4};
+
+    use std::io::Cursor;
+
+    #[test]
+    fn read_appended_chunks_by_offset() -> io::Result<()> {
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+        let mut log = ChunkLog::new(compression, Cursor::new(Vec::new()));
+
+        let chunks: Vec<_> = (0..5)
+            .map(|i| {
+                let key = ChunkKey3::new(i as u8 % 2, PointN([16 * i, -16, 32]));
+                let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(16));
+                let chunk = Array3x1::fill_with(extent, |p| p.x() + p.y() * i - p.z());
+
+                (key, chunk)
+            })
+            .collect();
+
+        let offsets = chunks
+            .iter()
+            .map(|(key, chunk)| log.append(*key, chunk))
+            .collect::<io::Result<Vec<_>>>()?;
+        log.flush()?;
+
+        // Read out of order, so each read has to seek.
+        for (offset, expected) in offsets.iter().zip(chunks.iter()).rev() {
+            assert_eq!(log.read_at(*offset)?, *expected);
+        }
+
+        // Appending after reading still goes to the end.
+        let (last_key, last_chunk) = &chunks[0];
+        let last_offset = log.append(*last_key, last_chunk)?;
+        assert!(last_offset > offsets[4]);
+        assert_eq!(log.read_at(last_offset)?, (*last_key, last_chunk.clone()));
+        assert_eq!(log.read_at(offsets[1])?, chunks[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupt_record_length_is_invalid_data() -> io::Result<()> {
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+        let mut log = ChunkLog::new(compression, Cursor::new(Vec::new()));
+
+        let key = ChunkKey3::new(0, Point3i::ZERO);
+        let chunk = Array3x1::fill(
+            Extent3i::from_min_and_shape(key.minimum, Point3i::fill(4)),
+            1,
+        );
+        let offset = log.append(key, &chunk)?;
+
+        let mut file = log.into_inner();
+        file.get_mut()[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut log = ChunkLog::new(
+            FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 }),
+            file,
+        );
+        assert_eq!(
+            log.read_at(offset).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+
+        Ok(())
+    }
+}
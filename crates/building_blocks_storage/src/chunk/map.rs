@@ -34,9 +34,9 @@
 //!
 //! # Serialization
 //!
-//! While `ChunkMap` derives `Deserialize` and `Serialize`, it will only be serializable if its constituent types are
-//! serializable. You should expect a `ChunkHashMap` with simple `Array` chunks to be serializable, but a `CompressibleChunkMap`
-//! is *not*.
+//! With the `serde` feature (enabled by default), `ChunkMap` derives `Deserialize` and `Serialize`, but it will only be
+//! serializable if its constituent types are serializable. You should expect a `ChunkHashMap` with simple `Array` chunks to
+//! be serializable, but a `CompressibleChunkMap` is *not*.
 //!
 //! However using `serde` for serializing large dynamic chunk maps is discouraged. Instead there is a `ChunkDb` backed by the
 //! `sled` embedded database which supports transactions and compression.
@@ -131,26 +131,41 @@
 //! ```
 
 pub mod builder;
+pub mod empty;
+pub mod frustum;
+pub mod heightfield;
 pub mod lod_view;
+pub mod ray_march;
 pub mod sampling;
+pub mod surface_voxels;
 
 pub use builder::*;
+pub use frustum::*;
+pub use heightfield::*;
 pub use lod_view::*;
 pub use sampling::*;
 
 use crate::{
+    array::ArrayIndexer,
     chunk::ChunkIndexer,
     dev_prelude::{
-        Array, ChunkKey, ChunkReadStorage, ChunkWriteStorage, FillExtent, ForEach, GetMutUnchecked,
-        GetRefUnchecked, GetUnchecked, IterChunkKeys,
+        Array, ChunkKey, ChunkReadStorage, ChunkWriteStorage, FillExtent, ForEach, ForEachMutPtr,
+        GetMutUnchecked, GetRefUnchecked, GetUnchecked, IterChunkKeys, Local, Stride,
     },
-    multi_ptr::MultiRef,
+    multi_ptr::{MultiMutPtr, MultiRef},
+    SmallKeyHashMap,
 };
 
-use building_blocks_core::{bounding_extent, point_traits::IntegerPoint, ExtentN, PointN};
+use building_blocks_core::{
+    bounding_extent, point_traits::IntegerPoint, Extent3i, ExtentN, Point3i, PointN,
+};
 
 use either::Either;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
 
 /// One piece of a chunked lattice map.
 pub trait Chunk {
@@ -195,15 +210,25 @@ impl<N, Chan> Chunk for Array<N, Chan> {
 /// - [GetMut](crate::access_traits::GetMut)
 /// - [ForEachMut](crate::access_traits::ForEachMut)
 /// - [WriteExtent](crate::access_traits::WriteExtent)
-#[derive(Deserialize, Serialize)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ChunkMap<N, T, Bldr, Store> {
     /// Translates from lattice coordinates to chunk key space.
     pub indexer: ChunkIndexer<N>,
     storage: Store,
     builder: Bldr,
     ambient_value: T, // Needed for GetRef to return a reference to non-temporary value
+    #[cfg_attr(feature = "serde", serde(skip, default))]
+    edit_observer: Option<ChunkEditObserver<N>>,
+    #[cfg_attr(feature = "serde", serde(skip, default))]
+    ambient_fn: Option<AmbientFn<N, T>>,
 }
 
+/// A callback that is given the key of every chunk edited through a `ChunkMap`. See `ChunkMap::set_edit_observer`.
+pub type ChunkEditObserver<N> = Box<dyn FnMut(ChunkKey<N>) + Send + Sync>;
+
+/// A function that generates the ambient value at each point of vacant chunks. See `ChunkMap::set_ambient_fn`.
+pub type AmbientFn<N, T> = Arc<dyn Fn(PointN<N>) -> T + Send + Sync>;
+
 /// A 2-dimensional `ChunkMap`.
 pub type ChunkMap2<T, Bldr, Store> = ChunkMap<[i32; 2], T, Bldr, Store>;
 /// A 3-dimensional `ChunkMap`.
@@ -233,6 +258,8 @@ where
             storage,
             builder,
             ambient_value,
+            edit_observer: None,
+            ambient_fn: None,
         }
     }
 }
@@ -261,6 +288,66 @@ impl<N, T, Bldr, Store> ChunkMap<N, T, Bldr, Store> {
         &self.builder
     }
 
+    /// Calls `observer` with the key of a chunk every time that chunk is written, deleted, or mutably borrowed through this
+    /// map, including through the access traits of a `ChunkMapLodView`. Since the observer can't know whether a mutable borrow
+    /// actually changed anything, it should treat the chunk as modified.
+    ///
+    /// Modifications made directly through `storage_mut` are not observed. The observer is not serialized with the map.
+    #[inline]
+    pub fn set_edit_observer(&mut self, observer: ChunkEditObserver<N>) {
+        self.edit_observer = Some(observer);
+    }
+
+    /// Makes reads of points in vacant chunks return `generator(p)` instead of the constant ambient value, e.g. to follow a base
+    /// terrain height function. This affects `clone_point`, `visit_chunks`, and the `Get`, `ForEach`, and `ReadExtent` impls of
+    /// `ChunkMapLodView`.
+    ///
+    /// Since `get_point` (and `GetRef`) must return a reference, it still uses the constant ambient value. Chunks that are
+    /// created on write are also still filled with the constant ambient value. Like the edit observer, the generator is not
+    /// serialized.
+    #[inline]
+    pub fn set_ambient_fn(&mut self, generator: impl Fn(PointN<N>) -> T + Send + Sync + 'static) {
+        self.ambient_fn = Some(Arc::new(generator));
+    }
+
+    /// Removes and returns the generator set by `set_ambient_fn`, so vacant chunks go back to the constant ambient value.
+    #[inline]
+    pub fn take_ambient_fn(&mut self) -> Option<AmbientFn<N, T>> {
+        self.ambient_fn.take()
+    }
+
+    /// The ambient value at `p`, i.e. the value read from `p` if its chunk is vacant.
+    #[inline]
+    pub fn ambient_value_at(&self, p: PointN<N>) -> T
+    where
+        T: Clone,
+    {
+        match &self.ambient_fn {
+            Some(generator) => generator(p),
+            None => self.ambient_value.clone(),
+        }
+    }
+
+    /// The `AmbientExtent` that represents any vacant chunk.
+    #[inline]
+    pub fn ambient_extent(&self) -> AmbientExtent<N, T>
+    where
+        T: Clone,
+    {
+        match &self.ambient_fn {
+            Some(generator) => {
+                AmbientExtent::from_fn(self.ambient_value.clone(), generator.clone())
+            }
+            None => AmbientExtent::new(self.ambient_value.clone()),
+        }
+    }
+
+    /// Removes and returns the observer set by `set_edit_observer`.
+    #[inline]
+    pub fn take_edit_observer(&mut self) -> Option<ChunkEditObserver<N>> {
+        self.edit_observer.take()
+    }
+
     /// Get an immutable view of a single level of detail `lod` in order to use the access traits.
     #[inline]
     pub fn lod_view(&self, lod: u8) -> ChunkMapLodView<&'_ Self> {
@@ -323,7 +410,7 @@ where
 
         self.get_chunk(ChunkKey::new(lod, chunk_min))
             .map(|chunk| unsafe { chunk.array().get_unchecked(p) })
-            .unwrap_or_else(|| self.ambient_value.clone())
+            .unwrap_or_else(|| self.ambient_value_at(p))
     }
 
     /// Get a reference to the values at point `p` in level of detail `lod`.
@@ -354,14 +441,26 @@ where
                 visitor(Either::Left(chunk))
             } else {
                 let chunk_extent = self.indexer.extent_for_chunk_with_min(chunk_min);
-                visitor(Either::Right((
-                    &chunk_extent,
-                    AmbientExtent::new(self.ambient_value.clone()),
-                )))
+                visitor(Either::Right((&chunk_extent, self.ambient_extent())))
             }
         }
     }
 
+    /// Returns an iterator over all occupied chunks at level of detail `lod` that overlap `extent`. Vacant chunks are skipped.
+    #[inline]
+    pub fn chunks_overlapping<'a>(
+        &'a self,
+        lod: u8,
+        extent: &ExtentN<N>,
+    ) -> impl 'a + Iterator<Item = (ChunkKey<N>, &'a Ch)> {
+        self.indexer
+            .chunk_mins_for_extent(extent)
+            .filter_map(move |chunk_min| {
+                self.get_chunk(ChunkKey::new(lod, chunk_min))
+                    .map(|chunk| (ChunkKey::new(lod, chunk_min), chunk))
+            })
+    }
+
     /// Call `visitor` on all occupied chunks that overlap `extent`.
     #[inline]
     pub fn visit_occupied_chunks(
@@ -392,6 +491,7 @@ where
     pub fn write_chunk(&mut self, key: ChunkKey<N>, chunk: Ch) {
         debug_assert!(self.indexer.chunk_min_is_valid(key.minimum));
 
+        notify_edit(&mut self.edit_observer, &key);
         self.storage.write(key, chunk);
     }
 
@@ -402,6 +502,7 @@ where
     pub fn replace_chunk(&mut self, key: ChunkKey<N>, chunk: Ch) -> Option<Ch> {
         debug_assert!(self.indexer.chunk_min_is_valid(key.minimum));
 
+        notify_edit(&mut self.edit_observer, &key);
         self.storage.replace(key, chunk)
     }
 
@@ -412,7 +513,12 @@ where
     pub fn get_mut_chunk(&mut self, key: ChunkKey<N>) -> Option<&mut Ch> {
         debug_assert!(self.indexer.chunk_min_is_valid(key.minimum));
 
-        self.storage.get_mut(key)
+        let chunk = self.storage.get_mut(ChunkKey::new(key.lod, key.minimum));
+        if chunk.is_some() {
+            notify_edit(&mut self.edit_observer, &key);
+        }
+
+        chunk
     }
 
     /// Mutably borrow the chunk at `key`. If the chunk doesn't exist, `create_chunk` is called to insert one.
@@ -426,6 +532,7 @@ where
     ) -> &mut Ch {
         debug_assert!(self.indexer.chunk_min_is_valid(key.minimum));
 
+        notify_edit(&mut self.edit_observer, &key);
         self.storage.get_mut_or_insert_with(key, create_chunk)
     }
 
@@ -440,10 +547,12 @@ where
             indexer,
             storage,
             builder,
+            edit_observer,
             ..
         } = self;
         let chunk_min = key.minimum;
 
+        notify_edit(edit_observer, &key);
         storage.get_mut_or_insert_with(key, || {
             builder.new_ambient(indexer.extent_for_chunk_with_min(chunk_min))
         })
@@ -490,9 +599,20 @@ where
         }
     }
 
+    /// Reserves space in the storage for every chunk overlapping `extent` (at any one level of detail), without creating any
+    /// chunks. This avoids growing the storage incrementally when about to fill a large `extent`.
+    ///
+    /// Chunks that already exist in `extent` are not subtracted, so this over-reserves when `extent` is partially filled.
+    #[inline]
+    pub fn reserve_extent(&mut self, extent: &ExtentN<N>) {
+        self.storage
+            .reserve(self.indexer.num_chunks_for_extent(extent));
+    }
+
     #[inline]
     pub fn delete_chunk(&mut self, key: ChunkKey<N>) {
         debug_assert!(self.indexer.chunk_min_is_valid(key.minimum));
+        notify_edit(&mut self.edit_observer, &key);
         self.storage.delete(key);
     }
 
@@ -500,8 +620,31 @@ where
     pub fn pop_chunk(&mut self, key: ChunkKey<N>) -> Option<Ch> {
         debug_assert!(self.indexer.chunk_min_is_valid(key.minimum));
 
+        notify_edit(&mut self.edit_observer, &key);
         self.storage.pop(key)
     }
+
+    /// Removes all of the chunks at `keys`, returning the removed chunks along with their keys. Keys of vacant chunks are
+    /// skipped, so they don't appear in the output.
+    ///
+    /// The chunks are removed with `ChunkWriteStorage::pop`, so a `CompressibleChunkStorage` decompresses any compressed
+    /// chunks before returning them.
+    pub fn remove_chunks<I>(&mut self, keys: I) -> Vec<(ChunkKey<N>, Ch)>
+    where
+        I: IntoIterator<Item = ChunkKey<N>>,
+    {
+        let mut removed = Vec::new();
+        for key in keys.into_iter() {
+            debug_assert!(self.indexer.chunk_min_is_valid(key.minimum));
+
+            if let Some(chunk) = self.storage.pop(key) {
+                notify_edit(&mut self.edit_observer, &key);
+                removed.push((key, chunk));
+            }
+        }
+
+        removed
+    }
 }
 
 impl<N, T, Bldr, Store> ChunkMap<N, T, Bldr, Store>
@@ -515,6 +658,94 @@ where
     }
 }
 
+impl<T, Bldr, Store> ChunkMap3<T, Bldr, Store> {
+    /// Writes `values` into all of `extent` in level of detail `lod`, allocating chunks as needed. `values` must be in the
+    /// stride order of an `Array` with `extent`, i.e. X varies fastest and Z slowest.
+    ///
+    /// Returns an error without writing anything if `values` doesn't have exactly one value for every point in `extent`.
+    pub fn write_extent_from_slice<MutPtr>(
+        &mut self,
+        lod: u8,
+        extent: &Extent3i,
+        values: &[T],
+    ) -> Result<(), SliceLengthMismatch>
+    where
+        for<'r> ChunkMapLodView<&'r mut Self>: ForEachMutPtr<[i32; 3], Point3i, Item = MutPtr>,
+        MutPtr: MultiMutPtr<Data = T>,
+        T: Clone,
+    {
+        let expected = extent.num_points();
+        if values.len() != expected {
+            return Err(SliceLengthMismatch {
+                expected,
+                actual: values.len(),
+            });
+        }
+
+        unsafe {
+            self.lod_view_mut(lod).for_each_mut_ptr(extent, |p, ptr| {
+                let Stride(i) =
+                    <[i32; 3]>::stride_from_local_point(extent.shape, Local(p - extent.minimum));
+                ptr.write(values[i].clone());
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// The error returned when a slice of values doesn't have the same length as the extent it's written to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SliceLengthMismatch {
+    /// The number of points in the extent.
+    pub expected: usize,
+    /// The length of the slice.
+    pub actual: usize,
+}
+
+impl fmt::Display for SliceLengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Expected {} values to fill the extent, but got {}",
+            self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for SliceLengthMismatch {}
+
+impl<T, Ch, Bldr, Store> ChunkMap3<T, Bldr, Store>
+where
+    T: Clone + Eq + Hash,
+    Ch: Chunk,
+    Ch::Array: ForEach<[i32; 3], Point3i, Item = T>,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Ch>,
+{
+    /// Counts the number of points in `extent` at level of detail `lod` that take each distinct value. Points in vacant
+    /// chunks are counted with the ambient value.
+    pub fn value_histogram(&self, lod: u8, extent: &Extent3i) -> SmallKeyHashMap<T, usize> {
+        let mut histogram = SmallKeyHashMap::default();
+        self.visit_chunks(lod, extent, |chunk| match chunk {
+            Either::Left(chunk) => chunk.array().for_each(extent, |_p, value| {
+                *histogram.entry(value).or_insert(0) += 1;
+            }),
+            Either::Right((chunk_extent, ambient)) => {
+                let vacant_extent = chunk_extent.intersection(extent);
+                if ambient.is_constant() {
+                    *histogram.entry(ambient.get()).or_insert(0) += vacant_extent.num_points();
+                } else {
+                    ambient.for_each(&vacant_extent, |_p, value| {
+                        *histogram.entry(value).or_insert(0) += 1;
+                    });
+                }
+            }
+        });
+
+        histogram
+    }
+}
+
 impl<'a, N, T, Bldr, Store> ChunkMap<N, T, Bldr, Store>
 where
     PointN<N>: IntegerPoint<N>,
@@ -535,27 +766,65 @@ where
     }
 }
 
-/// An extent that takes the same value everywhere.
-#[derive(Copy, Clone)]
+fn notify_edit<N>(observer: &mut Option<ChunkEditObserver<N>>, key: &ChunkKey<N>)
+where
+    PointN<N>: Copy,
+{
+    if let Some(observer) = observer {
+        observer(ChunkKey::new(key.lod, key.minimum));
+    }
+}
+
+/// The values of a vacant extent of a `ChunkMap`. This is either the constant ambient value or, if the map has an ambient
+/// generator (see `ChunkMap::set_ambient_fn`), a function of the point.
+///
+/// This is `Clone` but not `Copy`, since the generator is shared by reference counting.
+#[derive(Clone)]
 pub struct AmbientExtent<N, T> {
+    /// The constant ambient value. If there is also a generator, this is only used where a single value is required, like
+    /// the ambient value of a `TransformMap`.
     pub value: T,
-    _n: std::marker::PhantomData<N>,
+    generator: Option<AmbientFn<N, T>>,
 }
 
 impl<N, T> AmbientExtent<N, T> {
     pub fn new(value: T) -> Self {
         Self {
             value,
-            _n: Default::default(),
+            generator: None,
+        }
+    }
+
+    /// An extent whose value at point `p` is `generator(p)`.
+    pub fn from_fn(value: T, generator: AmbientFn<N, T>) -> Self {
+        Self {
+            value,
+            generator: Some(generator),
         }
     }
 
+    /// Returns `true` iff the value is the same at every point.
+    pub fn is_constant(&self) -> bool {
+        self.generator.is_none()
+    }
+
     pub fn get(&self) -> T
     where
         T: Clone,
     {
         self.value.clone()
     }
+
+    /// The value at point `p`.
+    pub fn get_at(&self, p: PointN<N>) -> T
+    where
+        T: Clone,
+    {
+        match &self.generator {
+            Some(generator) => generator(p),
+            None => self.value.clone(),
+        }
+    }
 }
 
 impl<N, T> ForEach<N, PointN<N>> for AmbientExtent<N, T>
@@ -567,7 +836,7 @@ where
 
     fn for_each(&self, extent: &ExtentN<N>, mut f: impl FnMut(PointN<N>, Self::Item)) {
         for p in extent.iter_points() {
-            f(p, self.value.clone());
+            f(p, self.get_at(p));
         }
     }
 }
@@ -653,6 +922,361 @@ mod tests {
         }
     }
 
+    #[test]
+    fn copy_extent_past_loaded_chunks_with_sampling_modes() {
+        let mut map = BUILDER.build_with_hash_map_storage();
+        let mut lod0 = map.lod_view_mut(0);
+
+        // Only the chunks with X in [0, 16) are loaded.
+        let loaded_extent = Extent3i::from_min_and_shape(Point3i::ZERO, PointN([16, 32, 32]));
+        for p in loaded_extent.iter_points() {
+            *lod0.get_mut(p) = 1 + p.y() + 100 * p.z();
+        }
+
+        let lod0 = map.lod_view(0);
+        let dst_extent = Extent3i::from_min_and_shape(PointN([8, 0, 0]), PointN([16, 32, 32]));
+
+        let mut ambient = Array3x1::fill(dst_extent, -1);
+        copy_extent(
+            &dst_extent,
+            &lod0.sampled(SamplingMode::Ambient),
+            &mut ambient,
+        );
+        let mut clamped = Array3x1::fill(dst_extent, -1);
+        copy_extent(
+            &dst_extent,
+            &lod0.sampled(SamplingMode::ClampToEdge),
+            &mut clamped,
+        );
+
+        for p in dst_extent.iter_points() {
+            let edge_value = 1 + p.y() + 100 * p.z();
+            if p.x() < 16 {
+                assert_eq!(ambient.get(p), edge_value);
+                assert_eq!(clamped.get(p), edge_value);
+            } else {
+                assert_eq!(ambient.get(p), 0);
+                assert_eq!(clamped.get(p), edge_value);
+            }
+        }
+    }
+
+    #[test]
+    fn write_extent_from_slice_then_read() {
+        let mut map = BUILDER.build_with_hash_map_storage();
+        let extent = Extent3i::from_min_and_shape(PointN([-5, 3, 10]), PointN([20, 7, 9]));
+        let gradient = |p: Point3i| p.x() + 100 * p.y() + 10_000 * p.z();
+        let values: Vec<_> = extent.iter_points().map(gradient).collect();
+
+        assert_eq!(
+            map.write_extent_from_slice(0, &extent, &values[1..]),
+            Err(SliceLengthMismatch {
+                expected: values.len(),
+                actual: values.len() - 1
+            })
+        );
+        assert!(map.storage().is_empty());
+
+        map.write_extent_from_slice(0, &extent, &values).unwrap();
+
+        let lod0 = map.lod_view(0);
+        for p in extent.padded(1).iter_points() {
+            let expected = if extent.contains(p) { gradient(p) } else { 0 };
+            assert_eq!(lod0.get(p), expected);
+        }
+    }
+
+    #[test]
+    fn value_histogram_counts_vacant_points_as_ambient() {
+        let mut map = BUILDER.build_with_hash_map_storage();
+        // A 32x32x32 region where the bottom quarter is 1 and the next quarter is 2. The top half stays vacant.
+        let region = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32));
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(Point3i::ZERO, PointN([32, 8, 32])),
+            1,
+        );
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(PointN([0, 8, 0]), PointN([32, 8, 32])),
+            2,
+        );
+
+        let histogram = map.value_histogram(0, &region);
+
+        let quarter = region.num_points() / 4;
+        assert_eq!(histogram.len(), 3);
+        assert_eq!(histogram[&0], 2 * quarter);
+        assert_eq!(histogram[&1], quarter);
+        assert_eq!(histogram[&2], quarter);
+
+        // Only points inside the extent are counted, even in partially covered chunks.
+        let histogram = map.value_histogram(0, &region.eroded(4));
+        assert_eq!(histogram[&1], 24 * 4 * 24);
+        assert_eq!(histogram[&2], 24 * 8 * 24);
+        assert_eq!(histogram[&0], 24 * 12 * 24);
+    }
+
+    #[test]
+    fn edit_observer_sees_every_edited_chunk() {
+        use std::sync::{Arc, Mutex};
+
+        let mut map = BUILDER.build_with_hash_map_storage();
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let observed_clone = observed.clone();
+        map.set_edit_observer(Box::new(move |key| {
+            observed_clone.lock().unwrap().push(key)
+        }));
+
+        let key1 = ChunkKey3::new(0, PointN([16, 0, 0]));
+        let key2 = ChunkKey3::new(0, PointN([-16, 0, 0]));
+        map.write_chunk(
+            key1,
+            BUILDER.new_ambient(map.indexer.extent_for_chunk_with_min(key1.minimum)),
+        );
+        *map.get_mut_point(0, PointN([-1, 0, 0])) = 1;
+        map.lod_view_mut(0).fill_extent(
+            &Extent3i::from_min_and_shape(PointN([17, 1, 1]), Point3i::ONES),
+            2,
+        );
+        // Reads are not edits.
+        assert_eq!(map.clone_point(0, PointN([17, 1, 1])), 2);
+        map.delete_chunk(key2);
+
+        assert_eq!(*observed.lock().unwrap(), vec![key1, key2, key1, key2]);
+
+        assert!(map.take_edit_observer().is_some());
+        map.delete_chunk(key1);
+        assert_eq!(observed.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn vacant_chunks_read_from_ambient_fn() {
+        let mut map = BUILDER.build_with_hash_map_storage();
+        // Solid below a base height that rises with X.
+        let base_height = |p: Point3i| (p.y() < p.x() / 4) as i32;
+        map.set_ambient_fn(base_height);
+        *map.get_mut_point(0, Point3i::ZERO) = 7;
+
+        let read_extent = Extent3i::from_min_and_shape(Point3i::fill(-20), Point3i::fill(40));
+        let expected = |p: Point3i| {
+            if p == Point3i::ZERO {
+                7
+            } else {
+                base_height(p)
+            }
+        };
+
+        let lod0 = map.lod_view(0);
+        for p in read_extent.iter_points() {
+            assert_eq!(lod0.get(p), expected(p));
+        }
+        lod0.for_each(&read_extent, |p, value| assert_eq!(value, expected(p)));
+
+        let mut copy = Array3x1::fill(read_extent, -1);
+        copy_extent(&read_extent, &lod0, &mut copy);
+        for p in read_extent.iter_points() {
+            assert_eq!(copy.get(p), expected(p));
+        }
+
+        // References can only point at the constant ambient value.
+        assert_eq!(lod0.get_ref(PointN([20, 0, 0])), &0);
+
+        assert!(map.take_ambient_fn().is_some());
+        assert_eq!(map.clone_point(0, PointN([-20, -20, 0])), 0);
+    }
+
+    #[test]
+    fn reserve_extent_preallocates_hash_map() {
+        let mut map = BUILDER.build_with_hash_map_storage();
+        let fill_extent = Extent3i::from_min_and_shape(Point3i::fill(-64), Point3i::fill(128));
+        let num_chunks = 8 * 8 * 8;
+
+        map.reserve_extent(&fill_extent);
+        let capacity = map.storage().capacity();
+        assert!(capacity >= num_chunks);
+        assert!(map.storage().is_empty());
+
+        map.fill_extent(0, &fill_extent, 1);
+        assert_eq!(map.storage().len(), num_chunks);
+        assert_eq!(map.storage().capacity(), capacity);
+    }
+
+    #[test]
+    fn empty_extent_operations_are_no_ops() {
+        let mut src = BUILDER.build_with_hash_map_storage();
+        src.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32)),
+            1,
+        );
+        let mut dst = BUILDER.build_with_hash_map_storage();
+        *dst.get_mut_point(0, PointN([40, 40, 40])) = 2;
+
+        for &shape in [PointN([0, 5, 5]), PointN([-2, 5, 5]), PointN([-2, -2, 5])].iter() {
+            // The minimum is in the middle of a chunk that doesn't exist yet in `dst`.
+            let empty = Extent3i::from_min_and_shape(Point3i::fill(5), shape);
+
+            copy_extent(&empty, &src.lod_view(0), &mut dst.lod_view_mut(0));
+            dst.fill_extent(0, &empty, 3);
+            dst.lod_view(0).for_each(&empty, |_: Point3i, _| {
+                panic!("Visited a point in an empty extent")
+            });
+            dst.lod_view_mut(0).for_each_mut(&empty, |_: Point3i, _| {
+                panic!("Visited a point in an empty extent")
+            });
+        }
+
+        let keys: Vec<_> = dst.storage().chunk_keys().cloned().collect();
+        assert_eq!(keys, vec![ChunkKey::new(0, PointN([32, 32, 32]))]);
+        assert_eq!(dst.clone_point(0, PointN([40, 40, 40])), 2);
+    }
+
+    #[test]
+    fn maps_with_same_hash_seed_iterate_in_same_order() {
+        let fill_extent = Extent3i::from_min_and_shape(Point3i::fill(-40), Point3i::fill(80));
+        let make_map = || {
+            let mut map = BUILDER.build_with_seeded_hash_map_storage(7);
+            map.fill_extent(0, &fill_extent, 1);
+            map
+        };
+        let map1 = make_map();
+        let map2 = make_map();
+
+        let keys1: Vec<_> = map1.storage().chunk_keys().cloned().collect();
+        let keys2: Vec<_> = map2.storage().chunk_keys().cloned().collect();
+        assert_eq!(keys1.len(), 6 * 6 * 6);
+        assert_eq!(keys1, keys2);
+    }
+
+    #[test]
+    fn chunks_overlapping_skips_vacant_chunks() {
+        let mut map = BUILDER.build_with_hash_map_storage();
+
+        let occupied_mins = [
+            PointN([0, 0, 0]),
+            PointN([16, 0, 0]),
+            PointN([-16, 0, 0]),
+            PointN([64, 64, 64]),
+        ];
+        for &min in occupied_mins.iter() {
+            *map.get_mut_point(0, min) = 1;
+        }
+        // Same chunk minimum, different LOD.
+        *map.get_mut_point(1, PointN([16, 0, 0])) = 1;
+
+        let query = Extent3i::from_min_and_shape(PointN([-1, 0, 0]), PointN([18, 1, 1]));
+        let mut keys: Vec<_> = map
+            .chunks_overlapping(0, &query)
+            .map(|(key, chunk)| {
+                assert_eq!(chunk.extent().minimum, key.minimum);
+                key
+            })
+            .collect();
+        keys.sort_by_key(|k| k.minimum.x());
+
+        assert_eq!(
+            keys,
+            vec![
+                ChunkKey3::new(0, PointN([-16, 0, 0])),
+                ChunkKey3::new(0, PointN([0, 0, 0])),
+                ChunkKey3::new(0, PointN([16, 0, 0])),
+            ]
+        );
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn chunks_overlapping_reads_compressed_chunks() {
+        use crate::chunk::ChunkState;
+        use crate::prelude::{FastCompressibleChunkStorageNx1, Lz4};
+
+        let mut map = BUILDER.build_with_write_storage(
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 }),
+        );
+
+        let occupied_mins = [PointN([0, 0, 0]), PointN([16, 0, 0]), PointN([64, 64, 64])];
+        for (i, &min) in occupied_mins.iter().enumerate() {
+            *map.get_mut_point(0, min) = i as i32 + 1;
+        }
+        // Compress the two oldest chunks, which are both in the query.
+        map.storage_mut().compress_lru();
+        map.storage_mut().compress_lru();
+        let compressed_key = ChunkKey3::new(0, PointN([0, 0, 0]));
+        assert_eq!(
+            map.storage().chunk_state(compressed_key),
+            ChunkState::Compressed
+        );
+
+        let query = Extent3i::from_min_and_shape(PointN([-1, 0, 0]), PointN([18, 1, 1]));
+        let mut chunks: Vec<_> = map
+            .chunks_overlapping(0, &query)
+            .map(|(key, chunk)| (key, chunk.get(key.minimum)))
+            .collect();
+        chunks.sort_by_key(|(k, _)| k.minimum.x());
+
+        assert_eq!(
+            chunks,
+            vec![
+                (ChunkKey3::new(0, PointN([0, 0, 0])), 1),
+                (ChunkKey3::new(0, PointN([16, 0, 0])), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn remove_chunks_skips_vacant_keys() {
+        let mut map = BUILDER.build_with_hash_map_storage();
+
+        let occupied = [
+            ChunkKey3::new(0, PointN([0, 0, 0])),
+            ChunkKey3::new(0, PointN([16, 0, 0])),
+            ChunkKey3::new(1, PointN([0, 0, 0])),
+        ];
+        for (i, &key) in occupied.iter().enumerate() {
+            map.write_chunk(
+                key,
+                Array3x1::fill(
+                    map.indexer.extent_for_chunk_with_min(key.minimum),
+                    i as i32 + 1,
+                ),
+            );
+        }
+
+        let vacant = ChunkKey3::new(0, PointN([-16, 0, 0]));
+        let removed = map.remove_chunks(vec![occupied[1], vacant, occupied[2]]);
+
+        assert_eq!(removed.len(), 2);
+        assert_eq!(removed[0].0, occupied[1]);
+        assert_eq!(removed[0].1.get(PointN([16, 0, 0])), 2);
+        assert_eq!(removed[1].0, occupied[2]);
+        assert_eq!(removed[1].1.get(PointN([0, 0, 0])), 3);
+
+        let remaining: Vec<_> = map.storage().chunk_keys().cloned().collect();
+        assert_eq!(remaining, vec![occupied[0]]);
+    }
+
+    #[cfg(feature = "lz4")]
+    #[test]
+    fn remove_chunks_decompresses_compressed_chunks() {
+        use crate::chunk::ChunkState;
+        use crate::prelude::{FastCompressibleChunkStorageNx1, Lz4};
+
+        let mut map = BUILDER.build_with_write_storage(
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 }),
+        );
+        let key = ChunkKey3::new(0, Point3i::ZERO);
+        *map.get_mut_point(0, PointN([1, 2, 3])) = 5;
+        map.storage_mut().compress_lru();
+        assert_eq!(map.storage().chunk_state(key), ChunkState::Compressed);
+
+        let removed = map.remove_chunks(Some(key));
+
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].1.get(PointN([1, 2, 3])), 5);
+        assert!(map.storage().is_empty());
+    }
+
     #[test]
     fn multichannel_accessors() {
         let builder = ChunkMapBuilder3x2::new(CHUNK_SHAPE, (0, 'a'));
@@ -1,14 +1,17 @@
 use crate::{
     array::FillChannels,
+    chunk::ChunkIndexer,
     dev_prelude::{
         Array, Channel, ChunkHashMap, ChunkKey, ChunkMap, ChunkReadStorage, ChunkWriteStorage,
         SmallKeyHashMap,
     },
+    seeded_small_key_build_hasher,
 };
 
 use building_blocks_core::{point_traits::IntegerPoint, ExtentN, PointN};
 
 use core::hash::Hash;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// An object that knows how to construct chunks for a `ChunkMap`.
@@ -22,6 +25,22 @@ pub trait ChunkMapBuilder<N, T>: Sized {
     /// Construct a new chunk with entirely ambient values.
     fn new_ambient(&self, extent: ExtentN<N>) -> Self::Chunk;
 
+    /// The extent spanned by the chunk at `key`, in the voxel coordinates of `key.lod`.
+    fn extent_for_chunk_at_key(&self, key: ChunkKey<N>) -> ExtentN<N>
+    where
+        PointN<N>: IntegerPoint<N>,
+    {
+        ChunkIndexer::new(self.chunk_shape()).extent_for_chunk_at_key(key)
+    }
+
+    /// Returns the key of the chunk at `lod` that contains `point`, which is given in the voxel coordinates of `lod`.
+    fn chunk_key_containing_point(&self, lod: u8, point: PointN<N>) -> ChunkKey<N>
+    where
+        PointN<N>: IntegerPoint<N>,
+    {
+        ChunkIndexer::new(self.chunk_shape()).chunk_key_containing_point(lod, point)
+    }
+
     /// Create a new `ChunkMap` with the given `storage` which must implement both `ChunkReadStorage` and `ChunkWriteStorage`.
     fn build_with_rw_storage<Store>(self, storage: Store) -> ChunkMap<N, T, Self, Store>
     where
@@ -57,10 +76,25 @@ pub trait ChunkMapBuilder<N, T>: Sized {
     {
         Self::build_with_rw_storage(self, SmallKeyHashMap::default())
     }
+
+    /// Like `build_with_hash_map_storage`, but the hash map uses a fixed `seed` instead of random keys, so iterating over its
+    /// chunks is reproducible from run to run.
+    fn build_with_seeded_hash_map_storage(self, seed: u64) -> ChunkHashMap<N, T, Self>
+    where
+        PointN<N>: IntegerPoint<N>,
+        ChunkKey<N>: Eq + Hash,
+    {
+        Self::build_with_rw_storage(
+            self,
+            SmallKeyHashMap::with_hasher(seeded_small_key_build_hasher(seed)),
+        )
+    }
 }
 
-/// A `ChunkMapBuilder` for `Array` chunks.
-#[derive(Clone, Copy, Deserialize, Serialize)]
+/// A `ChunkMapBuilder` for `Array` chunks. `T` is the tuple of channel values, e.g. `(A, B)`, and `Chan` is the matching tuple of
+/// `Channel`s, e.g. `(Channel<A>, Channel<B>)`. The aliases like `ChunkMapBuilder3x2` fill in `Chan` for up to 6 channels.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ChunkMapBuilderNxM<N, T, Chan> {
     pub chunk_shape: PointN<N>,
     pub ambient_value: T,
@@ -0,0 +1,68 @@
+use crate::{
+    dev_prelude::{Chunk, ChunkMap3, ChunkReadStorage, GetUnchecked},
+    IsEmpty,
+};
+
+use building_blocks_core::prelude::*;
+
+impl<T, Ch, Bldr, Store> ChunkMap3<T, Bldr, Store>
+where
+    T: Clone + IsEmpty,
+    Ch: Chunk,
+    Ch::Array: GetUnchecked<Point3i, Item = T>,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Ch>,
+{
+    /// Returns `true` iff every LOD0 voxel in `extent` is empty, e.g. to skip meshing a region. Returns `false` as soon as a
+    /// non-empty voxel is found.
+    ///
+    /// Vacant chunks are skipped entirely, so they are treated as empty regardless of the ambient value.
+    pub fn is_extent_empty(&self, extent: &Extent3i) -> bool {
+        self.chunks_overlapping(0, extent).all(|(key, chunk)| {
+            let scan_extent = self
+                .indexer
+                .extent_for_chunk_at_key(key)
+                .intersection(extent);
+            let array = chunk.array();
+
+            // This is safe because the scan extent is inside of the chunk.
+            scan_extent
+                .iter_points()
+                .all(|p| unsafe { array.get_unchecked(p) }.is_empty())
+        })
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn extent_with_one_set_voxel_is_not_empty() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), false);
+        let mut map = builder.build_with_hash_map_storage();
+
+        let set_point = PointN([20, 3, -5]);
+        *map.get_mut_point(0, set_point) = true;
+
+        // The extent also reaches into vacant chunks.
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-32), Point3i::fill(64));
+        assert!(!map.is_extent_empty(&extent));
+
+        // A region of the same chunk that doesn't contain the voxel.
+        let other_extent = Extent3i::from_min_and_shape(PointN([16, 4, -16]), Point3i::fill(16));
+        assert!(map.chunks_overlapping(0, &other_extent).next().is_some());
+        assert!(map.is_extent_empty(&other_extent));
+
+        *map.get_mut_point(0, set_point) = false;
+        assert!(map.is_extent_empty(&extent));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::dev_prelude::{ChunkKey3, ChunkMap3, IterChunkKeys};
+
+use building_blocks_core::prelude::*;
+
+/// A plane in 3D space, represented as the set of points `p` where `normal.dot(p) + distance == 0`. The points where
+/// `normal.dot(p) + distance >= 0` are considered to be "inside" of the plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane3 {
+    pub normal: Point3f,
+    pub distance: f32,
+}
+
+impl Plane3 {
+    pub fn new(normal: Point3f, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    /// The signed distance from the plane to `p`, scaled by the length of `normal`.
+    pub fn signed_distance(&self, p: Point3f) -> f32 {
+        self.normal.dot(p) + self.distance
+    }
+
+    /// Returns `true` iff any part of `extent` is inside of the plane.
+    pub fn intersects_or_contains(&self, extent: &Extent3i) -> bool {
+        // Only the corner furthest along the normal needs to be tested.
+        let min = Point3f::from(extent.minimum);
+        let lub = Point3f::from(extent.least_upper_bound());
+        let mut corner = min;
+        for i in 0..3 {
+            if self.normal.at(i) >= 0.0 {
+                corner.0[i] = lub.at(i);
+            }
+        }
+
+        self.signed_distance(corner) >= 0.0
+    }
+}
+
+impl<'a, T, Bldr, Store> ChunkMap3<T, Bldr, Store>
+where
+    Store: IterChunkKeys<'a, [i32; 3]>,
+{
+    /// Iterates over the keys of all chunks in level of detail `lod` whose extents are at least partially inside of all
+    /// `planes`. The planes are assumed to be in the coordinates of `lod`, i.e. the same space as the chunk keys.
+    ///
+    /// This is a conservative test, so some chunks outside of a frustum may be returned when they are near a corner or edge.
+    pub fn chunks_in_frustum(
+        &'a self,
+        lod: u8,
+        planes: &'a [Plane3; 6],
+    ) -> impl Iterator<Item = ChunkKey3> + 'a {
+        self.storage
+            .chunk_keys()
+            .filter(move |key| key.lod == lod)
+            .filter(move |key| {
+                let chunk_extent = self.indexer.extent_for_chunk_with_min(key.minimum);
+
+                planes
+                    .iter()
+                    .all(|plane| plane.intersects_or_contains(&chunk_extent))
+            })
+            .cloned()
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn frustum_excludes_chunks_outside_of_planes() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), 0);
+        let mut map = builder.build_with_hash_map_storage();
+
+        // A row of chunks from X = -32 to X = 64.
+        for i in -2..4 {
+            *map.get_mut_point(0, PointN([16 * i, 0, 0])) = 1;
+        }
+        // And one chunk at a different LOD.
+        *map.get_mut_point(1, PointN([0, 0, 0])) = 1;
+
+        // A truncated pyramid that opens along +X, cut off at X = 1 and X = 40. The chunk at X = -16 only
+        // touches the pyramid's apex, so it's outside.
+        let planes = [
+            Plane3::new(PointN([1.0, 0.0, 0.0]), -1.0),
+            Plane3::new(PointN([-1.0, 0.0, 0.0]), 40.0),
+            Plane3::new(PointN([1.0, 1.0, 0.0]), 0.0),
+            Plane3::new(PointN([1.0, -1.0, 0.0]), 0.0),
+            Plane3::new(PointN([1.0, 0.0, 1.0]), 0.0),
+            Plane3::new(PointN([1.0, 0.0, -1.0]), 0.0),
+        ];
+
+        let mut keys: Vec<_> = map
+            .chunks_in_frustum(0, &planes)
+            .map(|key| key.minimum)
+            .collect();
+        keys.sort_by_key(|p| p.x());
+
+        assert_eq!(
+            keys,
+            vec![PointN([0, 0, 0]), PointN([16, 0, 0]), PointN([32, 0, 0])]
+        );
+    }
+}
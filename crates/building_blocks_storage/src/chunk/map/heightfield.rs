@@ -0,0 +1,117 @@
+use crate::{
+    dev_prelude::{Array2x1, Chunk, ChunkMap3, ChunkReadStorage, GetMut, GetUnchecked},
+    IsEmpty,
+};
+
+use building_blocks_core::prelude::*;
+
+/// The height of a heightfield column that doesn't contain any non-empty voxels.
+pub const EMPTY_COLUMN_HEIGHT: i32 = i32::MIN;
+
+impl<T, Ch, Bldr, Store> ChunkMap3<T, Bldr, Store>
+where
+    T: Clone + IsEmpty,
+    Ch: Chunk,
+    Ch::Array: GetUnchecked<Point3i, Item = T>,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Ch>,
+{
+    /// Finds the height (Y coordinate) of the top-most non-empty voxel at LOD0 in each column of `extent`. The returned array
+    /// covers the XZ projection of `extent`, i.e. the value for column `(x, z)` is at `PointN([x, z])`. Columns without any
+    /// non-empty voxels have a height of `EMPTY_COLUMN_HEIGHT`.
+    ///
+    /// Chunks are scanned from the top down, and vacant chunks are skipped entirely, so they are treated as empty regardless of
+    /// the ambient value.
+    pub fn extract_heightfield(&self, extent: &Extent3i) -> Array2x1<i32> {
+        let column_extent = Extent2i::from_min_and_shape(
+            PointN([extent.minimum.x(), extent.minimum.z()]),
+            PointN([extent.shape.x(), extent.shape.z()]),
+        );
+        let mut heights = Array2x1::fill(column_extent, EMPTY_COLUMN_HEIGHT);
+
+        let mut chunks: Vec<_> = self.chunks_overlapping(0, extent).collect();
+        chunks.sort_by_key(|(key, _)| -key.minimum.y());
+
+        for (key, chunk) in chunks.into_iter() {
+            let scan_extent = self
+                .indexer
+                .extent_for_chunk_at_key(key)
+                .intersection(extent);
+            let scan_min = scan_extent.minimum;
+            let scan_max = scan_extent.max();
+            for z in scan_min.z()..=scan_max.z() {
+                for x in scan_min.x()..=scan_max.x() {
+                    let height = heights.get_mut(PointN([x, z]));
+                    // Chunks are visited from the top down, so a column is final once it has any height.
+                    if *height != EMPTY_COLUMN_HEIGHT {
+                        continue;
+                    }
+                    for y in (scan_min.y()..=scan_max.y()).rev() {
+                        // This is safe because the scan extent is inside of the chunk.
+                        let value = unsafe { chunk.array().get_unchecked(PointN([x, y, z])) };
+                        if !value.is_empty() {
+                            *height = y;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        heights
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn heightfield_of_slanted_surface() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), false);
+        let mut map = builder.build_with_hash_map_storage();
+
+        // The ground rises by one voxel for every step in X, until X = 32 where it's missing.
+        for z in 0..16 {
+            for x in 0..32 {
+                for y in -8..=x {
+                    *map.get_mut_point(0, PointN([x, y, z])) = true;
+                }
+            }
+        }
+
+        // The extracted region reaches into vacant chunks at Z >= 16 and above the top of the slope.
+        let extent = Extent3i::from_min_and_shape(PointN([0, -8, 0]), PointN([40, 36, 24]));
+        let heights = map.extract_heightfield(&extent);
+
+        assert_eq!(
+            *heights.extent(),
+            Extent2i::from_min_and_shape(PointN([0, 0]), PointN([40, 24]))
+        );
+        for z in 0..24 {
+            for x in 0..40 {
+                let expected = if z >= 16 || x >= 32 {
+                    EMPTY_COLUMN_HEIGHT
+                } else {
+                    // Clipped to the top of the extent.
+                    x.min(27)
+                };
+                assert_eq!(
+                    heights.get(PointN([x, z])),
+                    expected,
+                    "x = {}, z = {}",
+                    x,
+                    z
+                );
+            }
+        }
+    }
+}
@@ -3,12 +3,16 @@ use crate::{
     dev_prelude::{
         AmbientExtent, Chunk, ChunkKey, ChunkMap, ChunkMapBuilder, ChunkReadStorage,
         ChunkWriteStorage, FillExtent, ForEach, ForEachMut, ForEachMutPtr, Get, GetMut,
-        GetMutUnchecked, GetRef, GetRefUnchecked, GetUnchecked, ReadExtent, WriteExtent,
+        GetMutUnchecked, GetRef, GetRefUnchecked, GetUnchecked, IterChunkKeys, ReadExtent,
+        WriteExtent,
     },
     multi_ptr::*,
 };
 
-use building_blocks_core::{point_traits::IntegerPoint, ExtentN, PointN};
+use building_blocks_core::{
+    point_traits::{IntegerPoint, LatticeOrder},
+    ExtentN, PointN,
+};
 
 use either::Either;
 use std::ops::{Deref, DerefMut};
@@ -24,6 +28,30 @@ impl<Delegate> ChunkMapLodView<Delegate> {
     pub fn lod(&self) -> u8 {
         self.lod
     }
+
+    /// Borrows this view as a source for `copy_extent` that treats points outside of the loaded chunks according to `mode`.
+    #[inline]
+    pub fn sampled(&self, mode: SamplingMode) -> SampledLodView<'_, Delegate> {
+        SampledLodView { view: self, mode }
+    }
+}
+
+/// How a `SampledLodView` reads points in vacant chunks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SamplingMode {
+    /// Vacant chunks take the ambient value, just like the `ChunkMapLodView` itself.
+    Ambient,
+    /// Points outside of the bounding extent of the loaded chunks take the value of the nearest point in that extent. Vacant
+    /// chunks inside of the bounding extent still take the ambient value. If no chunks are loaded, this is the same as
+    /// `Ambient`.
+    ClampToEdge,
+}
+
+/// A `ChunkMapLodView` used as a `ReadExtent` source with a non-default `SamplingMode`. Created by
+/// `ChunkMapLodView::sampled`.
+pub struct SampledLodView<'a, Delegate> {
+    pub view: &'a ChunkMapLodView<Delegate>,
+    pub mode: SamplingMode,
 }
 
 //  ██████╗ ███████╗████████╗████████╗███████╗██████╗ ███████╗
@@ -209,9 +237,7 @@ where
                     self.delegate
                         .get_chunk(ChunkKey::new(self.lod, chunk_min))
                         .map(|chunk| Either::Left(ArrayCopySrc(chunk)))
-                        .unwrap_or_else(|| {
-                            Either::Right(AmbientExtent::new(self.delegate.ambient_value.clone()))
-                        }),
+                        .unwrap_or_else(|| Either::Right(self.delegate.ambient_extent())),
                 )
             })
             .collect::<Vec<_>>();
@@ -238,6 +264,88 @@ where
     }
 }
 
+impl<'a, 'b, Delegate, N, T: 'a, Ch: 'a, Bldr: 'a, Store: 'a> ReadExtent<'a, N>
+    for SampledLodView<'b, Delegate>
+where
+    Delegate: Deref<Target = ChunkMap<N, T, Bldr, Store>>,
+    PointN<N>: IntegerPoint<N>,
+    T: Clone,
+    Ch: Chunk,
+    Store: ChunkReadStorage<N, Chunk = Ch> + IterChunkKeys<'a, N>,
+{
+    type Src = SampledChunkCopySrc<'a, N, T, Delegate, &'a Ch>;
+    type SrcIter = std::vec::IntoIter<(ExtentN<N>, Self::Src)>;
+
+    fn read_extent(&'a self, extent: &ExtentN<N>) -> Self::SrcIter {
+        let view: &'a ChunkMapLodView<Delegate> = self.view;
+        let map: &'a ChunkMap<N, T, Bldr, Store> = &view.delegate;
+        let lod = view.lod;
+
+        let has_chunks = map.storage().chunk_keys().any(|key| key.lod == lod);
+        let bounds = match self.mode {
+            SamplingMode::ClampToEdge if has_chunks => Some(map.bounding_extent(lod)),
+            _ => None,
+        };
+
+        view.read_extent(extent)
+            .flat_map(|(sub_extent, src)| match (src, bounds) {
+                (Either::Right(ambient), Some(bounds)) => {
+                    // Only the points outside of the bounds are clamped.
+                    let mut pieces: Vec<_> = sub_extent
+                        .subtract(&bounds)
+                        .into_iter()
+                        .map(|outside| (outside, Either::Right(EdgeClampSrc { view, bounds })))
+                        .collect();
+                    let inside = sub_extent.intersection(&bounds);
+                    if !inside.is_empty() {
+                        pieces.push((inside, Either::Left(Either::Right(ambient))));
+                    }
+                    pieces
+                }
+                (src, _) => vec![(sub_extent, Either::Left(src))],
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Reads points from `view` after clamping them to `bounds`.
+#[doc(hidden)]
+pub struct EdgeClampSrc<'a, N, Delegate> {
+    pub view: &'a ChunkMapLodView<Delegate>,
+    pub bounds: ExtentN<N>,
+}
+
+impl<'a, N, Delegate> Clone for EdgeClampSrc<'a, N, Delegate>
+where
+    ExtentN<N>: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            view: self.view,
+            bounds: self.bounds.clone(),
+        }
+    }
+}
+
+impl<'a, N, Delegate> EdgeClampSrc<'a, N, Delegate>
+where
+    PointN<N>: IntegerPoint<N>,
+{
+    #[inline]
+    pub fn get<T>(&self, p: PointN<N>) -> T
+    where
+        ChunkMapLodView<Delegate>: Get<PointN<N>, Item = T>,
+    {
+        self.view
+            .get(p.join(self.bounds.minimum).meet(self.bounds.max()))
+    }
+}
+
+#[doc(hidden)]
+pub type SampledChunkCopySrc<'a, N, T, Delegate, Ch> =
+    Either<ChunkCopySrc<N, T, Ch>, EdgeClampSrc<'a, N, Delegate>>;
+
 #[doc(hidden)]
 pub type ChunkCopySrc<N, T, Ch> = Either<ArrayCopySrc<Ch>, AmbientExtent<N, T>>;
 #[doc(hidden)]
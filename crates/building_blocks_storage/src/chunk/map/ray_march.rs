@@ -0,0 +1,185 @@
+use crate::dev_prelude::{Chunk, ChunkMap3, ChunkReadStorage, GetUnchecked};
+
+use building_blocks_core::prelude::*;
+
+impl<T, Ch, Bldr, Store> ChunkMap3<T, Bldr, Store>
+where
+    T: Clone,
+    Ch: Chunk,
+    Ch::Array: GetUnchecked<Point3i, Item = T>,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Ch>,
+{
+    /// Samples LOD0 at every `step` along the ray from `origin` in direction `dir`, up to a distance of `max_dist`, and calls
+    /// `f` with each sample position and the value of the voxel containing it. Marching stops at the first sample where `f`
+    /// returns `true`, and that sample's position is returned. Vacant chunks yield the ambient value.
+    ///
+    /// `dir` doesn't need to be normalized.
+    ///
+    /// # Panics
+    ///
+    /// If `step` is not positive, `dir` is zero, or `max_dist` is not finite.
+    pub fn ray_march(
+        &self,
+        origin: Point3f,
+        dir: Point3f,
+        step: f32,
+        max_dist: f32,
+        mut f: impl FnMut(Point3f, &T) -> bool,
+    ) -> Option<Point3f> {
+        march(origin, dir, step, max_dist, |p| {
+            f(p, &self.clone_point(0, p.in_voxel()))
+        })
+    }
+
+    /// Like `ray_march`, but the value at each sample is trilinearly interpolated, which is usually what you want for a signed
+    /// distance field. The values of the map are located at integer points, the same as the vertex positions produced by
+    /// `surface_nets`.
+    ///
+    /// # Panics
+    ///
+    /// If `step` is not positive, `dir` is zero, or `max_dist` is not finite.
+    pub fn ray_march_trilinear(
+        &self,
+        origin: Point3f,
+        dir: Point3f,
+        step: f32,
+        max_dist: f32,
+        mut f: impl FnMut(Point3f, &T) -> bool,
+    ) -> Option<Point3f>
+    where
+        T: From<f32>,
+        f32: From<T>,
+    {
+        march(origin, dir, step, max_dist, |p| {
+            f(p, &T::from(self.sample_trilinear(p)))
+        })
+    }
+
+    fn sample_trilinear(&self, p: Point3f) -> f32
+    where
+        f32: From<T>,
+    {
+        let base = p.floor_int();
+        let t = p - Point3f::from(base);
+
+        let mut value = 0.0;
+        for corner in 0..8 {
+            let offset = PointN([corner & 1, (corner >> 1) & 1, (corner >> 2) & 1]);
+            let weight = lerp_weight(t.x(), offset.x())
+                * lerp_weight(t.y(), offset.y())
+                * lerp_weight(t.z(), offset.z());
+            if weight > 0.0 {
+                value += weight * f32::from(self.clone_point(0, base + offset));
+            }
+        }
+
+        value
+    }
+}
+
+fn lerp_weight(t: f32, offset: i32) -> f32 {
+    if offset == 0 {
+        1.0 - t
+    } else {
+        t
+    }
+}
+
+/// Calls `sample` at every `step` along the ray until it returns `true`. Sample positions are computed from the sample index,
+/// rather than accumulated, so rounding errors don't build up over long rays.
+fn march(
+    origin: Point3f,
+    dir: Point3f,
+    step: f32,
+    max_dist: f32,
+    mut sample: impl FnMut(Point3f) -> bool,
+) -> Option<Point3f> {
+    assert!(step > 0.0, "Ray march step must be positive");
+    assert!(max_dist.is_finite(), "Ray march distance must be finite");
+    let dir_norm = dir.norm();
+    assert!(dir_norm > 0.0, "Ray direction must not be zero");
+    let dir = dir / dir_norm;
+
+    if max_dist < 0.0 {
+        return None;
+    }
+    let num_steps = (max_dist / step).floor() as u64;
+    for i in 0..=num_steps {
+        let p = origin + dir * (i as f32 * step);
+        if sample(p) {
+            return Some(p);
+        }
+    }
+
+    None
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn trilinear_ray_march_hits_sdf_plane() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), 1.0f32);
+        let mut map = builder.build_with_hash_map_storage();
+
+        // A plane at X = 10.5, with the solid side at +X.
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32));
+        for p in extent.iter_points() {
+            *map.get_mut_point(0, p) = 10.5 - p.x() as f32;
+        }
+
+        let origin = PointN([0.25, 3.3, 4.1]);
+        let mut num_samples = 0;
+        let hit = map.ray_march_trilinear(origin, PointN([2.0, 0.0, 0.0]), 0.25, 30.0, |_p, d| {
+            num_samples += 1;
+            *d <= 0.0
+        });
+
+        // The field is linear, so interpolation is exact and the sample at X = 10.5 lands on the surface.
+        let hit = hit.unwrap();
+        assert!(
+            hit.l2_distance_squared(PointN([10.5, 3.3, 4.1])) < 1e-8,
+            "{:?}",
+            hit
+        );
+        assert_eq!(num_samples, 42);
+    }
+
+    #[test]
+    fn nearest_ray_march_treats_vacant_chunks_as_ambient() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), true);
+        let mut map = builder.build_with_hash_map_storage();
+
+        // Only the chunks in [0, 32)^3 are loaded, and they are all empty.
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32)),
+            false,
+        );
+
+        let origin = PointN([4.0, 4.5, 4.5]);
+        let hit = map.ray_march(origin, PointN([1.0, 0.0, 0.0]), 0.5, 100.0, |_p, solid| {
+            *solid
+        });
+        let hit = hit.unwrap();
+        assert_eq!(hit, PointN([32.0, 4.5, 4.5]));
+
+        // The ray stops short of the vacant chunks.
+        assert_eq!(
+            map.ray_march(origin, PointN([1.0, 0.0, 0.0]), 0.5, 27.0, |_p, solid| {
+                *solid
+            }),
+            None
+        );
+    }
+}
@@ -1,6 +1,10 @@
+pub mod channels;
+pub mod material;
 pub mod point;
 pub mod sdf_mean;
 
+pub use channels::*;
+pub use material::*;
 pub use point::*;
 pub use sdf_mean::*;
 
@@ -15,6 +19,69 @@ use std::borrow::Borrow;
 pub trait ChunkDownsampler<N, T, Src, Dst> {
     /// Samples `src_chunk` in order to write out just a portion of `dst_chunk`, starting at `dst_min`.
     fn downsample(&self, src_chunk: &Src, dst_chunk: &mut Dst, dst_min: Local<N>, lod_delta: u8);
+
+    /// How to handle source chunks that are missing from the map, e.g. because they are not loaded.
+    fn missing_chunk_policy(&self) -> MissingChunkPolicy {
+        MissingChunkPolicy::Ambient
+    }
+
+    /// Writes the portion of `dst_chunk`, starting at `dst_min`, that a missing source chunk would have covered. This is only
+    /// used with `MissingChunkPolicy::Exclude`. `present_siblings` are the other source chunks that downsample into the same
+    /// destination chunk, and it's never empty. By default, the destination samples are left unchanged.
+    fn downsample_missing(
+        &self,
+        _present_siblings: &[&Src],
+        _dst_chunk: &mut Dst,
+        _dst_min: Local<N>,
+        _lod_delta: u8,
+    ) {
+    }
+}
+
+/// Determines what a missing source chunk contributes when downsampling.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MissingChunkPolicy {
+    /// The missing chunk is treated as if it were filled with the ambient value.
+    Ambient,
+    /// The missing chunk is excluded. The destination samples that it would cover are written from its present siblings with
+    /// `ChunkDownsampler::downsample_missing`, e.g. `SdfMeanDownsampler` takes the mean over only the present siblings. If
+    /// none of the siblings are present either, the samples are filled with the ambient value.
+    Exclude,
+}
+
+impl Default for MissingChunkPolicy {
+    fn default() -> Self {
+        Self::Ambient
+    }
+}
+
+/// A `ChunkDownsampler` that uses the inner sampler, but with `MissingChunkPolicy::Exclude`, e.g.
+/// `ExcludeMissingChunks(SdfMeanDownsampler)`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ExcludeMissingChunks<S>(pub S);
+
+impl<N, T, Src, Dst, S> ChunkDownsampler<N, T, Src, Dst> for ExcludeMissingChunks<S>
+where
+    S: ChunkDownsampler<N, T, Src, Dst>,
+{
+    fn downsample(&self, src_chunk: &Src, dst_chunk: &mut Dst, dst_min: Local<N>, lod_delta: u8) {
+        self.0.downsample(src_chunk, dst_chunk, dst_min, lod_delta)
+    }
+
+    fn missing_chunk_policy(&self) -> MissingChunkPolicy {
+        MissingChunkPolicy::Exclude
+    }
+
+    fn downsample_missing(
+        &self,
+        present_siblings: &[&Src],
+        dst_chunk: &mut Dst,
+        dst_min: Local<N>,
+        lod_delta: u8,
+    ) {
+        self.0
+            .downsample_missing(present_siblings, dst_chunk, dst_min, lod_delta)
+    }
 }
 
 impl<N, T, Ch, Bldr, Store> ChunkMap<N, T, Bldr, Store>
@@ -26,7 +93,9 @@ where
     Bldr: ChunkMapBuilder<N, T, Chunk = Ch>,
     Store: ChunkWriteStorage<N, Chunk = Ch>,
 {
-    /// Downsamples the chunk at `src_chunk_key` into the specified destination level `dst_lod`.
+    /// Downsamples the chunk at `src_chunk_key` into the specified destination level `dst_lod`. If the source chunk is missing,
+    /// then `sampler`'s `MissingChunkPolicy` decides whether the destination is filled with the ambient value or from the
+    /// chunk's present siblings.
     pub fn downsample_chunk<Samp>(
         &mut self,
         sampler: &Samp,
@@ -39,8 +108,20 @@ where
         if let Some(src_chunk) = self.pop_chunk(src_chunk_key) {
             self.downsample_external_chunk(sampler, src_chunk_key, &src_chunk, dst_lod);
             self.write_chunk(src_chunk_key, src_chunk);
-        } else {
+        } else if sampler.missing_chunk_policy() == MissingChunkPolicy::Ambient {
             self.downsample_ambient_chunk(src_chunk_key, dst_lod)
+        } else {
+            let lod_delta = dst_lod - src_chunk_key.lod;
+            let siblings: Vec<_> = self
+                .sibling_chunk_keys(src_chunk_key, lod_delta)
+                .into_iter()
+                .filter_map(|key| self.pop_chunk(key).map(|chunk| (key, chunk)))
+                .collect();
+            let sibling_refs: Vec<&Ch> = siblings.iter().map(|(_, chunk)| chunk).collect();
+            self.downsample_missing_chunk(sampler, src_chunk_key, sibling_refs.as_slice(), dst_lod);
+            for (key, chunk) in siblings.into_iter() {
+                self.write_chunk(key, chunk);
+            }
         }
     }
 
@@ -65,6 +146,47 @@ where
         sampler.downsample(src_chunk, dst_chunk, dst.dst_offset, lod_delta);
     }
 
+    /// Writes the destination samples of the missing chunk at `src_chunk_key` from `present_siblings`, the other chunks at the
+    /// same level that downsample into the same destination chunk. If there are no present siblings, the samples are filled
+    /// with the ambient value.
+    pub fn downsample_missing_chunk<Samp, Src>(
+        &mut self,
+        sampler: &Samp,
+        src_chunk_key: ChunkKey<N>,
+        present_siblings: &[&Src],
+        dst_lod: u8,
+    ) where
+        Samp: ChunkDownsampler<N, T, Src, Ch>,
+    {
+        assert!(dst_lod > src_chunk_key.lod);
+
+        if present_siblings.is_empty() {
+            return self.downsample_ambient_chunk(src_chunk_key, dst_lod);
+        }
+
+        let chunk_shape = self.chunk_shape();
+        let lod_delta = dst_lod - src_chunk_key.lod;
+        let dst =
+            DownsampleDestination::for_source_chunk(chunk_shape, src_chunk_key.minimum, lod_delta);
+        let dst_chunk =
+            self.get_mut_chunk_or_insert_ambient(ChunkKey::new(dst_lod, dst.dst_chunk_min));
+        sampler.downsample_missing(present_siblings, dst_chunk, dst.dst_offset, lod_delta);
+    }
+
+    /// The keys of the other chunks at the level of `key` that downsample into the same destination chunk at `lod_delta`
+    /// levels up.
+    fn sibling_chunk_keys(&self, key: ChunkKey<N>, lod_delta: u8) -> Vec<ChunkKey<N>> {
+        let level_up_shape = self.chunk_shape() << lod_delta as i32;
+        let block =
+            ExtentN::from_min_and_shape(key.minimum - key.minimum % level_up_shape, level_up_shape);
+
+        self.indexer
+            .chunk_mins_for_extent(&block)
+            .filter(|&min| min != key.minimum)
+            .map(|min| ChunkKey::new(key.lod, min))
+            .collect()
+    }
+
     /// Fill the destination samples with the ambient value.
     pub fn downsample_ambient_chunk(&mut self, src_chunk_key: ChunkKey<N>, dst_lod: u8) {
         assert!(dst_lod > src_chunk_key.lod);
@@ -78,7 +200,7 @@ where
             self.get_mut_chunk_or_insert_ambient(ChunkKey::new(dst_lod, dst.dst_chunk_min));
         let dst_extent = ExtentN::from_min_and_shape(
             dst_chunk.extent().minimum + dst.dst_offset.0,
-            chunk_shape >> 1,
+            chunk_shape >> lod_delta as i32,
         );
         dst_chunk.fill_extent(&dst_extent, ambient_value);
     }
@@ -145,6 +267,10 @@ where
             ChunkDownsampler<[i32; 3], T, Ch, Ch> + ChunkDownsampler<[i32; 3], T, Lod0ChBorrow, Ch>,
     {
         let chunk_shape = self.chunk_shape();
+        let lod0_missing_chunk_policy =
+            <Samp as ChunkDownsampler<[i32; 3], T, Lod0ChBorrow, Ch>>::missing_chunk_policy(
+                sampler,
+            );
         let chunk_log2 = chunk_shape.map_components_unary(|c| c.trailing_zeros() as i32);
 
         let chunk_space_extent = *extent >> chunk_log2;
@@ -168,8 +294,22 @@ where
                                     src_chunk.borrow(),
                                     dst_lod,
                                 );
-                            } else {
+                            } else if lod0_missing_chunk_policy == MissingChunkPolicy::Ambient {
                                 self.downsample_ambient_chunk(src_chunk_key, dst_lod);
+                            } else {
+                                let siblings: Vec<_> = self
+                                    .sibling_chunk_keys(src_chunk_key, 1)
+                                    .into_iter()
+                                    .filter_map(|key| get_lod0_chunk(key.minimum))
+                                    .collect();
+                                let sibling_refs: Vec<&Lod0ChBorrow> =
+                                    siblings.iter().map(|chunk| chunk.borrow()).collect();
+                                self.downsample_missing_chunk(
+                                    sampler,
+                                    src_chunk_key,
+                                    sibling_refs.as_slice(),
+                                    dst_lod,
+                                );
                             }
                         } else {
                             self.downsample_chunk(sampler, src_chunk_key, dst_lod);
@@ -195,8 +335,12 @@ where
 {
     /// When downsampling a chunk at level `N`, the samples are used at the returned destination within level `N + level_delta`
     /// in the clipmap.
+    ///
+    /// Every axis of the chunk shape is handled separately, so chunks don't need to be cubes, but `lod_delta` can't exceed the
+    /// log2 of the shortest axis, or the chunk would downsample to nothing along that axis.
     fn for_source_chunk(chunk_shape: PointN<N>, src_chunk_min: PointN<N>, lod_delta: u8) -> Self {
         let lod_delta = lod_delta as i32;
+        debug_assert!(chunk_shape >> lod_delta > PointN::ZERO);
         let chunk_shape_log2 = chunk_shape.map_components_unary(|c| c.trailing_zeros() as i32);
         let level_up_log2 = chunk_shape_log2 + PointN::fill(lod_delta);
         let level_up_shape = chunk_shape << lod_delta;
@@ -220,12 +364,27 @@ where
     N: ArrayIndexer<N>,
     PointN<N>: IntegerPoint<N>,
 {
-    let dst_shape = chunk_shape >> lod_delta;
-    debug_assert!(dst_shape > PointN::ZERO);
+    downsample_for_each(chunk_shape, chunk_shape, dst_min, lod_delta)
+}
 
-    let iter_extent = ExtentN::from_min_and_shape(PointN::ZERO, dst_shape);
-    let dst_iter = N::make_stride_iter(chunk_shape, dst_min, PointN::ONES);
-    let src_iter = N::make_stride_iter(chunk_shape, Local(PointN::ZERO), PointN::ONES << lod_delta);
+/// Iterates over all of a source array with shape `src_shape` in lockstep with the corresponding region, starting at
+/// `dst_min`, of a destination array with shape `dst_shape`.
+fn downsample_for_each<N>(
+    src_shape: PointN<N>,
+    dst_shape: PointN<N>,
+    dst_min: Local<N>,
+    lod_delta: i32,
+) -> LockStepArrayForEach<N>
+where
+    N: ArrayIndexer<N>,
+    PointN<N>: IntegerPoint<N>,
+{
+    let iter_shape = src_shape >> lod_delta;
+    debug_assert!(iter_shape > PointN::ZERO);
+
+    let iter_extent = ExtentN::from_min_and_shape(PointN::ZERO, iter_shape);
+    let dst_iter = N::make_stride_iter(dst_shape, dst_min, PointN::ONES);
+    let src_iter = N::make_stride_iter(src_shape, Local(PointN::ZERO), PointN::ONES << lod_delta);
 
     LockStepArrayForEach::new(iter_extent, dst_iter, src_iter)
 }
@@ -294,6 +453,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn downsample_anisotropic_chunks() {
+        let chunk_shape = PointN([32, 32, 8]);
+        let builder = ChunkMapBuilder3x1::new(chunk_shape, -1);
+        let mut map = builder.build_with_hash_map_storage();
+
+        // Each value encodes its point, so we can tell exactly which point was sampled. The chunk at the origin is missing.
+        let encode = |p: Point3i| p.x() + 100 * p.y() + 10_000 * p.z();
+        let lod0_extent = Extent3i::from_min_and_shape(Point3i::ZERO, PointN([64, 64, 16]));
+        let missing_key = ChunkKey::new(0, Point3i::ZERO);
+        let missing_extent = map.indexer.extent_for_chunk_at_key(missing_key);
+        for p in lod0_extent.iter_points() {
+            if !missing_extent.contains(p) {
+                *map.get_mut_point(0, p) = encode(p);
+            }
+        }
+        let lod0_keys: Vec<_> = map
+            .indexer
+            .chunk_mins_for_extent(&lod0_extent)
+            .map(|min| ChunkKey::new(0, min))
+            .collect();
+        assert_eq!(lod0_keys.len(), 8);
+
+        // Downsample the missing chunk last, so it would overwrite its neighbors' samples if it covered too much.
+        for dst_lod in 1..=2 {
+            for &key in lod0_keys.iter().filter(|&&key| key != missing_key) {
+                map.downsample_chunk(&PointDownsampler, key, dst_lod);
+            }
+            map.downsample_chunk(&PointDownsampler, missing_key, dst_lod);
+
+            let scale = 1 << dst_lod;
+            let dst_extent = lod0_extent >> dst_lod as i32;
+            let missing_dst_extent = missing_extent >> dst_lod as i32;
+            for p in dst_extent.iter_points() {
+                let expected = if missing_dst_extent.contains(p) {
+                    -1
+                } else {
+                    encode(scale * p)
+                };
+                assert_eq!(
+                    map.clone_point(dst_lod, p),
+                    expected,
+                    "lod = {}, p = {:?}",
+                    dst_lod,
+                    p
+                );
+            }
+        }
+    }
+
     #[test]
     fn downsample_multichannel_chunks_with_index() {
         let num_lods = 6;
@@ -326,4 +535,65 @@ mod tests {
             &lod0_extent,
         );
     }
+
+    #[test]
+    fn sdf_mean_missing_chunk_policies() {
+        let chunk_shape = Point3i::fill(16);
+        let builder = ChunkMapBuilder3x1::new(chunk_shape, Sd8::ONE);
+        let child_keys: Vec<_> = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(2))
+            .iter_points()
+            .map(|p| ChunkKey::new(0, p * chunk_shape))
+            .collect();
+        let parent_key = ChunkKey::new(1, Point3i::ZERO);
+        // The region of the parent covered by each child.
+        let extent_in_parent =
+            |key: ChunkKey3| Extent3i::from_min_and_shape(key.minimum / 2, chunk_shape / 2);
+        // Each child downsamples to half of its value, since the mean is also renormalized by the LOD scale factor.
+        let child_value = |i: usize| -0.1 * (i + 1) as f32;
+
+        let parent_with_missing_child = |exclude: bool| {
+            let mut map = builder.build_with_hash_map_storage();
+            // Stale samples that must not survive downsampling.
+            map.fill_extent(
+                1,
+                &map.indexer.extent_for_chunk_at_key(parent_key),
+                Sd8::from(0.9),
+            );
+            for (i, &key) in child_keys.iter().enumerate().take(7) {
+                let child_extent = map.indexer.extent_for_chunk_at_key(key);
+                map.fill_extent(0, &child_extent, Sd8::from(child_value(i)));
+            }
+            for &key in child_keys.iter() {
+                if exclude {
+                    map.downsample_chunk(&ExcludeMissingChunks(SdfMeanDownsampler), key, 1);
+                } else {
+                    map.downsample_chunk(&SdfMeanDownsampler, key, 1);
+                }
+            }
+
+            map.get_chunk(parent_key).unwrap().clone()
+        };
+
+        let ambient_parent = parent_with_missing_child(false);
+        let exclude_parent = parent_with_missing_child(true);
+        let present_mean = (0..7).map(|i| child_value(i) / 2.0).sum::<f32>() / 7.0;
+        for p in extent_in_parent(child_keys[7]).iter_points() {
+            let ambient = f32::from(ambient_parent.get(p));
+            assert!((ambient - 1.0).abs() < 0.01, "{:?}: {}", p, ambient);
+            let exclude = f32::from(exclude_parent.get(p));
+            assert!(
+                (exclude - present_mean).abs() < 0.02,
+                "{:?}: {} != {}",
+                p,
+                exclude,
+                present_mean
+            );
+        }
+        for (i, &key) in child_keys.iter().enumerate().take(7) {
+            for p in extent_in_parent(key).iter_points() {
+                let exclude = f32::from(exclude_parent.get(p));
+                assert!((exclude - child_value(i) / 2.0).abs() < 0.02, "{:?}", p);
+            }
+        }
+    }
 }
@@ -0,0 +1,152 @@
+use super::MissingChunkPolicy;
+use crate::dev_prelude::{Array, Channel, ChunkDownsampler, Local};
+
+use building_blocks_core::prelude::*;
+
+/// A `ChunkDownsampler` for 2-channel chunks that downsamples each channel with its own sampler. This lets a map with both a
+/// discrete material channel and an SDF channel choose e.g. `ChannelDownsamplers2(PointDownsampler, SdfMeanDownsampler)`.
+///
+/// A missing source chunk is only excluded if both samplers exclude it, since the channels of a chunk can't be filled
+/// separately.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChannelDownsamplers2<A, B>(pub A, pub B);
+
+impl<N, A, B, T1, T2, Src1, Src2, Dst1, Dst2>
+    ChunkDownsampler<
+        N,
+        (T1, T2),
+        Array<N, (Channel<T1, Src1>, Channel<T2, Src2>)>,
+        Array<N, (Channel<T1, Dst1>, Channel<T2, Dst2>)>,
+    > for ChannelDownsamplers2<A, B>
+where
+    PointN<N>: IntegerPoint<N>,
+    Local<N>: Copy,
+    Src1: AsRef<[T1]>,
+    Src2: AsRef<[T2]>,
+    Dst1: AsMut<[T1]>,
+    Dst2: AsMut<[T2]>,
+    A: for<'s, 'd> ChunkDownsampler<
+        N,
+        T1,
+        Array<N, Channel<T1, &'s [T1]>>,
+        Array<N, Channel<T1, &'d mut [T1]>>,
+    >,
+    B: for<'s, 'd> ChunkDownsampler<
+        N,
+        T2,
+        Array<N, Channel<T2, &'s [T2]>>,
+        Array<N, Channel<T2, &'d mut [T2]>>,
+    >,
+{
+    fn downsample(
+        &self,
+        src_chunk: &Array<N, (Channel<T1, Src1>, Channel<T2, Src2>)>,
+        dst_chunk: &mut Array<N, (Channel<T1, Dst1>, Channel<T2, Dst2>)>,
+        dst_min: Local<N>,
+        lod_delta: u8,
+    ) {
+        self.0.downsample(
+            &src_chunk.borrow_channels(|(c1, _)| c1),
+            &mut dst_chunk.borrow_channels_mut(|(c1, _)| c1),
+            dst_min,
+            lod_delta,
+        );
+        self.1.downsample(
+            &src_chunk.borrow_channels(|(_, c2)| c2),
+            &mut dst_chunk.borrow_channels_mut(|(_, c2)| c2),
+            dst_min,
+            lod_delta,
+        );
+    }
+
+    fn missing_chunk_policy(&self) -> MissingChunkPolicy {
+        if self.0.missing_chunk_policy() == MissingChunkPolicy::Exclude
+            && self.1.missing_chunk_policy() == MissingChunkPolicy::Exclude
+        {
+            MissingChunkPolicy::Exclude
+        } else {
+            MissingChunkPolicy::Ambient
+        }
+    }
+
+    fn downsample_missing(
+        &self,
+        present_siblings: &[&Array<N, (Channel<T1, Src1>, Channel<T2, Src2>)>],
+        dst_chunk: &mut Array<N, (Channel<T1, Dst1>, Channel<T2, Dst2>)>,
+        dst_min: Local<N>,
+        lod_delta: u8,
+    ) {
+        let siblings1: Vec<_> = present_siblings
+            .iter()
+            .map(|sibling| sibling.borrow_channels(|(c1, _)| c1))
+            .collect();
+        let sibling_refs1: Vec<_> = siblings1.iter().collect();
+        self.0.downsample_missing(
+            sibling_refs1.as_slice(),
+            &mut dst_chunk.borrow_channels_mut(|(c1, _)| c1),
+            dst_min,
+            lod_delta,
+        );
+        let siblings2: Vec<_> = present_siblings
+            .iter()
+            .map(|sibling| sibling.borrow_channels(|(_, c2)| c2))
+            .collect();
+        let sibling_refs2: Vec<_> = siblings2.iter().collect();
+        self.1.downsample_missing(
+            sibling_refs2.as_slice(),
+            &mut dst_chunk.borrow_channels_mut(|(_, c2)| c2),
+            dst_min,
+            lod_delta,
+        );
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn material_is_point_sampled_and_sdf_is_averaged() {
+        let chunk_shape = Point3i::fill(16);
+        let src_key = ChunkKey::new(0, Point3i::ZERO);
+        let chunk_extent = Extent3i::from_min_and_shape(src_key.minimum, chunk_shape);
+
+        // Only the sampled points (with even components) have material 1, and only half of the SDF samples are negative, so
+        // point sampling would give -1 for the SDF, while the mean gives 0.
+        let src_chunk = Array3x2::fill_with(chunk_extent, |p: Point3i| {
+            let material = if (p.x() | p.y() | p.z()) & 1 == 0 {
+                1
+            } else {
+                2
+            };
+            let sdf = if p.x() & 1 == 0 { -1.0 } else { 1.0 };
+
+            (material, Sd8::from(sdf))
+        });
+
+        let builder = ChunkMapBuilder3x2::new(chunk_shape, (0u8, Sd8::ONE));
+        let mut map = builder.build_with_hash_map_storage();
+        map.write_chunk(src_key, src_chunk);
+
+        let sampler = ChannelDownsamplers2(PointDownsampler, SdfMeanDownsampler);
+        map.downsample_chunk(&sampler, src_key, 1);
+
+        let dst_chunk = map.get_chunk(ChunkKey::new(1, Point3i::ZERO)).unwrap();
+        let dst_extent = chunk_extent >> 1;
+        dst_chunk.for_each(&dst_extent, |p: Point3i, (material, sdf): (u8, Sd8)| {
+            assert_eq!(material, 1, "p = {:?}", p);
+            assert!(f32::from(sdf).abs() < 0.01, "p = {:?}, sdf = {:?}", p, sdf);
+        });
+
+        // The rest of the destination chunk is ambient.
+        assert_eq!(dst_chunk.get(PointN([8, 0, 0])), (0, Sd8::ONE));
+    }
+}
@@ -0,0 +1,209 @@
+use super::downsample_for_each;
+use crate::{
+    array::{ArrayForEach, ArrayIndexer},
+    dev_prelude::{
+        Array, Channel, ChunkDownsampler, GetMutUnchecked, GetUnchecked, IndexedArray, Local,
+        SdfMeanDownsampler, Stride,
+    },
+};
+
+use building_blocks_core::prelude::*;
+
+/// A `ChunkDownsampler` for chunks with a discrete material channel and an SDF channel, like `Array3x2<u8, Sd8>`. The SDF is
+/// downsampled with `SdfMeanDownsampler`, and each destination material is the most common material among the solid (negative
+/// SDF) voxels of its `2x2x2` region (assuming `lod_delta=1`), so a thin layer of one material doesn't vanish just because it
+/// missed the sampled corner. Ties go to the material found first. Regions without any solid voxels are point sampled like
+/// `PointDownsampler`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaterialModeDownsampler {
+    pub sdf: SdfMeanDownsampler,
+}
+
+impl<N, M, S, Src1, Src2, Dst1, Dst2>
+    ChunkDownsampler<
+        N,
+        (M, S),
+        Array<N, (Channel<M, Src1>, Channel<S, Src2>)>,
+        Array<N, (Channel<M, Dst1>, Channel<S, Dst2>)>,
+    > for MaterialModeDownsampler
+where
+    N: ArrayIndexer<N>,
+    PointN<N>: IntegerPoint<N>,
+    ArrayForEach<N>: Clone,
+    Local<N>: Copy,
+    M: Copy + Eq,
+    S: Copy,
+    f32: From<S>,
+    Src1: AsRef<[M]>,
+    Src2: AsRef<[S]>,
+    Dst1: AsMut<[M]>,
+    Dst2: AsMut<[S]>,
+    for<'s> Array<N, Channel<M, &'s [M]>>: GetUnchecked<Stride, Item = M>,
+    for<'s> Array<N, Channel<S, &'s [S]>>: GetUnchecked<Stride, Item = S>,
+    for<'d, 'r> Array<N, Channel<M, &'d mut [M]>>: GetMutUnchecked<'r, Stride, Item = &'r mut M>,
+    SdfMeanDownsampler: for<'s, 'd> ChunkDownsampler<
+        N,
+        S,
+        Array<N, Channel<S, &'s [S]>>,
+        Array<N, Channel<S, &'d mut [S]>>,
+    >,
+{
+    fn downsample(
+        &self,
+        src_chunk: &Array<N, (Channel<M, Src1>, Channel<S, Src2>)>,
+        dst_chunk: &mut Array<N, (Channel<M, Dst1>, Channel<S, Dst2>)>,
+        dst_min: Local<N>,
+        lod_delta: u8,
+    ) {
+        debug_assert!(lod_delta > 0);
+
+        let src_materials = src_chunk.borrow_channels(|(m, _)| m);
+        let src_sdf = src_chunk.borrow_channels(|(_, s)| s);
+        material_mode_downsample(
+            &src_materials,
+            &src_sdf,
+            &mut dst_chunk.borrow_channels_mut(|(m, _)| m),
+            dst_min,
+            lod_delta,
+        );
+        self.sdf.downsample(
+            &src_sdf,
+            &mut dst_chunk.borrow_channels_mut(|(_, s)| s),
+            dst_min,
+            lod_delta,
+        );
+    }
+
+    /// Only the SDF channel is written from the siblings. The materials of a missing chunk are left unchanged.
+    fn downsample_missing(
+        &self,
+        present_siblings: &[&Array<N, (Channel<M, Src1>, Channel<S, Src2>)>],
+        dst_chunk: &mut Array<N, (Channel<M, Dst1>, Channel<S, Dst2>)>,
+        dst_min: Local<N>,
+        lod_delta: u8,
+    ) {
+        let sibling_sdfs: Vec<_> = present_siblings
+            .iter()
+            .map(|sibling| sibling.borrow_channels(|(_, s)| s))
+            .collect();
+        let sibling_sdf_refs: Vec<_> = sibling_sdfs.iter().collect();
+        self.sdf.downsample_missing(
+            sibling_sdf_refs.as_slice(),
+            &mut dst_chunk.borrow_channels_mut(|(_, s)| s),
+            dst_min,
+            lod_delta,
+        );
+    }
+}
+
+fn material_mode_downsample<N, M, S, MatSrc, SdfSrc, MatDst>(
+    src_materials: &MatSrc,
+    src_sdf: &SdfSrc,
+    dst_materials: &mut MatDst,
+    dst_min: Local<N>,
+    lod_delta: u8,
+) where
+    N: ArrayIndexer<N>,
+    PointN<N>: IntegerPoint<N>,
+    ArrayForEach<N>: Clone,
+    M: Copy + Eq,
+    f32: From<S>,
+    MatSrc: GetUnchecked<Stride, Item = M> + IndexedArray<N>,
+    SdfSrc: GetUnchecked<Stride, Item = S>,
+    MatDst: for<'r> GetMutUnchecked<'r, Stride, Item = &'r mut M>,
+{
+    let lod_delta = lod_delta as i32;
+    let chunk_shape = src_materials.extent().shape; // Doesn't matter which chunk we choose, they should have the same shape.
+
+    let kernel_for_each = ArrayForEach::new_local_unchecked(
+        chunk_shape,
+        Local(PointN::ZERO),
+        ExtentN::from_min_and_shape(PointN::ZERO, PointN::fill(1 << lod_delta)),
+    );
+
+    let mut counts: Vec<(M, u32)> = Vec::new();
+    let for_each = downsample_for_each(chunk_shape, chunk_shape, dst_min, lod_delta);
+    N::for_each_lockstep_unchecked(for_each, |_p, (s_dst, s_src)| {
+        counts.clear();
+        N::for_each(kernel_for_each.clone(), |_p, neighbor_offset| {
+            let s = s_src + neighbor_offset;
+            if f32::from(unsafe { src_sdf.get_unchecked(s) }) < 0.0 {
+                let material = unsafe { src_materials.get_unchecked(s) };
+                if let Some((_, count)) = counts.iter_mut().find(|(m, _)| *m == material) {
+                    *count += 1;
+                } else {
+                    counts.push((material, 1));
+                }
+            }
+        });
+
+        // `max_by_key` would pick the last of the tied materials.
+        let mut mode = None;
+        for &(material, count) in counts.iter() {
+            if mode.map_or(true, |(_, max_count)| count > max_count) {
+                mode = Some((material, count));
+            }
+        }
+        let material = mode.map_or_else(
+            || unsafe { src_materials.get_unchecked(s_src) },
+            |(material, _)| material,
+        );
+        unsafe {
+            *dst_materials.get_mut_unchecked(s_dst) = material;
+        }
+    });
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn majority_solid_material_is_chosen() {
+        let chunk_shape = Point3i::fill(16);
+        let src_key = ChunkKey::new(0, Point3i::ZERO);
+        let chunk_extent = Extent3i::from_min_and_shape(src_key.minimum, chunk_shape);
+
+        // In every 2x2x2 block, the bottom layer (even Y) is empty with material 1, which is what a point sampler would pick. The
+        // top layer is solid, with three voxels of material 3 and one of material 2.
+        let src_chunk = Array3x2::fill_with(chunk_extent, |p: Point3i| {
+            if p.y() & 1 == 0 {
+                (1u8, Sd8::from(0.5))
+            } else if (p.x() | p.z()) & 1 == 0 {
+                (2, Sd8::from(-0.5))
+            } else {
+                (3, Sd8::from(-0.5))
+            }
+        });
+
+        let builder = ChunkMapBuilder3x2::new(chunk_shape, (0u8, Sd8::ONE));
+        let mut map = builder.build_with_hash_map_storage();
+        map.write_chunk(src_key, src_chunk);
+
+        map.downsample_chunk(&MaterialModeDownsampler::default(), src_key, 1);
+
+        let dst_chunk = map.get_chunk(ChunkKey::new(1, Point3i::ZERO)).unwrap();
+        let dst_extent = chunk_extent >> 1;
+        dst_chunk.for_each(&dst_extent, |p: Point3i, (material, sdf): (u8, Sd8)| {
+            assert_eq!(material, 3, "p = {:?}", p);
+            assert!(f32::from(sdf).abs() < 0.01, "p = {:?}, sdf = {:?}", p, sdf);
+        });
+
+        // Point sampling would have picked the empty material.
+        map.downsample_chunk(
+            &ChannelDownsamplers2(PointDownsampler, SdfMeanDownsampler),
+            src_key,
+            1,
+        );
+        let dst_chunk = map.get_chunk(ChunkKey::new(1, Point3i::ZERO)).unwrap();
+        assert_eq!(dst_chunk.get(PointN([0, 0, 0])).0, 1);
+    }
+}
@@ -1,16 +1,39 @@
-use super::chunk_downsample_for_each;
+use super::downsample_for_each;
 use crate::{
     array::{ArrayForEach, ArrayIndexer},
     dev_prelude::{ChunkDownsampler, IndexedArray, Local, Stride},
-    prelude::{GetMutUnchecked, GetUnchecked},
+    prelude::{Array3x1, GetMutUnchecked, GetUnchecked, Sd8},
 };
 
 use building_blocks_core::prelude::*;
 
 /// A `ChunkDownsampler` that takes the mean of each `2x2x2` region of a signed distance field. It also renormalizes the values
 /// to lie in the range `[-1.0, 1.0]`.
+///
+/// With `ExcludeMissingChunks(SdfMeanDownsampler)`, the samples of a missing chunk are the mean over only its present siblings.
+#[derive(Clone, Copy, Debug, Default)]
 pub struct SdfMeanDownsampler;
 
+impl SdfMeanDownsampler {
+    /// Downsamples all of `src` into `dst`, whose shape must be the shape of `src` divided by `2^lod_delta`. Unlike the
+    /// `ChunkDownsampler` implementation, this works on arrays of any shape, so it's not tied to the chunk pyramid.
+    pub fn downsample_array(src: &Array3x1<Sd8>, dst: &mut Array3x1<Sd8>, lod_delta: u8) {
+        assert!(lod_delta > 0);
+        let src_shape = src.extent().shape;
+        let dst_shape = dst.extent().shape;
+        assert_eq!(dst_shape, src_shape >> lod_delta as i32);
+
+        sdf_mean_downsample(
+            &[src],
+            src_shape,
+            dst,
+            dst_shape,
+            Local(Point3i::ZERO),
+            lod_delta,
+        );
+    }
+}
+
 impl<N, Src, Dst, T> ChunkDownsampler<N, T, Src, Dst> for SdfMeanDownsampler
 where
     N: ArrayIndexer<N>,
@@ -22,33 +45,129 @@ where
     Dst: for<'r> GetMutUnchecked<'r, Stride, Item = &'r mut T> + IndexedArray<N>,
 {
     fn downsample(&self, src_chunk: &Src, dst_chunk: &mut Dst, dst_min: Local<N>, lod_delta: u8) {
+        debug_assert!(lod_delta > 0);
         let chunk_shape = src_chunk.extent().shape; // Doesn't matter which chunk we choose, they should have the same shape.
 
-        debug_assert!(lod_delta > 0);
-        let lod_delta = lod_delta as i32;
+        sdf_mean_downsample(
+            &[src_chunk],
+            chunk_shape,
+            dst_chunk,
+            chunk_shape,
+            dst_min,
+            lod_delta,
+        );
+    }
 
-        let lod_scale_factor = 1 << lod_delta;
-        let src_shape_per_point = PointN::fill(lod_scale_factor);
+    fn downsample_missing(
+        &self,
+        present_siblings: &[&Src],
+        dst_chunk: &mut Dst,
+        dst_min: Local<N>,
+        lod_delta: u8,
+    ) {
+        debug_assert!(lod_delta > 0);
+        let chunk_shape = present_siblings[0].extent().shape;
 
-        let kernel_for_each = ArrayForEach::new_local_unchecked(
+        sdf_mean_downsample(
+            present_siblings,
             chunk_shape,
-            Local(PointN::ZERO),
-            ExtentN::from_min_and_shape(PointN::ZERO, src_shape_per_point),
+            dst_chunk,
+            chunk_shape,
+            dst_min,
+            lod_delta,
         );
+    }
+}
 
-        // Not only do we get the mean signed distance value by dividing by the volume, but we also re-normalize by dividing
-        // by the scale factor (the ratio between voxel edge lengths at the different resolutions).
-        let rescale = 1.0 / (lod_scale_factor * src_shape_per_point.volume()) as f32;
+/// Writes the renormalized mean of each `2^lod_delta` cube of `srcs` into `dst`, starting at `dst_min`. The mean is taken over
+/// the same cube of every array in `srcs`, which must all have the shape `src_shape`.
+fn sdf_mean_downsample<N, Src, Dst, T>(
+    srcs: &[&Src],
+    src_shape: PointN<N>,
+    dst: &mut Dst,
+    dst_shape: PointN<N>,
+    dst_min: Local<N>,
+    lod_delta: u8,
+) where
+    N: ArrayIndexer<N>,
+    PointN<N>: IntegerPoint<N>,
+    ArrayForEach<N>: Clone,
+    T: From<f32>,
+    f32: From<T>,
+    Src: GetUnchecked<Stride, Item = T>,
+    Dst: for<'r> GetMutUnchecked<'r, Stride, Item = &'r mut T>,
+{
+    let lod_delta = lod_delta as i32;
+
+    let lod_scale_factor = 1 << lod_delta;
+    let src_shape_per_point = PointN::fill(lod_scale_factor);
+
+    let kernel_for_each = ArrayForEach::new_local_unchecked(
+        src_shape,
+        Local(PointN::ZERO),
+        ExtentN::from_min_and_shape(PointN::ZERO, src_shape_per_point),
+    );
+
+    // Not only do we get the mean signed distance value by dividing by the volume, but we also re-normalize by dividing
+    // by the scale factor (the ratio between voxel edge lengths at the different resolutions).
+    let rescale =
+        1.0 / (lod_scale_factor * src_shape_per_point.volume() * srcs.len() as i32) as f32;
 
-        let for_each = chunk_downsample_for_each(chunk_shape, dst_min, lod_delta);
-        N::for_each_lockstep_unchecked(for_each, |_p, (s_dst, s_src)| {
-            let mut sum = 0.0;
+    let for_each = downsample_for_each(src_shape, dst_shape, dst_min, lod_delta);
+    N::for_each_lockstep_unchecked(for_each, |_p, (s_dst, s_src)| {
+        let mut sum = 0.0;
+        for src in srcs.iter() {
             N::for_each(kernel_for_each.clone(), |_p, neighbor_offset| {
-                sum += f32::from(unsafe { src_chunk.get_unchecked(s_src + neighbor_offset) });
+                sum += f32::from(unsafe { src.get_unchecked(s_src + neighbor_offset) });
             });
-            unsafe {
-                *dst_chunk.get_mut_unchecked(s_dst) = T::from(rescale * sum);
-            }
+        }
+        unsafe {
+            *dst.get_mut_unchecked(s_dst) = T::from(rescale * sum);
+        }
+    });
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::Get;
+
+    #[test]
+    fn downsample_gradient_array_matches_manual_mean() {
+        let src_extent = Extent3i::from_min_and_shape(Point3i::fill(-4), Point3i::fill(8));
+        let src = Array3x1::fill_with(src_extent, |p| {
+            Sd8::from(0.05 * (p.x() + 2 * p.y() - p.z()) as f32)
         });
+        let dst_extent = Extent3i::from_min_and_shape(Point3i::fill(-2), Point3i::fill(4));
+        let mut dst = Array3x1::fill(dst_extent, Sd8(0));
+
+        SdfMeanDownsampler::downsample_array(&src, &mut dst, 1);
+
+        let kernel = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(2));
+        for p in dst_extent.iter_points() {
+            let src_min = src_extent.minimum + (p - dst_extent.minimum) * 2;
+            let sum: f32 = kernel
+                .iter_points()
+                .map(|offset| f32::from(src.get(src_min + offset)))
+                .sum();
+            // The mean is also divided by the LOD scale factor.
+            let expected = sum / 8.0 / 2.0;
+
+            let actual = f32::from(dst.get(p));
+            assert!(
+                (actual - expected).abs() < 2.0 * Sd8::PRECISION,
+                "{:?}: {} != {}",
+                p,
+                actual,
+                expected
+            );
+        }
     }
 }
@@ -0,0 +1,94 @@
+use crate::dev_prelude::{Chunk, ChunkMap3, ChunkReadStorage, GetUnchecked};
+
+use building_blocks_core::prelude::*;
+
+impl<T, Ch, Bldr, Store> ChunkMap3<T, Bldr, Store>
+where
+    T: Clone,
+    Ch: Chunk,
+    Ch::Array: GetUnchecked<Point3i, Item = T>,
+    Store: ChunkReadStorage<[i32; 3], Chunk = Ch>,
+{
+    /// Returns the points and values of the LOD0 voxels in `extent` that are solid, according to `is_solid`, and have at least
+    /// one face-adjacent neighbor that isn't solid, i.e. the outer shell of the solid regions. Neighbors outside of `extent` are
+    /// still read from the map, and neighbors in vacant chunks have the ambient value.
+    ///
+    /// Vacant chunks are skipped, so none of their voxels are returned. The voxels are in no particular order.
+    pub fn extract_surface_voxels(
+        &self,
+        extent: &Extent3i,
+        is_solid: impl Fn(&T) -> bool,
+    ) -> Vec<(Point3i, T)> {
+        let neighbor_offsets = Point3i::von_neumann_offsets();
+
+        let mut surface = Vec::new();
+        for (key, chunk) in self.chunks_overlapping(0, extent) {
+            let chunk_extent = self.indexer.extent_for_chunk_at_key(key);
+            let array = chunk.array();
+            for p in chunk_extent.intersection(extent).iter_points() {
+                // This is safe because the scanned points are inside of the chunk.
+                let value = unsafe { array.get_unchecked(p) };
+                if !is_solid(&value) {
+                    continue;
+                }
+
+                let is_exposed = neighbor_offsets.iter().any(|&offset| {
+                    let n = p + offset;
+                    let neighbor = if chunk_extent.contains(n) {
+                        unsafe { array.get_unchecked(n) }
+                    } else {
+                        self.clone_point(0, n)
+                    };
+
+                    !is_solid(&neighbor)
+                });
+                if is_exposed {
+                    surface.push((p, value));
+                }
+            }
+        }
+
+        surface
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    use building_blocks_core::prelude::*;
+
+    #[test]
+    fn only_the_shell_of_a_solid_cube_is_extracted() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), 0u8);
+        let mut map = builder.build_with_hash_map_storage();
+
+        // The cube straddles the boundaries of 8 chunks, which are otherwise filled with the ambient value.
+        let cube = Extent3i::from_min_and_shape(Point3i::fill(12), Point3i::fill(8));
+        map.fill_extent(0, &cube, 1);
+
+        let mut surface = map.extract_surface_voxels(
+            &Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32)),
+            |v| *v != 0,
+        );
+        surface.sort_by_key(|(p, _)| *p.0);
+
+        let interior = cube.padded(-1);
+        let mut expected: Vec<_> = cube
+            .iter_points()
+            .filter(|p| !interior.contains(*p))
+            .map(|p| (p, 1))
+            .collect();
+        expected.sort_by_key(|(p, _)| *p.0);
+
+        assert_eq!(expected.len(), 8 * 8 * 8 - 6 * 6 * 6);
+        assert_eq!(surface, expected);
+    }
+}
@@ -1,16 +1,30 @@
 pub mod compressible;
+pub mod cow;
 pub mod hash_map;
+#[cfg(all(feature = "memmap2", feature = "serde"))]
+pub mod mmap;
+pub mod snapshot;
 
 pub use compressible::*;
+pub use cow::*;
 pub use hash_map::*;
+#[cfg(all(feature = "memmap2", feature = "serde"))]
+pub use mmap::*;
+pub use snapshot::*;
 
 use building_blocks_core::prelude::*;
 
 use auto_impl::auto_impl;
-use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 
 /// The key for a chunk at a particular level of detail.
-#[derive(Clone, Copy, Debug, Deserialize, Hash, Eq, PartialEq, Serialize)]
+///
+/// With a human-readable format like JSON, a key is serialized as `{ "lod": lod, "minimum": [x, y, z] }`. Compact formats
+/// like bincode store the fields in declaration order, `minimum` and then `lod`, so keys serialized by older versions can
+/// still be read.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize))]
 pub struct ChunkKey<N> {
     /// The minimum point of the chunk.
     pub minimum: PointN<N>,
@@ -32,6 +46,28 @@ impl<N> ChunkKey<N> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<N> Serialize for ChunkKey<N>
+where
+    PointN<N>: Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Deserializing a map accepts the fields in any order, but a compact format is positional, so only human-readable
+        // formats can lead with the LOD.
+        let lod_first = serializer.is_human_readable();
+        let mut state = serializer.serialize_struct("ChunkKey", 2)?;
+        if lod_first {
+            state.serialize_field("lod", &self.lod)?;
+            state.serialize_field("minimum", &self.minimum)?;
+        } else {
+            state.serialize_field("minimum", &self.minimum)?;
+            state.serialize_field("lod", &self.lod)?;
+        }
+
+        state.end()
+    }
+}
+
 /// Methods for reading chunks from storage.
 #[auto_impl(&, &mut)]
 pub trait ChunkReadStorage<N> {
@@ -67,6 +103,9 @@ pub trait ChunkWriteStorage<N> {
 
     /// Removes and returns the chunk at `key`.
     fn pop(&mut self, key: ChunkKey<N>) -> Option<Self::Chunk>;
+
+    /// Reserves capacity for at least `additional` more chunks, if the storage supports it. By default, this does nothing.
+    fn reserve(&mut self, _additional: usize) {}
 }
 
 #[auto_impl(&, &mut)]
@@ -78,3 +117,37 @@ where
 
     fn chunk_keys(&'a self) -> Self::Iter;
 }
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "serde"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chunk_key_json_round_trip() {
+        let key = ChunkKey3::new(3, PointN([-32, 0, i32::MAX]));
+
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(json, r#"{"lod":3,"minimum":[-32,0,2147483647]}"#);
+
+        let decoded: ChunkKey3 = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, key);
+    }
+
+    #[test]
+    fn chunk_key_bincode_keeps_declaration_order() {
+        let key = ChunkKey3::new(3, PointN([-32, 0, i32::MAX]));
+
+        let bytes = bincode::serialize(&key).unwrap();
+        assert_eq!(bytes, bincode::serialize(&(key.minimum, key.lod)).unwrap());
+
+        let decoded: ChunkKey3 = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded, key);
+    }
+}
@@ -5,17 +5,28 @@ use crate::{
         ChunkKey, ChunkMap, ChunkReadStorage, ChunkWriteStorage, Compressed, Compression,
         FastArrayCompression, FastChannelsCompression, FromBytesCompression, IterChunkKeys,
     },
-    SmallKeyBuildHasher,
+    SmallKeyBuildHasher, SmallKeyHashMap, SmallKeyHashSet,
 };
 
 use building_blocks_core::prelude::*;
 
 use core::hash::Hash;
+#[cfg(feature = "serde")]
+use serde::{de::DeserializeOwned, Serialize};
 use slab::Slab;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thread_local::ThreadLocal;
 
 /// A two-tier chunk storage. The first tier is an LRU cache of uncompressed chunks. The second tier is a `Slab` of compressed
 /// chunks.
+///
+/// Besides LRU order, the storage remembers when each cached chunk was last inserted or mutably accessed, so `compress_stale`
+/// can compress chunks that haven't been touched for some time. Like LRU order, accesses through `&self` are not tracked; use
+/// `touch` for those.
+///
+/// To keep mutable accesses cheap, they are only collected as they happen, and `compress_stale` timestamps them when it runs.
+/// So a chunk that was only mutably accessed may stay resident for up to one extra `compress_stale` interval.
 pub struct CompressibleChunkStorage<N, Compr>
 where
     N: Send,
@@ -26,6 +37,22 @@ where
     thread_local_caches: ThreadLocal<LocalChunkCache<N, Compr::Data>>,
     compression: Compr,
     compressed: CompressedChunks<Compr>,
+    last_access: SmallKeyHashMap<ChunkKey<N>, Instant>,
+    /// Chunks that were mutably accessed since the last `compress_stale`, but haven't been timestamped yet.
+    accessed_since_sweep: SmallKeyHashSet<ChunkKey<N>>,
+    /// The last key added to `accessed_since_sweep`, so repeated accesses to the same chunk don't need to hash the key.
+    last_accessed: Option<ChunkKey<N>>,
+    clock: AccessClock,
+}
+
+/// The source of times for tracking chunk accesses in a `CompressibleChunkStorage`. This is `Instant::now` by default.
+pub type AccessClock = Arc<dyn Fn() -> Instant + Send + Sync>;
+
+/// Runs the compression jobs of a `CompressibleChunkStorage`, e.g. on a thread pool owned by the application. This keeps the
+/// storage independent of any particular runtime.
+pub trait CompressionExecutor {
+    /// Calls `job(i)` for every `i` in `0..num_jobs`, possibly in parallel, and returns once all of the calls have finished.
+    fn execute(&self, num_jobs: usize, job: &(dyn Fn(usize) + Sync));
 }
 
 /// A `LocalCache` of chunks.
@@ -75,6 +102,86 @@ where
             main_cache: Default::default(),
             compression,
             compressed: Slab::new(),
+            last_access: Default::default(),
+            accessed_since_sweep: Default::default(),
+            last_accessed: None,
+            clock: Arc::new(Instant::now),
+        }
+    }
+
+    /// Replaces the clock used to track chunk accesses, e.g. to simulate the passage of time.
+    pub fn set_clock(&mut self, clock: AccessClock) {
+        self.clock = clock;
+    }
+
+    /// If the chunk at `key` is resident, marks it as most recently used and records that it was accessed now.
+    ///
+    /// Returns `false` iff the chunk is compressed, i.e. it would have to be decompressed before use. Absent chunks return
+    /// `true`.
+    pub fn touch(&mut self, key: ChunkKey<N>) -> bool {
+        match self.chunk_state(key.clone()) {
+            ChunkState::Resident => {
+                self.main_cache.touch_if_cached(key.clone());
+                self.mark_accessed(key);
+
+                true
+            }
+            ChunkState::Compressed => false,
+            ChunkState::Absent => true,
+        }
+    }
+
+    /// Compresses every cached chunk that hasn't been inserted, mutably accessed, or touched for at least `older_than`.
+    pub fn compress_stale(&mut self, older_than: Duration) {
+        let now = (self.clock)();
+        self.last_accessed = None;
+        for key in self.accessed_since_sweep.drain() {
+            self.last_access.insert(key, now);
+        }
+
+        let stale_keys: Vec<_> = self
+            .last_access
+            .iter()
+            .filter(|(_, &accessed)| now.saturating_duration_since(accessed) >= older_than)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in stale_keys.into_iter() {
+            self.forget_access(&key);
+            if self.chunk_state(key.clone()) != ChunkState::Resident {
+                continue;
+            }
+            let compressed_entry = self.compressed.vacant_entry();
+            if let Some(CacheEntry::Cached(chunk)) = self
+                .main_cache
+                .evict(key.clone(), CompressedLocation(compressed_entry.key()))
+            {
+                let compressed_chunk = self.compression.compress(&chunk);
+                trace_eviction(&key, &compressed_chunk);
+                compressed_entry.insert(compressed_chunk);
+            }
+        }
+    }
+
+    fn mark_accessed(&mut self, key: ChunkKey<N>) {
+        let now = (self.clock)();
+        self.last_access.insert(key, now);
+    }
+
+    /// Records a mutable access without reading the clock. See `compress_stale`.
+    #[inline]
+    fn mark_accessed_lazily(&mut self, key: &ChunkKey<N>) {
+        if self.last_accessed.as_ref() != Some(key) {
+            self.accessed_since_sweep.insert(key.clone());
+            self.last_accessed = Some(key.clone());
+        }
+    }
+
+    fn forget_access(&mut self, key: &ChunkKey<N>) {
+        self.last_access.remove(key);
+        self.accessed_since_sweep.remove(key);
+        if self.last_accessed.as_ref() == Some(key) {
+            self.last_accessed = None;
         }
     }
 
@@ -90,10 +197,61 @@ where
         self.len_cached() + self.len_compressed()
     }
 
+    /// The total number of bytes in all compressed chunks.
+    pub fn compressed_bytes(&self) -> usize {
+        self.compressed
+            .iter()
+            .map(|(_, chunk)| chunk.compressed_len())
+            .sum()
+    }
+
+    /// The total number of bytes that all compressed chunks would occupy if decompressed, as reported by
+    /// `Compression::uncompressed_len`. This only reads the header of each compressed chunk. A chunk whose uncompressed size
+    /// is unknown to the compression is counted at its compressed size.
+    pub fn uncompressed_bytes(&self) -> usize {
+        self.compressed
+            .iter()
+            .map(|(_, chunk)| {
+                self.compression
+                    .uncompressed_len(chunk.as_bytes())
+                    .unwrap_or_else(|| chunk.compressed_len())
+            })
+            .sum()
+    }
+
+    /// The ratio of `compressed_bytes` to `uncompressed_bytes` over all compressed chunks, so smaller is better. This is 1.0
+    /// when there are no compressed chunks.
+    pub fn compression_ratio(&self) -> f32 {
+        let uncompressed_bytes = self.uncompressed_bytes();
+        if uncompressed_bytes == 0 {
+            return 1.0;
+        }
+
+        self.compressed_bytes() as f32 / uncompressed_bytes as f32
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len_total() == 0
     }
 
+    /// Returns `true` iff there is a chunk at `key`, whether or not it's compressed. This never decompresses a chunk or
+    /// updates the LRU order.
+    pub fn contains_key(&self, key: ChunkKey<N>) -> bool {
+        self.main_cache.get(&key).is_some()
+    }
+
+    /// Returns the `ChunkState` of the chunk at `key`. This never decompresses a chunk or updates the LRU order.
+    ///
+    /// Compressed chunks that were decompressed into a thread-local cache by a read are still considered `Compressed` until
+    /// `flush_thread_local_caches` is called.
+    pub fn chunk_state(&self, key: ChunkKey<N>) -> ChunkState {
+        match self.main_cache.get(&key) {
+            None => ChunkState::Absent,
+            Some(CacheEntry::Cached(_)) => ChunkState::Resident,
+            Some(CacheEntry::Evicted(_)) => ChunkState::Compressed,
+        }
+    }
+
     /// Returns a copy of the `Chunk` at `key`.
     ///
     /// WARNING: the cache will not be updated. This method should be used for a read-modify-write workflow where it would be
@@ -114,11 +272,39 @@ where
         })
     }
 
+    /// Iterates over owned copies of every chunk, decompressing compressed chunks one at a time as the iterator advances.
+    ///
+    /// Unlike iterating over `&self`, the decompressed copies are not stored in any cache, and the LRU order is not updated,
+    /// so memory usage is bounded by the chunks that the caller holds onto. Resident chunks are cloned.
+    pub fn iter_all_decompressed(&self) -> impl '_ + Iterator<Item = (ChunkKey<N>, Compr::Data)>
+    where
+        Compr::Data: Clone,
+    {
+        let Self {
+            main_cache,
+            compressed,
+            compression,
+            ..
+        } = self;
+
+        main_cache.entries().map(move |(key, entry)| match entry {
+            CacheEntry::Cached(chunk) => (key.clone(), chunk.clone()),
+            CacheEntry::Evicted(location) => (
+                key.clone(),
+                compressed
+                    .get(location.0)
+                    .unwrap()
+                    .decompress_with(compression),
+            ),
+        })
+    }
+
     /// Remove the `Chunk` at `key`.
     pub fn remove(
         &mut self,
         key: ChunkKey<N>,
     ) -> Option<MaybeCompressed<Compr::Data, Compressed<Compr>>> {
+        self.forget_access(&key);
         self.main_cache.remove(&key).map(|entry| match entry {
             CacheEntry::Cached(chunk) => MaybeCompressed::Decompressed(chunk),
             CacheEntry::Evicted(location) => {
@@ -131,20 +317,91 @@ where
     /// decompressed and cached.
     pub fn compress_lru(&mut self) {
         let compressed_entry = self.compressed.vacant_entry();
-        if let Some((_, lru_chunk)) = self
+        if let Some((lru_key, lru_chunk)) = self
             .main_cache
             .evict_lru(CompressedLocation(compressed_entry.key()))
         {
-            compressed_entry.insert(self.compression.compress(&lru_chunk));
+            let compressed_chunk = self.compression.compress(&lru_chunk);
+            trace_eviction(&lru_key, &compressed_chunk);
+            compressed_entry.insert(compressed_chunk);
+            self.forget_access(&lru_key);
         }
     }
 
+    /// Compresses the `num_chunks` least-recently-used, cached chunks. If an `executor` is given, the chunks are compressed
+    /// by its jobs, one chunk per job. Otherwise they are compressed on the calling thread. Any chunk whose job the executor
+    /// didn't finish is compressed on the calling thread afterwards, so no chunk is lost.
+    ///
+    /// The result is the same as calling `compress_lru` `num_chunks` times.
+    pub fn compress_num_lru(
+        &mut self,
+        num_chunks: usize,
+        executor: Option<&dyn CompressionExecutor>,
+    ) where
+        Compr: Sync,
+        Compr::Data: Sync,
+        Compressed<Compr>: Send,
+    {
+        let executor = if let Some(executor) = executor {
+            executor
+        } else {
+            for _ in 0..num_chunks {
+                self.compress_lru();
+            }
+            return;
+        };
+
+        let num_chunks = num_chunks.min(self.len_cached());
+        let mut keys = Vec::with_capacity(num_chunks);
+        let mut chunks = Vec::with_capacity(num_chunks);
+        for _ in 0..num_chunks {
+            let (key, chunk) = self.remove_lru().unwrap();
+            keys.push(key);
+            chunks.push(chunk);
+        }
+
+        let compressed: Vec<Mutex<Option<Compressed<Compr>>>> =
+            chunks.iter().map(|_| Mutex::new(None)).collect();
+        let compression = &self.compression;
+        executor.execute(chunks.len(), &|i| {
+            *compressed[i].lock().unwrap() = Some(compression.compress(&chunks[i]));
+        });
+
+        for ((key, chunk), compressed_chunk) in keys
+            .into_iter()
+            .zip(chunks.iter())
+            .zip(compressed.into_iter())
+        {
+            let compressed_chunk = compressed_chunk
+                .into_inner()
+                .unwrap_or_else(|e| e.into_inner())
+                .unwrap_or_else(|| compression.compress(chunk));
+            trace_eviction(&key, &compressed_chunk);
+            self.insert_compressed(key, compressed_chunk);
+        }
+    }
+
+    /// Compresses every cached chunk. See `compress_num_lru`.
+    pub fn compress_all(&mut self, executor: Option<&dyn CompressionExecutor>)
+    where
+        Compr: Sync,
+        Compr::Data: Sync,
+        Compressed<Compr>: Send,
+    {
+        self.compress_num_lru(self.len_cached(), executor);
+    }
+
     /// Remove the least-recently-used, cached chunk.
     ///
     /// This is useful for removing a batch of chunks at a time before compressing them in parallel. Then call
     /// `insert_compressed`.
     pub fn remove_lru(&mut self) -> Option<(ChunkKey<N>, Compr::Data)> {
-        self.main_cache.remove_lru()
+        let removed = self.main_cache.remove_lru();
+        if let Some((key, _)) = &removed {
+            self.forget_access(key);
+        }
+
+        removed
     }
 
     /// Insert a compressed chunk. Returns the old chunk if one exists.
@@ -153,6 +410,7 @@ where
         key: ChunkKey<N>,
         compressed_chunk: Compressed<Compr>,
     ) -> Option<MaybeCompressed<Compr::Data, Compressed<Compr>>> {
+        self.forget_access(&key);
         let compressed_entry = self.compressed.vacant_entry();
         let old_entry = self
             .main_cache
@@ -173,6 +431,7 @@ where
         let taken_caches = std::mem::replace(&mut self.thread_local_caches, ThreadLocal::new());
         for mut cache in taken_caches.into_iter() {
             for (k, v) in cache.drain_iter() {
+                self.mark_accessed(k.clone());
                 self.main_cache.insert(k, v);
             }
         }
@@ -184,6 +443,7 @@ where
         key: ChunkKey<N>,
         chunk: Compr::Data,
     ) -> Option<MaybeCompressed<Compr::Data, Compressed<Compr>>> {
+        self.mark_accessed(key.clone());
         self.main_cache
             .insert(key, chunk)
             .map(|old_entry| match old_entry {
@@ -195,6 +455,62 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<N, Compr> CompressibleChunkStorage<N, Compr>
+where
+    N: Send,
+    ChunkKey<N>: Clone + DeserializeOwned + Eq + Hash + Serialize,
+    Compr: Compression,
+    Compr::Data: Send,
+{
+    /// Serializes every chunk into a single contiguous blob that can be restored with `from_blob`.
+    ///
+    /// Compressed chunks are copied as-is, while resident chunks are compressed on the fly without being evicted from the
+    /// cache. The blob is a bincode-serialized list of `(ChunkKey, compressed bytes)`.
+    pub fn to_blob(&self) -> Vec<u8> {
+        let Self {
+            main_cache,
+            compression,
+            compressed,
+            ..
+        } = self;
+
+        let entries: Vec<(ChunkKey<N>, Vec<u8>)> = main_cache
+            .entries()
+            .map(|(key, entry)| {
+                let bytes = match entry {
+                    CacheEntry::Cached(chunk) => {
+                        let mut bytes = Vec::new();
+                        compression.compress_into(chunk, &mut bytes);
+                        bytes
+                    }
+                    CacheEntry::Evicted(location) => {
+                        compressed.get(location.0).unwrap().compressed_bytes.clone()
+                    }
+                };
+
+                (key.clone(), bytes)
+            })
+            .collect();
+
+        bincode::serialize(&entries).expect("Failed to serialize chunk blob")
+    }
+
+    /// Restores a storage from a blob that was written by `to_blob` with the same type of `compression`. All chunks start out
+    /// compressed, and they will be decompressed into the cache on access.
+    pub fn from_blob(compression: Compr, bytes: &[u8]) -> bincode::Result<Self> {
+        let entries: Vec<(ChunkKey<N>, Vec<u8>)> = bincode::deserialize(bytes)?;
+
+        let mut storage = Self::new(compression);
+        for (key, bytes) in entries.into_iter() {
+            let chunk = Compressed::from_bytes(&storage.compression, bytes);
+            storage.insert_compressed(key, chunk);
+        }
+
+        Ok(storage)
+    }
+}
+
 impl<N, Compr> ChunkReadStorage<N> for CompressibleChunkStorage<N, Compr>
 where
     N: Send,
@@ -210,13 +526,16 @@ where
             thread_local_caches,
             main_cache,
             compressed,
+            compression,
             ..
         } = self;
         main_cache.get(&key).map(|entry| match entry {
             CacheEntry::Cached(value) => value,
             CacheEntry::Evicted(location) => thread_local_caches
                 .get_or(|| LocalChunkCache::default())
-                .get_or_insert_with(key, || compressed.get(location.0).unwrap().decompress()),
+                .get_or_insert_with(key.clone(), || {
+                    decompress_traced(&key, compression, compressed.get(location.0).unwrap())
+                }),
         })
     }
 }
@@ -232,14 +551,20 @@ where
 
     #[inline]
     fn get_mut(&mut self, key: ChunkKey<N>) -> Option<&mut Compr::Data> {
+        if self.contains_key(key.clone()) {
+            self.mark_accessed_lazily(&key);
+        }
+
         let Self {
             main_cache,
             compressed,
+            compression,
             ..
         } = self;
 
-        main_cache
-            .get_mut_or_repopulate_with(key, |location| compressed.remove(location.0).decompress())
+        main_cache.get_mut_or_repopulate_with(key.clone(), |location| {
+            decompress_traced(&key, compression, &compressed.remove(location.0))
+        })
     }
 
     #[inline]
@@ -248,14 +573,17 @@ where
         key: ChunkKey<N>,
         create_chunk: impl FnOnce() -> Compr::Data,
     ) -> &mut Compr::Data {
+        self.mark_accessed_lazily(&key);
+
         let Self {
             main_cache,
             compressed,
+            compression,
             ..
         } = self;
         main_cache.get_mut_or_insert_with(
-            key,
-            |location| compressed.remove(location.0).decompress(),
+            key.clone(),
+            |location| decompress_traced(&key, compression, &compressed.remove(location.0)),
             create_chunk,
         )
     }
@@ -263,10 +591,7 @@ where
     #[inline]
     fn replace(&mut self, key: ChunkKey<N>, chunk: Compr::Data) -> Option<Compr::Data> {
         self.insert_chunk(key, chunk)
-            .map(|old_chunk| match old_chunk {
-                MaybeCompressed::Decompressed(old_chunk) => old_chunk,
-                MaybeCompressed::Compressed(old_chunk) => old_chunk.decompress(),
-            })
+            .map(|old_chunk| old_chunk.into_decompressed_with(&self.compression))
     }
 
     #[inline]
@@ -281,10 +606,40 @@ where
 
     #[inline]
     fn pop(&mut self, key: ChunkKey<N>) -> Option<Compr::Data> {
-        self.remove(key).map(|ch| ch.into_decompressed())
+        self.remove(key)
+            .map(|ch| ch.into_decompressed_with(&self.compression))
     }
 }
 
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn trace_eviction<N, Compr>(key: &ChunkKey<N>, compressed_chunk: &Compressed<Compr>)
+where
+    Compr: Compression,
+{
+    debug_event!(
+        lod = key.lod,
+        compressed_bytes = compressed_chunk.compressed_bytes.len(),
+        "evicted chunk"
+    );
+}
+
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+fn decompress_traced<N, Compr>(
+    key: &ChunkKey<N>,
+    compression: &Compr,
+    compressed_chunk: &Compressed<Compr>,
+) -> Compr::Data
+where
+    Compr: Compression,
+{
+    debug_event!(
+        lod = key.lod,
+        compressed_bytes = compressed_chunk.compressed_bytes.len(),
+        "decompressed chunk"
+    );
+    compressed_chunk.decompress_with(compression)
+}
+
 impl<'a, N: 'a, Compr> IterChunkKeys<'a, N> for CompressibleChunkStorage<N, Compr>
 where
     N: Send,
@@ -314,6 +669,7 @@ where
             main_cache,
             thread_local_caches,
             compressed,
+            compression,
             ..
         } = self;
         Box::new(main_cache.entries().map(move |(key, entry)| match entry {
@@ -321,7 +677,10 @@ where
             CacheEntry::Evicted(location) => {
                 let local_cache = thread_local_caches.get_or(|| LocalChunkCache::default());
                 let chunk = local_cache.get_or_insert_with(key.clone(), || {
-                    compressed.get(location.0).unwrap().decompress()
+                    compressed
+                        .get(location.0)
+                        .unwrap()
+                        .decompress_with(compression)
                 });
                 (key, chunk)
             }
@@ -342,15 +701,30 @@ where
         let Self {
             main_cache,
             mut compressed,
+            compression,
             ..
         } = self;
         Box::new(main_cache.into_iter().map(move |(key, entry)| match entry {
             CacheEntry::Cached(chunk) => (key, chunk),
-            CacheEntry::Evicted(location) => (key, compressed.remove(location.0).decompress()),
+            CacheEntry::Evicted(location) => (
+                key,
+                compressed.remove(location.0).decompress_with(&compression),
+            ),
         }))
     }
 }
 
+/// Where a chunk lives in a `CompressibleChunkStorage`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChunkState {
+    /// There is no chunk for the key.
+    Absent,
+    /// The chunk is only stored in compressed form.
+    Compressed,
+    /// The chunk is decompressed in the LRU cache.
+    Resident,
+}
+
 /// An index into a compressed chunk slab.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct CompressedLocation(pub usize);
@@ -415,3 +789,373 @@ pub mod multichannel_aliases {
 }
 
 pub use multichannel_aliases::*;
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "lz4"))]
+mod test {
+    use super::*;
+    use crate::prelude::{Array3x1, FastArrayCompressionNx1, Lz4};
+
+    #[test]
+    fn compress_stale_only_compresses_untouched_chunks() {
+        let mut storage =
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+
+        let start = Instant::now();
+        let now = Arc::new(std::sync::Mutex::new(start));
+        let clock_now = now.clone();
+        storage.set_clock(Arc::new(move || *clock_now.lock().unwrap()));
+
+        let keys: Vec<_> = (0..3)
+            .map(|i| ChunkKey::new(0, PointN([16 * i, 0, 0])))
+            .collect();
+        for key in keys.iter() {
+            let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(16));
+            storage.insert_chunk(*key, Array3x1::fill(extent, 1u8));
+        }
+
+        *now.lock().unwrap() = start + Duration::from_secs(10);
+        assert!(storage.touch(keys[1]));
+
+        *now.lock().unwrap() = start + Duration::from_secs(15);
+        storage.compress_stale(Duration::from_secs(8));
+
+        assert_eq!(storage.chunk_state(keys[0]), ChunkState::Compressed);
+        assert_eq!(storage.chunk_state(keys[1]), ChunkState::Resident);
+        assert_eq!(storage.chunk_state(keys[2]), ChunkState::Compressed);
+
+        // The touched chunk becomes stale eventually.
+        *now.lock().unwrap() = start + Duration::from_secs(20);
+        storage.compress_stale(Duration::from_secs(8));
+        assert_eq!(storage.chunk_state(keys[1]), ChunkState::Compressed);
+        assert_eq!(storage.len_cached(), 0);
+    }
+
+    #[test]
+    fn mutable_accesses_are_timestamped_by_the_next_sweep() {
+        let mut storage =
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+
+        let start = Instant::now();
+        let now = Arc::new(std::sync::Mutex::new(start));
+        let clock_now = now.clone();
+        storage.set_clock(Arc::new(move || *clock_now.lock().unwrap()));
+
+        let keys: Vec<_> = (0..3)
+            .map(|i| ChunkKey::new(0, PointN([16 * i, 0, 0])))
+            .collect();
+        for key in keys.iter() {
+            let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(16));
+            storage.insert_chunk(*key, Array3x1::fill(extent, 1u8));
+        }
+
+        // Many writes to the same chunk only record a single access.
+        *now.lock().unwrap() = start + Duration::from_secs(10);
+        for _ in 0..100 {
+            storage.get_mut(keys[1]).unwrap();
+        }
+        assert_eq!(storage.accessed_since_sweep.len(), 1);
+
+        storage.compress_stale(Duration::from_secs(8));
+        assert_eq!(storage.chunk_state(keys[0]), ChunkState::Compressed);
+        assert_eq!(storage.chunk_state(keys[1]), ChunkState::Resident);
+        assert_eq!(storage.chunk_state(keys[2]), ChunkState::Compressed);
+        assert!(storage.accessed_since_sweep.is_empty());
+
+        // Compressed and deleted chunks are forgotten.
+        assert_eq!(storage.last_access.len(), 1);
+        storage.compress_lru();
+        assert_eq!(storage.chunk_state(keys[1]), ChunkState::Compressed);
+        assert!(storage.last_access.is_empty());
+        storage.get_mut(keys[0]).unwrap();
+        storage.delete(keys[0]);
+        assert!(storage.last_access.is_empty());
+        assert!(storage.accessed_since_sweep.is_empty());
+    }
+
+    /// Splits jobs evenly between a fixed number of scoped threads.
+    struct ScopedThreadPool {
+        num_threads: usize,
+    }
+
+    impl CompressionExecutor for ScopedThreadPool {
+        fn execute(&self, num_jobs: usize, job: &(dyn Fn(usize) + Sync)) {
+            std::thread::scope(|scope| {
+                for thread in 0..self.num_threads {
+                    scope.spawn(move || {
+                        for i in (thread..num_jobs).step_by(self.num_threads) {
+                            job(i);
+                        }
+                    });
+                }
+            });
+        }
+    }
+
+    /// Only runs the even jobs.
+    struct SkippingExecutor;
+
+    impl CompressionExecutor for SkippingExecutor {
+        fn execute(&self, num_jobs: usize, job: &(dyn Fn(usize) + Sync)) {
+            for i in (0..num_jobs).step_by(2) {
+                job(i);
+            }
+        }
+    }
+
+    #[test]
+    fn compress_all_compresses_chunks_skipped_by_executor() {
+        let mut storage =
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+        let keys: Vec<_> = (0..5)
+            .map(|i| ChunkKey::new(0, PointN([4 * i, 0, 0])))
+            .collect();
+        for (i, key) in keys.iter().enumerate() {
+            let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(4));
+            storage.insert_chunk(*key, Array3x1::fill(extent, i as u8));
+        }
+
+        storage.compress_all(Some(&SkippingExecutor));
+
+        assert_eq!(storage.len_cached(), 0);
+        assert_eq!(storage.len_compressed(), keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(4));
+            assert_eq!(storage.get(*key).unwrap(), &Array3x1::fill(extent, i as u8));
+        }
+    }
+
+    #[test]
+    fn compress_all_on_executor_matches_calling_thread() {
+        let keys: Vec<_> = (0..7)
+            .map(|i| ChunkKey::new(0, PointN([16 * i, 0, 0])))
+            .collect();
+        let fill_storage = || {
+            let mut storage =
+                FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+            for (i, key) in keys.iter().enumerate() {
+                let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(16));
+                storage.insert_chunk(
+                    *key,
+                    Array3x1::fill_with(extent, |p| (p.x() * p.y() + i as i32) as u8),
+                );
+            }
+            storage
+        };
+
+        let mut synchronous = fill_storage();
+        synchronous.compress_all(None);
+
+        let mut pooled = fill_storage();
+        pooled.compress_num_lru(2, Some(&ScopedThreadPool { num_threads: 3 }));
+        assert_eq!(pooled.len_cached(), 5);
+        pooled.compress_all(Some(&ScopedThreadPool { num_threads: 3 }));
+
+        assert_eq!(pooled.len_cached(), 0);
+        assert_eq!(pooled.len_compressed(), keys.len());
+        for key in keys.iter() {
+            let expected = match synchronous.copy_without_caching(*key) {
+                Some(MaybeCompressed::Compressed(chunk)) => chunk,
+                _ => panic!("Chunk should be compressed"),
+            };
+            let actual = match pooled.copy_without_caching(*key) {
+                Some(MaybeCompressed::Compressed(chunk)) => chunk,
+                _ => panic!("Chunk should be compressed"),
+            };
+            assert_eq!(actual.compressed_bytes, expected.compressed_bytes);
+        }
+    }
+
+    #[test]
+    fn homogeneous_chunks_have_low_compression_ratio() {
+        let mut storage =
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+        assert_eq!(storage.compression_ratio(), 1.0);
+
+        let chunk_shape = Point3i::fill(16);
+        for i in 0..4 {
+            let min = PointN([16 * i, 0, 0]);
+            let extent = Extent3i::from_min_and_shape(min, chunk_shape);
+            storage.insert_chunk(ChunkKey::new(0, min), Array3x1::fill(extent, 7u32));
+        }
+        // Leave one chunk resident; it isn't counted.
+        for _ in 0..3 {
+            storage.compress_lru();
+        }
+
+        assert_eq!(storage.uncompressed_bytes(), 3 * 16 * 16 * 16 * 4);
+        assert!(storage.compressed_bytes() > 0);
+        assert!(storage.compression_ratio() < 0.1);
+    }
+
+    #[test]
+    fn chunk_state_transitions_without_decompressing() {
+        let mut storage =
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+
+        let key = ChunkKey::new(0, Point3i::ZERO);
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(4));
+        assert_eq!(storage.chunk_state(key), ChunkState::Absent);
+        assert!(!storage.contains_key(key));
+
+        storage.insert_chunk(key, Array3x1::fill(extent, 1u8));
+        assert_eq!(storage.chunk_state(key), ChunkState::Resident);
+        assert!(storage.contains_key(key));
+
+        storage.compress_lru();
+        assert_eq!(storage.chunk_state(key), ChunkState::Compressed);
+        assert!(storage.contains_key(key));
+
+        // Querying the state must not have decompressed anything.
+        assert_eq!(storage.chunk_state(key), ChunkState::Compressed);
+        assert_eq!(storage.len_cached(), 0);
+        assert_eq!(storage.len_compressed(), 1);
+    }
+
+    #[test]
+    fn iter_all_decompressed_yields_every_chunk_once() {
+        let mut storage =
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(4));
+        let keys: Vec<_> = (0..6)
+            .map(|i| ChunkKey::new(0, PointN([4 * i, 0, 0])))
+            .collect();
+        for (i, key) in keys.iter().enumerate() {
+            storage.insert_chunk(*key, Array3x1::fill(extent, i as u8));
+        }
+        // Compress the first half.
+        for _ in 0..3 {
+            storage.compress_lru();
+        }
+        assert_eq!(storage.len_cached(), 3);
+        assert_eq!(storage.len_compressed(), 3);
+
+        let mut found: Vec<_> = storage.iter_all_decompressed().collect();
+        found.sort_by_key(|(key, _)| key.minimum.x());
+
+        assert_eq!(found.len(), keys.len());
+        for (i, (key, chunk)) in found.into_iter().enumerate() {
+            assert_eq!(key, keys[i]);
+            assert_eq!(chunk, Array3x1::fill(extent, i as u8));
+        }
+
+        // Nothing was cached or decompressed in place.
+        assert_eq!(storage.len_cached(), 3);
+        assert_eq!(storage.len_compressed(), 3);
+        assert_eq!(storage.chunk_state(keys[0]), ChunkState::Compressed);
+        assert_eq!(storage.chunk_state(keys[5]), ChunkState::Resident);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn blob_round_trip_restores_all_chunks_compressed() {
+        let mut storage =
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+
+        let keys: Vec<_> = (0..4)
+            .map(|i| ChunkKey::new(i as u8 % 2, PointN([4 * i, 0, 0])))
+            .collect();
+        for (i, key) in keys.iter().enumerate() {
+            let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(4));
+            storage.insert_chunk(
+                *key,
+                Array3x1::fill_with(extent, |p| (p.x() + i as i32) as u8),
+            );
+        }
+        // Leave a mix of resident and compressed chunks.
+        storage.compress_lru();
+        storage.compress_lru();
+
+        let blob = storage.to_blob();
+        assert_eq!(storage.len_cached(), 2);
+        assert_eq!(storage.len_compressed(), 2);
+
+        let restored = FastCompressibleChunkStorageNx1::from_blob(
+            FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 }),
+            &blob,
+        )
+        .unwrap();
+        assert_eq!(restored.len_cached(), 0);
+        assert_eq!(restored.len_compressed(), keys.len());
+
+        let mut restored_keys: Vec<_> = restored.chunk_keys().cloned().collect();
+        restored_keys.sort_by_key(|key| key.minimum.x());
+        assert_eq!(restored_keys, keys);
+
+        for key in keys.iter() {
+            assert_eq!(restored.chunk_state(*key), ChunkState::Compressed);
+            let original = storage
+                .copy_without_caching(*key)
+                .unwrap()
+                .into_decompressed();
+            assert_eq!(restored.get(*key), Some(&original));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn compress_lru_emits_eviction_event() {
+        use std::fmt::Write;
+        use tracing::{
+            field::{Field, Visit},
+            span, Event, Metadata, Subscriber,
+        };
+
+        /// Records the fields of every event as a `name=value` string.
+        #[derive(Clone, Default)]
+        struct EventRecorder(Arc<Mutex<Vec<String>>>);
+
+        struct FieldWriter<'a>(&'a mut String);
+
+        impl Visit for FieldWriter<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                write!(self.0, "{}={:?} ", field.name(), value).unwrap();
+            }
+        }
+
+        impl Subscriber for EventRecorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+                span::Id::from_u64(1)
+            }
+            fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+            fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = String::new();
+                event.record(&mut FieldWriter(&mut fields));
+                self.0.lock().unwrap().push(fields);
+            }
+            fn enter(&self, _span: &span::Id) {}
+            fn exit(&self, _span: &span::Id) {}
+        }
+
+        let key = ChunkKey::new(0, PointN([16, 0, 0]));
+        let recorder = EventRecorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut storage =
+                FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 });
+            let extent = Extent3i::from_min_and_shape(key.minimum, Point3i::fill(4));
+            storage.insert_chunk(key, Array3x1::fill(extent, 1u8));
+            storage.compress_lru();
+        });
+
+        let events = recorder.0.lock().unwrap();
+        assert!(
+            events
+                .iter()
+                .any(|e| e.contains("message=evicted chunk")
+                    && e.contains(&format!("key={:?}", key))),
+            "{:?}",
+            events
+        );
+    }
+}
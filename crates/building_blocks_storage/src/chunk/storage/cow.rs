@@ -0,0 +1,195 @@
+//! A chunk storage with copy-on-write chunks, for taking frequent snapshots of a `ChunkMap`.
+//!
+//! Each chunk in a `CowChunkStorage` is kept behind an `Arc`, so cloning the storage only clones pointers. A chunk is only
+//! deep-cloned when it is mutably borrowed while some other clone of the storage still shares it. This makes
+//! `ChunkMap::snapshot` cheap when only a few chunks change between snapshots.
+
+use crate::dev_prelude::{
+    ChunkKey, ChunkMap, ChunkMapBuilder, ChunkReadStorage, ChunkWriteStorage, IterChunkKeys,
+    SmallKeyHashMap,
+};
+
+use building_blocks_core::prelude::*;
+
+use core::hash::Hash;
+use std::collections::hash_map;
+use std::sync::Arc;
+
+/// A `HashMap` of chunks that are shared between clones until they are written. See the module documentation.
+pub struct CowChunkStorage<N, Ch> {
+    chunks: SmallKeyHashMap<ChunkKey<N>, Arc<Ch>>,
+}
+
+impl<N, Ch> Clone for CowChunkStorage<N, Ch> {
+    fn clone(&self) -> Self {
+        Self {
+            chunks: self.chunks.clone(),
+        }
+    }
+}
+
+impl<N, Ch> Default for CowChunkStorage<N, Ch> {
+    fn default() -> Self {
+        Self {
+            chunks: Default::default(),
+        }
+    }
+}
+
+impl<N, Ch> CowChunkStorage<N, Ch> {
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+impl<N, Ch> CowChunkStorage<N, Ch>
+where
+    ChunkKey<N>: Eq + Hash,
+{
+    /// Borrow the shared pointer to the chunk at `key`. This can be used to check whether two storages share a chunk with
+    /// `Arc::ptr_eq`.
+    #[inline]
+    pub fn get_shared(&self, key: ChunkKey<N>) -> Option<&Arc<Ch>> {
+        self.chunks.get(&key)
+    }
+}
+
+impl<N, Ch> ChunkReadStorage<N> for CowChunkStorage<N, Ch>
+where
+    ChunkKey<N>: Eq + Hash,
+{
+    type Chunk = Ch;
+
+    #[inline]
+    fn get(&self, key: ChunkKey<N>) -> Option<&Ch> {
+        self.chunks.get(&key).map(|chunk| &**chunk)
+    }
+}
+
+impl<N, Ch> ChunkWriteStorage<N> for CowChunkStorage<N, Ch>
+where
+    ChunkKey<N>: Eq + Hash,
+    Ch: Clone,
+{
+    type Chunk = Ch;
+
+    #[inline]
+    fn get_mut(&mut self, key: ChunkKey<N>) -> Option<&mut Ch> {
+        self.chunks.get_mut(&key).map(Arc::make_mut)
+    }
+
+    #[inline]
+    fn get_mut_or_insert_with(
+        &mut self,
+        key: ChunkKey<N>,
+        create_chunk: impl FnOnce() -> Ch,
+    ) -> &mut Ch {
+        Arc::make_mut(
+            self.chunks
+                .entry(key)
+                .or_insert_with(|| Arc::new(create_chunk())),
+        )
+    }
+
+    #[inline]
+    fn replace(&mut self, key: ChunkKey<N>, chunk: Ch) -> Option<Ch> {
+        self.chunks
+            .insert(key, Arc::new(chunk))
+            .map(unwrap_or_clone)
+    }
+
+    #[inline]
+    fn write(&mut self, key: ChunkKey<N>, chunk: Ch) {
+        self.chunks.insert(key, Arc::new(chunk));
+    }
+
+    #[inline]
+    fn delete(&mut self, key: ChunkKey<N>) {
+        self.chunks.remove(&key);
+    }
+
+    #[inline]
+    fn pop(&mut self, key: ChunkKey<N>) -> Option<Ch> {
+        self.chunks.remove(&key).map(unwrap_or_clone)
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.chunks.reserve(additional)
+    }
+}
+
+impl<'a, N, Ch> IterChunkKeys<'a, N> for CowChunkStorage<N, Ch>
+where
+    ChunkKey<N>: 'a,
+    Ch: 'a,
+{
+    type Iter = hash_map::Keys<'a, ChunkKey<N>, Arc<Ch>>;
+
+    fn chunk_keys(&'a self) -> Self::Iter {
+        self.chunks.keys()
+    }
+}
+
+impl<N, T, Ch, Bldr> ChunkMap<N, T, Bldr, CowChunkStorage<N, Ch>>
+where
+    PointN<N>: IntegerPoint<N>,
+    ChunkKey<N>: Eq + Hash,
+    Ch: Clone,
+    Bldr: ChunkMapBuilder<N, T, Chunk = Ch> + Clone,
+{
+    /// Returns a copy of this map that shares all of its chunks with `self`. Whichever map next writes to a shared chunk will
+    /// clone it first, so neither map observes the other's later edits. The edit observer is not copied.
+    pub fn snapshot(&self) -> Self {
+        self.builder()
+            .clone()
+            .build_with_rw_storage(self.storage().clone())
+    }
+}
+
+fn unwrap_or_clone<Ch: Clone>(chunk: Arc<Ch>) -> Ch {
+    Arc::try_unwrap(chunk).unwrap_or_else(|shared| (*shared).clone())
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::ChunkMapBuilder3x1;
+
+    #[test]
+    fn snapshot_shares_unedited_chunks() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), 0);
+        let mut map = builder.build_with_rw_storage(CowChunkStorage::default());
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32));
+        map.fill_extent(0, &extent, 1);
+        assert_eq!(map.storage().len(), 8);
+
+        let snapshot = map.snapshot();
+        *map.get_mut_point(0, Point3i::fill(1)) = 2;
+
+        let edited_key = ChunkKey::new(0, Point3i::ZERO);
+        assert_eq!(map.clone_point(0, Point3i::fill(1)), 2);
+        assert_eq!(snapshot.clone_point(0, Point3i::fill(1)), 1);
+        assert!(!Arc::ptr_eq(
+            map.storage().get_shared(edited_key).unwrap(),
+            snapshot.storage().get_shared(edited_key).unwrap()
+        ));
+        for &key in map.storage().chunk_keys().filter(|&&k| k != edited_key) {
+            assert!(Arc::ptr_eq(
+                map.storage().get_shared(key).unwrap(),
+                snapshot.storage().get_shared(key).unwrap()
+            ));
+        }
+    }
+}
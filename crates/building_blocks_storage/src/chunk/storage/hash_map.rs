@@ -56,6 +56,12 @@ where
     fn pop(&mut self, key: ChunkKey<N>) -> Option<Ch> {
         self.remove(&key)
     }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        // Call the `HashMap` method, not this one.
+        (**self).reserve(additional)
+    }
 }
 
 impl<'a, N, Ch> IterChunkKeys<'a, N> for SmallKeyHashMap<ChunkKey<N>, Ch>
@@ -0,0 +1,383 @@
+//! A read-only chunk storage for large, static worlds that don't fit in memory.
+//!
+//! Chunks are first "baked" into a single file with `bake_chunks`. The file layout is:
+//!
+//! ```text
+//! [compressed chunk 0][compressed chunk 1]...[index][index offset: u64 LE]
+//! ```
+//!
+//! where the index is a bincode-serialized list of `(ChunkKey, BakedChunkLocation)`. The file is then memory-mapped by
+//! `MmapChunkStorage`, which only decompresses chunks as they are accessed.
+
+use crate::{
+    caching::{CacheEntry, LocalCache, SmallKeyLruCache},
+    dev_prelude::{ChunkKey, ChunkReadStorage, Compression, IterChunkKeys, SmallKeyHashMap},
+    SmallKeyBuildHasher,
+};
+
+use core::hash::Hash;
+use core::ops::Range;
+use memmap2::Mmap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::hash_map;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use thread_local::ThreadLocal;
+
+/// The byte range of a single compressed chunk in a baked file.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BakedChunkLocation {
+    pub offset: u64,
+    pub len: u64,
+}
+
+impl BakedChunkLocation {
+    /// The range of bytes of this chunk, or `None` if it doesn't end at or before `limit`.
+    fn byte_range(&self, limit: u64) -> Option<Range<usize>> {
+        let end = self.offset.checked_add(self.len)?;
+        if end > limit {
+            return None;
+        }
+
+        Some(self.offset as usize..end as usize)
+    }
+}
+
+/// Compresses all `chunks` with `compression` and writes them, along with an index, to `writer`. The result can be opened
+/// with `MmapChunkStorage`.
+pub fn bake_chunks<'a, N: 'a, Compr>(
+    compression: &Compr,
+    chunks: impl Iterator<Item = (ChunkKey<N>, &'a Compr::Data)>,
+    mut writer: impl io::Write,
+) -> io::Result<()>
+where
+    ChunkKey<N>: Serialize,
+    Compr: Compression,
+    Compr::Data: 'a,
+{
+    let mut index = Vec::new();
+    let mut offset = 0;
+    let mut buffer = Vec::new();
+    for (key, chunk) in chunks {
+        buffer.clear();
+        compression.compress_into(chunk, &mut buffer);
+        writer.write_all(&buffer)?;
+
+        let len = buffer.len() as u64;
+        index.push((key, BakedChunkLocation { offset, len }));
+        offset += len;
+    }
+
+    let index_bytes =
+        bincode::serialize(&index).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    writer.write_all(&index_bytes)?;
+    writer.write_all(&offset.to_le_bytes())?;
+
+    writer.flush()
+}
+
+/// A read-only `ChunkReadStorage` backed by a memory-mapped file of compressed chunks that was written by `bake_chunks`.
+///
+/// Like `CompressibleChunkStorage`, chunks decompressed while reading from `&self` are kept in thread-local caches. Call
+/// `flush_thread_local_caches` occasionally to move them into an LRU cache, which is trimmed to hold at most `max_cached`
+/// chunks. Chunks that get trimmed are simply decompressed again from the file on the next access.
+pub struct MmapChunkStorage<N, Compr>
+where
+    N: Send,
+    Compr: Compression,
+    Compr::Data: Send,
+{
+    mmap: Mmap,
+    index: SmallKeyHashMap<ChunkKey<N>, BakedChunkLocation>,
+    main_cache: SmallKeyLruCache<ChunkKey<N>, Compr::Data>,
+    thread_local_caches: ThreadLocal<LocalChunkCache<N, Compr::Data>>,
+    max_cached: usize,
+    marker: std::marker::PhantomData<Compr>,
+}
+
+/// A `LocalCache` of chunks.
+type LocalChunkCache<N, Ch> = LocalCache<ChunkKey<N>, Ch, SmallKeyBuildHasher>;
+
+impl<N, Compr> MmapChunkStorage<N, Compr>
+where
+    N: Send,
+    ChunkKey<N>: Clone + DeserializeOwned + Eq + Hash,
+    Compr: Compression,
+    Compr::Data: Send,
+{
+    /// Memory-map the baked chunk file at `path`.
+    ///
+    /// # Safety
+    /// The file must not be modified by this or any other process while it is mapped. See `memmap2::Mmap::map`.
+    pub unsafe fn open(path: impl AsRef<Path>, max_cached: usize) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        Self::from_mmap(Mmap::map(&file)?, max_cached)
+    }
+
+    /// Reads the chunk index from an existing mapping of a baked chunk file.
+    pub fn from_mmap(mmap: Mmap, max_cached: usize) -> io::Result<Self> {
+        let bytes: &[u8] = &mmap;
+
+        if bytes.len() < 8 {
+            return Err(invalid_data("File is too small to contain a chunk index"));
+        }
+        let footer_start = bytes.len() - 8;
+        let mut footer = [0; 8];
+        footer.copy_from_slice(&bytes[footer_start..]);
+        let index_start = u64::from_le_bytes(footer);
+        if index_start > footer_start as u64 {
+            return Err(invalid_data("Chunk index offset is out of bounds"));
+        }
+
+        let entries: Vec<(ChunkKey<N>, BakedChunkLocation)> =
+            bincode::deserialize(&bytes[index_start as usize..footer_start])
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if entries
+            .iter()
+            .any(|(_, location)| location.byte_range(index_start).is_none())
+        {
+            return Err(invalid_data("Chunk location is out of bounds"));
+        }
+
+        Ok(Self {
+            mmap,
+            index: entries.into_iter().collect(),
+            main_cache: Default::default(),
+            thread_local_caches: Default::default(),
+            max_cached,
+            marker: Default::default(),
+        })
+    }
+
+    /// The number of chunks in the file.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// The number of decompressed chunks in the LRU cache. This does not include the thread-local caches.
+    pub fn len_cached(&self) -> usize {
+        self.main_cache.len_cached()
+    }
+
+    /// Consumes and flushes all thread local caches into the LRU cache, then drops the least-recently-used chunks until at
+    /// most `max_cached` remain.
+    pub fn flush_thread_local_caches(&mut self) {
+        let taken_caches = std::mem::replace(&mut self.thread_local_caches, ThreadLocal::new());
+        for mut cache in taken_caches.into_iter() {
+            for (k, v) in cache.drain_iter() {
+                self.main_cache.insert(k, v);
+            }
+        }
+        while self.main_cache.len_cached() > self.max_cached {
+            self.main_cache.remove_lru();
+        }
+    }
+
+    /// Drops all decompressed chunks.
+    pub fn clear_cache(&mut self) {
+        self.main_cache.clear();
+        self.thread_local_caches.clear();
+    }
+}
+
+impl<N, Compr> MmapChunkStorage<N, Compr>
+where
+    N: Send,
+    ChunkKey<N>: Clone + Eq + Hash,
+    Compr: Compression,
+    Compr::Data: Send,
+{
+    /// Borrow the chunk at `key`, decompressing it if it isn't cached. Returns an error if the chunk's bytes can't be
+    /// decompressed, e.g. because the file is corrupt.
+    pub fn try_get(&self, key: ChunkKey<N>) -> io::Result<Option<&Compr::Data>> {
+        let Self {
+            mmap,
+            index,
+            main_cache,
+            thread_local_caches,
+            ..
+        } = self;
+
+        if let Some(CacheEntry::Cached(chunk)) = main_cache.get(&key) {
+            return Ok(Some(chunk));
+        }
+
+        let location = match index.get(&key) {
+            Some(location) => *location,
+            None => return Ok(None),
+        };
+
+        thread_local_caches
+            .get_or(|| LocalChunkCache::default())
+            .try_get_or_insert_with(key, || decompress_chunk::<Compr>(mmap, location))
+            .map(Some)
+    }
+}
+
+impl<N, Compr> ChunkReadStorage<N> for MmapChunkStorage<N, Compr>
+where
+    N: Send,
+    ChunkKey<N>: Clone + Eq + Hash,
+    Compr: Compression,
+    Compr::Data: Send,
+{
+    type Chunk = Compr::Data;
+
+    /// Borrow the chunk at `key`.
+    ///
+    /// # Panics
+    ///
+    /// If the chunk can't be decompressed. Use `try_get` to handle corrupt files.
+    fn get(&self, key: ChunkKey<N>) -> Option<&Self::Chunk> {
+        self.try_get(key)
+            .unwrap_or_else(|e| panic!("Failed to decompress baked chunk: {}", e))
+    }
+}
+
+impl<'a, N: 'a, Compr> IterChunkKeys<'a, N> for MmapChunkStorage<N, Compr>
+where
+    N: Send,
+    Compr: Compression,
+    Compr::Data: Send,
+{
+    type Iter = hash_map::Keys<'a, ChunkKey<N>, BakedChunkLocation>;
+
+    fn chunk_keys(&'a self) -> Self::Iter {
+        self.index.keys()
+    }
+}
+
+fn decompress_chunk<Compr: Compression>(
+    mmap: &Mmap,
+    location: BakedChunkLocation,
+) -> io::Result<Compr::Data> {
+    let range = location
+        .byte_range(mmap.len() as u64)
+        .ok_or_else(|| invalid_data("Chunk location is out of bounds"))?;
+
+    Compr::decompress_from_reader(&mmap[range])
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "lz4"))]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    use building_blocks_core::prelude::*;
+
+    const BUILDER: ChunkMapBuilder3x1<i32> = ChunkMapBuilder3x1::new(PointN([16; 3]), 0);
+
+    #[test]
+    fn bake_and_read_mapped_chunks() -> io::Result<()> {
+        let mut map = BUILDER.build_with_hash_map_storage();
+        let filled_extent = Extent3i::from_min_and_shape(Point3i::fill(-20), Point3i::fill(40));
+        map.fill_extent(0, &filled_extent, 1);
+        *map.get_mut_point(0, Point3i::fill(5)) = 2;
+
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("chunks.bin");
+        bake_chunks(
+            &compression,
+            map.storage().iter().map(|(k, v)| (*k, v)),
+            io::BufWriter::new(File::create(&path)?),
+        )?;
+
+        let storage = unsafe { MmapChunkStorage::open(&path, 4)? };
+        assert_eq!(storage.len(), map.storage().len());
+
+        let mut mapped_map = BUILDER.build_with_read_storage(storage);
+        let sample_extent = Extent3i::from_min_and_shape(Point3i::fill(-32), Point3i::fill(64));
+        for p in sample_extent.iter_points().step_by(7) {
+            assert_eq!(mapped_map.clone_point(0, p), map.clone_point(0, p));
+        }
+        assert_eq!(mapped_map.clone_point(0, Point3i::fill(5)), 2);
+
+        mapped_map.storage_mut().flush_thread_local_caches();
+        assert_eq!(mapped_map.storage().len_cached(), 4);
+        assert_eq!(mapped_map.clone_point(0, Point3i::fill(5)), 2);
+
+        Ok(())
+    }
+
+    type Compr = FastArrayCompressionNx1<[i32; 3], Lz4, i32>;
+
+    /// Writes a baked file with the given chunk bytes and a hand-made index.
+    fn open_with_index(
+        chunk_bytes: &[u8],
+        index: &[(ChunkKey3, BakedChunkLocation)],
+    ) -> io::Result<MmapChunkStorage<[i32; 3], Compr>> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("chunks.bin");
+
+        let mut bytes = chunk_bytes.to_vec();
+        bytes.extend_from_slice(&bincode::serialize(index).unwrap());
+        bytes.extend_from_slice(&(chunk_bytes.len() as u64).to_le_bytes());
+        std::fs::write(&path, &bytes)?;
+
+        unsafe { MmapChunkStorage::open(&path, 4) }
+    }
+
+    #[test]
+    fn out_of_bounds_locations_are_rejected() {
+        let key = ChunkKey3::new(0, Point3i::ZERO);
+
+        let past_index =
+            open_with_index(&[0; 16], &[(key, BakedChunkLocation { offset: 8, len: 9 })]);
+        assert_eq!(past_index.err().unwrap().kind(), io::ErrorKind::InvalidData);
+
+        let overflowing = open_with_index(
+            &[0; 16],
+            &[(
+                key,
+                BakedChunkLocation {
+                    offset: u64::MAX,
+                    len: 2,
+                },
+            )],
+        );
+        assert_eq!(
+            overflowing.err().unwrap().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn truncated_chunk_is_an_error() -> io::Result<()> {
+        let compression = Compr::from_bytes_compression(Lz4 { level: 10 });
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+        let mut chunk_bytes = Vec::new();
+        compression.compress_into(&Array3x1::fill(extent, 1), &mut chunk_bytes);
+
+        // The recorded length cuts off the chunk in the middle of its header.
+        let key = ChunkKey3::new(0, Point3i::ZERO);
+        let storage = open_with_index(
+            &chunk_bytes,
+            &[(key, BakedChunkLocation { offset: 0, len: 4 })],
+        )?;
+
+        assert!(storage.try_get(key).is_err());
+        assert!(storage
+            .try_get(ChunkKey3::new(0, Point3i::fill(16)))?
+            .is_none());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+//! An immutable chunk storage that can be read from many threads at once.
+//!
+//! `CompressibleChunkStorage` must decompress chunks when they are read, so reading chunks from multiple threads requires
+//! care. A `SnapshotChunkStorage` instead holds every chunk decompressed behind an `Arc`, so it can be cheaply cloned and sent
+//! to any number of threads, which may all read from it without locking. The cost is memory: every chunk in the snapshot is
+//! decompressed.
+//!
+//! A snapshot is a copy of the chunks at the time it was taken. Later writes to the storage it was taken from are not
+//! visible through the snapshot, so a new snapshot must be taken to observe them.
+
+use crate::dev_prelude::{
+    ChunkKey, ChunkReadStorage, CompressibleChunkStorage, Compression, IterChunkKeys,
+    SmallKeyHashMap,
+};
+
+use core::hash::Hash;
+use std::collections::hash_map;
+use std::sync::Arc;
+
+/// A read-only, cheaply cloneable copy of a set of decompressed chunks. See the module documentation.
+pub struct SnapshotChunkStorage<N, Ch> {
+    chunks: Arc<SmallKeyHashMap<ChunkKey<N>, Ch>>,
+}
+
+impl<N, Ch> Clone for SnapshotChunkStorage<N, Ch> {
+    fn clone(&self) -> Self {
+        Self {
+            chunks: self.chunks.clone(),
+        }
+    }
+}
+
+impl<N, Ch> SnapshotChunkStorage<N, Ch> {
+    pub fn new(chunks: SmallKeyHashMap<ChunkKey<N>, Ch>) -> Self {
+        Self {
+            chunks: Arc::new(chunks),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+}
+
+impl<N, Ch> From<SmallKeyHashMap<ChunkKey<N>, Ch>> for SnapshotChunkStorage<N, Ch> {
+    fn from(chunks: SmallKeyHashMap<ChunkKey<N>, Ch>) -> Self {
+        Self::new(chunks)
+    }
+}
+
+impl<N, Compr> CompressibleChunkStorage<N, Compr>
+where
+    N: Send,
+    ChunkKey<N>: Clone + Eq + Hash,
+    Compr: Compression,
+    Compr::Data: Clone + Send,
+{
+    /// Takes a `SnapshotChunkStorage` of all chunks. Compressed chunks are decompressed into the snapshot, but they stay
+    /// compressed in `self`.
+    pub fn snapshot(&self) -> SnapshotChunkStorage<N, Compr::Data> {
+        SnapshotChunkStorage::new(self.iter_all_decompressed().collect())
+    }
+}
+
+impl<N, Ch> ChunkReadStorage<N> for SnapshotChunkStorage<N, Ch>
+where
+    ChunkKey<N>: Eq + Hash,
+{
+    type Chunk = Ch;
+
+    #[inline]
+    fn get(&self, key: ChunkKey<N>) -> Option<&Ch> {
+        self.chunks.get(&key)
+    }
+}
+
+impl<'a, N, Ch> IterChunkKeys<'a, N> for SnapshotChunkStorage<N, Ch>
+where
+    ChunkKey<N>: 'a,
+    Ch: 'a,
+{
+    type Iter = hash_map::Keys<'a, ChunkKey<N>, Ch>;
+
+    fn chunk_keys(&'a self) -> Self::Iter {
+        self.chunks.keys()
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "lz4"))]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+
+    use building_blocks_core::prelude::*;
+
+    const BUILDER: ChunkMapBuilder3x1<i32> = ChunkMapBuilder3x1::new(PointN([16; 3]), 0);
+
+    #[test]
+    fn concurrent_reads_of_snapshot() {
+        let mut map = BUILDER.build_with_rw_storage(
+            FastCompressibleChunkStorageNx1::with_bytes_compression(Lz4 { level: 10 }),
+        );
+        let filled_extent = Extent3i::from_min_and_shape(Point3i::fill(-32), Point3i::fill(64));
+        map.fill_extent(0, &filled_extent, 1);
+        *map.get_mut_point(0, Point3i::fill(5)) = 2;
+        // Leave some of the chunks compressed.
+        for _ in 0..20 {
+            map.storage_mut().compress_lru();
+        }
+
+        let snapshot = map.storage().snapshot();
+        assert_eq!(snapshot.len(), 4 * 4 * 4);
+
+        // Writes after taking the snapshot are not visible.
+        *map.get_mut_point(0, Point3i::fill(5)) = 3;
+
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let snapshot = snapshot.clone();
+                std::thread::spawn(move || {
+                    let map = BUILDER.build_with_read_storage(snapshot);
+                    let read_extent =
+                        Extent3i::from_min_and_shape(Point3i::fill(-40 + 4 * i), Point3i::fill(64));
+                    let mut sum = 0;
+                    map.lod_view(0)
+                        .for_each(&read_extent, |_p, value| sum += value);
+
+                    assert_eq!(map.clone_point(0, Point3i::fill(5)), 2);
+                    sum
+                })
+            })
+            .collect();
+
+        for (i, thread) in threads.into_iter().enumerate() {
+            let sum = thread.join().unwrap();
+            let read_extent =
+                Extent3i::from_min_and_shape(Point3i::fill(-40 + 4 * i as i32), Point3i::fill(64));
+            let expected = read_extent.intersection(&filled_extent).num_points() as i32 + 1;
+            assert_eq!(sum, expected);
+        }
+    }
+}
@@ -1,17 +1,24 @@
+#[cfg(feature = "serde")]
 mod compressed_bincode;
 
+#[cfg(feature = "lz4")]
+mod adaptive_compression;
 #[cfg(feature = "lz4")]
 mod lz4_compression;
 #[cfg(feature = "snap")]
 mod snappy_compression;
 
+#[cfg(feature = "serde")]
 pub use compressed_bincode::BincodeCompression;
 
+#[cfg(feature = "lz4")]
+pub use adaptive_compression::{byte_entropy, AdaptiveCompression, AdaptiveMode};
 #[cfg(feature = "lz4")]
 pub use lz4_compression::Lz4;
 #[cfg(feature = "snap")]
 pub use snappy_compression::Snappy;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::io;
 
@@ -29,6 +36,37 @@ pub trait Compression: Sized {
 
     fn decompress_from_reader(compressed_bytes: impl io::Read) -> io::Result<Self::Data>;
 
+    /// Same as `decompress_from_reader`, but this compression's own state is available, like the dictionary of a
+    /// `DictionaryCompression`. Callers that have the compression at hand should prefer this method. By default, the state is
+    /// ignored.
+    fn decompress_with(&self, compressed_bytes: impl io::Read) -> io::Result<Self::Data> {
+        Self::decompress_from_reader(compressed_bytes)
+    }
+
+    /// The number of bytes that `compressed_bytes` would occupy once decompressed, if this can be read from the header without
+    /// decompressing. By default, this is unknown.
+    fn uncompressed_len(&self, _compressed_bytes: &[u8]) -> Option<usize> {
+        None
+    }
+
+    /// Same as `decompress_from_reader`, but the result is written into `data`, replacing its contents. Implementations may
+    /// reuse the allocations already owned by `data`, so a single destination can be recycled for many decompressions. By
+    /// default, this just replaces `data`.
+    fn decompress_into_from_reader(
+        compressed_bytes: impl io::Read,
+        data: &mut Self::Data,
+    ) -> io::Result<()> {
+        *data = Self::decompress_from_reader(compressed_bytes)?;
+
+        Ok(())
+    }
+
+    /// Appends the compressed bytes of `data` onto `out`. This lets a single buffer be reused for many compressions, avoiding
+    /// an allocation per call.
+    fn compress_into(&self, data: &Self::Data, out: &mut Vec<u8>) {
+        self.compress_to_writer(data, out).unwrap();
+    }
+
     /// To preserve type information. prefer this method over `compress_to_writer`.
     fn compress(&self, data: &Self::Data) -> Compressed<Self> {
         Compressed::new(self, data)
@@ -41,7 +79,8 @@ pub trait FromBytesCompression<B> {
 
 /// A wrapper for bytes from compression algorithm `A`. This is slightly safer than manually calling `decompress` on any byte
 /// slice, since it remembers the original data type.
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Compressed<A> {
     pub compressed_bytes: Vec<u8>,
     marker: std::marker::PhantomData<A>,
@@ -53,7 +92,7 @@ where
 {
     pub fn new(compression: &A, data: &A::Data) -> Self {
         let mut compressed_bytes = Vec::new();
-        A::compress_to_writer(compression, data, &mut compressed_bytes).unwrap();
+        compression.compress_into(data, &mut compressed_bytes);
 
         Self {
             compressed_bytes,
@@ -61,13 +100,50 @@ where
         }
     }
 
+    /// Reconstructs a value that was previously taken apart with `into_bytes` or `as_bytes`. The `compression` is only used to
+    /// infer the type; `bytes` must have been produced by the same algorithm with the same `Data` type, or decompression will
+    /// fail.
+    pub fn from_bytes(_compression: &A, bytes: Vec<u8>) -> Self {
+        Self {
+            compressed_bytes: bytes,
+            marker: Default::default(),
+        }
+    }
+
     pub fn decompress(&self) -> A::Data {
         A::decompress_from_reader(self.compressed_bytes.as_slice()).unwrap()
     }
 
-    pub fn take_bytes(self) -> Vec<u8> {
+    /// Same as `decompress`, but uses `compression`'s state, as in `Compression::decompress_with`. `compression` must be the
+    /// one that compressed this value.
+    pub fn decompress_with(&self, compression: &A) -> A::Data {
+        compression
+            .decompress_with(self.compressed_bytes.as_slice())
+            .unwrap()
+    }
+
+    /// Decompresses into `data`, reusing its allocations where possible. This avoids allocating a new decompressed copy for
+    /// every call, e.g. when loading many chunks in a row.
+    pub fn decompress_into(&self, data: &mut A::Data) {
+        A::decompress_into_from_reader(self.compressed_bytes.as_slice(), data).unwrap()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.compressed_bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
         self.compressed_bytes
     }
+
+    pub fn take_bytes(self) -> Vec<u8> {
+        self.into_bytes()
+    }
+
+    /// The number of compressed bytes.
+    pub fn compressed_len(&self) -> usize {
+        self.compressed_bytes.len()
+    }
 }
 
 /// A compression algorithm that reads a stream of bytes.
@@ -95,6 +171,14 @@ impl<A: Compression> MaybeCompressed<A::Data, Compressed<A>> {
         }
     }
 
+    /// Same as `into_decompressed`, but uses `compression`'s state, as in `Compressed::decompress_with`.
+    pub fn into_decompressed_with(self, compression: &A) -> A::Data {
+        match self {
+            MaybeCompressed::Compressed(c) => c.decompress_with(compression),
+            MaybeCompressed::Decompressed(d) => d,
+        }
+    }
+
     pub fn unwrap_decompressed(self) -> A::Data {
         match self {
             MaybeCompressed::Compressed(_) => panic!("Must be decompressed"),
@@ -102,3 +186,35 @@ impl<A: Compression> MaybeCompressed<A::Data, Compressed<A>> {
         }
     }
 }
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "lz4"))]
+mod test {
+    use super::*;
+    use crate::prelude::{Array3x1, FastArrayCompressionNx1};
+
+    use building_blocks_core::prelude::*;
+
+    #[test]
+    fn compressed_bytes_roundtrip() {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-4), Point3i::fill(8));
+        let array = Array3x1::fill_with(extent, |p| p.x() * p.y() + p.z());
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+
+        let compressed = compression.compress(&array);
+        let len = compressed.compressed_len();
+        assert_eq!(compressed.as_bytes().len(), len);
+
+        let bytes = compressed.into_bytes();
+        assert_eq!(bytes.len(), len);
+
+        let reconstructed = Compressed::from_bytes(&compression, bytes);
+        assert_eq!(reconstructed.decompress(), array);
+    }
+}
@@ -0,0 +1,237 @@
+use super::{BytesCompression, Lz4};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// A `BytesCompression` that picks how hard to compress each input based on its entropy, so CPU isn't wasted compressing
+/// noise at a high LZ4 level.
+///
+/// The Shannon entropy of the byte histogram is estimated first. Inputs with at least `raw_entropy_threshold` bits per byte are
+/// stored raw, inputs with at most `high_level_entropy_threshold` bits per byte are compressed at `high_level`, and everything
+/// else is compressed at `low_level`. The chosen `AdaptiveMode` is written as a 1-byte header in front of the compressed bytes,
+/// so decompression doesn't need to know the thresholds. Raw bytes are also prefixed with their length as a little-endian
+/// `u64`, so like an LZ4 frame, they can be followed by more data, e.g. the next channel of an array.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+pub struct AdaptiveCompression {
+    /// In bits per byte, from 0 to 8.
+    pub raw_entropy_threshold: f32,
+    /// In bits per byte, from 0 to 8.
+    pub high_level_entropy_threshold: f32,
+    /// The LZ4 level for inputs between the two thresholds.
+    pub low_level: u32,
+    /// The LZ4 level for inputs below `high_level_entropy_threshold`.
+    pub high_level: u32,
+}
+
+impl Default for AdaptiveCompression {
+    fn default() -> Self {
+        Self {
+            raw_entropy_threshold: 7.5,
+            high_level_entropy_threshold: 4.0,
+            low_level: 0,
+            high_level: 10,
+        }
+    }
+}
+
+/// How `AdaptiveCompression` stored a particular input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u8)]
+pub enum AdaptiveMode {
+    Raw = 0,
+    Lz4LowLevel = 1,
+    Lz4HighLevel = 2,
+}
+
+impl AdaptiveMode {
+    fn from_u8(mode: u8) -> io::Result<Self> {
+        match mode {
+            0 => Ok(AdaptiveMode::Raw),
+            1 => Ok(AdaptiveMode::Lz4LowLevel),
+            2 => Ok(AdaptiveMode::Lz4HighLevel),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unknown adaptive compression mode",
+            )),
+        }
+    }
+}
+
+impl AdaptiveCompression {
+    /// The mode that `compress_bytes` would choose for `bytes`.
+    pub fn choose_mode(&self, bytes: &[u8]) -> AdaptiveMode {
+        let entropy = byte_entropy(bytes);
+        if entropy >= self.raw_entropy_threshold {
+            AdaptiveMode::Raw
+        } else if entropy <= self.high_level_entropy_threshold {
+            AdaptiveMode::Lz4HighLevel
+        } else {
+            AdaptiveMode::Lz4LowLevel
+        }
+    }
+
+    /// Reads the mode from the header of bytes that were compressed with `AdaptiveCompression`.
+    pub fn read_mode(mut compressed_bytes: impl io::Read) -> io::Result<AdaptiveMode> {
+        let mut mode = [0];
+        compressed_bytes.read_exact(&mut mode)?;
+
+        AdaptiveMode::from_u8(mode[0])
+    }
+}
+
+/// The Shannon entropy of the byte histogram of `bytes`, in bits per byte. This ignores the order of the bytes, so it's only an
+/// estimate of how well `bytes` will compress.
+pub fn byte_entropy(bytes: &[u8]) -> f32 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+
+    let mut histogram = [0usize; 256];
+    for &b in bytes.iter() {
+        histogram[b as usize] += 1;
+    }
+
+    let total = bytes.len() as f32;
+    histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f32 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+impl BytesCompression for AdaptiveCompression {
+    fn compress_bytes(
+        &self,
+        mut bytes: impl io::Read,
+        mut compressed_bytes: impl io::Write,
+    ) -> io::Result<()> {
+        // The entropy needs the whole input before anything can be written.
+        let mut buffer = Vec::new();
+        bytes.read_to_end(&mut buffer)?;
+
+        let mode = self.choose_mode(&buffer);
+        compressed_bytes.write_all(&[mode as u8])?;
+        match mode {
+            AdaptiveMode::Raw => {
+                compressed_bytes.write_all(&(buffer.len() as u64).to_le_bytes())?;
+                compressed_bytes.write_all(&buffer)
+            }
+            AdaptiveMode::Lz4LowLevel => Lz4 {
+                level: self.low_level,
+            }
+            .compress_bytes(buffer.as_slice(), compressed_bytes),
+            AdaptiveMode::Lz4HighLevel => Lz4 {
+                level: self.high_level,
+            }
+            .compress_bytes(buffer.as_slice(), compressed_bytes),
+        }
+    }
+
+    fn decompress_bytes(
+        mut compressed_bytes: impl io::Read,
+        mut bytes: impl io::Write,
+    ) -> io::Result<()> {
+        match Self::read_mode(&mut compressed_bytes)? {
+            AdaptiveMode::Raw => {
+                let mut len = [0; 8];
+                compressed_bytes.read_exact(&mut len)?;
+                let len = u64::from_le_bytes(len);
+
+                // Copy rather than allocating `len` bytes up front, in case the length is corrupt.
+                let copied = io::copy(&mut compressed_bytes.take(len), &mut bytes)?;
+                if copied != len {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Raw bytes are shorter than their length",
+                    ));
+                }
+
+                Ok(())
+            }
+            // The LZ4 frame doesn't depend on the level.
+            AdaptiveMode::Lz4LowLevel | AdaptiveMode::Lz4HighLevel => {
+                Lz4::decompress_bytes(compressed_bytes, bytes)
+            }
+        }
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{
+        Array3x1, Array3x2, Compression, FastArrayCompressionNx1, FastArrayCompressionNx2,
+        FromBytesCompression,
+    };
+
+    use building_blocks_core::prelude::*;
+    use rand::Rng;
+
+    #[test]
+    fn random_chunk_is_stored_raw_and_constant_chunk_is_compressed() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+        let mut rng = rand::thread_rng();
+        let random_chunk = Array3x1::fill_with(extent, |_| rng.gen::<u32>());
+        let constant_chunk = Array3x1::fill(extent, 7u32);
+
+        let compression = AdaptiveCompression::default();
+        let compress = |chunk: &Array3x1<u32>| {
+            let mut compressed = Vec::new();
+            compression
+                .compress_bytes(
+                    bytemuck::cast_slice::<u32, u8>(chunk.channels().store().as_slice()),
+                    &mut compressed,
+                )
+                .unwrap();
+
+            compressed
+        };
+
+        let random_compressed = compress(&random_chunk);
+        let constant_compressed = compress(&constant_chunk);
+        assert_eq!(
+            AdaptiveCompression::read_mode(random_compressed.as_slice()).unwrap(),
+            AdaptiveMode::Raw
+        );
+        assert_eq!(
+            AdaptiveCompression::read_mode(constant_compressed.as_slice()).unwrap(),
+            AdaptiveMode::Lz4HighLevel
+        );
+        assert!(constant_compressed.len() < random_compressed.len());
+
+        // Both round trip as chunks.
+        let chunk_compression = FastArrayCompressionNx1::from_bytes_compression(compression);
+        for chunk in [random_chunk, constant_chunk].iter() {
+            assert_eq!(chunk_compression.compress(chunk).decompress(), *chunk);
+        }
+    }
+
+    #[test]
+    fn multichannel_chunk_with_raw_channel_round_trips() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+        let mut rng = rand::thread_rng();
+        let chunk = Array3x2::fill_with(extent, |_| (rng.gen::<u32>(), 7u8));
+        let random_values = chunk.channels().0.store().as_slice();
+
+        // The random channel is stored raw, so the constant channel after it must still be found.
+        let compression = AdaptiveCompression::default();
+        assert_eq!(
+            compression.choose_mode(bytemuck::cast_slice::<u32, u8>(random_values)),
+            AdaptiveMode::Raw
+        );
+        let chunk_compression = FastArrayCompressionNx2::from_bytes_compression(compression);
+        assert_eq!(chunk_compression.compress(&chunk).decompress(), chunk);
+    }
+}
@@ -69,7 +69,10 @@ where
 #[cfg(all(test, feature = "snap"))]
 mod tests {
     use super::*;
-    use crate::prelude::Snappy;
+    use crate::prelude::{Array3x1, Snappy};
+
+    use building_blocks_core::prelude::*;
+
     use serde::Deserialize;
 
     #[derive(Clone, Debug, Eq, Deserialize, Serialize, PartialEq)]
@@ -85,4 +88,54 @@ mod tests {
 
         assert_eq!(foo, decompressed_foo);
     }
+
+    #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+    struct Material {
+        name: String,
+        hardness: u8,
+    }
+
+    #[test]
+    fn compress_and_decompress_array_of_non_pod_type() {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-2), Point3i::fill(4));
+        let array = Array3x1::fill_with(extent, |p| Material {
+            name: format!("material {}", p.x()),
+            hardness: p.y() as u8,
+        });
+
+        let compression = BincodeCompression::new(Snappy);
+        let decompressed = compression.compress(&array).decompress();
+
+        assert_eq!(decompressed, array);
+    }
+
+    #[test]
+    fn compress_many_into_reused_buffer() {
+        let foos: Vec<_> = (0u8..10).map(|i| Foo((i..100).collect())).collect();
+
+        let compression = BincodeCompression::new(Snappy);
+        let mut buffer = Vec::new();
+        let mut ranges = Vec::new();
+        for foo in foos.iter() {
+            let start = buffer.len();
+            compression.compress_into(foo, &mut buffer);
+            ranges.push(start..buffer.len());
+        }
+
+        for (foo, range) in foos.iter().zip(ranges.into_iter()) {
+            let decompressed_foo =
+                BincodeCompression::<Foo, Snappy>::decompress_from_reader(&buffer[range]).unwrap();
+            assert_eq!(foo, &decompressed_foo);
+        }
+
+        // Clearing keeps the allocation around for the next batch.
+        let capacity = buffer.capacity();
+        buffer.clear();
+        compression.compress_into(&foos[0], &mut buffer);
+        assert_eq!(buffer.capacity(), capacity);
+        assert_eq!(
+            BincodeCompression::<Foo, Snappy>::decompress_from_reader(buffer.as_slice()).unwrap(),
+            foos[0]
+        );
+    }
 }
@@ -1,10 +1,12 @@
 use super::BytesCompression;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::io;
 
 /// The [LZ4 compression algorithm](https://en.wikipedia.org/wiki/LZ4_(compression_algorithm)).
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Lz4 {
     /// The compression level, from 0 to 10. 0 is fastest and least aggressive. 10 is slowest and
     /// most aggressive.
@@ -1,11 +1,13 @@
 use super::BytesCompression;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::io;
 
 /// The [Snappy compression algorithm](https://en.wikipedia.org/wiki/Snappy_(compression)).
 /// Uses a pure Rust implementation, making it suitable for use with the WASM target.
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Snappy;
 
 impl BytesCompression for Snappy {
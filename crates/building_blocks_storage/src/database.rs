@@ -1,17 +1,21 @@
+mod checksum;
 mod chunk_db;
 mod delta_batch;
 mod key;
 mod read_result;
 mod readable;
+mod writer;
 
 #[cfg(feature = "sled-snapshots")]
 mod versioned_chunk_db;
 
+pub use checksum::{CorruptChunk, CHECKSUM_LEN};
 pub use chunk_db::*;
 pub use delta_batch::*;
 pub use key::*;
 pub use read_result::*;
 pub use readable::*;
+pub use writer::*;
 
 #[cfg(feature = "sled-snapshots")]
 pub use versioned_chunk_db::*;
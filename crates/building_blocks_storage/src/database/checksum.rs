@@ -0,0 +1,72 @@
+use sled::IVec;
+
+use std::fmt;
+use std::io;
+
+/// The number of bytes of CRC32 checksum appended to each chunk value.
+pub const CHECKSUM_LEN: usize = 4;
+
+/// The error returned when a chunk value read from a DB with checksums doesn't match its checksum.
+///
+/// Since reads return a `sled::Result`, this is reported as a `sled::Error::Io` with `io::ErrorKind::InvalidData`. The
+/// `CorruptChunk` can be recovered with `io::Error::get_ref` and `downcast_ref`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CorruptChunk {
+    /// The DB key bytes of the corrupt chunk.
+    pub key: IVec,
+}
+
+impl fmt::Display for CorruptChunk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Checksum mismatch for chunk with key {:?}",
+            self.key.as_ref()
+        )
+    }
+}
+
+impl std::error::Error for CorruptChunk {}
+
+impl From<CorruptChunk> for sled::Error {
+    fn from(e: CorruptChunk) -> Self {
+        sled::Error::Io(io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Returns a copy of `value` with its CRC32 checksum appended in little-endian.
+pub(crate) fn append_checksum(value: &[u8]) -> IVec {
+    let mut bytes = Vec::with_capacity(value.len() + CHECKSUM_LEN);
+    bytes.extend_from_slice(value);
+    bytes.extend_from_slice(&crc32(value).to_le_bytes());
+
+    IVec::from(bytes)
+}
+
+/// Verifies and removes the checksum that was appended to the value stored at `key` by `append_checksum`.
+pub(crate) fn strip_checksum(key: &IVec, value: &IVec) -> Result<IVec, CorruptChunk> {
+    let corrupt = || CorruptChunk { key: key.clone() };
+
+    if value.len() < CHECKSUM_LEN {
+        return Err(corrupt());
+    }
+    let (data, checksum) = value.split_at(value.len() - CHECKSUM_LEN);
+    let mut checksum_bytes = [0; CHECKSUM_LEN];
+    checksum_bytes.copy_from_slice(checksum);
+    if crc32(data) != u32::from_le_bytes(checksum_bytes) {
+        return Err(corrupt());
+    }
+
+    Ok(IVec::from(data))
+}
+
+#[cfg(feature = "checksums")]
+fn crc32(bytes: &[u8]) -> u32 {
+    crc32fast::hash(bytes)
+}
+
+// A `ChunkDb` can't be opened with checksums unless the feature is enabled.
+#[cfg(not(feature = "checksums"))]
+fn crc32(_bytes: &[u8]) -> u32 {
+    unreachable!("Checksums require the \"checksums\" feature")
+}
@@ -1,23 +1,44 @@
-use super::{DatabaseKey, DeltaBatch, DeltaBatchBuilder, ReadableChunkDb};
+use super::{
+    checksum::{append_checksum, strip_checksum},
+    DatabaseKey, Delta, DeltaBatch, DeltaBatchBuilder, ReadableChunkDb,
+};
 
-use crate::prelude::ChunkKey;
+use crate::{
+    dev_prelude::{BytesCompression, DictionaryCompression, FixedEndian},
+    prelude::{ChunkKey, Compression},
+};
+
+use building_blocks_core::prelude::*;
 
 use sled;
-use sled::Tree;
+use sled::transaction::{ConflictableTransactionError, TransactionError};
+use sled::{IVec, Transactional, Tree};
 
 /// A persistent, crash-consistent key-value store of compressed chunks, backed by the `sled` crate.
 ///
 /// The keys are Morton codes for the corresponding chunk coordinates. This ensures that all of the chunks in an orthant are
 /// stored in a contiguous key space.
 ///
+/// By default, all levels of detail share a single tree. Use `with_separate_lod_trees` to give each LOD its own tree, e.g. so
+/// they can be compacted independently.
+///
 /// Note that while writes are applied atomically, reads are not isolated. Reads rely on iteration over Morton keys, and `sled`
 /// does not yet provide transactional iteration.
 ///
-/// The DB values are only portable if the `compression` used respects endianness of the current machine. Use
-/// `BincodeCompression` if you absolutely need portability across machines with different endianness.
+/// The DB keys are always encoded as big-endian, so they are portable across machines. The DB values are only portable if the
+/// `compression` used is independent of the endianness of the current machine. `FastArrayCompression` encodes voxel values as
+/// little-endian, so it is portable for any `FixedEndian` voxel type.
+///
+/// With the `checksums` feature, a DB constructed with `new_with_metadata` can store a CRC32 checksum with every chunk value
+/// to detect corruption on read. The choice is persisted in the given metadata tree, so it's honored when reopening the DB
+/// with the same metadata tree. `new` and `new_with_compression` don't have a metadata tree, so they never use checksums.
 pub struct ChunkDb<N, Compr = ()> {
-    tree: Tree,
+    /// Either a single tree shared by all LODs, or one tree per LOD.
+    trees: Vec<Tree>,
+    /// Holds the settings of the DB, if it was constructed with one.
+    meta_tree: Option<Tree>,
     compression: Compr,
+    checksums: bool,
     marker: std::marker::PhantomData<N>,
 }
 
@@ -29,11 +50,7 @@ pub type ChunkDb3<Compr> = ChunkDb<[i32; 3], Compr>;
 impl<N> ChunkDb<N> {
     /// Construct a `ChunkDb` without compression.
     pub fn new(tree: Tree) -> Self {
-        Self {
-            tree,
-            compression: (),
-            marker: Default::default(),
-        }
+        Self::new_with_compression(tree, ())
     }
 }
 
@@ -41,39 +58,325 @@ impl<N, Compr> ChunkDb<N, Compr> {
     /// Construct a `ChunkDb` with `compression`.
     pub fn new_with_compression(tree: Tree, compression: Compr) -> Self {
         Self {
-            tree,
+            trees: vec![tree],
+            meta_tree: None,
+            compression,
+            checksums: false,
+            marker: Default::default(),
+        }
+    }
+
+    /// Construct a `ChunkDb` with `compression` that keeps its settings in `meta_tree`. If `checksums` is `true`, a CRC32
+    /// checksum is stored with each chunk value and verified on every read.
+    ///
+    /// The choice is persisted in `meta_tree`. When reopening an existing DB, the persisted choice takes precedence over
+    /// `checksums`, so readers always know whether to expect checksums. Returns `sled::Error::Unsupported` if the DB needs
+    /// checksums but the `checksums` feature is disabled.
+    pub fn new_with_metadata(
+        tree: Tree,
+        meta_tree: Tree,
+        compression: Compr,
+        checksums: bool,
+    ) -> sled::Result<Self> {
+        let checksums = open_checksums_setting(&meta_tree, checksums)?;
+
+        Ok(Self {
+            trees: vec![tree],
+            meta_tree: Some(meta_tree),
+            compression,
+            checksums,
+            marker: Default::default(),
+        })
+    }
+
+    /// Construct a `ChunkDb` with `compression` that stores each of the `num_lods` levels of detail in a separate tree of
+    /// `db`. The tree for LOD `i` is named `"{tree_name_prefix}_lod{i}"`.
+    pub fn with_separate_lod_trees(
+        db: &sled::Db,
+        tree_name_prefix: &str,
+        num_lods: u8,
+        compression: Compr,
+    ) -> sled::Result<Self> {
+        Ok(Self {
+            trees: open_lod_trees(db, tree_name_prefix, num_lods)?,
+            meta_tree: None,
+            compression,
+            checksums: false,
+            marker: Default::default(),
+        })
+    }
+
+    /// Like `with_separate_lod_trees`, but the settings for all of the LOD trees are kept in `meta_tree`, and `checksums` are
+    /// used if this is a new DB, as in `new_with_metadata`.
+    pub fn with_separate_lod_trees_and_metadata(
+        db: &sled::Db,
+        tree_name_prefix: &str,
+        num_lods: u8,
+        meta_tree: Tree,
+        compression: Compr,
+        checksums: bool,
+    ) -> sled::Result<Self> {
+        let trees = open_lod_trees(db, tree_name_prefix, num_lods)?;
+        let checksums = open_checksums_setting(&meta_tree, checksums)?;
+
+        Ok(Self {
+            trees,
+            meta_tree: Some(meta_tree),
             compression,
+            checksums,
             marker: Default::default(),
+        })
+    }
+
+    pub fn compression(&self) -> &Compr {
+        &self.compression
+    }
+
+    /// Returns `true` iff each LOD is stored in a separate tree.
+    pub fn has_separate_lod_trees(&self) -> bool {
+        self.trees.len() > 1
+    }
+
+    /// The codec recorded in the metadata tree by the last `recompress`, if any. Always `None` without a metadata tree.
+    pub fn compression_codec(&self) -> sled::Result<Option<IVec>> {
+        match &self.meta_tree {
+            Some(meta_tree) => meta_tree.get(CODEC_META_KEY),
+            None => Ok(None),
+        }
+    }
+
+    /// Re-encodes every chunk in the DB with `new_compression`, e.g. to migrate from `Lz4` to `Snappy`. Checksums, if enabled,
+    /// are recomputed for the new values.
+    ///
+    /// Chunks are streamed in batches of `RECOMPRESS_BATCH_LEN`, so only one batch of re-encoded chunks is held in memory at a
+    /// time. Each batch is applied atomically, but if recompression is interrupted, e.g. by a crash, the DB is left with a mix
+    /// of old and new encodings.
+    ///
+    /// Once every chunk is re-encoded, `codec` is recorded in the metadata tree, if there is one (see `compression_codec`).
+    /// The codec is chosen by the caller, e.g. `"snappy"`, so it stays the same across compiler and crate versions.
+    pub fn recompress<C2>(self, new_compression: C2, codec: &str) -> sled::Result<ChunkDb<N, C2>>
+    where
+        Compr: Compression,
+        C2: Compression<Data = Compr::Data>,
+    {
+        self.recompress_in_batches(new_compression, codec, RECOMPRESS_BATCH_LEN)
+    }
+
+    fn recompress_in_batches<C2>(
+        self,
+        new_compression: C2,
+        codec: &str,
+        batch_len: usize,
+    ) -> sled::Result<ChunkDb<N, C2>>
+    where
+        Compr: Compression,
+        C2: Compression<Data = Compr::Data>,
+    {
+        let mut new_bytes = Vec::new();
+        for tree in self.trees.iter() {
+            let mut batch = sled::Batch::default();
+            let mut num_batched = 0;
+            for entry in tree.iter() {
+                let (key_bytes, value_bytes) = entry?;
+                let old_bytes = if self.checksums {
+                    strip_checksum(&key_bytes, &value_bytes)?
+                } else {
+                    value_bytes
+                };
+                let chunk = self.compression.decompress_with(old_bytes.as_ref())?;
+
+                new_bytes.clear();
+                new_compression.compress_into(&chunk, &mut new_bytes);
+                let new_value = if self.checksums {
+                    append_checksum(&new_bytes)
+                } else {
+                    sled::IVec::from(new_bytes.as_slice())
+                };
+                batch.insert(key_bytes, new_value);
+
+                num_batched += 1;
+                if num_batched == batch_len {
+                    tree.apply_batch(std::mem::take(&mut batch))?;
+                    num_batched = 0;
+                }
+            }
+            tree.apply_batch(batch)?;
         }
+        if let Some(meta_tree) = &self.meta_tree {
+            meta_tree.insert(CODEC_META_KEY, codec)?;
+        }
+
+        Ok(ChunkDb {
+            trees: self.trees,
+            meta_tree: self.meta_tree,
+            compression: new_compression,
+            checksums: self.checksums,
+            marker: Default::default(),
+        })
     }
 }
 
+/// The number of chunks that `ChunkDb::recompress` re-encodes before writing them.
+pub const RECOMPRESS_BATCH_LEN: usize = 256;
+
+impl<N, T, B> ChunkDb<N, DictionaryCompression<N, T, B>>
+where
+    PointN<N>: IntegerPoint<N>,
+    T: FixedEndian,
+    B: BytesCompression,
+{
+    /// Construct a `ChunkDb` like `new_with_metadata`, which compresses with `bytes_compression` and the dictionary that was
+    /// stored in `meta_tree` by `save_compression_dictionary`. Fails with `io::ErrorKind::NotFound` if there is no dictionary.
+    pub fn new_with_dictionary(
+        tree: Tree,
+        meta_tree: Tree,
+        bytes_compression: B,
+        checksums: bool,
+    ) -> sled::Result<Self> {
+        let dictionary = meta_tree.get(DICTIONARY_META_KEY)?.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "No compression dictionary in the metadata tree",
+            )
+        })?;
+        let compression =
+            DictionaryCompression::from_dictionary(bytes_compression, dictionary.as_ref());
+
+        Self::new_with_metadata(tree, meta_tree, compression, checksums)
+    }
+
+    /// Stores the dictionary of this DB's `DictionaryCompression` in the metadata tree, so the DB can be reopened with
+    /// `new_with_dictionary`. The dictionary only needs to be stored once for all of the chunks in the DB. Returns
+    /// `sled::Error::Unsupported` if the DB has no metadata tree.
+    pub fn save_compression_dictionary(&self) -> sled::Result<()> {
+        let meta_tree = self
+            .meta_tree
+            .as_ref()
+            .ok_or_else(|| sled::Error::Unsupported("ChunkDb has no metadata tree".to_string()))?;
+        meta_tree.insert(DICTIONARY_META_KEY, self.compression.dictionary())?;
+
+        Ok(())
+    }
+}
+
+const CHECKSUMS_META_KEY: &[u8] = b"checksums";
+const CODEC_META_KEY: &[u8] = b"codec";
+const DICTIONARY_META_KEY: &[u8] = b"dictionary";
+
+fn open_lod_trees(db: &sled::Db, tree_name_prefix: &str, num_lods: u8) -> sled::Result<Vec<Tree>> {
+    assert!(num_lods > 0);
+
+    (0..num_lods)
+        .map(|lod| db.open_tree(format!("{}_lod{}", tree_name_prefix, lod)))
+        .collect()
+}
+
+/// Returns the checksum setting persisted in `meta_tree`, persisting `checksums` if there is none yet.
+fn open_checksums_setting(meta_tree: &Tree, checksums: bool) -> sled::Result<bool> {
+    let persisted = meta_tree.get(CHECKSUMS_META_KEY)?;
+    let checksums = match &persisted {
+        Some(value) => value.as_ref() == [1u8].as_ref(),
+        None => checksums,
+    };
+    if checksums && !cfg!(feature = "checksums") {
+        return Err(sled::Error::Unsupported(format!(
+            "Tree {} uses checksums, which require the \"checksums\" feature",
+            String::from_utf8_lossy(&meta_tree.name())
+        )));
+    }
+    if persisted.is_none() {
+        meta_tree.insert(CHECKSUMS_META_KEY, vec![checksums as u8])?;
+    }
+
+    Ok(checksums)
+}
+
+fn lod_out_of_range(lod: u8) -> sled::Error {
+    sled::Error::Unsupported(format!("No tree for LOD {}", lod))
+}
+
 impl<N, Compr> ReadableChunkDb for ChunkDb<N, Compr> {
     type Compr = Compr;
 
     fn data_tree(&self) -> &Tree {
-        &self.tree
+        &self.trees[0]
+    }
+
+    fn data_tree_for_lod(&self, lod: u8) -> sled::Result<&Tree> {
+        if self.has_separate_lod_trees() {
+            self.trees
+                .get(lod as usize)
+                .ok_or_else(|| lod_out_of_range(lod))
+        } else {
+            Ok(&self.trees[0])
+        }
+    }
+
+    fn has_checksums(&self) -> bool {
+        self.checksums
     }
 }
 
 impl<N, Compr> ChunkDb<N, Compr>
 where
     ChunkKey<N>: DatabaseKey<N>,
-    Compr: Copy,
+    Compr: Clone,
 {
     pub async fn flush(&self) -> sled::Result<usize> {
-        self.tree.flush_async().await
+        let mut bytes_flushed = 0;
+        for tree in self.trees.iter() {
+            bytes_flushed += tree.flush_async().await?;
+        }
+        Ok(bytes_flushed)
     }
 
     pub fn start_delta_batch(
         &self,
     ) -> DeltaBatchBuilder<N, <ChunkKey<N> as DatabaseKey<N>>::OrdKey, Compr> {
-        DeltaBatchBuilder::new(self.compression)
+        DeltaBatchBuilder::new(self.compression.clone())
     }
 
-    /// Applies a set of chunk deltas atomically.
-    pub fn apply_deltas(&self, batch: DeltaBatch) -> sled::Result<()> {
-        self.tree.apply_batch(sled::Batch::from(batch))
+    /// Applies a set of chunk deltas atomically, even if they span multiple LOD trees.
+    ///
+    /// Returns `sled::Error::Unsupported` without applying anything if a delta's LOD has no tree.
+    pub fn apply_deltas(&self, mut batch: DeltaBatch) -> sled::Result<()> {
+        if self.checksums {
+            for delta in batch.deltas.iter_mut() {
+                if let Delta::Insert(_, chunk_bytes) = delta {
+                    *chunk_bytes = append_checksum(chunk_bytes);
+                }
+            }
+        }
+
+        if !self.has_separate_lod_trees() {
+            return self.trees[0].apply_batch(sled::Batch::from(batch));
+        }
+
+        // The first byte of every key is the LOD.
+        let mut lod_batches = vec![sled::Batch::default(); self.trees.len()];
+        for delta in batch.deltas.into_iter() {
+            let lod = delta.key()[0];
+            let lod_batch = lod_batches
+                .get_mut(lod as usize)
+                .ok_or_else(|| lod_out_of_range(lod))?;
+            match delta {
+                Delta::Insert(key_bytes, chunk_bytes) => lod_batch.insert(key_bytes, chunk_bytes),
+                Delta::Remove(key_bytes) => lod_batch.remove(key_bytes),
+            }
+        }
+
+        self.trees
+            .as_slice()
+            .transaction(|trees| {
+                for (tree, lod_batch) in trees.iter().zip(lod_batches.iter()) {
+                    tree.apply_batch(lod_batch)?;
+                }
+                Ok::<_, ConflictableTransactionError<()>>(())
+            })
+            .map_err(|e| match e {
+                TransactionError::Storage(e) => e,
+                TransactionError::Abort(()) => unreachable!("Batch application never aborts"),
+            })
     }
 }
 
@@ -87,8 +390,16 @@ where
 #[cfg(test)]
 mod test {
     use crate::{
-        database::Delta,
-        prelude::{Array3x2, ChunkKey3, FastArrayCompressionNx2, FromBytesCompression, Lz4},
+        chunk::ChunkState,
+        database::{ChunkWarmth, CorruptChunk, Delta},
+        dev_prelude::CompressibleChunkStorage,
+        dev_prelude::DictionaryCompression,
+        octree::active_clipmap_lod_chunks,
+        prelude::{
+            Array3x1, Array3x2, ChunkDb3, ChunkIndexer3, ChunkKey3, ChunkUnits, ClipMapConfig3,
+            FastArrayCompressionNx2, FromBytesCompression, Lz4, OctreeSet,
+        },
+        SmallKeyHashSet,
     };
 
     use super::*;
@@ -115,9 +426,7 @@ mod test {
             .use_compression(false)
             .mode(sled::Mode::LowSpace)
             .open()?;
-        let tree = db.open_tree("chunks")?;
-
-        let chunk_db = ChunkDb::new(tree);
+        let chunk_db = ChunkDb::new(db.open_tree("chunks")?);
 
         let mut batch = chunk_db.start_delta_batch();
         batch.add_raw_deltas(
@@ -172,11 +481,9 @@ mod test {
             .use_compression(false)
             .mode(sled::Mode::LowSpace)
             .open()?;
-        let tree = db.open_tree("chunks")?;
 
-        // NOTE: This compression is not portable because it is naive to endianness.
         let compression = FastArrayCompressionNx2::from_bytes_compression(Lz4 { level: 10 });
-        let chunk_db = ChunkDb::new_with_compression(tree, compression);
+        let chunk_db = ChunkDb::new_with_compression(db.open_tree("chunks")?, compression);
 
         let mut batch = chunk_db.start_delta_batch();
         futures::executor::block_on(
@@ -204,4 +511,478 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn anisotropic_chunk_keys_are_read_in_morton_order() -> sled::Result<()> {
+        let chunk_shape = PointN([32, 32, 8]);
+        let extent = Extent3i::from_min_and_shape(PointN([-64, -64, -16]), PointN([128, 128, 32]));
+        let indexer = ChunkIndexer3::new(chunk_shape);
+        let keys: Vec<_> = indexer
+            .chunk_mins_for_extent(&extent)
+            .map(|min| ChunkKey3::new(0, min))
+            .collect();
+        assert_eq!(keys.len(), 4 * 4 * 4);
+
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+        let chunk_db = ChunkDb::new(db.open_tree("chunks")?);
+
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(keys.iter().map(|&k| Delta::Insert(k, IVec::from("data"))));
+        chunk_db.apply_deltas(batch.build())?;
+
+        let mut sorted_keys = keys.clone();
+        sorted_keys.sort_by_key(|k| Morton3::from(k.minimum));
+        let all_keys: Vec<_> = chunk_db
+            .read_all_chunks::<[i32; 3]>(0)?
+            .take_with_raw_values()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(all_keys, sorted_keys);
+
+        // The octant is in voxel coordinates, so it covers 2 chunks along X and Y, but only 2 of the 8 chunks along Z that
+        // would fit in a cube of this size are in the DB.
+        let octant = Octant::new_unchecked(Point3i::ZERO, 64);
+        let octant_keys: Vec<_> = chunk_db
+            .read_chunks_in_orthant(0, octant)?
+            .take_with_raw_values()
+            .map(|(k, _)| k)
+            .collect();
+        let expected_keys: Vec<_> = sorted_keys
+            .iter()
+            .cloned()
+            .filter(|k| Extent3i::from(octant).contains(k.minimum))
+            .collect();
+        assert_eq!(expected_keys.len(), 2 * 2 * 2);
+        assert_eq!(octant_keys, expected_keys);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dictionary_is_loaded_from_metadata_to_decompress() -> sled::Result<()> {
+        let chunk_shape = Point3i::fill(16);
+        let chunks: Vec<_> = (0..4)
+            .map(|i| {
+                let min = PointN([16 * i, 0, 0]);
+                let extent = Extent3i::from_min_and_shape(min, chunk_shape);
+                let chunk = Array3x1::fill_with(extent, |p| (p.y() * 16 + p.z() + i) as u16);
+
+                (ChunkKey3::new(0, min), chunk)
+            })
+            .collect();
+
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+
+        let compression =
+            DictionaryCompression::train(Lz4 { level: 10 }, chunks.iter().map(|(_, c)| c));
+        let meta_tree = db.open_tree("chunks_meta")?;
+        let chunk_db = ChunkDb3::new_with_metadata(
+            db.open_tree("chunks")?,
+            meta_tree.clone(),
+            compression.clone(),
+            false,
+        )?;
+        chunk_db.save_compression_dictionary()?;
+        let mut batch = chunk_db.start_delta_batch();
+        futures::executor::block_on(
+            batch.add_and_compress_deltas(chunks.iter().map(|(k, v)| Delta::Insert(*k, v))),
+        );
+        chunk_db.apply_deltas(batch.build())?;
+        drop(chunk_db);
+
+        let chunk_db = ChunkDb3::new_with_dictionary(
+            db.open_tree("chunks")?,
+            meta_tree,
+            Lz4 { level: 10 },
+            false,
+        )?;
+        assert_eq!(
+            chunk_db.compression().dictionary(),
+            compression.dictionary()
+        );
+        let mut read_chunks = Vec::new();
+        futures::executor::block_on(
+            chunk_db
+                .read_all_chunks::<[i32; 3]>(0)?
+                .decompress_with(chunk_db.compression(), |k, v| read_chunks.push((k, v))),
+        );
+        assert_eq!(read_chunks, chunks);
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_clipmap_returns_active_chunks() -> sled::Result<()> {
+        let chunk_shape = Point3i::fill(16);
+        let num_lods = 2;
+        let config = ClipMapConfig3::new(num_lods, ChunkUnits(2), chunk_shape);
+        // Every chunk in the clip box at LOD 1, for both centers below.
+        let octree = OctreeSet::new_full(Extent3i::from_min_and_shape(
+            Point3i::fill(-4),
+            Point3i::fill(8),
+        ));
+        // Seed more chunks than the clipmap covers, so some have to be filtered out of the range scans.
+        let chunk_domain = Extent3i::from_min_and_shape(Point3i::fill(-6), Point3i::fill(12));
+
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+        let chunk_db = ChunkDb::new(db.open_tree("chunks")?);
+
+        // Seed every chunk at every LOD, only some of which are active.
+        let mut batch = chunk_db.start_delta_batch();
+        for lod in 0..num_lods {
+            let lod_domain = chunk_domain >> lod as i32;
+            batch.add_raw_deltas(
+                lod_domain.iter_points().map(|p| {
+                    Delta::Insert(ChunkKey3::new(lod, p * chunk_shape), IVec::from("data"))
+                }),
+            );
+        }
+        chunk_db.apply_deltas(batch.build())?;
+
+        // The clipmap around the origin has active chunks in every octant.
+        for lod0_center in [Point3i::ZERO, Point3i::fill(1)] {
+            let lod0_center = ChunkUnits(lod0_center);
+            let mut expected_keys = SmallKeyHashSet::default();
+            active_clipmap_lod_chunks(&config, &octree, lod0_center, |key| {
+                expected_keys.insert(key);
+            });
+            assert!(expected_keys.iter().any(|k| k.lod == 0));
+            assert!(expected_keys.iter().any(|k| k.lod == 1));
+
+            let read_keys: Vec<_> = chunk_db
+                .read_clipmap(&config, lod0_center)?
+                .take_with_raw_values()
+                .map(|(k, _)| k)
+                .collect();
+            assert_eq!(read_keys.len(), expected_keys.len());
+            assert_eq!(
+                read_keys.into_iter().collect::<SmallKeyHashSet<_>>(),
+                expected_keys
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn cold_read_leaves_chunks_compressed() -> sled::Result<()> {
+        let chunk_shape = Point3i::fill(16);
+        let write_chunks: Vec<_> = [PointN([0, 0, 0]), PointN([16, 0, 0]), PointN([0, 16, 0])]
+            .iter()
+            .map(|&min| {
+                (
+                    ChunkKey3::new(0, min),
+                    Array3x2::fill(Extent3i::from_min_and_shape(min, chunk_shape), (1u16, b'a')),
+                )
+            })
+            .collect();
+
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+
+        let compression = FastArrayCompressionNx2::from_bytes_compression(Lz4 { level: 10 });
+        let chunk_db = ChunkDb::new_with_compression(db.open_tree("chunks")?, compression);
+
+        let mut batch = chunk_db.start_delta_batch();
+        futures::executor::block_on(
+            batch.add_and_compress_deltas(write_chunks.iter().map(|(k, v)| Delta::Insert(*k, v))),
+        );
+        chunk_db.apply_deltas(batch.build())?;
+
+        let octant = Octant::new_unchecked(Point3i::ZERO, 32);
+        let mut storage = CompressibleChunkStorage::new(compression);
+        chunk_db
+            .read_chunks_in_orthant(0, octant)?
+            .insert_into_storage(&mut storage, ChunkWarmth::Cold);
+
+        assert_eq!(storage.len_cached(), 0);
+        assert_eq!(storage.len_compressed(), write_chunks.len());
+        for (key, chunk) in write_chunks.iter() {
+            assert_eq!(storage.chunk_state(*key), ChunkState::Compressed);
+            let read_chunk = storage
+                .copy_without_caching(*key)
+                .unwrap()
+                .into_decompressed();
+            assert_eq!(&read_chunk, chunk);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "checksums")]
+    #[test]
+    fn corrupt_chunk_fails_checksum() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+
+        let chunk_db = ChunkDb3::new_with_metadata(
+            db.open_tree("chunks")?,
+            db.open_tree("chunks_meta")?,
+            (),
+            true,
+        )?;
+        assert!(chunk_db.has_checksums());
+
+        let key = ChunkKey3::new(0, Point3i::ZERO);
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(std::iter::once(Delta::Insert(key, IVec::from("data"))));
+        chunk_db.apply_deltas(batch.build())?;
+
+        // Intact values are returned without the checksum.
+        let read_chunks: Vec<_> = chunk_db
+            .read_all_chunks::<[i32; 3]>(0)?
+            .take_with_raw_values()
+            .collect();
+        assert_eq!(read_chunks, vec![(key, IVec::from("data"))]);
+
+        // Flip a byte of the stored chunk.
+        let (key_bytes, value) = chunk_db.data_tree().iter().next().unwrap()?;
+        let mut corrupt_value = value.to_vec();
+        corrupt_value[1] ^= 0xff;
+        chunk_db.data_tree().insert(&key_bytes, corrupt_value)?;
+
+        match chunk_db.read_all_chunks::<[i32; 3]>(0) {
+            Err(sled::Error::Io(e)) => {
+                let corrupt = e.get_ref().unwrap().downcast_ref::<CorruptChunk>().unwrap();
+                assert_eq!(corrupt.key, key_bytes);
+            }
+            _ => panic!("Expected a CorruptChunk error"),
+        }
+
+        // Reopening without asking for checksums still uses the persisted setting.
+        drop(chunk_db);
+        let reopened = ChunkDb3::new_with_metadata(
+            db.open_tree("chunks")?,
+            db.open_tree("chunks_meta")?,
+            (),
+            false,
+        )?;
+        assert!(reopened.has_checksums());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "checksums")]
+    #[test]
+    fn separate_lod_trees_persist_checksums() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+
+        let meta_tree = db.open_tree("chunks_meta")?;
+        let chunk_db = ChunkDb3::with_separate_lod_trees_and_metadata(
+            &db,
+            "chunks",
+            2,
+            meta_tree.clone(),
+            (),
+            true,
+        )?;
+        let key = ChunkKey3::new(1, Point3i::ZERO);
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(std::iter::once(Delta::Insert(key, IVec::from("data"))));
+        chunk_db.apply_deltas(batch.build())?;
+        drop(chunk_db);
+
+        let reopened =
+            ChunkDb3::with_separate_lod_trees_and_metadata(&db, "chunks", 2, meta_tree, (), false)?;
+        assert!(reopened.has_checksums());
+        let read_chunks: Vec<_> = reopened
+            .read_all_chunks::<[i32; 3]>(1)?
+            .take_with_raw_values()
+            .collect();
+        assert_eq!(read_chunks, vec![(key, IVec::from("data"))]);
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "checksums"))]
+    #[test]
+    fn checksums_require_feature() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+
+        let open = |checksums| {
+            ChunkDb3::new_with_metadata(
+                db.open_tree("chunks")?,
+                db.open_tree("chunks_meta")?,
+                (),
+                checksums,
+            )
+        };
+        assert!(matches!(open(true), Err(sled::Error::Unsupported(_))));
+        // Nothing was persisted by the failed open.
+        assert!(!open(false)?.has_checksums());
+
+        Ok(())
+    }
+
+    #[test]
+    fn separate_lod_trees() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+
+        let chunk_db = ChunkDb3::with_separate_lod_trees(&db, "chunks", 2, ())?;
+        assert!(chunk_db.has_separate_lod_trees());
+
+        let lod0_keys = [
+            ChunkKey3::new(0, PointN([0, 0, 0])),
+            ChunkKey3::new(0, PointN([16, 0, 0])),
+            ChunkKey3::new(0, PointN([0, 16, 0])),
+        ];
+        let lod1_keys = [ChunkKey3::new(1, PointN([0, 0, 0]))];
+
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(
+            lod0_keys
+                .iter()
+                .chain(lod1_keys.iter())
+                .map(|&k| Delta::Insert(k, IVec::from("data"))),
+        );
+        chunk_db.apply_deltas(batch.build())?;
+
+        assert_eq!(chunk_db.data_tree_for_lod(0)?.len(), lod0_keys.len());
+        assert_eq!(chunk_db.data_tree_for_lod(1)?.len(), lod1_keys.len());
+
+        let read_lod1_keys: Vec<_> = chunk_db
+            .read_all_chunks::<[i32; 3]>(1)?
+            .take_with_raw_values::<[i32; 3]>()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(read_lod1_keys, lod1_keys);
+
+        let octant = Octant::new_unchecked(Point3i::ZERO, 32);
+        let read_lod0_keys: Vec<ChunkKey3> = chunk_db
+            .read_chunks_in_orthant(0, octant)?
+            .take_with_raw_values()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(read_lod0_keys.len(), lod0_keys.len());
+
+        // Removing a key only touches its own LOD's tree.
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(std::iter::once(Delta::Remove(lod1_keys[0])));
+        chunk_db.apply_deltas(batch.build())?;
+        assert_eq!(chunk_db.data_tree_for_lod(0)?.len(), lod0_keys.len());
+        assert!(chunk_db.data_tree_for_lod(1)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_range_lod_is_an_error() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+
+        let chunk_db = ChunkDb3::with_separate_lod_trees(&db, "chunks", 2, ())?;
+
+        assert!(matches!(
+            chunk_db.data_tree_for_lod(2),
+            Err(sled::Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            chunk_db.read_all_chunks::<[i32; 3]>(2),
+            Err(sled::Error::Unsupported(_))
+        ));
+
+        // Nothing is written if any delta is out of range.
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(
+            [
+                ChunkKey3::new(0, PointN([0, 0, 0])),
+                ChunkKey3::new(2, PointN([0, 0, 0])),
+            ]
+            .iter()
+            .map(|&k| Delta::Insert(k, IVec::from("data"))),
+        );
+        assert!(matches!(
+            chunk_db.apply_deltas(batch.build()),
+            Err(sled::Error::Unsupported(_))
+        ));
+        assert!(chunk_db.data_tree_for_lod(0)?.is_empty());
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "snap", feature = "checksums"))]
+    #[test]
+    fn recompressed_db_reads_identical_chunks() -> sled::Result<()> {
+        use crate::{compression::Snappy, prelude::FastArrayCompressionNx2};
+
+        let chunk_shape = Point3i::fill(16);
+        let write_chunks: Vec<_> = (0..4)
+            .map(|i| {
+                let min = PointN([16 * i, 0, 0]);
+                let extent = Extent3i::from_min_and_shape(min, chunk_shape);
+                (
+                    ChunkKey3::new(0, min),
+                    Array3x2::fill_with(extent, |p| (p.x() as u16, (p.y() + i) as u8)),
+                )
+            })
+            .collect();
+
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+        let lz4 = FastArrayCompressionNx2::from_bytes_compression(Lz4 { level: 10 });
+        let chunk_db = ChunkDb3::new_with_metadata(
+            db.open_tree("chunks")?,
+            db.open_tree("chunks_meta")?,
+            lz4,
+            true,
+        )?;
+
+        let mut batch = chunk_db.start_delta_batch();
+        futures::executor::block_on(
+            batch.add_and_compress_deltas(write_chunks.iter().map(|(k, v)| Delta::Insert(*k, v))),
+        );
+        chunk_db.apply_deltas(batch.build())?;
+
+        // Use small batches, so some chunks are rewritten before others are re-encoded.
+        let snappy = FastArrayCompressionNx2::from_bytes_compression(Snappy);
+        assert_eq!(chunk_db.compression_codec()?, None);
+        let chunk_db = chunk_db.recompress_in_batches(snappy, "snappy", 3)?;
+        assert_eq!(chunk_db.compression_codec()?, Some(IVec::from("snappy")));
+
+        let mut read_chunks = Vec::new();
+        futures::executor::block_on(
+            chunk_db
+                .read_all_chunks::<[i32; 3]>(0)?
+                .decompress(|k, v| read_chunks.push((k, v))),
+        );
+        assert_eq!(read_chunks, write_chunks);
+
+        Ok(())
+    }
 }
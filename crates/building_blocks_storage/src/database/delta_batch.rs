@@ -1,8 +1,14 @@
-use crate::dev_prelude::{ChunkKey, Compression, DatabaseKey, Delta};
+use super::checksum::strip_checksum;
+
+use crate::dev_prelude::{
+    Chunk, ChunkKey, ChunkKey3, ChunkMap3, ChunkMapBuilder, ChunkReadStorage, ChunkWriteStorage,
+    Compression, DatabaseKey, Delta, OctreeChunkIndex, ReadableChunkDb,
+};
 
 use futures::future::join_all;
 use sled::IVec;
 use std::borrow::Borrow;
+use std::io;
 
 /// Creates a [DeltaBatch]. This handles sorting the deltas in Morton order and compressing the chunk data.
 pub struct DeltaBatchBuilder<N, K, Compr = ()> {
@@ -37,11 +43,11 @@ where
         &mut self,
         deltas: impl Iterator<Item = Delta<ChunkKey<N>, Data>>,
     ) where
-        Compr: Compression + Copy,
+        Compr: Compression,
         Data: Borrow<Compr::Data>,
     {
         // Compress all of the chunks in parallel.
-        let compression = self.compression.clone();
+        let compression = &self.compression;
         let mut raw_deltas: Vec<_> = join_all(deltas.map(|delta| async move {
             match delta {
                 Delta::Insert(k, v) => Delta::Insert(
@@ -91,6 +97,102 @@ pub struct DeltaBatch {
     pub(crate) deltas: Vec<Delta<IVec, IVec>>,
 }
 
+impl DeltaBatch {
+    /// Iterate over the deltas in the order they will be applied, i.e. sorted by key bytes.
+    pub fn iter(&self) -> impl Iterator<Item = &Delta<IVec, IVec>> {
+        self.deltas.iter()
+    }
+
+    /// The number of deltas in the batch.
+    pub fn len(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deltas.is_empty()
+    }
+
+    /// Computes the batch that undoes `self`, given that `self` has not yet been applied to `base`. Each key in `self` is
+    /// reset to its current value in `base`, or removed if `base` doesn't have it. The compressed chunk bytes are copied
+    /// as-is, so the inverse uses the same compression as `base`.
+    ///
+    /// Returns an error if reading from `base` fails, or if `base` has checksums and one of the old chunks is corrupt.
+    pub fn invert(&self, base: &impl ReadableChunkDb) -> sled::Result<DeltaBatch> {
+        let deltas = self
+            .deltas
+            .iter()
+            .map(|delta| {
+                let key_bytes = delta.key();
+                // The first byte of every key is the LOD.
+                let tree = base.data_tree_for_lod(key_bytes[0])?;
+
+                Ok(match tree.get(key_bytes)? {
+                    Some(old_chunk_bytes) if base.has_checksums() => Delta::Insert(
+                        key_bytes.clone(),
+                        strip_checksum(key_bytes, &old_chunk_bytes)?,
+                    ),
+                    Some(old_chunk_bytes) => Delta::Insert(key_bytes.clone(), old_chunk_bytes),
+                    None => Delta::Remove(key_bytes.clone()),
+                })
+            })
+            .collect::<sled::Result<Vec<_>>>()?;
+
+        Ok(DeltaBatch { deltas })
+    }
+}
+
+impl<T, Ch, Bldr, Store> ChunkMap3<T, Bldr, Store>
+where
+    Ch: Chunk,
+    Bldr: ChunkMapBuilder<[i32; 3], T, Chunk = Ch>,
+    Store: ChunkWriteStorage<[i32; 3], Chunk = Ch>,
+{
+    /// Applies all of the inserts and removes in `batch` to this map's storage, as if it were a [ChunkDb](super::ChunkDb).
+    /// This is useful for replaying edits in memory, e.g. for undo and redo.
+    ///
+    /// `Compr` must be the compression that was used to build `batch`. Returns the error from the first chunk that fails to
+    /// decompress, in which case the preceding deltas have already been applied.
+    pub fn apply_delta_batch<Compr>(&mut self, batch: &DeltaBatch) -> io::Result<()>
+    where
+        Compr: Compression<Data = Ch>,
+    {
+        for delta in batch.iter() {
+            match delta {
+                Delta::Insert(key_bytes, chunk_bytes) => {
+                    let key = ChunkKey3::from_ord_key(ChunkKey3::ord_key_from_be_bytes(key_bytes));
+                    let chunk = Compr::decompress_from_reader(chunk_bytes.as_ref())?;
+                    self.write_chunk(key, chunk);
+                }
+                Delta::Remove(key_bytes) => {
+                    let key = ChunkKey3::from_ord_key(ChunkKey3::ord_key_from_be_bytes(key_bytes));
+                    self.delete_chunk(key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl OctreeChunkIndex {
+    /// Updates the occupancy of the LOD0 chunks referenced by `batch` to match `chunk_map`, which should already have
+    /// `batch` applied, e.g. by [ChunkMap3::apply_delta_batch]. All other chunks keep their current occupancy.
+    pub fn apply_delta_batch<T, Bldr, Store>(
+        &mut self,
+        batch: &DeltaBatch,
+        chunk_map: &ChunkMap3<T, Bldr, Store>,
+    ) where
+        Store: ChunkReadStorage<[i32; 3]>,
+    {
+        self.rebuild_chunks(
+            chunk_map,
+            batch.iter().map(|delta| {
+                ChunkKey3::from_ord_key(ChunkKey3::ord_key_from_be_bytes(delta.key()))
+            }),
+        );
+    }
+}
+
 impl From<DeltaBatch> for sled::Batch {
     fn from(batch: DeltaBatch) -> Self {
         let mut new_batch = sled::Batch::default();
@@ -105,3 +207,220 @@ impl From<DeltaBatch> for sled::Batch {
         new_batch
     }
 }
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(all(test, feature = "lz4"))]
+mod test {
+    use super::*;
+    use crate::prelude::{
+        ChunkDb3, ChunkMapBuilder3x1, FastArrayCompressionNx1, FromBytesCompression, IterChunkKeys,
+        Lz4,
+    };
+
+    use building_blocks_core::prelude::*;
+
+    fn key_bytes(key: ChunkKey3) -> IVec {
+        IVec::from(ChunkKey3::ord_key_to_be_bytes(key.into_ord_key()).as_ref())
+    }
+
+    #[test]
+    fn iterate_deltas_without_consuming_batch() {
+        let k1 = ChunkKey3::new(0, PointN([16, 0, 0]));
+        let k2 = ChunkKey3::new(0, PointN([0, 16, 0]));
+        let k3 = ChunkKey3::new(0, PointN([0, 0, 16]));
+
+        let mut builder = DeltaBatchBuilder::<[i32; 3], _>::new(());
+        builder.add_raw_deltas(
+            vec![
+                Delta::Insert(k1, IVec::from("one")),
+                Delta::Remove(k2),
+                Delta::Insert(k3, IVec::from("three")),
+            ]
+            .into_iter(),
+        );
+        let batch = builder.build();
+
+        assert_eq!(batch.len(), 3);
+        assert!(!batch.is_empty());
+
+        let mut inserts = Vec::new();
+        let mut removes = Vec::new();
+        for delta in batch.iter() {
+            match delta {
+                Delta::Insert(k, v) => inserts.push((k.clone(), v.clone())),
+                Delta::Remove(k) => removes.push(k.clone()),
+            }
+        }
+        inserts.sort();
+        let mut expected_inserts = vec![
+            (key_bytes(k1), IVec::from("one")),
+            (key_bytes(k3), IVec::from("three")),
+        ];
+        expected_inserts.sort();
+        assert_eq!(inserts, expected_inserts);
+        assert_eq!(removes, vec![key_bytes(k2)]);
+
+        // The batch is still usable after iterating.
+        assert_eq!(batch.iter().count(), 3);
+        let _ = sled::Batch::from(batch);
+    }
+
+    #[test]
+    fn replay_delta_batch_onto_fresh_map() {
+        let builder = ChunkMapBuilder3x1::new(PointN([16; 3]), 0);
+        let compression = FastArrayCompressionNx1::from_bytes_compression(Lz4 { level: 10 });
+
+        let mut map = builder.build_with_hash_map_storage();
+        let filled_extent = Extent3i::from_min_and_shape(Point3i::fill(-20), Point3i::fill(40));
+        map.fill_extent(0, &filled_extent, 1);
+        *map.get_mut_point(0, Point3i::fill(5)) = 2;
+        let removed_key = ChunkKey3::new(0, Point3i::fill(-32));
+        map.delete_chunk(removed_key);
+
+        // A fresh map that has a stale chunk where the first map deleted one.
+        let mut replayed = builder.build_with_hash_map_storage();
+        replayed.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(Point3i::fill(-32), Point3i::fill(16)),
+            7,
+        );
+
+        let mut batch_builder = DeltaBatchBuilder::<[i32; 3], _, _>::new(compression);
+        futures::executor::block_on(
+            batch_builder
+                .add_and_compress_deltas(map.storage().iter().map(|(k, v)| Delta::Insert(*k, v))),
+        );
+        batch_builder.add_raw_deltas(std::iter::once(Delta::Remove(removed_key)));
+        let batch = batch_builder.build();
+
+        replayed
+            .apply_delta_batch::<FastArrayCompressionNx1<[i32; 3], Lz4, i32>>(&batch)
+            .unwrap();
+
+        assert_eq!(replayed.storage().len(), map.storage().len());
+        for key in map.storage().chunk_keys() {
+            assert_eq!(replayed.get_chunk(*key), map.get_chunk(*key));
+        }
+        assert!(replayed.get_chunk(removed_key).is_none());
+    }
+
+    #[test]
+    fn replaying_corrupt_chunk_is_an_error() {
+        let mut map = ChunkMapBuilder3x1::new(PointN([16; 3]), 0).build_with_hash_map_storage();
+
+        let mut batch_builder = DeltaBatchBuilder::<[i32; 3], _, _>::new(());
+        batch_builder.add_raw_deltas(std::iter::once(Delta::Insert(
+            ChunkKey3::new(0, Point3i::ZERO),
+            IVec::from("not a chunk"),
+        )));
+        let batch = batch_builder.build();
+
+        assert!(map
+            .apply_delta_batch::<FastArrayCompressionNx1<[i32; 3], Lz4, i32>>(&batch)
+            .is_err());
+    }
+
+    #[test]
+    fn octree_index_follows_applied_batch() {
+        let chunk_shape = Point3i::fill(16);
+        let builder = ChunkMapBuilder3x1::new(chunk_shape, 0);
+        let mut map = builder.build_with_hash_map_storage();
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32)),
+            1,
+        );
+        // Superchunks are 4x4x4 chunks.
+        let mut index = OctreeChunkIndex::index_chunk_map(6, 3, &map);
+
+        let emptied_key = ChunkKey3::new(0, PointN([16, 0, 16]));
+        let mut batch_builder = DeltaBatchBuilder::<[i32; 3], _, _>::new(());
+        batch_builder.add_raw_deltas(std::iter::once(Delta::Remove(emptied_key)));
+        let batch = batch_builder.build();
+
+        map.apply_delta_batch::<FastArrayCompressionNx1<[i32; 3], Lz4, i32>>(&batch)
+            .unwrap();
+        index.apply_delta_batch(&batch, &map);
+
+        let mut occupied_chunks = Vec::new();
+        index.visit_octrees(
+            &Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(64)),
+            &mut |octree| {
+                for p in octree.extent().iter_points() {
+                    if octree.contains(p) {
+                        occupied_chunks.push(p);
+                    }
+                }
+            },
+        );
+        occupied_chunks.sort_by_key(|p| p.0);
+        let mut expected: Vec<_> = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(2))
+            .iter_points()
+            .filter(|&p| p != emptied_key.minimum >> 4)
+            .collect();
+        expected.sort_by_key(|p| p.0);
+        assert_eq!(occupied_chunks, expected);
+    }
+
+    #[test]
+    fn applying_inverted_batch_restores_db() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+        let chunk_db = ChunkDb3::with_separate_lod_trees(&db, "chunks", 2, ())?;
+
+        let kept = ChunkKey3::new(0, PointN([0, 0, 0]));
+        let overwritten = ChunkKey3::new(0, PointN([16, 0, 0]));
+        let removed = ChunkKey3::new(1, PointN([0, 0, 0]));
+        let added = ChunkKey3::new(0, PointN([0, 16, 0]));
+
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(
+            [kept, overwritten, removed]
+                .iter()
+                .map(|&k| Delta::Insert(k, IVec::from("original"))),
+        );
+        chunk_db.apply_deltas(batch.build())?;
+
+        let snapshot = |chunk_db: &ChunkDb3<()>| -> sled::Result<Vec<Vec<(IVec, IVec)>>> {
+            (0..2)
+                .map(|lod| {
+                    chunk_db
+                        .data_tree_for_lod(lod)?
+                        .iter()
+                        .collect::<sled::Result<Vec<_>>>()
+                })
+                .collect()
+        };
+        let original = snapshot(&chunk_db)?;
+
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(
+            vec![
+                Delta::Insert(overwritten, IVec::from("edited")),
+                Delta::Remove(removed),
+                Delta::Insert(added, IVec::from("new")),
+            ]
+            .into_iter(),
+        );
+        let batch = batch.build();
+        let inverse = batch.invert(&chunk_db)?;
+        assert_eq!(inverse.len(), batch.len());
+
+        chunk_db.apply_deltas(batch)?;
+        assert_ne!(snapshot(&chunk_db)?, original);
+
+        chunk_db.apply_deltas(inverse)?;
+        assert_eq!(snapshot(&chunk_db)?, original);
+
+        Ok(())
+    }
+}
@@ -4,6 +4,10 @@ use building_blocks_core::prelude::*;
 
 use core::ops::{Bound, RangeInclusive};
 
+/// A key that can be encoded as bytes for the database.
+///
+/// The byte encoding is always big-endian, regardless of the target's endianness. Not only does this make databases portable,
+/// but it also means that the lexicographic order of the encoded bytes matches the order of `OrdKey`.
 pub trait DatabaseKey<N> {
     type OrdKey: Copy + Ord;
     type KeyBytes: AsRef<[u8]>;
@@ -138,3 +142,32 @@ pub(crate) fn map_bound<X, Y>(b: Bound<&X>, f: impl FnOnce(&X) -> Y) -> Bound<Y>
         Bound::Unbounded => Bound::Unbounded,
     }
 }
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_key_fixture_on_any_target() {
+        let key = ChunkKey3::new(2, PointN([1, 0, 0]));
+        let ord_key = key.into_ord_key();
+
+        let bytes = ChunkKey3::ord_key_to_be_bytes(ord_key);
+        assert_eq!(bytes[0], 2);
+        assert!(ChunkKey3::ord_key_from_be_bytes(&bytes) == ord_key);
+        assert_eq!(ChunkKey3::from_ord_key(ord_key), key);
+
+        let fixture: [u8; 9] = [1, 0, 0, 0, 0, 0, 0, 0, 0x03];
+        let (lod, morton) = ChunkKey2::ord_key_from_be_bytes(&fixture);
+        assert_eq!(lod, 1);
+        assert_eq!(morton.0, 3);
+        assert_eq!(ChunkKey2::ord_key_to_be_bytes((lod, morton)), fixture);
+    }
+}
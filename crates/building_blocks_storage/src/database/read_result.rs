@@ -1,7 +1,21 @@
-use crate::dev_prelude::{ChunkKey, Compression, DatabaseKey};
+use crate::dev_prelude::{
+    ChunkKey, Compressed, CompressibleChunkStorage, Compression, DatabaseKey,
+};
 
 use futures::future::join_all;
 use sled::IVec;
+use std::hash::Hash;
+use std::io;
+
+/// Whether chunks loaded into a `CompressibleChunkStorage` should be decompressed immediately.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChunkWarmth {
+    /// Decompress each chunk, so it's resident in the cache.
+    Hot,
+    /// Insert each chunk in the compressed form it was stored in. Chunks are only decompressed when they're accessed, so a
+    /// bulk load doesn't force the entire region to be resident.
+    Cold,
+}
 
 /// A wrapper around key-value pairs read from a `ChunkDb`.
 pub struct ReadResult<Compr> {
@@ -45,20 +59,74 @@ impl<Compr> ReadResult<Compr> {
         })
     }
 
+    /// Insert all chunks into `storage`. With `ChunkWarmth::Cold`, the compressed values are inserted as they are, without
+    /// decompressing them, so every loaded chunk has `ChunkState::Compressed`.
+    ///
+    /// The values must have been compressed with `storage`'s compression algorithm, i.e. the same `Compr` as this DB.
+    pub fn insert_into_storage<N>(
+        self,
+        storage: &mut CompressibleChunkStorage<N, Compr>,
+        warmth: ChunkWarmth,
+    ) where
+        N: Send,
+        ChunkKey<N>: Clone + DatabaseKey<N> + Eq + Hash,
+        Compr: Compression,
+        Compr::Data: Send,
+    {
+        for (key, value) in self.take_with_raw_values() {
+            let compressed = Compressed::from_bytes(storage.compression(), value.to_vec());
+            match warmth {
+                ChunkWarmth::Hot => {
+                    let chunk = compressed.decompress_with(storage.compression());
+                    storage.insert_chunk(key, chunk);
+                }
+                ChunkWarmth::Cold => {
+                    storage.insert_compressed(key, compressed);
+                }
+            }
+        }
+    }
+
     /// Concurrently decompress all values, calling `chunk_rx` on each key-value pair.
-    pub async fn decompress<N, F>(self, mut chunk_rx: F)
+    pub async fn decompress<N, F>(self, chunk_rx: F)
     where
         ChunkKey<N>: DatabaseKey<N>,
         Compr: Compression,
         F: FnMut(ChunkKey<N>, Compr::Data),
     {
+        self.decompress_by(|bytes| Compr::decompress_from_reader(bytes), chunk_rx)
+            .await
+    }
+
+    /// Same as `decompress`, but uses `compression`'s state, as in `Compression::decompress_with`. This is required for
+    /// compressions like `DictionaryCompression`.
+    pub async fn decompress_with<N, F>(self, compression: &Compr, chunk_rx: F)
+    where
+        ChunkKey<N>: DatabaseKey<N>,
+        Compr: Compression,
+        F: FnMut(ChunkKey<N>, Compr::Data),
+    {
+        self.decompress_by(|bytes| compression.decompress_with(bytes), chunk_rx)
+            .await
+    }
+
+    async fn decompress_by<N, F>(
+        self,
+        decompress_chunk: impl Fn(&[u8]) -> io::Result<Compr::Data>,
+        mut chunk_rx: F,
+    ) where
+        ChunkKey<N>: DatabaseKey<N>,
+        Compr: Compression,
+        F: FnMut(ChunkKey<N>, Compr::Data),
+    {
+        let decompress_chunk = &decompress_chunk;
         for batch in self.key_value_pairs.chunks(16) {
             for (chunk_key, chunk) in
                 join_all(batch.iter().map(|(key, compressed_chunk)| async move {
                     let ord_key = ChunkKey::<N>::ord_key_from_be_bytes(key.as_ref());
                     let chunk_key = ChunkKey::<N>::from_ord_key(ord_key);
 
-                    let chunk = Compr::decompress_from_reader(compressed_chunk.as_ref()).unwrap();
+                    let chunk = decompress_chunk(compressed_chunk.as_ref()).unwrap();
 
                     (chunk_key, chunk)
                 }))
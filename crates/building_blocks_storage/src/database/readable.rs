@@ -1,10 +1,14 @@
-use super::{key::map_bound, DatabaseKey, ReadResult};
+use super::{checksum::strip_checksum, key::map_bound, DatabaseKey, ReadResult};
 
-use crate::prelude::ChunkKey;
+use crate::{
+    octree::clipmap_lod_chunks,
+    prelude::{ChunkKey, ChunkKey3, ChunkUnits, ClipMapConfig3},
+};
 
 use building_blocks_core::{orthants_covering_extent, prelude::*};
 
-use core::ops::RangeBounds;
+use core::ops::{Bound, RangeBounds};
+use sled::IVec;
 
 /// Shared behavior for chunk databases, i.e. those that are keyed on `ChunkKey`.
 pub trait ReadableChunkDb {
@@ -12,6 +16,20 @@ pub trait ReadableChunkDb {
 
     fn data_tree(&self) -> &sled::Tree;
 
+    /// The tree that stores chunks for `lod`. Unless the DB separates its levels of detail into different trees, this is the
+    /// same as `data_tree`.
+    ///
+    /// Returns `sled::Error::Unsupported` if the DB has no tree for `lod`.
+    fn data_tree_for_lod(&self, _lod: u8) -> sled::Result<&sled::Tree> {
+        Ok(self.data_tree())
+    }
+
+    /// Returns `true` iff every chunk value is stored with a trailing checksum. Reads verify and strip the checksums, returning
+    /// a [CorruptChunk](super::CorruptChunk) error on mismatch.
+    fn has_checksums(&self) -> bool {
+        false
+    }
+
     /// Scans the given orthant for chunks. Because chunk keys are stored in Morton order, the chunks in any orthant are
     /// guaranteed to be contiguous.
     ///
@@ -59,7 +77,53 @@ pub trait ReadableChunkDb {
         self.read_morton_range(ChunkKey::<N>::full_range(lod))
     }
 
+    /// Reads the chunks that are active in a clipmap centered on the LOD0 chunk at `focus`, i.e. those found by
+    /// `clipmap_lod_chunks`. Chunks that aren't in the DB are skipped.
+    ///
+    /// Each level of detail is read with a single range scan between its smallest and largest active keys, and only the active
+    /// keys are kept from that scan.
+    fn read_clipmap(
+        &self,
+        config: &ClipMapConfig3,
+        focus: ChunkUnits<Point3i>,
+    ) -> sled::Result<ReadResult<Self::Compr>> {
+        let mut keys_by_lod = Vec::new();
+        clipmap_lod_chunks(config, focus, |key| {
+            let lod = key.lod as usize;
+            if keys_by_lod.len() <= lod {
+                keys_by_lod.resize_with(lod + 1, Vec::new);
+            }
+            keys_by_lod[lod].push(key.into_ord_key());
+        });
+
+        let mut key_value_pairs = Vec::new();
+        for mut lod_keys in keys_by_lod.into_iter() {
+            if lod_keys.is_empty() {
+                continue;
+            }
+            lod_keys.sort_unstable();
+
+            let (lod, _) = lod_keys[0];
+            let first = ChunkKey3::ord_key_to_be_bytes(lod_keys[0]);
+            let last = ChunkKey3::ord_key_to_be_bytes(*lod_keys.last().unwrap());
+            for key_value in self.data_tree_for_lod(lod)?.range(first..=last) {
+                let (key, value) = key_value?;
+                if lod_keys
+                    .binary_search(&ChunkKey3::ord_key_from_be_bytes(&key))
+                    .is_ok()
+                {
+                    key_value_pairs.push((key, value));
+                }
+            }
+        }
+
+        finish_read(self.has_checksums(), key_value_pairs)
+    }
+
     /// Reads all chunks in the given `range` of Morton codes.
+    ///
+    /// If the DB stores levels of detail in separate trees, then only the tree for the LOD of the range's start bound is
+    /// scanned.
     fn read_morton_range<N, R>(&self, range: R) -> sled::Result<ReadResult<Self::Compr>>
     where
         ChunkKey<N>: DatabaseKey<N>,
@@ -67,10 +131,33 @@ pub trait ReadableChunkDb {
     {
         let key_range_start = map_bound(range.start_bound(), |k| ChunkKey::ord_key_to_be_bytes(*k));
         let key_range_end = map_bound(range.end_bound(), |k| ChunkKey::ord_key_to_be_bytes(*k));
-        let key_value_pairs = self
-            .data_tree()
+        // The first byte of every key is the LOD.
+        let tree = match &key_range_start {
+            Bound::Included(k) | Bound::Excluded(k) => self.data_tree_for_lod(k.as_ref()[0])?,
+            Bound::Unbounded => self.data_tree(),
+        };
+        let key_value_pairs = tree
             .range((key_range_start, key_range_end))
             .collect::<Result<Vec<_>, _>>()?;
-        Ok(ReadResult::new(key_value_pairs))
+
+        finish_read(self.has_checksums(), key_value_pairs)
     }
 }
+
+fn finish_read<Compr>(
+    has_checksums: bool,
+    mut key_value_pairs: Vec<(IVec, IVec)>,
+) -> sled::Result<ReadResult<Compr>> {
+    debug_event!(
+        num_chunks = key_value_pairs.len(),
+        num_bytes = key_value_pairs.iter().map(|(_, v)| v.len()).sum::<usize>(),
+        "read chunks from database"
+    );
+    if has_checksums {
+        for (key, value) in key_value_pairs.iter_mut() {
+            *value = strip_checksum(key, value)?;
+        }
+    }
+
+    Ok(ReadResult::new(key_value_pairs))
+}
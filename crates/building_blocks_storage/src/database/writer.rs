@@ -0,0 +1,200 @@
+use super::{ChunkDb, DatabaseKey, DeltaBatch};
+
+use crate::prelude::ChunkKey;
+
+use std::sync::mpsc::{channel, sync_channel, Sender, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+enum WriterMessage {
+    Apply(DeltaBatch),
+    Flush(Sender<sled::Result<()>>),
+}
+
+/// Applies `DeltaBatch`es to a `ChunkDb` on a background thread, so producers of edits don't wait on `sled` commits.
+///
+/// Batches are queued on a bounded channel and applied in the order they were submitted. When the queue is full, `submit`
+/// blocks until the background thread catches up, which keeps a fast producer from queueing an unbounded amount of work.
+///
+/// Errors from applying a batch are reported by the next call to `flush` or `close`. Dropping the writer waits for the queue
+/// to drain like `close`, but any error since the last `flush` is lost.
+pub struct ChunkDbWriter {
+    sender: Option<SyncSender<WriterMessage>>,
+    thread: Option<JoinHandle<sled::Result<()>>>,
+}
+
+impl ChunkDbWriter {
+    /// Spawns the background thread that writes to `db`. At most `queue_capacity` batches can be waiting to be applied.
+    pub fn new<N, Compr>(db: Arc<ChunkDb<N, Compr>>, queue_capacity: usize) -> Self
+    where
+        ChunkKey<N>: DatabaseKey<N>,
+        ChunkDb<N, Compr>: Send + Sync + 'static,
+        Compr: Clone,
+    {
+        let (sender, receiver) = sync_channel(queue_capacity);
+
+        let thread = thread::spawn(move || {
+            let mut first_error = None;
+            for message in receiver.into_iter() {
+                match message {
+                    WriterMessage::Apply(batch) => {
+                        if let Err(e) = db.apply_deltas(batch) {
+                            if first_error.is_none() {
+                                first_error = Some(e);
+                            }
+                        }
+                    }
+                    WriterMessage::Flush(done) => {
+                        // The flusher may have given up waiting, which is fine.
+                        let _ = done.send(first_error.take().map_or(Ok(()), Err));
+                    }
+                }
+            }
+
+            first_error.map_or(Ok(()), Err)
+        });
+
+        Self {
+            sender: Some(sender),
+            thread: Some(thread),
+        }
+    }
+
+    /// Queues `batch` to be applied. This blocks while the queue is full.
+    pub fn submit(&self, batch: DeltaBatch) {
+        self.sender()
+            .send(WriterMessage::Apply(batch))
+            .expect("ChunkDbWriter thread exited");
+    }
+
+    /// Blocks until all previously submitted batches have been applied. Returns the first error from applying any batch
+    /// since the last flush.
+    ///
+    /// This does not flush the `sled` trees to disk; use `ChunkDb::flush` for durability.
+    pub fn flush(&self) -> sled::Result<()> {
+        let (done_sender, done_receiver) = channel();
+        self.sender()
+            .send(WriterMessage::Flush(done_sender))
+            .expect("ChunkDbWriter thread exited");
+
+        done_receiver.recv().expect("ChunkDbWriter thread exited")
+    }
+
+    /// Waits for all submitted batches to be applied and stops the background thread. Returns the first error from applying
+    /// any batch since the last flush.
+    pub fn close(mut self) -> sled::Result<()> {
+        // Closing the channel stops the background thread once the queue is drained.
+        self.sender.take();
+        let thread = self.thread.take().unwrap();
+
+        thread.join().expect("ChunkDbWriter thread panicked")
+    }
+
+    fn sender(&self) -> &SyncSender<WriterMessage> {
+        self.sender.as_ref().unwrap()
+    }
+}
+
+impl Drop for ChunkDbWriter {
+    /// Waits for all submitted batches to be applied. Errors since the last flush are discarded; use `close` to see them.
+    fn drop(&mut self) {
+        // Closing the channel stops the background thread once the queue is drained.
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::{
+        database::{Delta, ReadableChunkDb},
+        prelude::ChunkKey3,
+    };
+
+    use building_blocks_core::prelude::*;
+
+    use sled::IVec;
+
+    #[test]
+    fn flushed_batches_are_readable() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+        let chunk_db = Arc::new(ChunkDb::new(db.open_tree("chunks")?));
+
+        // A small queue, so submitting blocks some of the time.
+        let writer = ChunkDbWriter::new(chunk_db.clone(), 2);
+
+        let keys: Vec<_> = (0..8)
+            .map(|i| ChunkKey3::new(0, PointN([16 * i, 0, 0])))
+            .collect();
+        for key_pair in keys.chunks(2) {
+            let mut batch = chunk_db.start_delta_batch();
+            batch.add_raw_deltas(
+                key_pair
+                    .iter()
+                    .map(|&k| Delta::Insert(k, IVec::from("data"))),
+            );
+            writer.submit(batch.build());
+        }
+        // A later batch overrides an earlier one.
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(std::iter::once(Delta::Remove(keys[0])));
+        writer.submit(batch.build());
+
+        writer.flush()?;
+
+        let read_keys: Vec<_> = chunk_db
+            .read_all_chunks::<[i32; 3]>(0)?
+            .take_with_raw_values()
+            .map(|(k, v)| {
+                assert_eq!(v, IVec::from("data"));
+                k
+            })
+            .collect();
+        assert_eq!(read_keys, &keys[1..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn close_applies_queued_batches() -> sled::Result<()> {
+        let db = sled::Config::default()
+            .temporary(true)
+            .use_compression(false)
+            .mode(sled::Mode::LowSpace)
+            .open()?;
+        let chunk_db = Arc::new(ChunkDb::new(db.open_tree("chunks")?));
+        let writer = ChunkDbWriter::new(chunk_db.clone(), 2);
+
+        let key = ChunkKey3::new(0, PointN([0, 0, 0]));
+        let mut batch = chunk_db.start_delta_batch();
+        batch.add_raw_deltas(std::iter::once(Delta::Insert(key, IVec::from("data"))));
+        writer.submit(batch.build());
+
+        // No flush, so close is the only place an error could be reported.
+        writer.close()?;
+
+        let read_keys: Vec<_> = chunk_db
+            .read_all_chunks::<[i32; 3]>(0)?
+            .take_with_raw_values()
+            .map(|(k, _)| k)
+            .collect();
+        assert_eq!(read_keys, vec![key]);
+
+        Ok(())
+    }
+}
@@ -12,6 +12,9 @@
 #![warn(clippy::doc_markdown)]
 #![doc = include_str!("crate_doc.md")]
 
+#[macro_use]
+mod trace;
+
 #[macro_use]
 pub mod access_traits;
 pub mod array;
@@ -44,18 +47,34 @@ pub type SmallKeyHashMap<K, V> = ahash::AHashMap<K, V>;
 pub type SmallKeyHashSet<K> = ahash::AHashSet<K>;
 pub type SmallKeyBuildHasher = ahash::RandomState;
 
+/// Creates a `SmallKeyBuildHasher` with fixed keys derived from `seed`. Hash maps built with the same seed iterate in the same
+/// order given the same sequence of insertions and removals, which is useful for reproducible tests.
+#[inline]
+pub fn seeded_small_key_build_hasher(seed: u64) -> SmallKeyBuildHasher {
+    ahash::RandomState::with_seeds(
+        seed,
+        seed ^ 0x243f_6a88_85a3_08d3,
+        seed ^ 0x1319_8a2e_0370_7344,
+        seed ^ 0xa409_3822_299f_31d0,
+    )
+}
+
 #[doc(hidden)]
 pub mod prelude {
     pub use super::{
-        array::{IndexedArray, Local, Stride},
-        chunk::{
-            AmbientExtent, Chunk, ChunkDownsampler, ChunkKey, ChunkKey2, ChunkKey3,
-            ChunkMapBuilder, ChunkReadStorage, ChunkUnits, ChunkWriteStorage, IterChunkKeys,
-            PointDownsampler, SdfMeanDownsampler,
+        array::{
+            channels::{BitPackedCompression, FixedEndian},
+            DoubleBufferedArray, DoubleBufferedArray2x1, DoubleBufferedArray3x1, IndexedArray,
+            Local, Neighborhood26, Stride,
         },
-        compression::{
-            BincodeCompression, BytesCompression, Compressed, Compression, FromBytesCompression,
+        chunk::{
+            AmbientExtent, ChannelDownsamplers2, Chunk, ChunkDownsampler, ChunkIndexer,
+            ChunkIndexer2, ChunkIndexer3, ChunkKey, ChunkKey2, ChunkKey3, ChunkMapBuilder,
+            ChunkMapBuilderNxM, ChunkReadStorage, ChunkUnits, ChunkWriteStorage,
+            CompressionExecutor, ExcludeMissingChunks, IterChunkKeys, MaterialModeDownsampler,
+            MissingChunkPolicy, Plane3, PointDownsampler, SamplingMode, SdfMeanDownsampler,
         },
+        compression::{BytesCompression, Compressed, Compression, FromBytesCompression},
         func::Func,
         octree::{
             ChunkedOctreeSet, ClipMapConfig3, ClipMapUpdate3, LodChunkUpdate3, OctreeChunkIndex,
@@ -73,14 +92,18 @@ pub mod prelude {
     pub use super::chunk::storage::compressible::multichannel_aliases::*;
     pub use super::chunk::storage::hash_map::multichannel_aliases::*;
 
-    #[cfg(feature = "lz4")]
-    pub use super::compression::Lz4;
+    #[cfg(all(feature = "memmap2", feature = "serde"))]
+    pub use super::chunk::{bake_chunks, MmapChunkStorage};
+    #[cfg(feature = "serde")]
+    pub use super::compression::BincodeCompression;
     #[cfg(feature = "snap")]
     pub use super::compression::Snappy;
+    #[cfg(feature = "lz4")]
+    pub use super::compression::{AdaptiveCompression, AdaptiveMode, Lz4};
     #[cfg(feature = "sled")]
     pub use super::database::{
-        ChunkDb, ChunkDb2, ChunkDb3, Delta, DeltaBatch, DeltaBatchBuilder, ReadResult,
-        ReadableChunkDb,
+        ChunkDb, ChunkDb2, ChunkDb3, ChunkDbWriter, ChunkWarmth, Delta, DeltaBatch,
+        DeltaBatchBuilder, ReadResult, ReadableChunkDb,
     };
     #[cfg(feature = "sled-snapshots")]
     pub use super::database::{VersionedChunkDb, VersionedChunkDb2, VersionedChunkDb3};
@@ -93,14 +116,11 @@ pub mod dev_prelude {
 
     pub use super::{
         array::{
-            channels::{Channel, Channels, FastChannelsCompression},
-            compression::FastArrayCompression,
+            channels::{Channel, Channels, ChannelsCompression, FastChannelsCompression},
+            compression::{DictionaryCompression, FastArrayCompression, SparseChunkCompression},
             Array, IndexedArray,
         },
-        chunk::{
-            ChunkHashMap, ChunkMap, ChunkMap2, ChunkMap3, ChunkMapBuilderNxM,
-            CompressibleChunkStorage,
-        },
+        chunk::{ChunkHashMap, ChunkMap, ChunkMap2, ChunkMap3, CompressibleChunkStorage},
         SmallKeyHashMap, SmallKeyHashSet,
     };
 
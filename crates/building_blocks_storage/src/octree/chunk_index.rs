@@ -53,19 +53,21 @@
 
 use crate::{
     dev_prelude::{
-        Array3x1, ChunkKey3, ChunkMap3, ChunkUnits, ChunkedOctreeSet, ClipMapConfig3,
-        ClipMapUpdate3, GetMutUnchecked, IterChunkKeys, LodChunkUpdate3, OctreeSet,
-        SmallKeyHashMap,
+        Array3x1, ChunkKey3, ChunkMap3, ChunkReadStorage, ChunkUnits, ChunkedOctreeSet,
+        ClipMapConfig3, ClipMapUpdate3, GetMutUnchecked, IterChunkKeys, LodChunkUpdate3,
+        OctreeNode, OctreeSet, SmallKeyHashMap, VisitStatus,
     },
     octree::active_clipmap_lod_chunks,
 };
 
 use building_blocks_core::prelude::*;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// A `ChunkedOctreeSet` that indexes the chunks of a `ChunkMap`. Useful for representing a clipmap.
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct OctreeChunkIndex {
     /// An unbounded set of chunk keys, but scaled down to be contiguous, i.e. it operates in `ChunkUnits`.
     superchunk_octrees: ChunkedOctreeSet,
@@ -213,6 +215,111 @@ impl OctreeChunkIndex {
         self.superchunk_octrees.insert_chunk(superchunk_min, octree)
     }
 
+    /// Updates the occupancy of only the LOD0 chunks that intersect `extent` to match `chunk_map`. This is cheaper than
+    /// re-indexing the whole map after an edit. Chunks outside of `extent` keep their current occupancy, even if they have
+    /// changed in `chunk_map`.
+    ///
+    /// `extent` is in voxel units, and the chunk shape of `chunk_map` must match this index.
+    pub fn rebuild_extent<T, Bldr, Store>(
+        &mut self,
+        chunk_map: &ChunkMap3<T, Bldr, Store>,
+        extent: Extent3i,
+    ) where
+        Store: ChunkReadStorage<[i32; 3]>,
+    {
+        assert_eq!(chunk_map.indexer.chunk_shape(), self.chunk_shape());
+        if extent.is_empty() {
+            return;
+        }
+
+        let chunk_exponent = self.chunk_exponent;
+        let extent_in_chunks = Extent3i::from_min_and_max(
+            extent.minimum >> chunk_exponent,
+            extent.max() >> chunk_exponent,
+        );
+
+        let superchunk_mins: Vec<_> = self
+            .superchunk_octrees
+            .indexer
+            .chunk_mins_for_extent(&extent)
+            .collect();
+        for superchunk_min in superchunk_mins.into_iter() {
+            let superchunk_extent_in_chunks =
+                Extent3i::from_min_and_shape(superchunk_min, self.superchunk_shape())
+                    >> chunk_exponent;
+            let chunks_in_extent = extent_in_chunks
+                .intersection(&superchunk_extent_in_chunks)
+                .iter_points();
+            self.rebuild_superchunk(chunk_map, superchunk_min, chunks_in_extent);
+        }
+    }
+
+    /// Updates the occupancy of only the LOD0 chunks at `chunk_keys` to match `chunk_map`. Keys at other levels of detail
+    /// are ignored.
+    ///
+    /// The chunk shape of `chunk_map` must match this index.
+    pub fn rebuild_chunks<T, Bldr, Store>(
+        &mut self,
+        chunk_map: &ChunkMap3<T, Bldr, Store>,
+        chunk_keys: impl IntoIterator<Item = ChunkKey3>,
+    ) where
+        Store: ChunkReadStorage<[i32; 3]>,
+    {
+        assert_eq!(chunk_map.indexer.chunk_shape(), self.chunk_shape());
+
+        let mut superchunk_chunks = SmallKeyHashMap::<_, Vec<_>>::default();
+        for key in chunk_keys.into_iter().filter(|key| key.lod == 0) {
+            let superchunk_min = self
+                .superchunk_octrees
+                .indexer
+                .min_of_chunk_containing_point(key.minimum);
+            superchunk_chunks
+                .entry(superchunk_min)
+                .or_default()
+                .push(key.minimum >> self.chunk_exponent);
+        }
+
+        for (superchunk_min, chunks) in superchunk_chunks.into_iter() {
+            self.rebuild_superchunk(chunk_map, superchunk_min, chunks.into_iter());
+        }
+    }
+
+    /// Sets the occupancy of each of `chunks` (in chunk units) in the superchunk at `superchunk_min` to match `chunk_map`.
+    fn rebuild_superchunk<T, Bldr, Store>(
+        &mut self,
+        chunk_map: &ChunkMap3<T, Bldr, Store>,
+        superchunk_min: Point3i,
+        chunks: impl Iterator<Item = Point3i>,
+    ) where
+        Store: ChunkReadStorage<[i32; 3]>,
+    {
+        let chunk_exponent = self.chunk_exponent;
+        let superchunk_extent_in_chunks =
+            Extent3i::from_min_and_shape(superchunk_min, self.superchunk_shape()) >> chunk_exponent;
+
+        // Start from the existing occupancy so we don't touch the other chunks.
+        let old_octree = self.superchunk_octrees.pop_chunk(superchunk_min);
+        let mut bitset = Array3x1::fill_with(superchunk_extent_in_chunks, |p| {
+            old_octree
+                .as_ref()
+                .map_or(false, |octree| octree.contains(p))
+        });
+        for p in chunks {
+            let is_occupied = chunk_map
+                .storage()
+                .get(ChunkKey3::new(0, p << chunk_exponent))
+                .is_some();
+            unsafe {
+                *bitset.get_mut_unchecked(p) = is_occupied;
+            }
+        }
+
+        let octree = OctreeSet::from_array3(&bitset, *bitset.extent());
+        if !octree.is_empty() {
+            self.superchunk_octrees.insert_chunk(superchunk_min, octree);
+        }
+    }
+
     pub fn pop_superchunk(&mut self, superchunk_min: Point3i) -> Option<OctreeSet> {
         self.superchunk_octrees.pop_chunk(superchunk_min)
     }
@@ -267,6 +374,30 @@ impl OctreeChunkIndex {
         self.superchunk_octrees.subtract_extent(&extent.0)
     }
 
+    /// The world-space centers of all occupied chunks at level of detail `lod`. A chunk at `lod` is occupied if any of the
+    /// LOD0 chunks it covers are occupied. Useful for visualizing the index as a point cloud.
+    pub fn occupied_chunk_centers(&self, lod: u8) -> Vec<Point3f> {
+        let chunk_exponent = self.chunk_exponent;
+        let mut centers = Vec::new();
+        self.superchunk_octrees.visit_all_octrees(&mut |octree| {
+            octree.visit_all_octants_in_preorder(&mut |node: &OctreeNode| {
+                let octant = node.octant();
+                if octant.exponent() > lod {
+                    return VisitStatus::Continue;
+                }
+
+                // Octants are in chunk units, so scale them up to voxels.
+                let world_min = octant.minimum() << chunk_exponent as i32;
+                let half_edge = (octant.edge_length() << chunk_exponent) as f32 / 2.0;
+                centers.push(Point3f::from(world_min) + Point3f::fill(half_edge));
+
+                VisitStatus::Stop
+            });
+        });
+
+        centers
+    }
+
     /// Visit all superchunk octrees that overlap `extent`.
     pub fn visit_octrees(&self, extent: &Extent3i, visitor: &mut impl FnMut(&OctreeSet)) {
         self.superchunk_octrees.visit_octrees(extent, visitor)
@@ -289,3 +420,118 @@ fn validate_params(superchunk_exponent: u8, chunk_exponent: u8, num_lods: u8) {
     let max_lods = superchunk_exponent - chunk_exponent + 1;
     assert!(num_lods <= max_lods);
 }
+
+// ████████╗███████╗███████╗████████╗
+// ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
+//    ██║   █████╗  ███████╗   ██║
+//    ██║   ██╔══╝  ╚════██║   ██║
+//    ██║   ███████╗███████║   ██║
+//    ╚═╝   ╚══════╝╚══════╝   ╚═╝
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::ChunkMapBuilder3x1;
+
+    use std::collections::HashSet;
+
+    fn indexed_chunk_keys(index: &OctreeChunkIndex, extent: &Extent3i) -> HashSet<ChunkKey3> {
+        let mut keys = HashSet::new();
+        index.visit_octrees(extent, &mut |octree: &OctreeSet| {
+            octree.visit_all_octants_in_preorder(&mut |node: &OctreeNode| {
+                if node.octant().is_single_voxel() {
+                    keys.insert(ChunkKey3::new(
+                        0,
+                        node.octant().minimum() << index.chunk_exponent(),
+                    ));
+                }
+                VisitStatus::Continue
+            });
+        });
+
+        keys
+    }
+
+    #[test]
+    fn occupied_chunk_centers_at_each_lod() {
+        let chunk_shape = Point3i::fill(16);
+        let builder = ChunkMapBuilder3x1::new(chunk_shape, 0);
+        let mut map = builder.build_with_hash_map_storage();
+        // A 2x2x2 block of chunks, plus one chunk in a different superchunk.
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32)),
+            1,
+        );
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(PointN([64, 0, 0]), chunk_shape),
+            1,
+        );
+
+        // Superchunks are 4x4x4 chunks.
+        let index = OctreeChunkIndex::index_chunk_map(6, 3, &map);
+
+        let mut lod0_centers = index.occupied_chunk_centers(0);
+        lod0_centers.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        let mut expected_lod0_centers: Vec<_> =
+            Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(2))
+                .iter_points()
+                .map(|p| Point3f::from(p * chunk_shape) + Point3f::fill(8.0))
+                .chain(std::iter::once(PointN([72.0, 8.0, 8.0])))
+                .collect();
+        expected_lod0_centers.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        assert_eq!(lod0_centers, expected_lod0_centers);
+
+        // Each LOD1 chunk covers 2x2x2 LOD0 chunks.
+        let mut lod1_centers = index.occupied_chunk_centers(1);
+        lod1_centers.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        assert_eq!(
+            lod1_centers,
+            vec![PointN([16.0, 16.0, 16.0]), PointN([80.0, 16.0, 16.0])]
+        );
+    }
+
+    #[test]
+    fn rebuild_extent_only_updates_chunks_in_extent() {
+        let chunk_shape = Point3i::fill(16);
+        let builder = ChunkMapBuilder3x1::new(chunk_shape, 0);
+        let mut map = builder.build_with_hash_map_storage();
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(64)),
+            1,
+        );
+
+        // Superchunks are 4x4x4 chunks.
+        let mut index = OctreeChunkIndex::index_chunk_map(6, 3, &map);
+        let world = Extent3i::from_min_and_shape(Point3i::fill(-128), Point3i::fill(256));
+        let original_keys = indexed_chunk_keys(&index, &world);
+        assert_eq!(original_keys.len(), 64);
+
+        let edited_key = ChunkKey3::new(0, Point3i::fill(16));
+        let stale_key = ChunkKey3::new(0, Point3i::fill(48));
+        let added_key = ChunkKey3::new(0, PointN([-16, 0, 0]));
+        map.delete_chunk(edited_key);
+        map.delete_chunk(stale_key);
+        map.fill_extent(
+            0,
+            &Extent3i::from_min_and_shape(added_key.minimum, chunk_shape),
+            1,
+        );
+
+        let edited_extent = Extent3i::from_min_and_shape(edited_key.minimum, chunk_shape);
+        index.rebuild_extent(&map, edited_extent);
+        let mut expected_keys = original_keys.clone();
+        expected_keys.remove(&edited_key);
+        assert_eq!(indexed_chunk_keys(&index, &world), expected_keys);
+
+        // This chunk is in a new superchunk.
+        index.rebuild_extent(
+            &map,
+            Extent3i::from_min_and_shape(added_key.minimum, chunk_shape),
+        );
+        expected_keys.insert(added_key);
+        assert_eq!(indexed_chunk_keys(&index, &world), expected_keys);
+    }
+}
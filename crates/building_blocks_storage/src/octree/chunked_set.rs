@@ -2,11 +2,13 @@ use crate::{chunk::ChunkIndexer, octree::OctreeSet, SmallKeyHashMap};
 
 use building_blocks_core::prelude::*;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// A hash map of `OctreeSet`s which, unlike the vanilla `OctreeSet`, supports an unbounded set of points (within the bounds of
 /// `Point3i`).
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ChunkedOctreeSet {
     /// Indexer used to find the octree for a given chunk.
     pub indexer: ChunkIndexer<[i32; 3]>,
@@ -42,6 +44,13 @@ impl ChunkedOctreeSet {
         }
     }
 
+    /// Visit every octree, regardless of location.
+    pub fn visit_all_octrees(&self, visitor: &mut impl FnMut(&OctreeSet)) {
+        for octree in self.octrees.values() {
+            (visitor)(octree);
+        }
+    }
+
     pub fn add_extent(&mut self, extent: &Extent3i) {
         let Self {
             octrees, indexer, ..
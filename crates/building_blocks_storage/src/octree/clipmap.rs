@@ -49,7 +49,7 @@ pub fn active_clipmap_lod_chunks(
 
     octree.visit_all_octants_in_preorder(&mut |node: &OctreeNode| {
         let octant = node.octant();
-        let lod = octant.exponent();
+        let lod = node.level();
         if lod >= config.num_lods {
             return VisitStatus::Continue;
         }
@@ -68,6 +68,74 @@ pub fn active_clipmap_lod_chunks(
     });
 }
 
+/// Finds the `ChunkKey3`s that are active when the clipmap is centered at `lod0_center`, assuming that every chunk exists.
+/// These are the chunks that `active_clipmap_lod_chunks` would find in a full octree covering the clip box of the coarsest
+/// level of detail, i.e. `2 * clip_box_radius` chunks along each axis around the center at that level.
+///
+/// Unlike `active_clipmap_lod_chunks`, this doesn't need an octree, so it can find the chunks to load before any are known to
+/// exist.
+pub fn clipmap_lod_chunks(
+    config: &ClipMapConfig3,
+    lod0_center: ChunkUnits<Point3i>,
+    mut active_rx: impl FnMut(ChunkKey3),
+) {
+    if config.num_lods == 0 {
+        return;
+    }
+
+    let chunk_log2 = config.chunk_edge_length_log2();
+    let centers = all_lod_centers(lod0_center.0, config.num_lods);
+
+    let high_lod_boundary = config.clip_box_radius >> 1;
+
+    let top_lod = config.num_lods - 1;
+    let top_clip_box = Extent3i::from_min_and_shape(
+        centers[top_lod as usize] - Point3i::fill(config.clip_box_radius),
+        Point3i::fill(2 * config.clip_box_radius),
+    );
+    for lod_p in top_clip_box.iter_points() {
+        visit_clipmap_chunk(
+            chunk_log2,
+            &centers,
+            high_lod_boundary,
+            top_lod,
+            lod_p,
+            &mut active_rx,
+        );
+    }
+}
+
+/// Same as the octree traversal in `active_clipmap_lod_chunks`, except that every child exists. `lod_p` is the position of the
+/// chunk in chunk units at `lod`.
+fn visit_clipmap_chunk(
+    chunk_log2: i32,
+    centers: &[Point3i],
+    high_lod_boundary: i32,
+    lod: u8,
+    lod_p: Point3i,
+    active_rx: &mut impl FnMut(ChunkKey3),
+) {
+    if lod == 0 || offset_from_lod_center(lod, lod_p, centers) > high_lod_boundary {
+        // This chunk can be rendered at this level of detail.
+        active_rx(ChunkKey {
+            lod,
+            minimum: lod_p << chunk_log2,
+        });
+    } else {
+        // This chunk should be rendered with more detail.
+        for child_p in Extent3i::from_min_and_shape(lod_p << 1, Point3i::fill(2)).iter_points() {
+            visit_clipmap_chunk(
+                chunk_log2,
+                centers,
+                high_lod_boundary,
+                lod - 1,
+                child_p,
+                active_rx,
+            );
+        }
+    }
+}
+
 /// A notification that a chunk (at a particular level of detail) must be split or merged. This is usually the result of a
 /// camera movement.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -133,7 +201,7 @@ impl ClipMapUpdate3 {
         octree.visit_all_octants_in_preorder(&mut |node: &OctreeNode| {
             let octant = node.octant();
 
-            let lod = octant.exponent();
+            let lod = node.level();
             if lod >= self.num_lods || lod == 0 {
                 return VisitStatus::Continue;
             }
@@ -208,7 +276,7 @@ fn find_merge_or_split_descendants(
 ) -> Vec<ChunkKey3> {
     let mut matching_chunks = Vec::with_capacity(8);
     node.visit_all_octants_in_preorder(octree, &mut |node: &OctreeNode| {
-        let lod = node.octant().exponent();
+        let lod = node.level();
         let old_offset_from_center = get_offset_from_lod_center(node.octant(), centers);
         if lod == 0 || old_offset_from_center > high_lod_boundary {
             matching_chunks.push(octant_chunk_key(chunk_log2, node.octant()));
@@ -224,7 +292,11 @@ fn find_merge_or_split_descendants(
 
 fn get_offset_from_lod_center(octant: &Octant, centers: &[Point3i]) -> i32 {
     let lod = octant.exponent();
-    let lod_p = octant.minimum() >> lod;
+
+    offset_from_lod_center(lod, octant.minimum() >> lod, centers)
+}
+
+fn offset_from_lod_center(lod: u8, lod_p: Point3i, centers: &[Point3i]) -> i32 {
     let lod_center = centers[lod as usize];
 
     (lod_p - lod_center)
@@ -382,6 +454,27 @@ mod test {
         }
     }
 
+    #[test]
+    fn clipmap_lod_chunks_match_full_octree() {
+        let config = ClipMapConfig3::new(NUM_LODS, ChunkUnits(CLIP_BOX_RADIUS), CHUNK_SHAPE);
+
+        // This is exactly the clip box at LOD 1 for both centers.
+        let octree = OctreeSet::new_full(Extent3i::from_min_and_shape(
+            Point3i::fill(-4),
+            Point3i::fill(8),
+        ));
+
+        for lod0_center in [Point3i::ZERO, Point3i::fill(1)] {
+            let lod0_center = ChunkUnits(lod0_center);
+            let mut keys = SmallKeyHashSet::new();
+            clipmap_lod_chunks(&config, lod0_center, |key| {
+                assert!(keys.insert(key));
+            });
+
+            assert_eq!(keys, ActiveChunks::new(&config, &octree, lod0_center).keys);
+        }
+    }
+
     /// This just stores the state of active chunks so that we can compare a known correct "active set" with one that has been
     /// modified via any number of calls to `apply_update`.
     #[derive(Debug, Eq, PartialEq)]
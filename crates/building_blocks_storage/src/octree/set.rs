@@ -61,16 +61,23 @@ use crate::dev_prelude::*;
 use building_blocks_core::prelude::*;
 
 use core::ops::Deref;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
 
 /// A sparse set of voxel coordinates (3D integer points). Supports spatial queries.
 ///
-/// The octree is a cube shape and the edge lengths can only be a power of 2, at most 64.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+/// The octree is a cube shape and the edge lengths can only be a power of 2. By default, the leaves of the octree are single
+/// voxels, and the edge length can be at most 64. The leaves can also be made larger cubes of `2 ^ leaf_exponent` voxels. In
+/// that case, the set has block granularity: a leaf is occupied iff any of its voxels has been added, and the edge length can
+/// be at most `64 * 2 ^ leaf_exponent`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct OctreeSet {
     extent: Extent3i,
     power: u8,
+    #[cfg_attr(feature = "serde", serde(default))]
+    leaf_exponent: u8,
     root_exists: bool,
     // Save memory by using 2-byte location codes as hash map keys instead of 64-bit node pointers. The total memory usage can
     // be approximated as 4 bytes per node, assuming the hashbrown table has 1 byte of overhead per entry.
@@ -80,31 +87,42 @@ pub struct OctreeSet {
 impl OctreeSet {
     /// Make an empty set in the universe (domain) of `extent`.
     pub fn new_empty(extent: Extent3i) -> Self {
-        Self::new_without_nodes(extent, false)
+        Self::new_empty_with_leaf_exponent(extent, 0)
     }
 
     /// Make a full set in the universe (domain) of `extent`.
     pub fn new_full(extent: Extent3i) -> Self {
-        Self::new_without_nodes(extent, true)
+        Self::new_full_with_leaf_exponent(extent, 0)
     }
 
-    fn new_without_nodes(extent: Extent3i, root_exists: bool) -> Self {
-        let power = Self::check_extent(&extent);
+    /// Same as `new_empty`, but the leaves have edge length `2 ^ leaf_exponent`.
+    pub fn new_empty_with_leaf_exponent(extent: Extent3i, leaf_exponent: u8) -> Self {
+        Self::new_without_nodes(extent, leaf_exponent, false)
+    }
+
+    /// Same as `new_full`, but the leaves have edge length `2 ^ leaf_exponent`.
+    pub fn new_full_with_leaf_exponent(extent: Extent3i, leaf_exponent: u8) -> Self {
+        Self::new_without_nodes(extent, leaf_exponent, true)
+    }
+
+    fn new_without_nodes(extent: Extent3i, leaf_exponent: u8, root_exists: bool) -> Self {
+        let power = Self::check_extent(&extent, leaf_exponent);
 
         Self {
             power,
+            leaf_exponent,
             root_exists,
             extent,
             nodes: SmallKeyHashMap::default(),
         }
     }
 
-    fn check_extent(extent: &Extent3i) -> u8 {
+    fn check_extent(extent: &Extent3i, leaf_exponent: u8) -> u8 {
         assert!(extent.shape.dimensions_are_powers_of_2());
         assert!(extent.shape.is_cube());
         let power = extent.shape.x().trailing_zeros() as u8;
         // Constrained by 16-bit location code.
-        assert!(power > 0 && power <= 6);
+        assert!(power > leaf_exponent && power - leaf_exponent <= 6);
 
         power
     }
@@ -115,6 +133,44 @@ impl OctreeSet {
     /// trait). `extent` must be cube-shaped with edge length being a power of 2. For power `P` where edge length is `2^P`, we
     /// must have `0 < P <= 6`, because there is a maximum fixed depth of the octree.
     pub fn from_array3<A, T>(array: &A, extent: Extent3i) -> Self
+    where
+        A: IndexedArray<[i32; 3]> + GetUnchecked<Stride, Item = T>,
+        T: Clone + IsEmpty,
+    {
+        Self::from_array3_with_leaf_exponent(array, extent, 0)
+    }
+
+    /// Constructs an `OctreeSet` which contains all of the points in `extent` whose values at LOD0 of `map` satisfy
+    /// `is_solid`. Points in vacant chunks are not in the set, regardless of the ambient value. `extent` has the same
+    /// requirements as in `from_array3`.
+    pub fn from_chunk_map<T, Ch, Bldr, Store>(
+        map: &ChunkMap3<T, Bldr, Store>,
+        extent: Extent3i,
+        is_solid: impl Fn(&T) -> bool,
+    ) -> Self
+    where
+        T: Clone,
+        Ch: Chunk,
+        Ch::Array: ForEach<[i32; 3], Point3i, Item = T>,
+        Store: ChunkReadStorage<[i32; 3], Chunk = Ch>,
+    {
+        let mut solid = Array3x1::fill(extent, false);
+        for (_key, chunk) in map.chunks_overlapping(0, &extent) {
+            chunk.array().for_each(&extent, |p, value| {
+                *solid.get_mut(p) = is_solid(&value);
+            });
+        }
+
+        Self::from_array3(&solid, extent)
+    }
+
+    /// Same as `from_array3`, but the leaves have edge length `2 ^ leaf_exponent`. A leaf is occupied iff any of its voxels are
+    /// not empty. For power `P`, we must have `leaf_exponent < P <= leaf_exponent + 6`.
+    pub fn from_array3_with_leaf_exponent<A, T>(
+        array: &A,
+        extent: Extent3i,
+        leaf_exponent: u8,
+    ) -> Self
     where
         A: IndexedArray<[i32; 3]> + GetUnchecked<Stride, Item = T>,
         T: Clone + IsEmpty,
@@ -126,8 +182,9 @@ impl OctreeSet {
             extent
         );
 
-        let power = Self::check_extent(&extent);
+        let power = Self::check_extent(&extent, leaf_exponent);
         let edge_length = 1 << power;
+        let leaf_edge_length = 1 << leaf_exponent;
 
         // These are the corners of the root octant, in local coordinates.
         let mut corner_offsets = [Local(Point3i::ZERO); 8];
@@ -140,6 +197,14 @@ impl OctreeSet {
         // Convert into strides for indexing efficiency.
         let mut corner_strides = [Stride(0); 8];
         array.strides_from_local_points(&corner_offsets, &mut corner_strides);
+        // Also the offsets of every voxel in a leaf.
+        let leaf_offsets: Vec<_> =
+            Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(leaf_edge_length))
+                .iter_points()
+                .map(Local)
+                .collect();
+        let mut leaf_strides = vec![Stride(0); leaf_offsets.len()];
+        array.strides_from_local_points(&leaf_offsets, &mut leaf_strides);
 
         let mut nodes = SmallKeyHashMap::default();
         let min_local = Local(extent.minimum - array.extent().minimum);
@@ -149,7 +214,9 @@ impl OctreeSet {
             root_code,
             root_minimum,
             edge_length,
+            leaf_edge_length,
             &corner_strides,
+            &leaf_strides,
             array,
             &mut nodes,
         );
@@ -157,6 +224,7 @@ impl OctreeSet {
         Self {
             extent,
             power,
+            leaf_exponent,
             root_exists,
             nodes,
         }
@@ -166,7 +234,9 @@ impl OctreeSet {
         code: LocationCode,
         minimum: Stride,
         edge_length: i32,
+        leaf_edge_length: i32,
         corner_strides: &[Stride],
+        leaf_strides: &[Stride],
         array: &A,
         nodes: &mut SmallKeyHashMap<LocationCode, ChildBitMask>,
     ) -> (bool, bool)
@@ -174,10 +244,12 @@ impl OctreeSet {
         A: IndexedArray<[i32; 3]> + GetUnchecked<Stride, Item = T>,
         T: Clone + IsEmpty,
     {
-        // Base case where the octant is a single voxel. The `code` is invalid and unnecessary in this case; we avoid using it
-        // by returning early.
-        if edge_length == 1 {
-            let exists = unsafe { !array.get_unchecked(minimum).is_empty() };
+        // Base case where the octant is a leaf. The `code` is invalid and unnecessary in this case; we avoid using it by
+        // returning early.
+        if edge_length == leaf_edge_length {
+            let exists = leaf_strides
+                .iter()
+                .any(|&offset| unsafe { !array.get_unchecked(minimum + offset).is_empty() });
             return (exists, exists);
         }
 
@@ -199,7 +271,9 @@ impl OctreeSet {
                 octant_code,
                 octant_min,
                 half_edge_length,
+                leaf_edge_length,
                 &octant_corner_strides,
+                leaf_strides,
                 array,
                 nodes,
             );
@@ -216,6 +290,83 @@ impl OctreeSet {
         (exists, all_children_full)
     }
 
+    /// Constructs an `OctreeSet` which contains exactly `points`. `extent` must satisfy the same constraints as in
+    /// `from_array3`, and it must contain every point. Duplicate points are allowed.
+    ///
+    /// This is much faster than adding the points one at a time. The points are sorted in Morton order so that every octant
+    /// is a contiguous range of points, and then the tree is built bottom-up in a single pass.
+    pub fn from_points(extent: Extent3i, points: &[Point3i]) -> Self {
+        let power = Self::check_extent(&extent, 0);
+
+        let mut local_points: Vec<Point3i> = points
+            .iter()
+            .map(|&p| {
+                assert!(extent.contains(p), "{:?} does not contain {:?}", extent, p);
+                p - extent.minimum
+            })
+            .collect();
+        local_points.sort_unstable_by_key(|&p| Morton3::from(p));
+        local_points.dedup();
+
+        let mut nodes = SmallKeyHashMap::default();
+        let (root_exists, _full) =
+            Self::partition_points(LocationCode::ROOT, power, &local_points, &mut nodes);
+
+        Self {
+            extent,
+            power,
+            leaf_exponent: 0,
+            root_exists,
+            nodes,
+        }
+    }
+
+    /// `points` must be unique, sorted in Morton order, and all contained in the octant at `code`, which has edge length
+    /// `2 ^ level`. Returns `(exists, is_full)`.
+    fn partition_points(
+        code: LocationCode,
+        level: u8,
+        points: &[Point3i],
+        nodes: &mut SmallKeyHashMap<LocationCode, ChildBitMask>,
+    ) -> (bool, bool) {
+        if points.is_empty() {
+            return (false, false);
+        }
+        // Since the points are unique, this is the only way to fill the octant. This also covers the single voxel case.
+        if points.len() == 1 << (3 * level as usize) {
+            return (true, true);
+        }
+
+        let child_level = level - 1;
+        let child_index = |p: &Point3i| {
+            ((p.x() >> child_level) & 1)
+                | (((p.y() >> child_level) & 1) << 1)
+                | (((p.z() >> child_level) & 1) << 2)
+        };
+
+        let mut child_bitmask = 0;
+        let mut remaining_points = points;
+        let extended_code = code.extend();
+        for octant in 0..8 {
+            let split = remaining_points.partition_point(|p| child_index(p) <= octant);
+            let (octant_points, rest) = remaining_points.split_at(split);
+            remaining_points = rest;
+
+            let (child_exists, _child_full) = Self::partition_points(
+                extended_code.with_lowest_octant(octant as u16),
+                child_level,
+                octant_points,
+                nodes,
+            );
+            child_bitmask |= (child_exists as u8) << octant;
+        }
+
+        // Not all children are full, or else we would have returned early.
+        nodes.insert(code, child_bitmask);
+
+        (true, false)
+    }
+
     /// The exponent P such that `self.edge_length() = 2 ^ P`.
     pub fn power(&self) -> u8 {
         self.power
@@ -226,6 +377,79 @@ impl OctreeSet {
         1 << self.power
     }
 
+    /// The exponent L such that `self.leaf_edge_length() = 2 ^ L`.
+    pub fn leaf_exponent(&self) -> u8 {
+        self.leaf_exponent
+    }
+
+    /// The length of any edge of a leaf octant, i.e. the granularity of the set.
+    pub fn leaf_edge_length(&self) -> i32 {
+        1 << self.leaf_exponent
+    }
+
+    fn is_leaf_octant(&self, octant: &Octant) -> bool {
+        octant.exponent() == self.leaf_exponent
+    }
+
+    /// Returns `true` iff `point` is in the set. If the leaves are larger than a single voxel, this is `true` for every point
+    /// in an occupied leaf.
+    pub fn contains(&self, point: Point3i) -> bool {
+        if !self.root_exists || !self.extent.contains(point) {
+            return false;
+        }
+
+        let mut code = LocationCode::ROOT;
+        let mut octant = self.octant();
+        loop {
+            if self.is_leaf_octant(&octant) {
+                return true;
+            }
+            let child_bitmask = if let Some(&child_bitmask) = self.nodes.get(&code) {
+                child_bitmask
+            } else {
+                // Implicit leaf node.
+                return true;
+            };
+
+            let half_edge_length = octant.edge_length() >> 1;
+            let offset = point - octant.minimum();
+            let child_index = (offset.x() >= half_edge_length) as u8
+                | ((offset.y() >= half_edge_length) as u8) << 1
+                | ((offset.z() >= half_edge_length) as u8) << 2;
+            if child_bitmask & (1 << child_index) == 0 {
+                return false;
+            }
+
+            code = code.extend().with_lowest_octant(child_index as u16);
+            octant = octant.child(child_index);
+        }
+    }
+
+    /// Returns `true` iff `self` and `other` contain the same points within `extent`. Points outside of `extent` are ignored.
+    /// This returns as soon as a point in only one of the sets is found.
+    pub fn equals_in_extent(&self, other: &Self, extent: &Extent3i) -> bool {
+        // Points outside of both octree extents aren't contained in either set, so only the bounding extent of the two
+        // intersections needs to be checked.
+        let self_part = extent.intersection(&self.extent);
+        let other_part = extent.intersection(&other.extent);
+        let search_extent = if self_part.is_empty() {
+            other_part
+        } else if other_part.is_empty() {
+            self_part
+        } else {
+            Extent3i::from_min_and_lub(
+                self_part.minimum.meet(other_part.minimum),
+                self_part
+                    .least_upper_bound()
+                    .join(other_part.least_upper_bound()),
+            )
+        };
+
+        search_extent
+            .iter_points()
+            .all(|p| self.contains(p) == other.contains(p))
+    }
+
     /// The entire octant spanned by the octree.
     pub fn octant(&self) -> OctreeOctant {
         OctreeOctant(Octant::new_unchecked(
@@ -335,7 +559,11 @@ impl OctreeSet {
     ) -> VisitStatus {
         self._visit_branches_and_fat_leaves_in_preorder(code, octant, &mut |node: &OctreeNode| {
             if node.is_full() {
-                node.octant.visit_self_and_descendants_in_preorder(visitor)
+                node.octant
+                    .visit_self_and_descendants_down_to_exponent_in_preorder(
+                        self.leaf_exponent,
+                        visitor,
+                    )
             } else {
                 visitor.visit_octant(node)
             }
@@ -355,7 +583,11 @@ impl OctreeSet {
             predicate,
             &mut |node: &OctreeNode| {
                 if node.is_full() {
-                    node.octant.visit_self_and_descendants_in_postorder(visitor)
+                    node.octant
+                        .visit_self_and_descendants_down_to_exponent_in_postorder(
+                            self.leaf_exponent,
+                            visitor,
+                        )
                 } else {
                     visitor.visit_octant(node)
                 }
@@ -403,8 +635,8 @@ impl OctreeSet {
     ) -> VisitStatus {
         // Precondition: code exists.
 
-        // Base case where the octant is a single leaf voxel.
-        if octant.is_single_voxel() {
+        // Base case where the octant is a leaf.
+        if self.is_leaf_octant(&octant) {
             return visitor.visit_octant(&OctreeNode::leaf(octant));
         }
 
@@ -463,8 +695,8 @@ impl OctreeSet {
             }
         };
 
-        // Base case where the octant is a single leaf voxel.
-        if octant.is_single_voxel() {
+        // Base case where the octant is a leaf.
+        if self.is_leaf_octant(&octant) {
             return handle_leaf(octant);
         }
 
@@ -546,7 +778,7 @@ impl OctreeSet {
 
         let child_octant = parent.octant.child(child_index);
 
-        if child_octant.is_single_voxel() {
+        if self.is_leaf_octant(&child_octant) {
             // The child is a leaf, so we don't need to extend the code or look for a child bitmask.
             return Some(OctreeNode {
                 code: LocationCode::LEAF,
@@ -589,18 +821,19 @@ impl OctreeSet {
         already_exists: bool,
         add_extent: &Extent3i,
     ) -> (bool, bool) {
-        if octant.is_single_voxel() {
-            let intersects = add_extent.contains(octant.minimum());
-            return (intersects, intersects || already_exists);
-        }
-
         let octant_extent = Extent3i::from(octant.0);
         let octant_intersection = add_extent.intersection(&octant_extent);
 
+        if self.is_leaf_octant(&octant) {
+            // The whole leaf is occupied if any of its points are added.
+            let intersects = !octant_intersection.is_empty();
+            return (intersects, intersects || already_exists);
+        }
+
         if octant_extent == octant_intersection {
             // The octant is a subset of the extent being added, so we can make it an implicit leaf.
             if already_exists {
-                self.remove_subtree(&code, octant.exponent());
+                self.remove_subtree(&code, self.tree_level(&octant));
             }
             return (true, true);
         }
@@ -637,7 +870,7 @@ impl OctreeSet {
         if child_bitmask != 0 && !all_children_full {
             self.nodes.insert(code, child_bitmask);
         } else if already_had_bitmask && all_children_full {
-            self.remove_subtree(&code, octant.exponent());
+            self.remove_subtree(&code, self.tree_level(&octant));
         }
 
         (true, all_children_full)
@@ -659,16 +892,17 @@ impl OctreeSet {
     ) -> bool {
         // Precondition: octant is not already empty.
 
-        if octant.is_single_voxel() {
-            return !sub_extent.contains(octant.minimum());
-        }
-
         let octant_extent = Extent3i::from(octant.0);
         let octant_intersection = sub_extent.intersection(&octant_extent);
 
+        if self.is_leaf_octant(&octant) {
+            // We don't know which points of the leaf were added, so it's only removed when all of its points are subtracted.
+            return octant_extent != octant_intersection;
+        }
+
         if octant_extent == octant_intersection {
             // The octant is a subset of the extent being subtracted, so we can remove the entire subtree.
-            self.remove_subtree(&code, octant.exponent());
+            self.remove_subtree(&code, self.tree_level(&octant));
             return false;
         }
 
@@ -717,6 +951,11 @@ impl OctreeSet {
         }
     }
 
+    /// The number of levels between `octant` and the leaves.
+    fn tree_level(&self, octant: &Octant) -> u8 {
+        octant.exponent() - self.leaf_exponent
+    }
+
     fn remove_subtree(&mut self, code: &LocationCode, level: u8) {
         if let Some(child_bitmask) = self.nodes.remove(code) {
             if level == 1 {
@@ -752,6 +991,98 @@ impl OctreeSet {
 
         points
     }
+
+    /// The number of voxels in the set, i.e. the sum of the volumes of all full octants.
+    pub fn occupied_volume(&self) -> u64 {
+        let mut volume = 0;
+        self.visit_branches_and_fat_leaves_in_preorder(&mut |node: &OctreeNode| {
+            if node.is_full() {
+                volume += (node.octant().edge_length() as u64).pow(3);
+            }
+
+            VisitStatus::Continue
+        });
+
+        volume
+    }
+
+    /// The number of voxel faces that are not shared with another voxel in the set, which is the surface area in units of
+    /// voxel face area. Voxels outside of the octree's extent are considered empty.
+    ///
+    /// Only the voxels on the boundary of each full octant need to be checked for an empty neighbor.
+    pub fn surface_area(&self) -> u64 {
+        let mut area = 0;
+        self.visit_branches_and_fat_leaves_in_preorder(&mut |node: &OctreeNode| {
+            if !node.is_full() {
+                return VisitStatus::Continue;
+            }
+
+            let octant_extent = Extent3i::from(*node.octant());
+            let max = octant_extent.max();
+            for axis in 0..3 {
+                let mut unit = Point3i::ZERO;
+                unit.0[axis] = 1;
+
+                let mut min_face = octant_extent;
+                min_face.shape.0[axis] = 1;
+                let mut max_face = min_face;
+                max_face.minimum.0[axis] = max.at(axis);
+
+                for p in min_face.iter_points() {
+                    if !self.contains(p - unit) {
+                        area += 1;
+                    }
+                }
+                for p in max_face.iter_points() {
+                    if !self.contains(p + unit) {
+                        area += 1;
+                    }
+                }
+            }
+
+            VisitStatus::Continue
+        });
+
+        area
+    }
+
+    /// Iterates over the extents of all fat leaves in pre-order. Every fat leaf is a full octant at least as large as a leaf, and
+    /// the extents are disjoint, so their union is exactly the set of occupied voxels.
+    pub fn iter_leaf_extents(&self) -> impl '_ + Iterator<Item = Extent3i> {
+        let mut stack = Vec::new();
+        if self.root_exists {
+            stack.push((LocationCode::ROOT, self.octant()));
+        }
+
+        std::iter::from_fn(move || {
+            while let Some((code, octant)) = stack.pop() {
+                let child_bitmask = if self.is_leaf_octant(&octant) {
+                    None
+                } else {
+                    self.nodes.get(&code)
+                };
+                let child_bitmask = if let Some(&child_bitmask) = child_bitmask {
+                    child_bitmask
+                } else {
+                    // Either a leaf octant or an implicit leaf node.
+                    return Some(Extent3i::from(*octant));
+                };
+
+                // Push in reverse so children are popped in order.
+                let extended_code = code.extend();
+                for child_index in (0..8).rev() {
+                    if (child_bitmask & (1 << child_index)) != 0 {
+                        stack.push((
+                            extended_code.with_lowest_octant(child_index as u16),
+                            octant.child(child_index),
+                        ));
+                    }
+                }
+            }
+
+            None
+        })
+    }
 }
 
 /// Represents a single non-empty octant in the octree. Can be used for manual traversal by calling `OctreeSet::get_child`.
@@ -778,6 +1109,15 @@ impl OctreeNode {
         self.child_bitmask
     }
 
+    /// The level of this node in the octree, where single-voxel leaves are at level 0 and the root is at
+    /// `OctreeSet::power()`. The edge length of the node's octant is `1 << level`.
+    ///
+    /// When the octree belongs to an `OctreeChunkIndex`, this is the LOD of the chunk that covers this node's octant.
+    #[inline]
+    pub fn level(&self) -> u8 {
+        self.octant.exponent()
+    }
+
     /// Similar to `OctreeSet::visit_branches_and_fat_leaves`, but only for the subtree at this `OctreeNode`.
     #[inline]
     pub fn visit_branches_and_fat_leaves_in_preorder(
@@ -820,7 +1160,11 @@ impl OctreeNode {
         visitor: &mut impl OctreeVisitor,
     ) -> VisitStatus {
         if self.is_full() {
-            self.octant.visit_self_and_descendants_in_preorder(visitor)
+            self.octant
+                .visit_self_and_descendants_down_to_exponent_in_preorder(
+                    octree.leaf_exponent,
+                    visitor,
+                )
         } else {
             octree._visit_all_octants_in_preorder(self.code, self.octant, visitor)
         }
@@ -835,7 +1179,11 @@ impl OctreeNode {
         visitor: &mut impl OctreeVisitor,
     ) -> VisitStatus {
         if self.is_full() {
-            self.octant.visit_self_and_descendants_in_postorder(visitor)
+            self.octant
+                .visit_self_and_descendants_down_to_exponent_in_postorder(
+                    octree.leaf_exponent,
+                    visitor,
+                )
         } else {
             octree._visit_all_octants_in_postorder(self.code, self.octant, predicate, visitor)
         }
@@ -877,7 +1225,8 @@ const FULL_CHILD_BIT_MASK: ChildBitMask = 0xFF;
 /// level N-5:
 ///   loc = 0b1000000000000000, ...
 /// ```
-#[derive(Clone, Copy, Deserialize, Hash, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 struct LocationCode(u16);
 
 impl std::fmt::Debug for LocationCode {
@@ -929,17 +1278,27 @@ impl OctreeOctant {
     pub fn visit_self_and_descendants_in_preorder(
         self,
         visitor: &mut impl OctreeVisitor,
+    ) -> VisitStatus {
+        self.visit_self_and_descendants_down_to_exponent_in_preorder(0, visitor)
+    }
+
+    /// Same as `visit_self_and_descendants_in_preorder`, but only visits descendants with edge length at least
+    /// `2 ^ min_exponent`.
+    pub fn visit_self_and_descendants_down_to_exponent_in_preorder(
+        self,
+        min_exponent: u8,
+        visitor: &mut impl OctreeVisitor,
     ) -> VisitStatus {
         let status = visitor.visit_octant(&OctreeNode::leaf(self));
 
-        if self.is_single_voxel() || status != VisitStatus::Continue {
+        if self.exponent() <= min_exponent || status != VisitStatus::Continue {
             return status;
         }
 
         for child_index in 0..8 {
             match self
                 .child(child_index)
-                .visit_self_and_descendants_in_preorder(visitor)
+                .visit_self_and_descendants_down_to_exponent_in_preorder(min_exponent, visitor)
             {
                 VisitStatus::Continue => (),
                 VisitStatus::ExitEarly => return VisitStatus::ExitEarly,
@@ -955,14 +1314,24 @@ impl OctreeOctant {
         self,
         visitor: &mut impl OctreeVisitor,
     ) -> VisitStatus {
-        if self.is_single_voxel() {
+        self.visit_self_and_descendants_down_to_exponent_in_postorder(0, visitor)
+    }
+
+    /// Same as `visit_self_and_descendants_in_postorder`, but only visits descendants with edge length at least
+    /// `2 ^ min_exponent`.
+    pub fn visit_self_and_descendants_down_to_exponent_in_postorder(
+        self,
+        min_exponent: u8,
+        visitor: &mut impl OctreeVisitor,
+    ) -> VisitStatus {
+        if self.exponent() <= min_exponent {
             return visitor.visit_octant(&OctreeNode::leaf(self));
         }
 
         for child_index in 0..8 {
             match self
                 .child(child_index)
-                .visit_self_and_descendants_in_postorder(visitor)
+                .visit_self_and_descendants_down_to_exponent_in_postorder(min_exponent, visitor)
             {
                 VisitStatus::Continue => (),
                 VisitStatus::ExitEarly => return VisitStatus::ExitEarly,
@@ -1040,6 +1409,51 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn equals_in_extent_ignores_differences_outside_of_extent() {
+        let domain = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+        let inner = Extent3i::from_min_and_shape(Point3i::fill(4), Point3i::fill(8));
+
+        let full = OctreeSet::new_full(domain);
+        let mut edited = OctreeSet::new_full(domain);
+        edited.subtract_extent(&Extent3i::from_min_and_shape(
+            Point3i::ZERO,
+            Point3i::fill(2),
+        ));
+        edited.subtract_extent(&Extent3i::from_min_and_shape(
+            PointN([14, 0, 0]),
+            Point3i::fill(2),
+        ));
+
+        assert!(full.equals_in_extent(&edited, &inner));
+        assert!(edited.equals_in_extent(&full, &inner));
+        assert!(!full.equals_in_extent(&edited, &domain));
+
+        // Differences inside of the extent are found.
+        edited.subtract_extent(&Extent3i::from_min_and_shape(
+            Point3i::fill(8),
+            Point3i::ONES,
+        ));
+        assert!(!full.equals_in_extent(&edited, &inner));
+    }
+
+    #[test]
+    fn from_chunk_map_contains_solid_voxels() {
+        let builder = ChunkMapBuilder3x1::new(Point3i::fill(16), 0);
+        let mut map = builder.build_with_hash_map_storage();
+        let solid_box = Extent3i::from_min_and_shape(Point3i::fill(4), Point3i::fill(8));
+        let water = Extent3i::from_min_and_shape(PointN([16, 0, 0]), Point3i::fill(8));
+        map.fill_extent(0, &solid_box, 1);
+        map.fill_extent(0, &water, 2);
+
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32));
+        let octree = OctreeSet::from_chunk_map(&map, extent, |&value| value == 1);
+
+        for p in extent.iter_points() {
+            assert_eq!(octree.contains(p), solid_box.contains(p), "p = {:?}", p);
+        }
+    }
+
     #[test]
     fn subtract_extents() {
         let domain = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(32));
@@ -1189,6 +1603,191 @@ mod tests {
         assert_eq!(non_empty_voxels, octant_voxels);
     }
 
+    #[test]
+    fn from_points_matches_adding_points_individually() {
+        let domain = Extent3i::from_min_and_shape(PointN([-16, 0, 16]), Point3i::fill(32));
+
+        let mut rng = rand::thread_rng();
+        let mut points: Vec<Point3i> = (0..300)
+            .map(|_| {
+                domain.minimum
+                    + PointN([
+                        rng.gen_range(0..32),
+                        rng.gen_range(0..32),
+                        rng.gen_range(0..32),
+                    ])
+            })
+            .collect();
+        // Include some full octants so that subtrees get collapsed, and some duplicates.
+        let full_octant =
+            Extent3i::from_min_and_shape(domain.minimum + Point3i::fill(8), Point3i::fill(4));
+        points.extend(full_octant.iter_points());
+        points.extend(full_octant.iter_points().take(10));
+
+        let mut expected = OctreeSet::new_empty(domain);
+        for &p in points.iter() {
+            expected.add_extent(&Extent3i::from_min_and_shape(p, Point3i::ONES));
+        }
+
+        let set = OctreeSet::from_points(domain, &points);
+        set.assert_all_nodes_reachable();
+        assert_eq!(set, expected);
+
+        assert_eq!(
+            OctreeSet::from_points(domain, &[]),
+            OctreeSet::new_empty(domain)
+        );
+        let all_points: Vec<_> = domain.iter_points().collect();
+        assert_eq!(
+            OctreeSet::from_points(domain, &all_points),
+            OctreeSet::new_full(domain)
+        );
+    }
+
+    #[test]
+    fn membership_with_two_voxel_leaves() {
+        let domain = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+        let mut voxels = Array3x1::fill(domain, Voxel(false));
+        let set_points = [PointN([1, 1, 1]), PointN([6, 2, 10]), PointN([15, 15, 15])];
+        for &p in set_points.iter() {
+            *voxels.get_mut(p) = Voxel(true);
+        }
+
+        let octree = OctreeSet::from_array3_with_leaf_exponent(&voxels, domain, 1);
+        octree.assert_all_nodes_reachable();
+        assert_eq!(octree.leaf_edge_length(), 2);
+
+        let occupied_blocks: HashSet<Point3i> = set_points.iter().map(|&p| p >> 1).collect();
+        for p in domain.iter_points() {
+            assert_eq!(octree.contains(p), occupied_blocks.contains(&(p >> 1)));
+        }
+
+        let mut leaf_count = 0;
+        octree.visit_all_octants_in_preorder(&mut |node: &OctreeNode| {
+            assert!(node.level() >= 1);
+            if node.level() == 1 {
+                leaf_count += 1;
+            }
+
+            VisitStatus::Continue
+        });
+        assert_eq!(leaf_count, occupied_blocks.len());
+
+        // Adding a single voxel occupies its whole block, and subtracting only part of a block leaves it occupied.
+        let mut octree = octree;
+        octree.add_extent(&Extent3i::from_min_and_shape(
+            PointN([8, 8, 8]),
+            Point3i::ONES,
+        ));
+        assert!(octree.contains(PointN([9, 9, 9])));
+        octree.subtract_extent(&Extent3i::from_min_and_shape(
+            PointN([8, 8, 8]),
+            Point3i::ONES,
+        ));
+        assert!(octree.contains(PointN([9, 9, 9])));
+        octree.subtract_extent(&Extent3i::from_min_and_shape(
+            PointN([8, 8, 8]),
+            Point3i::fill(2),
+        ));
+        assert!(!octree.contains(PointN([9, 9, 9])));
+        octree.assert_all_nodes_reachable();
+    }
+
+    #[test]
+    fn node_level_matches_octant_size() {
+        let voxels = random_voxels();
+        let octree = OctreeSet::from_array3(&voxels, *voxels.extent());
+
+        let mut visited_root = false;
+        octree.visit_all_octants_in_preorder(&mut |node: &OctreeNode| {
+            assert_eq!(
+                Extent3i::from(*node.octant()).shape,
+                Point3i::fill(1 << node.level())
+            );
+            if node.level() == octree.power() {
+                visited_root = true;
+            }
+            if node.octant().is_single_voxel() {
+                assert_eq!(node.level(), 0);
+            }
+
+            VisitStatus::Continue
+        });
+        assert!(visited_root);
+    }
+
+    #[test]
+    fn solid_cube_volume_and_surface_area() {
+        let domain = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+
+        for &n in [1, 4, 5].iter() {
+            let mut octree = OctreeSet::new_empty(domain);
+            octree.add_extent(&Extent3i::from_min_and_shape(
+                Point3i::fill(3),
+                Point3i::fill(n),
+            ));
+
+            let n = n as u64;
+            assert_eq!(octree.occupied_volume(), n * n * n);
+            assert_eq!(octree.surface_area(), 6 * n * n);
+        }
+
+        // The boundary of the domain is exposed.
+        let octree = OctreeSet::new_full(domain);
+        assert_eq!(octree.occupied_volume(), 16 * 16 * 16);
+        assert_eq!(octree.surface_area(), 6 * 16 * 16);
+
+        let octree = OctreeSet::new_empty(domain);
+        assert_eq!(octree.occupied_volume(), 0);
+        assert_eq!(octree.surface_area(), 0);
+    }
+
+    #[test]
+    fn leaf_extents_cover_occupied_voxels() {
+        let domain = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(16));
+
+        for &leaf_exponent in [0, 2].iter() {
+            let mut octree = OctreeSet::new_empty_with_leaf_exponent(domain, leaf_exponent);
+            // A full octant, which should be yielded as a single extent, and a block that only partially overlaps leaves.
+            octree.add_extent(&Extent3i::from_min_and_shape(
+                Point3i::fill(8),
+                Point3i::fill(8),
+            ));
+            octree.add_extent(&Extent3i::from_min_and_shape(
+                PointN([1, 2, 3]),
+                PointN([3, 1, 2]),
+            ));
+
+            let extents: Vec<_> = octree.iter_leaf_extents().collect();
+            assert!(extents.contains(&Extent3i::from_min_and_shape(
+                Point3i::fill(8),
+                Point3i::fill(8)
+            )));
+
+            let leaf_shape = Point3i::fill(octree.leaf_edge_length());
+            let mut covered = HashSet::new();
+            for extent in extents.iter() {
+                assert!(extent.shape >= leaf_shape);
+                assert_eq!(extent.minimum % leaf_shape, Point3i::ZERO);
+                for p in extent.iter_points() {
+                    // Leaf extents are disjoint.
+                    assert!(covered.insert(p));
+                }
+            }
+            for p in domain.iter_points() {
+                assert_eq!(covered.contains(&p), octree.contains(p), "p = {:?}", p);
+            }
+        }
+
+        assert_eq!(OctreeSet::new_empty(domain).iter_leaf_extents().count(), 0);
+        assert_eq!(
+            OctreeSet::new_full(domain)
+                .iter_leaf_extents()
+                .collect::<Vec<_>>(),
+            vec![domain]
+        );
+    }
+
     fn random_voxels() -> Array3x1<Voxel> {
         let mut rng = rand::thread_rng();
         let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(64));
@@ -1208,7 +1807,7 @@ mod tests {
     impl OctreeSet {
         fn assert_all_nodes_reachable(&self) {
             let num_reachable_nodes = if self.root_exists {
-                self.count_nodes(LocationCode::ROOT, self.power())
+                self.count_nodes(LocationCode::ROOT, self.power() - self.leaf_exponent())
             } else {
                 0
             };
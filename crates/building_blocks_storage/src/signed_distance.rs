@@ -1,4 +1,12 @@
+use crate::dev_prelude::{
+    Array3x1, ArrayNx1, FixedEndian, ForEach, Get, IndexedArray, Local, Stride,
+};
+
+use building_blocks_core::prelude::*;
+
 use bytemuck::{Pod, Zeroable};
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 pub trait SignedDistance: Into<f32> {
@@ -13,10 +21,12 @@ impl SignedDistance for f32 {
 }
 
 /// A signed distance value in the range `[-1.0, 1.0]` with 8 bits of precision.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Sd8(pub i8);
 /// A signed distance value in the range `[-1.0, 1.0]` with 16 bits of precision.
-#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Sd16(pub i16);
 
 unsafe impl Zeroable for Sd8 {}
@@ -25,6 +35,20 @@ unsafe impl Pod for Sd8 {}
 unsafe impl Zeroable for Sd16 {}
 unsafe impl Pod for Sd16 {}
 
+impl FixedEndian for Sd8 {
+    #[inline]
+    fn to_le(self) -> Self {
+        self
+    }
+}
+
+impl FixedEndian for Sd16 {
+    #[inline]
+    fn to_le(self) -> Self {
+        Sd16(self.0.to_le())
+    }
+}
+
 impl Sd8 {
     pub const RESOLUTION: f32 = std::i8::MAX as f32;
     pub const PRECISION: f32 = 1.0 / Self::RESOLUTION;
@@ -77,6 +101,287 @@ impl SignedDistance for Sd16 {
     }
 }
 
+impl From<Sd16> for Sd8 {
+    /// Rounds to the nearest representable distance.
+    #[inline]
+    fn from(s: Sd16) -> Self {
+        Sd8((f32::from(s) * Sd8::RESOLUTION).round() as i8)
+    }
+}
+impl From<Sd8> for Sd16 {
+    /// Rounds to the nearest representable distance.
+    #[inline]
+    fn from(s: Sd8) -> Self {
+        Sd16((f32::from(s) * Sd16::RESOLUTION).round() as i16)
+    }
+}
+
+impl<N, T, Store> ArrayNx1<N, T, Store>
+where
+    PointN<N>: IntegerPoint<N>,
+    T: SignedDistance + Copy,
+    Store: Deref<Target = [T]>,
+{
+    /// Creates a new array by converting every signed distance to type `S`, e.g. from `Sd16` to `Sd8`. Each distance is
+    /// represented as closely as the precision of `S` allows.
+    pub fn convert_sdf_precision<S>(&self) -> ArrayNx1<N, S>
+    where
+        S: SignedDistance + From<T>,
+    {
+        let values: Vec<S> = self
+            .channels()
+            .store()
+            .iter()
+            .map(|&d| S::from(d))
+            .collect();
+
+        ArrayNx1::new_one_channel(*self.extent(), values)
+    }
+}
+
+/// Uniformly dilates (positive `delta`) or erodes (negative `delta`) the solid represented by `sdf` by subtracting `delta`
+/// from every distance.
+///
+/// Quantized distances like `Sd8` are dequantized before subtracting `delta` and then requantized, so the result saturates at
+/// the limits of the representable range.
+pub fn offset<N, T, Store>(sdf: &mut ArrayNx1<N, T, Store>, delta: f32)
+where
+    T: SignedDistance + From<f32> + Copy,
+    Store: DerefMut<Target = [T]>,
+{
+    for d in sdf.channels_mut().store_mut().iter_mut() {
+        let distance: f32 = (*d).into();
+        *d = T::from(distance - delta);
+    }
+}
+
+/// Samples `sdf` at the real-valued position `p` by trilinearly interpolating the dequantized distances of the 8 surrounding
+/// voxels.
+///
+/// Like the surface extraction algorithms, this treats the value of voxel `v` as the distance at the integer point `v`, so
+/// sampling exactly at an integer point returns that voxel's value. Positions outside of the array extent are clamped to the
+/// nearest point inside of it.
+///
+/// # Panics
+/// If the extent of `sdf` is empty.
+pub fn sample_trilinear(sdf: &Array3x1<Sd8>, p: Point3f) -> f32 {
+    let extent = sdf.extent();
+    assert!(!extent.is_empty(), "Can't sample an empty SDF");
+
+    let min = extent.minimum;
+    let max = extent.max();
+    let min_f = Point3f::from(min);
+    let max_f = Point3f::from(max);
+    let p = p.join(min_f).meet(max_f);
+
+    let base = p.floor_int();
+    let t = p - Point3f::from(base);
+    let next = (base + Point3i::ONES).meet(max);
+
+    // Reads the corner that is offset by 0 or 1 along each axis.
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let pick = |i: usize, d: i32| if d == 0 { base.at(i) } else { next.at(i) };
+
+        f32::from(sdf.get(PointN([pick(0, dx), pick(1, dy), pick(2, dz)])))
+    };
+    let lerp = |a: f32, b: f32, t: f32| a + t * (b - a);
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), t.x());
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), t.x());
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), t.x());
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), t.x());
+
+    lerp(lerp(x00, x10, t.y()), lerp(x01, x11, t.y()), t.z())
+}
+
+/// Colors every voxel of `extent` by the direction of the gradient of `sdf`, for visualizing an SDF, e.g. as a 3D texture.
+///
+/// Only voxels on the surface, i.e. those with a face neighbor of the opposite sign, are colored. The gradient is estimated
+/// with central differences of the dequantized distances (one-sided at the boundary of `sdf`), normalized, and mapped from
+/// `[-1, 1]` to RGB with `n * 0.5 + 0.5`. All other voxels, including those outside of `sdf`, are black.
+pub fn debug_normal_colors(sdf: &Array3x1<Sd8>, extent: &Extent3i) -> Array3x1<[u8; 3]> {
+    let sdf_extent = *sdf.extent();
+    let min = sdf_extent.minimum;
+    let max = sdf_extent.max();
+    let basis = Point3i::basis();
+    let face_offsets = Point3i::von_neumann_offsets();
+
+    Array3x1::fill_with(*extent, |p: Point3i| {
+        if !sdf_extent.contains(p) {
+            return [0; 3];
+        }
+
+        let is_negative = sdf.get(p).is_negative();
+        let on_surface = face_offsets.iter().any(|&offset| {
+            let n = p + offset;
+
+            sdf_extent.contains(n) && sdf.get(n).is_negative() != is_negative
+        });
+        if !on_surface {
+            return [0; 3];
+        }
+
+        let distance = |q: Point3i| f32::from(sdf.get(q.join(min).meet(max)));
+        let mut gradient = Point3f::ZERO;
+        for (axis, &e) in basis.iter().enumerate() {
+            gradient.0[axis] = distance(p + e) - distance(p - e);
+        }
+        let norm = gradient.norm();
+        if norm == 0.0 {
+            return [0; 3];
+        }
+        let normal = gradient / norm;
+
+        let to_color = |c: f32| ((c * 0.5 + 0.5) * 255.0).round() as u8;
+
+        [
+            to_color(normal.x()),
+            to_color(normal.y()),
+            to_color(normal.z()),
+        ]
+    })
+}
+
+/// Rebuilds `sdf` so that every value is the signed Euclidean distance, in voxel units, to the surface where the original
+/// values cross zero. This is useful after operations like CSG, which only keep the distances correct near the surface.
+///
+/// The voxels next to a sign change are initialized by linearly interpolating the crossing along each axis. The remaining
+/// distances are found by solving the Eikonal equation `|∇d| = 1` with the fast sweeping method. Signs are preserved. If no
+/// values in `sdf` change sign, it is left unchanged.
+pub fn redistance(sdf: &mut Array3x1<f32>) {
+    let extent = *sdf.extent();
+    let phi = sdf.channels().store().clone();
+
+    let mut distances = vec![f32::INFINITY; phi.len()];
+    let mut is_fixed = vec![false; phi.len()];
+    let mut any_fixed = false;
+    let original: &Array3x1<f32> = sdf;
+    original.for_each(&extent, |(p, Stride(i)): (Point3i, Stride), value: f32| {
+        // Combine the nearest crossing along each axis, as if the surface were locally planar.
+        let mut inv_distance_sq = 0.0;
+        let mut on_surface = false;
+        let mut near_surface = false;
+        for axis in 0..3 {
+            let nearest_crossing = axis_neighbors(original, p, axis)
+                .iter()
+                .flatten()
+                .map(|&n| phi[n])
+                .filter(|&neighbor| (neighbor < 0.0) != (value < 0.0))
+                .map(|neighbor| value / (value - neighbor))
+                .fold(f32::INFINITY, f32::min);
+            if nearest_crossing.is_finite() {
+                near_surface = true;
+                if nearest_crossing == 0.0 {
+                    on_surface = true;
+                } else {
+                    inv_distance_sq += 1.0 / (nearest_crossing * nearest_crossing);
+                }
+            }
+        }
+        if near_surface {
+            distances[i] = if on_surface {
+                0.0
+            } else {
+                1.0 / inv_distance_sq.sqrt()
+            };
+            is_fixed[i] = true;
+            any_fixed = true;
+        }
+    });
+    if !any_fixed {
+        return;
+    }
+
+    // Two rounds of sweeps in all 8 diagonal directions are enough for the distances to converge.
+    for _ in 0..2 {
+        for &dir in SWEEP_DIRECTIONS.iter() {
+            for p in extent.iter_points() {
+                // Reflect the point to iterate in the direction of the sweep.
+                let mut p = p;
+                for axis in 0..3 {
+                    if dir[axis] < 0 {
+                        p.0[axis] = extent.minimum.at(axis) + extent.max().at(axis) - p.at(axis);
+                    }
+                }
+                let Stride(i) = original.stride_from_local_point(Local(p - extent.minimum));
+                if is_fixed[i] {
+                    continue;
+                }
+
+                let mut upwind = [f32::INFINITY; 3];
+                for (axis, nearest) in upwind.iter_mut().enumerate() {
+                    *nearest = axis_neighbors(original, p, axis)
+                        .iter()
+                        .flatten()
+                        .map(|&n| distances[n])
+                        .fold(f32::INFINITY, f32::min);
+                }
+                if let Some(d) = solve_eikonal(&mut upwind) {
+                    distances[i] = distances[i].min(d);
+                }
+            }
+        }
+    }
+
+    for (value, (original, distance)) in sdf
+        .channels_mut()
+        .store_mut()
+        .iter_mut()
+        .zip(phi.into_iter().zip(distances.into_iter()))
+    {
+        *value = if original < 0.0 { -distance } else { distance };
+    }
+}
+
+const SWEEP_DIRECTIONS: [[i32; 3]; 8] = [
+    [1, 1, 1],
+    [-1, 1, 1],
+    [1, -1, 1],
+    [-1, -1, 1],
+    [1, 1, -1],
+    [-1, 1, -1],
+    [1, -1, -1],
+    [-1, -1, -1],
+];
+
+/// The strides of the neighbors of `p` along `axis`, if they are in bounds.
+fn axis_neighbors(sdf: &Array3x1<f32>, p: Point3i, axis: usize) -> [Option<usize>; 2] {
+    let neighbor = |delta: i32| {
+        let mut n = p;
+        n.0[axis] += delta;
+
+        if sdf.contains(n) {
+            let Stride(i) = sdf.stride_from_local_point(Local(n - sdf.extent().minimum));
+            Some(i)
+        } else {
+            None
+        }
+    };
+
+    [neighbor(-1), neighbor(1)]
+}
+
+/// Solves the Godunov upwind discretization of `|∇d| = 1` given the smallest neighboring distance along each axis. Returns
+/// `None` if all of the neighbors are still unknown.
+fn solve_eikonal(upwind: &mut [f32; 3]) -> Option<f32> {
+    upwind.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let [a, b, c] = *upwind;
+    if !a.is_finite() {
+        return None;
+    }
+
+    let mut d = a + 1.0;
+    if d > b {
+        d = 0.5 * (a + b + (2.0 - (a - b) * (a - b)).sqrt());
+        if d > c {
+            let sum = a + b + c;
+            d = (sum + (sum * sum - 3.0 * (a * a + b * b + c * c - 1.0)).sqrt()) / 3.0;
+        }
+    }
+
+    Some(d)
+}
+
 // ████████╗███████╗███████╗████████╗
 // ╚══██╔══╝██╔════╝██╔════╝╚══██╔══╝
 //    ██║   █████╗  ███████╗   ██║
@@ -99,6 +404,19 @@ mod tests {
         assert_eq!(Sd8(0), Sd8::from(0.0));
     }
 
+    #[test]
+    fn sd8_offset_saturates() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, PointN([3, 1, 1]));
+        let mut sdf = Array3x1::fill_with(extent, |p| Sd8::from(0.5 * (p.x() - 1) as f32));
+
+        offset(&mut sdf, 0.75);
+
+        let offset_distances: Vec<f32> = sdf.channels().store().iter().map(|&d| d.into()).collect();
+        assert_eq!(offset_distances[0], -1.0);
+        assert!((offset_distances[1] + 0.75).abs() < Sd8::PRECISION);
+        assert!((offset_distances[2] + 0.25).abs() < 2.0 * Sd8::PRECISION);
+    }
+
     #[test]
     fn sd16_boundary_conversions() {
         assert_eq!(-1.0, f32::from(Sd16::NEG_ONE));
@@ -109,4 +427,148 @@ mod tests {
         assert_eq!(Sd16::ONE, Sd16::from(1.0));
         assert_eq!(Sd16(0), Sd16::from(0.0));
     }
+
+    #[test]
+    fn sd16_to_sd8_array_round_trip_error_is_bounded() {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-8), Point3i::fill(16));
+        let sd16 =
+            Array3x1::fill_with(extent, |p| Sd16::from(0.05 * Point3f::from(p).norm() - 0.4));
+
+        let sd8: Array3x1<Sd8> = sd16.convert_sdf_precision();
+        let round_trip: Array3x1<Sd16> = sd8.convert_sdf_precision();
+
+        // Rounding to the nearest Sd8 loses at most half of a step.
+        let max_error = 0.5 * Sd8::PRECISION + Sd16::PRECISION;
+        for p in extent.iter_points() {
+            let expected = f32::from(sd16.get(p));
+            assert!((f32::from(sd8.get(p)) - expected).abs() <= max_error);
+            assert!((f32::from(round_trip.get(p)) - expected).abs() <= max_error);
+        }
+
+        assert_eq!(Sd8::from(Sd16::ONE), Sd8::ONE);
+        assert_eq!(Sd8::from(Sd16::NEG_ONE), Sd8::NEG_ONE);
+        assert_eq!(Sd16::from(Sd8::ONE), Sd16::ONE);
+        assert_eq!(Sd16::from(Sd8::NEG_ONE), Sd16::NEG_ONE);
+    }
+
+    #[test]
+    fn redistanced_sphere_has_unit_gradient() {
+        let radius = 5.0;
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-10), Point3i::fill(21));
+        // The zero crossing is a sphere, but the values grow quadratically with distance.
+        let mut sdf = Array3x1::fill_with(extent, |p| {
+            Point3f::from(p).norm_squared() - radius * radius
+        });
+
+        redistance(&mut sdf);
+
+        // Central differences, so stay off of the boundary.
+        let interior = extent.eroded(1);
+        for p in interior.iter_points() {
+            let true_distance = Point3f::from(p).norm() - radius;
+            if true_distance < 2.0 || true_distance > 4.0 {
+                continue;
+            }
+
+            let mut gradient = Point3f::ZERO;
+            for axis in 0..3 {
+                let mut offset = Point3i::ZERO;
+                offset.0[axis] = 1;
+                gradient.0[axis] = 0.5 * (sdf.get(p + offset) - sdf.get(p - offset));
+            }
+            let gradient_norm = gradient.norm();
+            assert!(
+                (gradient_norm - 1.0).abs() < 0.1,
+                "|gradient| = {} at {:?}",
+                gradient_norm,
+                p
+            );
+            assert!((sdf.get(p) - true_distance).abs() < 0.5);
+        }
+
+        // Interior distances are negative and close to the true distance.
+        for p in extent.iter_points() {
+            let true_distance = Point3f::from(p).norm() - radius;
+            if true_distance < -3.0 || true_distance > -1.0 {
+                continue;
+            }
+            let d = sdf.get(p);
+            assert!(d < 0.0, "{} at {:?}", d, p);
+            assert!(
+                (d - true_distance).abs() < 0.5,
+                "{} != {} at {:?}",
+                d,
+                true_distance,
+                p
+            );
+        }
+
+        // The surface stays where the original values crossed zero.
+        for p in extent.iter_points() {
+            let true_distance = Point3f::from(p).norm() - radius;
+            if true_distance.abs() < 1.0 {
+                assert!((sdf.get(p) - true_distance).abs() < 0.25);
+            }
+        }
+        assert_eq!(sdf.get(PointN([5, 0, 0])), 0.0);
+        assert_eq!(sdf.get(PointN([3, 4, 0])), 0.0);
+        assert!(sdf.get(PointN([4, 0, 0])) < 0.0);
+        assert!(sdf.get(PointN([6, 0, 0])) > 0.0);
+
+        // Signs are preserved.
+        assert!(sdf.get(Point3i::ZERO) < 0.0);
+        assert!(sdf.get(Point3i::fill(9)) > 0.0);
+    }
+
+    #[test]
+    fn trilinear_sample_of_plane_matches_analytic_distance() {
+        // A plane through (2.5, 0, 0) with normal (2, 1, 1) / sqrt(6), scaled so the distances stay in [-1, 1].
+        let normal = PointN([2.0f32, 1.0, 1.0]) * (1.0 / 6.0f32.sqrt());
+        let plane_distance = |p: Point3f| 0.1 * normal.dot(p - PointN([2.5, 0.0, 0.0]));
+
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-4), Point3i::fill(8));
+        let sdf = Array3x1::fill_with(extent, |p| Sd8::from(plane_distance(Point3f::from(p))));
+
+        // Quantization error of the 8 corners is at most half a step each.
+        let tolerance = Sd8::PRECISION;
+        for &p in [
+            PointN([0.25, -1.5, 2.75]),
+            PointN([-3.9, 0.1, 0.5]),
+            PointN([2.0, 2.0, -0.3]),
+        ]
+        .iter()
+        {
+            let sampled = sample_trilinear(&sdf, p);
+            let expected = plane_distance(p);
+            assert!(
+                (sampled - expected).abs() <= tolerance,
+                "{} != {} at {:?}",
+                sampled,
+                expected,
+                p
+            );
+        }
+
+        // Outside of the array, the position is clamped.
+        assert_eq!(
+            sample_trilinear(&sdf, PointN([100.0, 3.0, 3.0])),
+            f32::from(sdf.get(PointN([3, 3, 3])))
+        );
+    }
+
+    #[test]
+    fn sphere_surface_colors_point_outward() {
+        let extent = Extent3i::from_min_and_shape(Point3i::fill(-8), Point3i::fill(16));
+        let sdf = Array3x1::fill_with(extent, |p| Sd8::from(0.1 * (Point3f::from(p).norm() - 5.0)));
+
+        let colors = debug_normal_colors(&sdf, &extent);
+
+        // The +X pole is on the surface and its normal points along +X.
+        let [r, g, b] = colors.get(PointN([5, 0, 0]));
+        assert!(r > 250 && r > g + 100 && r > b + 100, "{:?}", [r, g, b]);
+
+        // Voxels away from the surface are black.
+        assert_eq!(colors.get(Point3i::ZERO), [0; 3]);
+        assert_eq!(colors.get(PointN([7, 7, 7])), [0; 3]);
+    }
 }
@@ -0,0 +1,9 @@
+//! Optional instrumentation with the `tracing` crate. Without the `tracing` feature, all of this compiles to nothing.
+
+/// Emits a `tracing::debug!` event with the given arguments iff the `tracing` feature is enabled.
+macro_rules! debug_event {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)+);
+    };
+}
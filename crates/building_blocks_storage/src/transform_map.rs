@@ -79,6 +79,27 @@ impl<'a, Delegate, F> TransformMap<'a, Delegate, F> {
             transform,
         }
     }
+
+    /// Iterates over all points in `extent`, yielding each point along with its value in the delegate and the transformed
+    /// value. This is mostly useful for debugging a transform.
+    #[inline]
+    pub fn inspect_iter<'b, N: 'b, In, Out>(
+        &'b self,
+        extent: &ExtentN<N>,
+    ) -> impl Iterator<Item = (PointN<N>, In, Out)> + 'b
+    where
+        PointN<N>: IntegerPoint<N>,
+        F: Fn(In) -> Out,
+        In: Clone,
+        Delegate: Get<PointN<N>, Item = In>,
+    {
+        extent.iter_points().map(move |p| {
+            let src = self.delegate.get(p);
+            let dst = (self.transform)(src.clone());
+
+            (p, src, dst)
+        })
+    }
 }
 
 impl<'a, Delegate, F, In, Out, Coord> Get<Coord> for TransformMap<'a, Delegate, F>
@@ -164,6 +185,8 @@ where
     type Src = TransformChunkCopySrc<'a, N, F, Out, Ch>;
     type SrcIter = TransformChunkCopySrcIter<'a, N, F, In, Ch>;
 
+    /// Vacant chunks are read as the transformed constant ambient value. If the delegate has an ambient generator (see
+    /// `ChunkMap::set_ambient_fn`), it is lost, so copy from the map itself, then transform, if the generated values matter.
     fn read_extent(&'a self, extent: &ExtentN<N>) -> Self::SrcIter {
         TransformChunkCopySrcIter {
             chunk_iter: self.delegate.read_extent(extent),
@@ -198,6 +221,7 @@ where
                     .map_left(|array_src| {
                         ArrayCopySrc(TransformMap::new(array_src.0, self.transform))
                     })
+                    // The generator can't be carried through, since `F` isn't required to be `'static`.
                     .map_right(|ambient| AmbientExtent::new((self.transform)(ambient.value))),
             )
         })
@@ -243,6 +267,21 @@ mod tests {
         assert_eq!(outer_map.get(Point3i::ZERO), 1);
     }
 
+    #[test]
+    fn inspect_iter_pairs_source_and_transformed_values() {
+        let extent = Extent3i::from_min_and_shape(Point3i::ZERO, Point3i::fill(4));
+        let src = Array3x1::fill_with(extent, |p| p.x() + 4 * p.y() + 16 * p.z());
+        let tfm = TransformMap::new(&src, |value: i32| value + 1);
+
+        let mut num_points = 0;
+        for (p, src_value, dst_value) in tfm.inspect_iter(&extent) {
+            assert_eq!(src_value, src.get(p));
+            assert_eq!(dst_value, src_value + 1);
+            num_points += 1;
+        }
+        assert_eq!(num_points, extent.num_points());
+    }
+
     #[cfg(feature = "lz4")]
     #[test]
     fn copy_from_transformed_array() {
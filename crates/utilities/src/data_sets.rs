@@ -1,5 +1,5 @@
 use building_blocks_core::prelude::*;
-use building_blocks_storage::prelude::Array3x1;
+use building_blocks_storage::prelude::{Array3x1, Array3x2, Sd8};
 
 // TODO: it would be nice if all crates could share this module, but it causes this issue:
 // https://github.com/rust-lang/cargo/issues/6765
@@ -29,3 +29,89 @@ where
 
     (map, sphere_radius)
 }
+
+/// The scale of `terrain_sdf_array`, i.e. the distance from the surface (in voxels) where the `Sd8` values saturate.
+const TERRAIN_SDF_SCALE: f32 = 4.0;
+
+/// A signed distance field of rolling terrain, where the height of the surface is a few octaves of value noise. Unlike
+/// synthetic patterns, this has the large uniform regions and smooth surface of real terrain, which matters for benchmarking
+/// compression. The same `seed` always produces the same terrain.
+pub fn terrain_sdf_array(extent: Extent3i, seed: u32) -> Array3x1<Sd8> {
+    Array3x1::fill_with(extent, |p| {
+        terrain_sdf(p, terrain_height(p.x(), p.z(), seed))
+    })
+}
+
+/// The same terrain as `terrain_sdf_array`, with a material channel of air (0), grass (1), dirt (2), and stone (3) layers.
+pub fn terrain_material_array(extent: Extent3i, seed: u32) -> Array3x2<u8, Sd8> {
+    Array3x2::fill_with(extent, |p| {
+        let height = terrain_height(p.x(), p.z(), seed);
+        let depth = height - p.y() as f32;
+        let material = if depth < 0.0 {
+            0
+        } else if depth < 1.0 {
+            1
+        } else if depth < 4.0 {
+            2
+        } else {
+            3
+        };
+
+        (material, terrain_sdf(p, height))
+    })
+}
+
+fn terrain_sdf(p: Point3i, height: f32) -> Sd8 {
+    Sd8::from((p.y() as f32 - height) / TERRAIN_SDF_SCALE)
+}
+
+/// The height of the terrain surface at column `(x, z)`.
+pub fn terrain_height(x: i32, z: i32, seed: u32) -> f32 {
+    let mut height = 0.0;
+    let mut amplitude = 16.0;
+    let mut wavelength = 64.0;
+    for octave in 0..4 {
+        height += amplitude
+            * value_noise_2d(
+                x as f32 / wavelength,
+                z as f32 / wavelength,
+                seed.wrapping_add(octave),
+            );
+        amplitude *= 0.5;
+        wavelength *= 0.5;
+    }
+
+    height
+}
+
+/// Smoothly interpolates between pseudo-random values in `[-1, 1]` at the integer lattice points.
+fn value_noise_2d(x: f32, z: f32, seed: u32) -> f32 {
+    let (x0, z0) = (x.floor(), z.floor());
+    let (tx, tz) = (smoothstep(x - x0), smoothstep(z - z0));
+    let (x0, z0) = (x0 as i32, z0 as i32);
+    let value = |dx, dz| lattice_value(x0 + dx, z0 + dz, seed);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    lerp(
+        lerp(value(0, 0), value(1, 0), tx),
+        lerp(value(0, 1), value(1, 1), tx),
+        tz,
+    )
+}
+
+fn smoothstep(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lattice_value(x: i32, z: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (z as u32).wrapping_mul(0xd816_3841)
+        ^ seed.wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+
+    h as f32 / u32::MAX as f32 * 2.0 - 1.0
+}